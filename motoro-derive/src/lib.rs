@@ -0,0 +1,63 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+/// Generates vertex input attributes and binding tables from `#[repr(C)]` struct.
+///
+/// Attribute locations follow field declaration order, formats are resolved via
+/// `VertexFormat` trait and offsets are computed by compiler, so layout can't silently
+/// diverge from struct definition.
+#[proc_macro_derive(VertexLayout)]
+pub fn derive_vertex_layout(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let repr_c = input.attrs.iter().any(|attr| {
+        attr.path().is_ident("repr")
+            && attr
+                .parse_args::<syn::Ident>()
+                .map(|repr| repr == "C")
+                .unwrap_or(false)
+    });
+    if !repr_c {
+        return Error::new_spanned(name, "VertexLayout requires #[repr(C)] struct")
+            .to_compile_error()
+            .into();
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Error::new_spanned(name, "VertexLayout requires named fields")
+                    .to_compile_error()
+                    .into()
+            }
+        },
+        _ => {
+            return Error::new_spanned(name, "VertexLayout can be derived for structs only")
+                .to_compile_error()
+                .into()
+        }
+    };
+    let attributes = fields.iter().enumerate().map(|(location, field)| {
+        let location = location as u32;
+        let field_name = field.ident.as_ref().expect("named field");
+        let field_type = &field.ty;
+        quote! {
+            ::motoro::vertex_attribute(
+                #location,
+                <#field_type as ::motoro::VertexFormat>::FORMAT,
+                ::core::mem::offset_of!(#name, #field_name) as u32,
+            )
+        }
+    });
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        impl #impl_generics ::motoro::VertexLayout for #name #type_generics #where_clause {
+            const ATTRIBUTES: &'static [::motoro::VertexAttribute] = &[#(#attributes),*];
+            const BINDINGS: &'static [::motoro::VertexBinding] = &[
+                ::motoro::vertex_binding(::core::mem::size_of::<#name #type_generics>() as u32)
+            ];
+        }
+    };
+    expanded.into()
+}
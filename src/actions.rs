@@ -0,0 +1,239 @@
+use crate::UserInput;
+use sdl2::controller::{Axis, Button};
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Scancode};
+use std::collections::HashMap;
+
+/// Describes physical input bound to action.
+///
+/// Keys are referenced by SDL key names (e.g. `"Space"`, `"Left Shift"`), gamepad buttons
+/// and axes by SDL game controller names (e.g. `"a"`, `"dpup"`, `"leftx"`), so bindings
+/// can be loaded from config files:
+///
+/// ```json
+/// {
+///     "jump": [{"scancode": "Space"}, {"button": "a"}],
+///     "move_x": [{"scancodes": ["A", "D"]}, {"axis": "leftx"}]
+/// }
+/// ```
+///
+/// Scancode bindings refer to physical key positions named by US layout and should be
+/// preferred for movement, key bindings refer to key labels of current layout.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Binding {
+    Key(String),
+    Scancode(String),
+    Mouse(MouseBinding),
+    Button(String),
+    Axis(String),
+    /// Pair of negative and positive keys acting as axis, e.g. `["A", "D"]`.
+    Keys([String; 2]),
+    /// Pair of negative and positive physical keys acting as axis.
+    Scancodes([String; 2]),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MouseBinding {
+    Left,
+    Right,
+}
+
+impl Binding {
+    pub fn key(keycode: Keycode) -> Self {
+        Binding::Key(keycode.name())
+    }
+
+    pub fn scancode(scancode: Scancode) -> Self {
+        Binding::Scancode(scancode.name().to_string())
+    }
+
+    pub fn button(button: Button) -> Self {
+        Binding::Button(button.string())
+    }
+
+    pub fn axis(axis: Axis) -> Self {
+        Binding::Axis(axis.string())
+    }
+
+    /// Returns binding value in range -1.0 to 1.0, buttons and keys are 0.0 or 1.0.
+    fn value(&self, input: &UserInput) -> f32 {
+        let key_down = |name: &str| match Keycode::from_name(name) {
+            Some(keycode) => input.keys.down.contains(&keycode),
+            None => false,
+        };
+        let scancode_down = |name: &str| match Scancode::from_name(name) {
+            Some(scancode) => input.keys.scancodes.contains(&scancode),
+            None => false,
+        };
+        let value = |down: bool| if down { 1.0 } else { 0.0 };
+        match self {
+            Binding::Key(name) => value(key_down(name)),
+            Binding::Scancode(name) => value(scancode_down(name)),
+            Binding::Mouse(MouseBinding::Left) => value(input.mouse.left.down),
+            Binding::Mouse(MouseBinding::Right) => value(input.mouse.right.down),
+            Binding::Button(name) => match Button::from_string(name) {
+                Some(button) => value(input.gamepad.buttons.contains(&button)),
+                None => 0.0,
+            },
+            Binding::Axis(name) => match Axis::from_string(name) {
+                Some(axis) => input.gamepad.axis(axis),
+                None => 0.0,
+            },
+            Binding::Keys([negative, positive]) => {
+                value(key_down(positive)) - value(key_down(negative))
+            }
+            Binding::Scancodes([negative, positive]) => {
+                value(scancode_down(positive)) - value(scancode_down(negative))
+            }
+        }
+    }
+}
+
+/// State of action in current frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ActionState {
+    /// Value of strongest binding in range -1.0 to 1.0.
+    pub value: f32,
+    pub down: bool,
+    /// Action became down in current frame.
+    pub pressed: bool,
+    /// Action became up in current frame.
+    pub released: bool,
+}
+
+/// Maps named actions (e.g. `"jump"`, `"move_x"`) to keys, mouse buttons and gamepad inputs,
+/// so game logic doesn't depend on raw input and bindings can be changed at runtime.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(from = "HashMap<String, Vec<Binding>>")]
+pub struct ActionMap {
+    bindings: HashMap<String, Vec<Binding>>,
+    states: HashMap<String, ActionState>,
+}
+
+impl From<HashMap<String, Vec<Binding>>> for ActionMap {
+    fn from(bindings: HashMap<String, Vec<Binding>>) -> Self {
+        Self {
+            bindings,
+            states: HashMap::new(),
+        }
+    }
+}
+
+impl ActionMap {
+    /// Axis value at which axis bound to action makes it down.
+    pub const AXIS_THRESHOLD: f32 = 0.5;
+
+    pub fn bind(mut self, action: &str, binding: Binding) -> Self {
+        self.bindings
+            .entry(action.to_string())
+            .or_default()
+            .push(binding);
+        self
+    }
+
+    /// Replaces all bindings of action, e.g. after player changes controls in settings.
+    pub fn rebind(&mut self, action: &str, bindings: Vec<Binding>) {
+        self.bindings.insert(action.to_string(), bindings);
+    }
+
+    pub fn bindings(&self) -> &HashMap<String, Vec<Binding>> {
+        &self.bindings
+    }
+
+    /// Updates action states, must be called once per frame after input is captured.
+    pub fn update(&mut self, input: &UserInput) {
+        for (action, bindings) in &self.bindings {
+            let value = bindings.iter().map(|binding| binding.value(input)).fold(
+                0.0,
+                |strongest: f32, value| {
+                    if value.abs() > strongest.abs() {
+                        value
+                    } else {
+                        strongest
+                    }
+                },
+            );
+            let down = value.abs() >= Self::AXIS_THRESHOLD;
+            let state = self.states.entry(action.clone()).or_default();
+            *state = ActionState {
+                value,
+                down,
+                pressed: down && !state.down,
+                released: !down && state.down,
+            };
+        }
+    }
+
+    pub fn state(&self, action: &str) -> ActionState {
+        self.states.get(action).copied().unwrap_or_default()
+    }
+
+    pub fn value(&self, action: &str) -> f32 {
+        self.state(action).value
+    }
+
+    pub fn down(&self, action: &str) -> bool {
+        self.state(action).down
+    }
+
+    pub fn pressed(&self, action: &str) -> bool {
+        self.state(action).pressed
+    }
+
+    pub fn released(&self, action: &str) -> bool {
+        self.state(action).released
+    }
+
+    /// Returns binding of first key, mouse button or gamepad input pressed in current frame,
+    /// e.g. to rebind action to whatever player presses next. Keys are captured by physical
+    /// position, so bindings survive keyboard layout switch.
+    pub fn capture(input: &UserInput) -> Option<Binding> {
+        for event in &input.events {
+            let binding = match event {
+                Event::KeyDown {
+                    scancode: Some(scancode),
+                    ..
+                } => Binding::scancode(*scancode),
+                Event::MouseButtonDown { mouse_btn, .. } => match mouse_btn {
+                    sdl2::mouse::MouseButton::Left => Binding::Mouse(MouseBinding::Left),
+                    sdl2::mouse::MouseButton::Right => Binding::Mouse(MouseBinding::Right),
+                    _ => continue,
+                },
+                Event::ControllerButtonDown { button, .. } => Binding::button(*button),
+                Event::ControllerAxisMotion { axis, value, .. }
+                    if (*value as f32 / i16::MAX as f32).abs() >= Self::AXIS_THRESHOLD =>
+                {
+                    Binding::axis(*axis)
+                }
+                _ => continue,
+            };
+            return Some(binding);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ActionMap, Binding, UserInput};
+
+    #[test]
+    pub fn test_key_axis_action_value_and_transitions() {
+        let mut actions = ActionMap::default()
+            .bind("move_x", Binding::Keys(["A".to_string(), "D".to_string()]))
+            .bind("jump", Binding::Key("Space".to_string()));
+        let mut input = UserInput::default();
+        input.keys.down.push(sdl2::keyboard::Keycode::D);
+        input.keys.down.push(sdl2::keyboard::Keycode::Space);
+        actions.update(&input);
+        assert_eq!(actions.value("move_x"), 1.0);
+        assert!(actions.pressed("jump"));
+        input.keys.down.clear();
+        actions.update(&input);
+        assert_eq!(actions.value("move_x"), 0.0);
+        assert!(actions.released("jump"));
+        assert!(!actions.down("jump"));
+    }
+}
@@ -1,23 +1,50 @@
 pub use crate::colors::*;
 
 pub use crate::textures::*;
+pub use crate::vulkan::compute::{compute_range, ComputeProgram, ComputeSurface};
+pub use crate::vulkan::memory::MemoryStats;
 pub use crate::vulkan::program::*;
+use crate::vulkan::retire::Retired;
+use crate::vulkan::sampler::create_sampler;
+pub use crate::vulkan::sampler::SamplerOptions;
 pub use crate::vulkan::shaders::*;
+pub use crate::vulkan::stats::FrameStats;
+/// Window owned by external framework, see [`Vulkan::create_from_raw_handle`].
+#[cfg(feature = "raw-window-handle")]
+pub use crate::vulkan::surface::RawWindow;
+pub use crate::vulkan::target::{MultiTarget, RenderTarget};
+pub use crate::vulkan::validation::{
+    log_validation_summary, validation_summary, ValidationMessage,
+};
 pub use crate::vulkan::variables::*;
-use crate::{Camera, Graphics};
+/// Renders to window of other windowing library instead of SDL window created by [`Graphics`].
+#[cfg(any(feature = "winit", feature = "raw-window-handle"))]
+pub use crate::vulkan::{surface::VulkanWindow, Vulkan};
+use crate::{Camera, CameraHandle, Frame, Graphics};
+use log::warn;
+use std::sync::{Arc, RwLock};
 use vulkanalia::vk;
-use vulkanalia::vk::{DeviceV1_0, HasBuilder, PipelineVertexInputStateCreateInfo};
+use vulkanalia::vk::PipelineVertexInputStateCreateInfo;
 
 impl Graphics {
-    pub fn camera(&mut self) -> Box<Camera> {
-        let mut camera = Box::new(Camera::create(self));
+    pub fn camera(&mut self) -> CameraHandle {
+        let mut camera = Camera::create(self);
         camera.update(&self.vulkan);
-        self.vulkan.register_camera(&mut camera);
+        let camera = Arc::new(RwLock::new(camera));
+        self.vulkan.register_camera(&camera);
         camera
     }
 
+    /// Creates simple drawing facade, see [`Frame`].
+    pub fn create_frame(&mut self) -> Result<Frame, ProgramError> {
+        Frame::create(self)
+    }
+
     pub fn textures(&self, slot: u32, binding: u32) -> Textures {
-        Textures::create(slot, binding, &self.vulkan.device)
+        let device = &self.vulkan.device;
+        let textures = Textures::create(slot, binding, device, self.max_textures);
+        self.texture_slots.register(textures.slots());
+        textures
     }
 
     pub fn uniform<T>(&self, slot: u32, binding: u32) -> Uniform<T> {
@@ -31,6 +58,37 @@ impl Graphics {
         unsafe { Storage::create(&self.vulkan, n) }
     }
 
+    /// Creates texel buffer read by shaders as `samplerBuffer`, see [`TexelBuffer`].
+    pub fn texel_buffer<T>(
+        &self,
+        n: usize,
+        format: vk::Format,
+    ) -> Result<TexelBuffer<T>, VariableError>
+    where
+        T: Default + Clone + Copy,
+    {
+        let kind = vk::DescriptorType::UNIFORM_TEXEL_BUFFER;
+        unsafe { TexelBuffer::create(&self.vulkan, n, format, kind) }
+    }
+
+    /// Creates texel buffer read and written by shaders as `imageBuffer`.
+    pub fn storage_texel_buffer<T>(
+        &self,
+        n: usize,
+        format: vk::Format,
+    ) -> Result<TexelBuffer<T>, VariableError>
+    where
+        T: Default + Clone + Copy,
+    {
+        let kind = vk::DescriptorType::STORAGE_TEXEL_BUFFER;
+        unsafe { TexelBuffer::create(&self.vulkan, n, format, kind) }
+    }
+
+    /// Creates storage in device local memory, see [`DeviceStorage`].
+    pub fn device_storage<T>(&self, n: usize, per_frame: bool) -> DeviceStorage<T> {
+        unsafe { DeviceStorage::create(&self.vulkan, n, per_frame) }
+    }
+
     pub fn mesh(&self, n: usize) -> Mesh {
         unsafe { Mesh::create(&self.vulkan, n) }
     }
@@ -39,29 +97,71 @@ impl Graphics {
         self.textures.create_texture(width, height, data)
     }
 
+    /// Creates texture of data in given format, see [`TextureFormat`].
+    pub fn texture_with_format(
+        &self,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        data: &[u8],
+    ) -> Texture {
+        self.textures
+            .create_texture_with_format(width, height, format, data)
+    }
+
+    /// Creates texture of float values in float format, e.g. height field in R32F.
+    ///
+    /// Fails if format is not float or values don't match channels of texture size.
+    pub fn texture_from_floats(
+        &self,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        values: &[f32],
+    ) -> Result<Texture, TextureError> {
+        let data = format
+            .encode_floats(values)
+            .ok_or_else(|| TextureError(format!("texture format {format:?} must be float")))?;
+        let size = format.data_size(width, height);
+        if data.len() != size {
+            return Err(TextureError(format!(
+                "texture {width}x{height} {format:?} must have {size} bytes of values, got {}",
+                data.len()
+            )));
+        }
+        Ok(self.texture_with_format(width, height, format, &data))
+    }
+
     pub fn create_pixel_perfect_sampler(&self) -> vk::Sampler {
-        let info = vk::SamplerCreateInfo::builder()
-            .mag_filter(vk::Filter::NEAREST)
-            .min_filter(vk::Filter::NEAREST)
-            .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-            .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-            .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-            .anisotropy_enable(false)
-            .max_anisotropy(16.0)
-            .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
-            .unnormalized_coordinates(false)
-            .compare_enable(false)
-            .compare_op(vk::CompareOp::ALWAYS)
-            .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
-            .min_lod(0.0)
-            .max_lod(0.0)
-            .mip_lod_bias(0.0);
-        unsafe {
-            self.vulkan
-                .device
-                .create_sampler(&info, None)
-                .expect("sampler must be created")
+        self.create_sampler(SamplerOptions::default().lod(0.0, 0.0))
+    }
+
+    pub fn create_linear_sampler(&self) -> vk::Sampler {
+        self.create_sampler(SamplerOptions::default().linear().lod(0.0, 0.0))
+    }
+
+    /// Creates sampler with texture filtering of graphics config.
+    pub fn create_default_sampler(&self) -> vk::Sampler {
+        self.create_sampler(SamplerOptions::default().filtering(self.filtering))
+    }
+
+    /// Returns sampler of options, samplers are cached, so renderers requesting equal
+    /// options share single sampler.
+    pub fn create_sampler(&self, mut options: SamplerOptions) -> vk::Sampler {
+        if options.lod_bias != 0.0 && !self.vulkan.lod_bias_supported {
+            warn!(
+                "Sampler LOD bias is not supported by device, ignores {}",
+                options.lod_bias
+            );
+            options.lod_bias = 0.0;
         }
+        let mut samplers = self.samplers.borrow_mut();
+        if let Some((_, sampler)) = samplers.iter().find(|(cached, _)| *cached == options) {
+            return *sampler;
+        }
+        let sampler = unsafe { create_sampler(&self.vulkan.device, options) };
+        samplers.push((options, sampler));
+        sampler
     }
 
     pub fn create_program(
@@ -73,36 +173,191 @@ impl Graphics {
         sampler: vk::Sampler,
         layouts: Vec<vk::DescriptorSetLayout>,
         vertex_input: Option<PipelineVertexInputStateCreateInfo>,
-    ) -> Box<Program> {
+        options: ProgramOptions,
+    ) -> Result<Box<Program>, ProgramError> {
         let program = unsafe {
             Program::create(
                 name,
                 &self.vulkan.device,
                 &self.vulkan.swapchain,
                 self.vulkan.render_pass,
+                self.vulkan.samples,
                 vert,
                 frag,
                 push_constants,
                 sampler,
                 layouts,
                 vertex_input,
-            )
+                options,
+            )?
         };
         let mut program = Box::new(program);
         self.vulkan.register(&mut program);
-        program
+        Ok(program)
+    }
+
+    pub fn create_compute_program(
+        &mut self,
+        name: &str,
+        shader: Shader,
+        push_constants: Vec<vk::PushConstantRange>,
+        layouts: Vec<vk::DescriptorSetLayout>,
+    ) -> Result<Box<ComputeProgram>, ProgramError> {
+        let program = unsafe {
+            ComputeProgram::create(name, &self.vulkan.device, shader, push_constants, layouts)?
+        };
+        self.vulkan.register_compute(&program);
+        Ok(Box::new(program))
     }
 
+    /// Records compute commands and submits them to compute queue, so dispatches run
+    /// asynchronously with graphics. Current frame rendering waits for completion
+    /// before vertex input, so results can be consumed by draws of the same frame.
+    ///
+    /// Must be called between `clear` and `present`.
+    pub fn submit_async_compute<F>(&mut self, record: F)
+    where
+        F: FnOnce(vk::CommandBuffer),
+    {
+        self.vulkan.submit_async_compute(record);
+    }
+
+    pub fn create_render_target(&self, width: u32, height: u32) -> RenderTarget {
+        unsafe { RenderTarget::create(&self.vulkan, width, height) }
+    }
+
+    /// Starts rendering into offscreen target, draws of all programs go to target
+    /// until `end_render_target`. Target texture can be sampled after that.
+    ///
+    /// Must be called between `clear` and `present`.
+    pub fn begin_render_target(&mut self, target: &mut RenderTarget, color: impl Colors) {
+        self.vulkan.begin_target(target, color.to_vec4());
+    }
+
+    pub fn end_render_target(&mut self, target: &mut RenderTarget) {
+        self.vulkan.end_target(target);
+    }
+
+    /// Destroys target once frames in flight which may render to or sample it complete.
+    pub fn destroy_render_target(&mut self, target: RenderTarget) {
+        self.texture_slots.release(target.texture);
+        self.vulkan.retire(Retired::RenderTarget(target));
+    }
+
+    /// Creates target with color attachment of every format, see [`MultiTarget`].
+    pub fn create_multi_target(
+        &self,
+        width: u32,
+        height: u32,
+        formats: &[vk::Format],
+    ) -> MultiTarget {
+        unsafe { MultiTarget::create(&self.vulkan, width, height, formats) }
+    }
+
+    /// Creates program writing all attachments of multi target, target must outlive program.
+    pub fn create_multi_target_program(
+        &mut self,
+        target: &MultiTarget,
+        name: &str,
+        vert: Shader,
+        frag: Shader,
+        push_constants: Vec<vk::PushConstantRange>,
+        sampler: vk::Sampler,
+        layouts: Vec<vk::DescriptorSetLayout>,
+        vertex_input: Option<PipelineVertexInputStateCreateInfo>,
+        options: ProgramOptions,
+    ) -> Result<Box<Program>, ProgramError> {
+        let options = options.color_attachments(target.textures.len());
+        let program = unsafe {
+            Program::create(
+                name,
+                &self.vulkan.device,
+                &self.vulkan.swapchain,
+                target.render_pass,
+                vk::SampleCountFlags::_1,
+                vert,
+                frag,
+                push_constants,
+                sampler,
+                layouts,
+                vertex_input,
+                options,
+            )?
+        };
+        program.set_target_render_pass(target.render_pass);
+        let mut program = Box::new(program);
+        self.vulkan.register(&mut program);
+        Ok(program)
+    }
+
+    /// Starts rendering into all attachments of multi target, like `begin_render_target`.
+    pub fn begin_multi_target(&mut self, target: &mut MultiTarget, color: impl Colors) {
+        self.vulkan.begin_multi_target(target, color.to_vec4());
+    }
+
+    pub fn end_multi_target(&mut self, target: &mut MultiTarget) {
+        self.vulkan.end_multi_target(target);
+    }
+
+    /// Destroys target once frames in flight which may render to or sample it complete.
+    pub fn destroy_multi_target(&mut self, target: MultiTarget) {
+        for texture in &target.textures {
+            self.texture_slots.release(*texture);
+        }
+        self.vulkan.retire(Retired::MultiTarget(target));
+    }
+
+    pub fn create_compute_surface(&self, width: u32, height: u32) -> ComputeSurface {
+        unsafe { ComputeSurface::create(&self.vulkan, width, height) }
+    }
+
+    /// Renders whole frame by compute shader without graphics pipeline, replaces
+    /// `clear` and `present` calls. Recorded dispatches must write surface image
+    /// bound by given descriptor, result is scaled to window and presented.
+    pub fn render_compute<F>(&mut self, surface: &ComputeSurface, record: F)
+    where
+        F: FnOnce(vk::CommandBuffer, vk::DescriptorSet),
+    {
+        let commands = self.vulkan.prepare_compute(&self.window);
+        let frame = self.vulkan.frame_in_flight();
+        let swapchain = &self.vulkan.swapchain;
+        unsafe {
+            surface.begin(commands, frame);
+            record(commands, surface.descriptor(frame));
+            surface.blit(
+                commands,
+                frame,
+                swapchain.images[self.vulkan.chain],
+                swapchain.extent,
+            );
+        }
+        self.vulkan.present_compute();
+        self.record_frame_stats();
+        self.create_deferred_values();
+        self.limiter.wait();
+    }
+
+    /// Destroys surface once frames in flight which may write or blit it complete.
+    pub fn destroy_compute_surface(&mut self, surface: ComputeSurface) {
+        self.vulkan.retire(Retired::ComputeSurface(surface));
+    }
+
+    /// Returns index of frame in flight, per-frame resources must be updated by this index.
     pub fn frame(&self) -> usize {
-        self.vulkan.chain
+        self.vulkan.frame_in_flight()
     }
 
-    pub fn destroy_texture(&self, texture: Texture) {
-        texture.destroy(&self.vulkan.device);
+    /// Destroys texture once frames in flight which may sample it complete.
+    pub fn destroy_texture(&mut self, texture: Texture) {
+        self.texture_slots.release(texture);
+        self.vulkan.retire(Retired::Texture(texture));
     }
 
-    pub fn destroy_mesh(&self, mesh: &Mesh) {
-        mesh.destroy();
+    /// Destroys mesh vertex buffers once frames in flight which may read them complete.
+    pub fn destroy_mesh(&mut self, mesh: Mesh) {
+        for buffer in mesh.take_buffers() {
+            self.vulkan.retire(Retired::Buffer(buffer));
+        }
     }
 }
 
@@ -1,10 +1,16 @@
 use crate::math::{
-    mat4_from_scale, mat4_from_translation, mat4_identity, mat4_look_at_rh, mat4_mul,
-    mat4_orthographic, Mat4, Vec2, Vec2u, Vec3, VecArith, VecComponents, VecMagnitude, VecNeg,
+    mat4_from_rotation_z, mat4_from_scale, mat4_from_translation, mat4_identity, mat4_look_at_rh,
+    mat4_mul, mat4_orthographic, Mat4, Rect, Vec2, Vec2u, Vec3, VecArith, VecCast, VecComponents,
+    VecFloor, VecMagnitude, VecNeg,
 };
 use crate::vulkan::Vulkan;
 use crate::{Graphics, UserInput};
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::Scancode;
+use std::sync::{Arc, RwLock};
+
+/// Camera shared with Vulkan, which updates its screen once swapchain is recreated,
+/// so camera must not be locked while frame is prepared.
+pub type CameraHandle = Arc<RwLock<Camera>>;
 
 pub struct Camera {
     pub eye: Vec3,
@@ -13,13 +19,104 @@ pub struct Camera {
     pub zoom: f32,
     pub screen: Vec2,
     resolution_reference: Option<[u32; 2]>,
+    letterbox: bool,
+    dpi_scaled: bool,
+    dpi_scale: f32,
+    /// Top left corner of content in screen pixels, non-zero if letterbox bars are shown.
+    pub origin: Vec2,
     pub enabled: bool,
     pub control_speed: f32,
     pub speed: f32,
+    pub shake: CameraShake,
     proj: Mat4,
     view: Mat4,
 }
 
+/// Trauma-based camera shake, see [`Camera::add_trauma`].
+///
+/// Shake intensity is square of trauma, so small hits barely move camera while big ones
+/// shake it hard. Trauma decays linearly, shake is driven by smooth noise of time.
+#[derive(Clone, Copy, Debug)]
+pub struct CameraShake {
+    /// Current trauma in range 0.0 to 1.0.
+    pub trauma: f32,
+    /// Noise frequency, roughly oscillations per second.
+    pub frequency: f32,
+    /// Trauma loss per second.
+    pub decay: f32,
+    /// Max translation in world units at full trauma.
+    pub max_offset: Vec2,
+    /// Max rotation in radians around screen center at full trauma.
+    pub max_angle: f32,
+    time: f32,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            frequency: 15.0,
+            decay: 1.0,
+            max_offset: [16.0, 16.0],
+            max_angle: 0.1,
+            time: 0.0,
+        }
+    }
+}
+
+impl CameraShake {
+    pub fn frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    pub fn decay(mut self, decay: f32) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    /// Sets translational component, zero offset disables it.
+    pub fn max_offset(mut self, offset: Vec2) -> Self {
+        self.max_offset = offset;
+        self
+    }
+
+    /// Sets rotational component, zero angle disables it.
+    pub fn max_angle(mut self, angle: f32) -> Self {
+        self.max_angle = angle;
+        self
+    }
+
+    pub fn update(&mut self, time: f32) {
+        if self.trauma > 0.0 {
+            self.time += time;
+            self.trauma = (self.trauma - self.decay * time).max(0.0);
+        }
+    }
+
+    fn intensity(&self) -> f32 {
+        self.trauma * self.trauma
+    }
+
+    pub fn offset(&self) -> Vec2 {
+        let intensity = self.intensity();
+        [
+            self.max_offset.x() * intensity * self.noise(0.0),
+            self.max_offset.y() * intensity * self.noise(1.0),
+        ]
+    }
+
+    pub fn angle(&self) -> f32 {
+        self.max_angle * self.intensity() * self.noise(2.0)
+    }
+
+    /// Returns smooth noise in range -1.0 to 1.0, channels are decorrelated by seed.
+    fn noise(&self, seed: f32) -> f32 {
+        let t = self.time * self.frequency + seed * 17.0;
+        (t.sin() + (t * 2.31 + seed).sin() * 0.5 + (t * 4.17 + seed * 3.0).sin() * 0.25) / 1.75
+    }
+}
+
 impl Camera {
     pub fn create(graphics: &Graphics) -> Self {
         let camera = Self {
@@ -29,9 +126,14 @@ impl Camera {
             zoom: 1.0,
             screen: [0.0; 2],
             resolution_reference: None,
+            letterbox: false,
+            dpi_scaled: false,
+            dpi_scale: graphics.dpi_scale(),
+            origin: [0.0; 2],
             enabled: false,
             control_speed: 100.0,
             speed: 100.0,
+            shake: CameraShake::default(),
             proj: mat4_identity(),
             view: mat4_identity(),
         };
@@ -49,21 +151,94 @@ impl Camera {
         self
     }
 
+    pub fn shake(mut self, shake: CameraShake) -> Self {
+        self.shake = shake;
+        self
+    }
+
+    /// Adds trauma to shake camera, e.g. 0.3 for hit and 1.0 for explosion.
+    /// Trauma is clamped to 1.0.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.shake.trauma = (self.shake.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// Advances shake by scaled frame time, must be called once per frame while shake is used,
+    /// shake freezes while game clock is paused.
+    pub fn update_shake(&mut self, input: &UserInput) {
+        self.shake.update(input.clock.delta());
+    }
+
     pub fn reference(mut self, resolution: Vec2u) -> Self {
         self.resolution_reference = Some(resolution);
-        if let Some(reference) = self.resolution_reference {
-            self.resolution_scale = self.screen.y() / reference.y() as f32;
-        }
+        self.update_scale();
+        self
+    }
+
+    /// Keeps aspect of resolution reference, if window aspect differs content is scaled
+    /// to fit and centered between black bars (letterbox or pillarbox), see [`Camera::bars`].
+    pub fn letterbox(mut self, enabled: bool) -> Self {
+        self.letterbox = enabled;
+        self.update_scale();
+        self
+    }
+
+    /// Scales content by dpi scale of display window is on if there is no resolution
+    /// reference, so content keeps its physical size on high DPI displays.
+    pub fn dpi_scaled(mut self, enabled: bool) -> Self {
+        self.dpi_scaled = enabled;
+        self.update_scale();
         self
     }
 
+    pub(crate) fn set_dpi_scale(&mut self, scale: f32) {
+        self.dpi_scale = scale;
+    }
+
     pub(crate) fn update(&mut self, vulkan: &Vulkan) {
         self.update_screen(vulkan.swapchain_image_size());
+        self.update_scale();
+    }
+
+    fn update_scale(&mut self) {
+        self.origin = [0.0; 2];
         if let Some(reference) = self.resolution_reference {
-            self.resolution_scale = self.screen.y() / reference.y() as f32;
+            let reference: Vec2 = reference.cast();
+            self.resolution_scale = self.screen.y() / reference.y();
+            if self.letterbox {
+                let scale = self.screen.div(reference);
+                self.resolution_scale = scale.x().min(scale.y());
+                let content = reference.mul(self.resolution_scale);
+                self.origin = self.screen.sub(content).mul(0.5).floor();
+            }
+        } else if self.dpi_scaled {
+            self.resolution_scale = self.dpi_scale;
+        } else {
+            self.resolution_scale = 1.0;
         }
     }
 
+    /// Returns size of content area in screen pixels, whole screen unless letterboxed.
+    pub fn content(&self) -> Vec2 {
+        self.screen.sub(self.origin.mul(2.0))
+    }
+
+    /// Returns rects (in screen pixels) of letterbox bars around content,
+    /// they should be filled after content drawn, so content overflow is covered.
+    pub fn bars(&self) -> Vec<Rect> {
+        let [x, y] = self.origin;
+        let [width, height] = self.screen;
+        let mut bars = vec![];
+        if x > 0.0 {
+            bars.push(Rect::new([0.0, 0.0], [x, height]));
+            bars.push(Rect::new([width - x, 0.0], [x, height]));
+        }
+        if y > 0.0 {
+            bars.push(Rect::new([0.0, 0.0], [width, y]));
+            bars.push(Rect::new([0.0, height - y], [width, y]));
+        }
+        bars
+    }
+
     fn update_screen(&mut self, screen: Vec2) {
         if screen != self.screen {
             self.screen = screen;
@@ -81,19 +256,20 @@ impl Camera {
             self.zoom += 0.05;
         }
         let mut delta = [0.0, 0.0, 0.0];
-        if input.keys.down.contains(&Keycode::W) {
+        if input.keys.scancodes.contains(&Scancode::W) {
             delta[1] -= 1.0;
         }
-        if input.keys.down.contains(&Keycode::A) {
+        if input.keys.scancodes.contains(&Scancode::A) {
             delta[0] -= 1.0;
         }
-        if input.keys.down.contains(&Keycode::S) {
+        if input.keys.scancodes.contains(&Scancode::S) {
             delta[1] += 1.0;
         }
-        if input.keys.down.contains(&Keycode::D) {
+        if input.keys.scancodes.contains(&Scancode::D) {
             delta[0] += 1.0;
         }
-        let time = input.time.as_secs_f32();
+        // debug camera stays controllable while game clock is paused
+        let time = input.clock.unscaled_delta();
         let delta = delta.normal().mul(time * self.control_speed);
 
         self.eye_target = self.eye_target.add(delta);
@@ -110,7 +286,59 @@ impl Camera {
     }
 
     pub fn viewport(&self) -> Vec2 {
-        self.screen.div(self.resolution_scale)
+        self.content().div(self.resolution_scale)
+    }
+
+    /// Returns world rect visible in content area, rotated by shake view is bounded
+    /// by axis-aligned rect, so culling against it never drops visible elements.
+    pub fn visible_rect(&self) -> Rect {
+        let [x, y, _] = self.scaling();
+        let [width, height] = self.content();
+        let [shake_x, shake_y] = self.shake.offset();
+        let (sin, cos) = self.shake.angle().sin_cos();
+        let center = [width * 0.5, height * 0.5];
+        let corners = [[0.0, 0.0], [width, 0.0], [width, height], [0.0, height]];
+        let points: Vec<Vec2> = corners
+            .into_iter()
+            .map(|[cx, cy]| {
+                // inverse of shake rotation around content center
+                let [dx, dy] = [cx - center[0], cy - center[1]];
+                let qx = center[0] + dx * cos + dy * sin;
+                let qy = center[1] - dx * sin + dy * cos;
+                [
+                    qx / x + self.eye.x() - shake_x,
+                    qy / y + self.eye.y() - shake_y,
+                ]
+            })
+            .collect();
+        Rect::from_points(&points)
+    }
+
+    /// Returns position in window pixels of world point, shake is taken into account.
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        let [x, y, _] = self.scaling();
+        let [width, height] = self.content();
+        let [shake_x, shake_y] = self.shake.offset();
+        let (sin, cos) = self.shake.angle().sin_cos();
+        let dx = (point[0] - self.eye.x() + shake_x) * x - width * 0.5;
+        let dy = (point[1] - self.eye.y() + shake_y) * y - height * 0.5;
+        [
+            self.origin[0] + width * 0.5 + dx * cos - dy * sin,
+            self.origin[1] + height * 0.5 + dx * sin + dy * cos,
+        ]
+    }
+
+    /// Returns bounding rect in window pixels of world rect.
+    pub fn world_to_screen_rect(&self, rect: &Rect) -> Rect {
+        let [x, y] = rect.min();
+        let [right, bottom] = rect.max();
+        let corners = [[x, y], [right, y], [right, bottom], [x, bottom]];
+        Rect::from_points(&corners.map(|corner| self.world_to_screen(corner)))
+    }
+
+    /// Returns true if world rect is at least partially visible.
+    pub fn is_visible(&self, bounds: &Rect) -> bool {
+        self.visible_rect().intersects(bounds)
     }
 
     pub fn offset(&self) -> Vec3 {
@@ -122,7 +350,13 @@ impl Camera {
 
     fn half_screen(&self) -> Vec3 {
         let [x, y, _] = self.scaling();
-        [self.screen.x() / x, self.screen.y() / y, 0.0].mul(0.5)
+        let [width, height] = self.content();
+        [width / x, height / y, 0.0].mul(0.5)
+    }
+
+    fn origin_translation(&self) -> Mat4 {
+        let [x, y] = self.origin;
+        mat4_from_translation([x, y, 0.0])
     }
 
     pub fn scaling(&self) -> Vec3 {
@@ -135,11 +369,28 @@ impl Camera {
 
     pub fn center2(&self) {}
 
+    /// Returns world transform, shake is applied to transform only, so eye and eye target
+    /// keep unaffected positions.
     pub fn get_transform(&self) -> Transform {
-        let model = mat4_mul(
+        let [x, y] = self.shake.offset();
+        let mut model = mat4_mul(
             mat4_from_scale(self.scaling()),
-            mat4_from_translation(self.offset()),
+            mat4_from_translation(self.offset().add([x, y, 0.0])),
         );
+        let angle = self.shake.angle();
+        if angle != 0.0 {
+            let [width, height] = self.content();
+            let center = [width, height, 0.0].mul(0.5);
+            let rotation = mat4_mul(
+                mat4_from_translation(center),
+                mat4_mul(
+                    mat4_from_rotation_z(angle),
+                    mat4_from_translation(center.neg()),
+                ),
+            );
+            model = mat4_mul(rotation, model);
+        }
+        let model = mat4_mul(self.origin_translation(), model);
         Transform {
             model,
             view: self.view,
@@ -148,7 +399,10 @@ impl Camera {
     }
 
     pub fn get_screen_transform(&self) -> Transform {
-        let model = mat4_from_scale([self.resolution_scale, self.resolution_scale, 1.0]);
+        let model = mat4_mul(
+            self.origin_translation(),
+            mat4_from_scale([self.resolution_scale, self.resolution_scale, 1.0]),
+        );
         Transform {
             model,
             view: self.view,
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+/// Game time derived from frame time, can be scaled for slow motion or paused.
+///
+/// Advanced by [`crate::Graphics::capture_user_input`] and available as `input.clock`.
+/// Scaled time should drive simulation, animations and shader time uniforms,
+/// unscaled time should drive UI and debug controls which work while game is paused.
+#[derive(Debug, Clone)]
+pub struct Clock {
+    /// Multiplier of frame time, e.g. 0.2 for slow motion.
+    pub scale: f32,
+    pub paused: bool,
+    /// Maximum scaled delta in seconds, so simulation doesn't jump after long stall,
+    /// e.g. loading or window dragging.
+    pub max_delta: f32,
+    delta: f32,
+    unscaled_delta: f32,
+    total: f64,
+    unscaled_total: f64,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            paused: false,
+            max_delta: 0.25,
+            delta: 0.0,
+            unscaled_delta: 0.0,
+            total: 0.0,
+            unscaled_total: 0.0,
+        }
+    }
+}
+
+impl Clock {
+    pub(crate) fn tick(&mut self, time: Duration) {
+        self.unscaled_delta = time.as_secs_f32();
+        self.unscaled_total += time.as_secs_f64();
+        self.delta = if self.paused {
+            0.0
+        } else {
+            (self.unscaled_delta * self.scale).min(self.max_delta)
+        };
+        self.total += self.delta as f64;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Returns scaled frame time in seconds, zero while paused.
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+
+    /// Returns real frame time in seconds.
+    pub fn unscaled_delta(&self) -> f32 {
+        self.unscaled_delta
+    }
+
+    /// Returns scaled time in seconds since start.
+    pub fn total(&self) -> f32 {
+        self.total as f32
+    }
+
+    /// Returns real time in seconds since start.
+    pub fn unscaled_total(&self) -> f32 {
+        self.unscaled_total as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Clock;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_scaled_and_paused_time() {
+        let mut clock = Clock::default();
+        clock.scale = 0.5;
+        clock.tick(Duration::from_millis(100));
+        clock.pause();
+        clock.tick(Duration::from_millis(100));
+        assert_eq!(clock.delta(), 0.0);
+        assert!((clock.unscaled_delta() - 0.1).abs() < 1e-6);
+        assert!((clock.total() - 0.05).abs() < 1e-6);
+        assert!((clock.unscaled_total() - 0.2).abs() < 1e-6);
+        clock.resume();
+        clock.tick(Duration::from_secs(2));
+        assert_eq!(clock.delta(), clock.max_delta);
+    }
+}
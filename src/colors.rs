@@ -1,4 +1,4 @@
-use crate::math::Vec4;
+use crate::math::{lerp, Vec2, Vec4, VecArith, VecMagnitude};
 
 pub trait Colors {
     fn to_vec4(&self) -> Vec4;
@@ -57,7 +57,461 @@ impl Colors for &str {
                     a as f32 / 255.0,
                 ]
             }
-            _ => [1.0, 1.0, 1.0, 1.0],
+            value => parse_color_function(value)
+                .or_else(|| get_named_color(value))
+                .unwrap_or([1.0, 1.0, 1.0, 1.0]),
         }
     }
 }
+
+/// Conversions of RGBA colors with components in range 0.0 to 1.0.
+pub trait ColorConversions {
+    /// Returns `[hue, saturation, value, alpha]`, hue in degrees.
+    fn to_hsv(&self) -> Vec4;
+
+    /// Returns `[hue, saturation, lightness, alpha]`, hue in degrees.
+    fn to_hsl(&self) -> Vec4;
+
+    /// Increases HSL lightness by amount, e.g. 0.1 for hovered button.
+    fn lighten(&self, amount: f32) -> Vec4;
+
+    fn darken(&self, amount: f32) -> Vec4;
+
+    /// Multiplies color by alpha for premultiplied alpha blending.
+    fn premultiply(&self) -> Vec4;
+
+    /// Decodes sRGB color to linear for sRGB-correct rendering, alpha is kept.
+    fn to_linear(&self) -> Vec4;
+
+    /// Encodes linear color to sRGB, alpha is kept.
+    fn to_srgb(&self) -> Vec4;
+}
+
+impl ColorConversions for Vec4 {
+    fn to_hsv(&self) -> Vec4 {
+        let [r, g, b, a] = *self;
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+        [hue, saturation, max, a]
+    }
+
+    fn to_hsl(&self) -> Vec4 {
+        let [hue, saturation, value, a] = self.to_hsv();
+        let lightness = value * (1.0 - saturation / 2.0);
+        let range = lightness.min(1.0 - lightness);
+        let saturation = if range == 0.0 {
+            0.0
+        } else {
+            (value - lightness) / range
+        };
+        [hue, saturation, lightness, a]
+    }
+
+    fn lighten(&self, amount: f32) -> Vec4 {
+        let [hue, saturation, lightness, a] = self.to_hsl();
+        hsl(hue, saturation, (lightness + amount).clamp(0.0, 1.0), a)
+    }
+
+    fn darken(&self, amount: f32) -> Vec4 {
+        self.lighten(-amount)
+    }
+
+    fn premultiply(&self) -> Vec4 {
+        let [r, g, b, a] = *self;
+        [r * a, g * a, b * a, a]
+    }
+
+    fn to_linear(&self) -> Vec4 {
+        let decode = |value: f32| {
+            if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let [r, g, b, a] = *self;
+        [decode(r), decode(g), decode(b), a]
+    }
+
+    fn to_srgb(&self) -> Vec4 {
+        let encode = |value: f32| {
+            if value <= 0.0031308 {
+                value * 12.92
+            } else {
+                1.055 * value.powf(1.0 / 2.4) - 0.055
+            }
+        };
+        let [r, g, b, a] = *self;
+        [encode(r), encode(g), encode(b), a]
+    }
+}
+
+/// Returns color of hue in degrees, saturation and value in range 0.0 to 1.0.
+pub fn hsv(hue: f32, saturation: f32, value: f32, alpha: f32) -> Vec4 {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let [r, g, b] = match sector as u32 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+    let m = value - chroma;
+    [r + m, g + m, b + m, alpha]
+}
+
+/// Returns color of hue in degrees, saturation and lightness in range 0.0 to 1.0.
+pub fn hsl(hue: f32, saturation: f32, lightness: f32, alpha: f32) -> Vec4 {
+    let value = lightness + saturation * lightness.min(1.0 - lightness);
+    let saturation = if value == 0.0 {
+        0.0
+    } else {
+        2.0 * (1.0 - lightness / value)
+    };
+    hsv(hue, saturation, value, alpha)
+}
+
+/// Parses `rgb()`, `rgba()`, `hsl()` and `hsla()` notations with comma or space
+/// separated components, e.g. `rgba(255, 128, 0, 0.5)` or `hsl(30deg 100% 50% / 50%)`.
+fn parse_color_function(value: &str) -> Option<Vec4> {
+    let (name, args) = value.trim().split_once('(')?;
+    let args: Vec<&str> = args
+        .strip_suffix(')')?
+        .split(|char: char| char == ',' || char == '/' || char.is_whitespace())
+        .filter(|arg| !arg.is_empty())
+        .collect();
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+    let alpha = match args.get(3) {
+        Some(alpha) => parse_color_component(alpha, 1.0)?,
+        None => 1.0,
+    };
+    match name.trim().to_ascii_lowercase().as_str() {
+        "rgb" | "rgba" => Some([
+            parse_color_component(args[0], 255.0)?,
+            parse_color_component(args[1], 255.0)?,
+            parse_color_component(args[2], 255.0)?,
+            alpha,
+        ]),
+        "hsl" | "hsla" => {
+            let hue = args[0].strip_suffix("deg").unwrap_or(args[0]);
+            Some(hsl(
+                hue.parse().ok()?,
+                parse_color_component(args[1], 100.0)?,
+                parse_color_component(args[2], 100.0)?,
+                alpha,
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Returns component normalized to range 0.0 to 1.0, percentages are relative to 100%.
+fn parse_color_component(value: &str, range: f32) -> Option<f32> {
+    let value = match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().ok()? / 100.0,
+        None => value.parse::<f32>().ok()? / range,
+    };
+    Some(value.clamp(0.0, 1.0))
+}
+
+/// Geometry gradient parameter is evaluated along.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientShape {
+    /// Parameter is projection of point on line from start (0.0) to end (1.0).
+    Linear { start: Vec2, end: Vec2 },
+    /// Parameter is distance from center (0.0) relative to radius (1.0).
+    Radial { center: Vec2, radius: f32 },
+}
+
+/// Color gradient shared by CPU-side vertex colors and renderers.
+///
+/// ```ignore
+/// let sky = Gradient::linear([0.0, 0.0], [0.0, 600.0])
+///     .stop(0.0, "midnightblue")
+///     .stop(1.0, "#ff8c00");
+/// mesh.add_polygon_with_gradient(&points, &sky);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    pub shape: GradientShape,
+    /// Color stops `(position, color)` sorted by position.
+    pub stops: Vec<(f32, Vec4)>,
+}
+
+impl Gradient {
+    pub fn linear(start: Vec2, end: Vec2) -> Self {
+        Self {
+            shape: GradientShape::Linear { start, end },
+            stops: vec![],
+        }
+    }
+
+    pub fn radial(center: Vec2, radius: f32) -> Self {
+        Self {
+            shape: GradientShape::Radial { center, radius },
+            stops: vec![],
+        }
+    }
+
+    /// Adds color stop, stops with equal position make hard edge in order of adding.
+    pub fn stop(mut self, position: f32, color: impl Colors) -> Self {
+        let index = self.stops.partition_point(|(stop, _)| *stop <= position);
+        self.stops.insert(index, (position, color.to_vec4()));
+        self
+    }
+
+    /// Returns color at parameter, clamped to colors of first and last stops.
+    pub fn sample(&self, t: f32) -> Vec4 {
+        let index = self.stops.partition_point(|(stop, _)| *stop <= t);
+        if index == 0 {
+            return self
+                .stops
+                .first()
+                .map(|(_, color)| *color)
+                .unwrap_or([0.0; 4]);
+        }
+        let (start, from) = self.stops[index - 1];
+        let (end, to) = match self.stops.get(index) {
+            Some(stop) => *stop,
+            None => return from,
+        };
+        let t = (t - start) / (end - start);
+        [0, 1, 2, 3].map(|channel| lerp(from[channel], to[channel], t))
+    }
+
+    /// Returns gradient parameter of point, not clamped.
+    pub fn parameter(&self, point: Vec2) -> f32 {
+        match self.shape {
+            GradientShape::Linear { start, end } => {
+                let direction = end.sub(start);
+                let length = direction.dot(direction);
+                if length == 0.0 {
+                    return 0.0;
+                }
+                point.sub(start).dot(direction) / length
+            }
+            GradientShape::Radial { center, radius } => {
+                if radius == 0.0 {
+                    return 0.0;
+                }
+                point.sub(center).magnitude() / radius
+            }
+        }
+    }
+
+    pub fn color_at(&self, point: Vec2) -> Vec4 {
+        self.sample(self.parameter(point))
+    }
+}
+
+fn get_named_color(name: &str) -> Option<Vec4> {
+    let name = name.to_ascii_lowercase();
+    let index = NAMED_COLORS
+        .binary_search_by(|(key, _)| (*key).cmp(name.as_str()))
+        .ok()?;
+    let [r, g, b] = NAMED_COLORS[index].1;
+    Some([r, g, b, 255].to_vec4())
+}
+
+/// CSS named colors sorted by name.
+const NAMED_COLORS: [(&str, [u8; 3]); 148] = [
+    ("aliceblue", [0xf0, 0xf8, 0xff]),
+    ("antiquewhite", [0xfa, 0xeb, 0xd7]),
+    ("aqua", [0x00, 0xff, 0xff]),
+    ("aquamarine", [0x7f, 0xff, 0xd4]),
+    ("azure", [0xf0, 0xff, 0xff]),
+    ("beige", [0xf5, 0xf5, 0xdc]),
+    ("bisque", [0xff, 0xe4, 0xc4]),
+    ("black", [0x00, 0x00, 0x00]),
+    ("blanchedalmond", [0xff, 0xeb, 0xcd]),
+    ("blue", [0x00, 0x00, 0xff]),
+    ("blueviolet", [0x8a, 0x2b, 0xe2]),
+    ("brown", [0xa5, 0x2a, 0x2a]),
+    ("burlywood", [0xde, 0xb8, 0x87]),
+    ("cadetblue", [0x5f, 0x9e, 0xa0]),
+    ("chartreuse", [0x7f, 0xff, 0x00]),
+    ("chocolate", [0xd2, 0x69, 0x1e]),
+    ("coral", [0xff, 0x7f, 0x50]),
+    ("cornflowerblue", [0x64, 0x95, 0xed]),
+    ("cornsilk", [0xff, 0xf8, 0xdc]),
+    ("crimson", [0xdc, 0x14, 0x3c]),
+    ("cyan", [0x00, 0xff, 0xff]),
+    ("darkblue", [0x00, 0x00, 0x8b]),
+    ("darkcyan", [0x00, 0x8b, 0x8b]),
+    ("darkgoldenrod", [0xb8, 0x86, 0x0b]),
+    ("darkgray", [0xa9, 0xa9, 0xa9]),
+    ("darkgreen", [0x00, 0x64, 0x00]),
+    ("darkgrey", [0xa9, 0xa9, 0xa9]),
+    ("darkkhaki", [0xbd, 0xb7, 0x6b]),
+    ("darkmagenta", [0x8b, 0x00, 0x8b]),
+    ("darkolivegreen", [0x55, 0x6b, 0x2f]),
+    ("darkorange", [0xff, 0x8c, 0x00]),
+    ("darkorchid", [0x99, 0x32, 0xcc]),
+    ("darkred", [0x8b, 0x00, 0x00]),
+    ("darksalmon", [0xe9, 0x96, 0x7a]),
+    ("darkseagreen", [0x8f, 0xbc, 0x8f]),
+    ("darkslateblue", [0x48, 0x3d, 0x8b]),
+    ("darkslategray", [0x2f, 0x4f, 0x4f]),
+    ("darkslategrey", [0x2f, 0x4f, 0x4f]),
+    ("darkturquoise", [0x00, 0xce, 0xd1]),
+    ("darkviolet", [0x94, 0x00, 0xd3]),
+    ("deeppink", [0xff, 0x14, 0x93]),
+    ("deepskyblue", [0x00, 0xbf, 0xff]),
+    ("dimgray", [0x69, 0x69, 0x69]),
+    ("dimgrey", [0x69, 0x69, 0x69]),
+    ("dodgerblue", [0x1e, 0x90, 0xff]),
+    ("firebrick", [0xb2, 0x22, 0x22]),
+    ("floralwhite", [0xff, 0xfa, 0xf0]),
+    ("forestgreen", [0x22, 0x8b, 0x22]),
+    ("fuchsia", [0xff, 0x00, 0xff]),
+    ("gainsboro", [0xdc, 0xdc, 0xdc]),
+    ("ghostwhite", [0xf8, 0xf8, 0xff]),
+    ("gold", [0xff, 0xd7, 0x00]),
+    ("goldenrod", [0xda, 0xa5, 0x20]),
+    ("gray", [0x80, 0x80, 0x80]),
+    ("green", [0x00, 0x80, 0x00]),
+    ("greenyellow", [0xad, 0xff, 0x2f]),
+    ("grey", [0x80, 0x80, 0x80]),
+    ("honeydew", [0xf0, 0xff, 0xf0]),
+    ("hotpink", [0xff, 0x69, 0xb4]),
+    ("indianred", [0xcd, 0x5c, 0x5c]),
+    ("indigo", [0x4b, 0x00, 0x82]),
+    ("ivory", [0xff, 0xff, 0xf0]),
+    ("khaki", [0xf0, 0xe6, 0x8c]),
+    ("lavender", [0xe6, 0xe6, 0xfa]),
+    ("lavenderblush", [0xff, 0xf0, 0xf5]),
+    ("lawngreen", [0x7c, 0xfc, 0x00]),
+    ("lemonchiffon", [0xff, 0xfa, 0xcd]),
+    ("lightblue", [0xad, 0xd8, 0xe6]),
+    ("lightcoral", [0xf0, 0x80, 0x80]),
+    ("lightcyan", [0xe0, 0xff, 0xff]),
+    ("lightgoldenrodyellow", [0xfa, 0xfa, 0xd2]),
+    ("lightgray", [0xd3, 0xd3, 0xd3]),
+    ("lightgreen", [0x90, 0xee, 0x90]),
+    ("lightgrey", [0xd3, 0xd3, 0xd3]),
+    ("lightpink", [0xff, 0xb6, 0xc1]),
+    ("lightsalmon", [0xff, 0xa0, 0x7a]),
+    ("lightseagreen", [0x20, 0xb2, 0xaa]),
+    ("lightskyblue", [0x87, 0xce, 0xfa]),
+    ("lightslategray", [0x77, 0x88, 0x99]),
+    ("lightslategrey", [0x77, 0x88, 0x99]),
+    ("lightsteelblue", [0xb0, 0xc4, 0xde]),
+    ("lightyellow", [0xff, 0xff, 0xe0]),
+    ("lime", [0x00, 0xff, 0x00]),
+    ("limegreen", [0x32, 0xcd, 0x32]),
+    ("linen", [0xfa, 0xf0, 0xe6]),
+    ("magenta", [0xff, 0x00, 0xff]),
+    ("maroon", [0x80, 0x00, 0x00]),
+    ("mediumaquamarine", [0x66, 0xcd, 0xaa]),
+    ("mediumblue", [0x00, 0x00, 0xcd]),
+    ("mediumorchid", [0xba, 0x55, 0xd3]),
+    ("mediumpurple", [0x93, 0x70, 0xdb]),
+    ("mediumseagreen", [0x3c, 0xb3, 0x71]),
+    ("mediumslateblue", [0x7b, 0x68, 0xee]),
+    ("mediumspringgreen", [0x00, 0xfa, 0x9a]),
+    ("mediumturquoise", [0x48, 0xd1, 0xcc]),
+    ("mediumvioletred", [0xc7, 0x15, 0x85]),
+    ("midnightblue", [0x19, 0x19, 0x70]),
+    ("mintcream", [0xf5, 0xff, 0xfa]),
+    ("mistyrose", [0xff, 0xe4, 0xe1]),
+    ("moccasin", [0xff, 0xe4, 0xb5]),
+    ("navajowhite", [0xff, 0xde, 0xad]),
+    ("navy", [0x00, 0x00, 0x80]),
+    ("oldlace", [0xfd, 0xf5, 0xe6]),
+    ("olive", [0x80, 0x80, 0x00]),
+    ("olivedrab", [0x6b, 0x8e, 0x23]),
+    ("orange", [0xff, 0xa5, 0x00]),
+    ("orangered", [0xff, 0x45, 0x00]),
+    ("orchid", [0xda, 0x70, 0xd6]),
+    ("palegoldenrod", [0xee, 0xe8, 0xaa]),
+    ("palegreen", [0x98, 0xfb, 0x98]),
+    ("paleturquoise", [0xaf, 0xee, 0xee]),
+    ("palevioletred", [0xdb, 0x70, 0x93]),
+    ("papayawhip", [0xff, 0xef, 0xd5]),
+    ("peachpuff", [0xff, 0xda, 0xb9]),
+    ("peru", [0xcd, 0x85, 0x3f]),
+    ("pink", [0xff, 0xc0, 0xcb]),
+    ("plum", [0xdd, 0xa0, 0xdd]),
+    ("powderblue", [0xb0, 0xe0, 0xe6]),
+    ("purple", [0x80, 0x00, 0x80]),
+    ("rebeccapurple", [0x66, 0x33, 0x99]),
+    ("red", [0xff, 0x00, 0x00]),
+    ("rosybrown", [0xbc, 0x8f, 0x8f]),
+    ("royalblue", [0x41, 0x69, 0xe1]),
+    ("saddlebrown", [0x8b, 0x45, 0x13]),
+    ("salmon", [0xfa, 0x80, 0x72]),
+    ("sandybrown", [0xf4, 0xa4, 0x60]),
+    ("seagreen", [0x2e, 0x8b, 0x57]),
+    ("seashell", [0xff, 0xf5, 0xee]),
+    ("sienna", [0xa0, 0x52, 0x2d]),
+    ("silver", [0xc0, 0xc0, 0xc0]),
+    ("skyblue", [0x87, 0xce, 0xeb]),
+    ("slateblue", [0x6a, 0x5a, 0xcd]),
+    ("slategray", [0x70, 0x80, 0x90]),
+    ("slategrey", [0x70, 0x80, 0x90]),
+    ("snow", [0xff, 0xfa, 0xfa]),
+    ("springgreen", [0x00, 0xff, 0x7f]),
+    ("steelblue", [0x46, 0x82, 0xb4]),
+    ("tan", [0xd2, 0xb4, 0x8c]),
+    ("teal", [0x00, 0x80, 0x80]),
+    ("thistle", [0xd8, 0xbf, 0xd8]),
+    ("tomato", [0xff, 0x63, 0x47]),
+    ("turquoise", [0x40, 0xe0, 0xd0]),
+    ("violet", [0xee, 0x82, 0xee]),
+    ("wheat", [0xf5, 0xde, 0xb3]),
+    ("white", [0xff, 0xff, 0xff]),
+    ("whitesmoke", [0xf5, 0xf5, 0xf5]),
+    ("yellow", [0xff, 0xff, 0x00]),
+    ("yellowgreen", [0x9a, 0xcd, 0x32]),
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::{hsv, ColorConversions, Colors, Gradient};
+
+    #[test]
+    pub fn test_css_color_parsing() {
+        assert_eq!(
+            "rebeccapurple".to_vec4(),
+            [0x66, 0x33, 0x99, 0xff].to_vec4()
+        );
+        assert_eq!("rgb(255, 0, 51)".to_vec4(), [1.0, 0.0, 0.2, 1.0]);
+        assert_eq!("rgba(0 100% 0 / 50%)".to_vec4(), [0.0, 1.0, 0.0, 0.5]);
+        assert_eq!("hsl(120deg, 100%, 25%)".to_vec4(), [0.0, 0.5, 0.0, 1.0]);
+        let color = [0.2, 0.4, 0.6, 1.0];
+        let [h, s, v, a] = color.to_hsv();
+        let restored = hsv(h, s, v, a);
+        assert!(color
+            .iter()
+            .zip(restored)
+            .all(|(a, b)| (a - b).abs() < 1e-5));
+        assert_eq!([0.0, 0.5, 0.0, 1.0].lighten(0.25), [0.0, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    pub fn test_gradient_sampling() {
+        let gradient = Gradient::linear([0.0, 0.0], [0.0, 100.0])
+            .stop(1.0, [0.0, 0.0, 1.0, 1.0])
+            .stop(0.0, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(gradient.color_at([50.0, 25.0]), [0.75, 0.0, 0.25, 1.0]);
+        assert_eq!(gradient.color_at([0.0, -10.0]), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(gradient.color_at([0.0, 200.0]), [0.0, 0.0, 1.0, 1.0]);
+        let radial = Gradient::radial([10.0, 10.0], 10.0).stop(0.5, "white");
+        assert_eq!(radial.color_at([13.0, 14.0]), [1.0; 4]);
+    }
+}
@@ -1,3 +1,8 @@
+use log::{info, warn};
+use std::env;
+use std::str::FromStr;
+use vulkanalia::vk;
+
 #[derive(Clone, Debug, serde::Deserialize)]
 pub struct GraphicsConfig {
     #[serde(default = "default_title")]
@@ -6,12 +11,65 @@ pub struct GraphicsConfig {
     pub mode: GraphicsMode,
     #[serde(default = "default_resolution")]
     pub resolution: [u32; 2],
+    /// Window position relative to top left corner of display, centered if not set.
     #[serde(default)]
     pub position: Option<[i32; 2]>,
+    #[serde(default)]
+    pub display: GraphicsDisplay,
     #[serde(default = "default_vsync")]
     pub vsync: bool,
+    /// Overrides present mode derived from vsync, see [`PresentMode`].
+    /// Explicit present mode wins, vsync is ignored if both are set.
+    #[serde(default)]
+    pub present_mode: Option<PresentMode>,
     #[serde(default)]
     pub fonts: FontsConfig,
+    #[serde(default)]
+    pub redraw: RedrawMode,
+    /// Maximum frames per second, e.g. for menus or IMMEDIATE present mode,
+    /// frame rate is limited by present mode only if not set.
+    #[serde(default)]
+    pub fps_limit: Option<f32>,
+    /// Shows [`crate::renderers::StatsOverlay`] on start, overlay is toggled by F3 anyway.
+    #[serde(default)]
+    pub stats_overlay: bool,
+    /// Directories watched for changed PNG and TTF files, e.g. `["./assets"]`.
+    /// Changed textures and fonts are reloaded in place, nothing is watched if empty.
+    #[serde(default)]
+    pub watch_assets: Vec<String>,
+    /// Recreates programs when shader files change, enabled in debug builds by default.
+    /// Every frame checks modification time of all shader files while enabled.
+    #[serde(default = "default_hot_reload_shaders")]
+    pub hot_reload_shaders: bool,
+    /// Samples per pixel of color and depth attachments, 1 disables multisample anti-aliasing.
+    /// Clamped to highest count supported by device.
+    #[serde(default = "default_msaa_samples")]
+    pub msaa_samples: u32,
+    /// Filtering of samplers created by [`crate::Graphics::create_default_sampler`].
+    #[serde(default)]
+    pub filtering: TextureFiltering,
+    /// Capacity of every bindless texture array, e.g. raised for asset-heavy games.
+    /// Clamped to descriptor indexing limits of device.
+    #[serde(default = "default_max_textures")]
+    pub max_textures: u32,
+    /// Uses SRGB formats for swapchain and textures, so blending and gradients are computed
+    /// in linear space. Clear colors are converted to linear, vertex and shader colors
+    /// must be linear, see [`crate::ColorConversions::to_linear`].
+    ///
+    /// Textures with non-color data, e.g. normal maps, LUTs and MSDF fonts, are decoded
+    /// as sRGB too and must be compensated in shaders.
+    #[serde(default)]
+    pub srgb: bool,
+    /// Multiplier of final colors applied by [`crate::renderers::DisplayAdjustment`],
+    /// player adjustable with gamma, see [`crate::Graphics::set_brightness`].
+    #[serde(default = "default_display_adjustment")]
+    pub brightness: f32,
+    /// Exponent divisor of final colors, values above 1.0 brighten dark tones.
+    #[serde(default = "default_display_adjustment")]
+    pub gamma: f32,
+    /// Enables Vulkan validation layer, also enabled by `VULKAN_DEBUG` environment variable.
+    #[serde(default)]
+    pub validation: bool,
 }
 
 impl Default for GraphicsConfig {
@@ -21,13 +79,42 @@ impl Default for GraphicsConfig {
             mode: GraphicsMode::default(),
             resolution: default_resolution(),
             position: None,
+            display: GraphicsDisplay::default(),
             vsync: default_vsync(),
+            present_mode: None,
             fonts: FontsConfig::default(),
+            redraw: RedrawMode::default(),
+            fps_limit: None,
+            stats_overlay: false,
+            watch_assets: vec![],
+            hot_reload_shaders: default_hot_reload_shaders(),
+            msaa_samples: default_msaa_samples(),
+            filtering: TextureFiltering::default(),
+            max_textures: default_max_textures(),
+            srgb: false,
+            brightness: default_display_adjustment(),
+            gamma: default_display_adjustment(),
+            validation: false,
         }
     }
 }
 
 impl GraphicsConfig {
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    pub fn mode(mut self, mode: GraphicsMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn validation(mut self, enabled: bool) -> Self {
+        self.validation = enabled;
+        self
+    }
+
     pub fn fonts<F>(mut self, config: F) -> Self
     where
         F: FnOnce(FontsConfig) -> FontsConfig,
@@ -45,6 +132,244 @@ impl GraphicsConfig {
         self.position = Some(position);
         self
     }
+
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.vsync = vsync;
+        self
+    }
+
+    pub fn present_mode(mut self, mode: PresentMode) -> Self {
+        self.present_mode = Some(mode);
+        self
+    }
+
+    /// Returns explicit present mode or mode derived from vsync, FIFO if vsync enabled
+    /// and IMMEDIATE otherwise.
+    pub fn get_present_mode(&self) -> PresentMode {
+        match self.present_mode {
+            Some(mode) => mode,
+            None if self.vsync => PresentMode::Fifo,
+            None => PresentMode::Immediate,
+        }
+    }
+
+    pub fn msaa_samples(mut self, samples: u32) -> Self {
+        self.msaa_samples = samples;
+        self
+    }
+
+    pub fn filtering(mut self, filtering: TextureFiltering) -> Self {
+        self.filtering = filtering;
+        self
+    }
+
+    pub fn max_textures(mut self, max_textures: u32) -> Self {
+        self.max_textures = max_textures;
+        self
+    }
+
+    pub fn display(mut self, display: GraphicsDisplay) -> Self {
+        self.display = display;
+        self
+    }
+
+    pub fn redraw(mut self, redraw: RedrawMode) -> Self {
+        self.redraw = redraw;
+        self
+    }
+
+    pub fn fps_limit(mut self, fps: f32) -> Self {
+        self.fps_limit = Some(fps);
+        self
+    }
+
+    pub fn stats_overlay(mut self, enabled: bool) -> Self {
+        self.stats_overlay = enabled;
+        self
+    }
+
+    pub fn watch_assets(mut self, directory: &str) -> Self {
+        self.watch_assets.push(directory.to_string());
+        self
+    }
+
+    pub fn hot_reload_shaders(mut self, enabled: bool) -> Self {
+        self.hot_reload_shaders = enabled;
+        self
+    }
+
+    pub fn srgb(mut self, enabled: bool) -> Self {
+        self.srgb = enabled;
+        self
+    }
+
+    pub fn brightness(mut self, brightness: f32) -> Self {
+        self.brightness = brightness;
+        self
+    }
+
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    /// Overrides loaded config by `MOTORO_*` environment variables, so QA can test other
+    /// modes and resolutions without editing files. Applied by [`crate::Graphics::create`].
+    ///
+    /// | Variable            | Example                                          |
+    /// |---------------------|--------------------------------------------------|
+    /// | `MOTORO_TITLE`      | `Game (QA build)`                                |
+    /// | `MOTORO_MODE`       | `windowed`, `fullscreen`, `borderless`, `hidden` |
+    /// | `MOTORO_RESOLUTION` | `1280x720`                                       |
+    /// | `MOTORO_POSITION`   | `100,100`                                        |
+    /// | `MOTORO_DISPLAY`    | `1`, `DELL U2720Q`                               |
+    /// | `MOTORO_VSYNC`      | `true`, `false`, `1`, `0`                        |
+    /// | `MOTORO_PRESENT`    | `fifo`, `fifo_relaxed`, `mailbox`, `immediate`   |
+    /// | `MOTORO_REDRAW`     | `continuous`, `on_demand`                        |
+    /// | `MOTORO_MSAA`       | `1`, `4`                                         |
+    /// | `MOTORO_FPS_LIMIT`  | `30`, `144`                                      |
+    /// | `MOTORO_STATS`      | `true`, `false`, `1`, `0`                        |
+    /// | `MOTORO_HOT_RELOAD` | `true`, `false`, `1`, `0`                        |
+    /// | `MOTORO_SRGB`       | `true`, `false`, `1`, `0`                        |
+    /// | `MOTORO_BRIGHTNESS` | `1.2`                                            |
+    /// | `MOTORO_GAMMA`      | `2.2`                                            |
+    ///
+    /// Invalid values are logged and ignored. `MOTORO_VSYNC` has no effect if present mode
+    /// is set explicitly in config or by `MOTORO_PRESENT`, which is logged as warning.
+    pub fn env_overrides(self) -> Self {
+        self.overrides(|name| env::var(name).ok())
+    }
+
+    fn overrides(mut self, var: impl Fn(&str) -> Option<String>) -> Self {
+        fn apply<T>(
+            var: &impl Fn(&str) -> Option<String>,
+            name: &str,
+            parse: impl Fn(&str) -> Option<T>,
+            target: &mut T,
+        ) {
+            if let Some(value) = var(name) {
+                match parse(value.trim()) {
+                    Some(parsed) => {
+                        info!("Overrides config by {name}={value}");
+                        *target = parsed;
+                    }
+                    None => warn!("Unable to override config, invalid {name}={value}"),
+                }
+            }
+        }
+        apply(
+            &var,
+            "MOTORO_TITLE",
+            |value| Some(value.to_string()),
+            &mut self.title,
+        );
+        apply(&var, "MOTORO_MODE", parse_mode, &mut self.mode);
+        apply(
+            &var,
+            "MOTORO_RESOLUTION",
+            |value| parse_pair(value, 'x'),
+            &mut self.resolution,
+        );
+        apply(
+            &var,
+            "MOTORO_POSITION",
+            |value| parse_pair(value, ',').map(Some),
+            &mut self.position,
+        );
+        apply(&var, "MOTORO_DISPLAY", parse_display, &mut self.display);
+        apply(&var, "MOTORO_VSYNC", parse_bool, &mut self.vsync);
+        apply(
+            &var,
+            "MOTORO_PRESENT",
+            |value| parse_present_mode(value).map(Some),
+            &mut self.present_mode,
+        );
+        if let (Some(_), Some(mode)) = (var("MOTORO_VSYNC"), self.present_mode) {
+            warn!("Ignores MOTORO_VSYNC, present mode is set explicitly to {mode:?}");
+        }
+        apply(&var, "MOTORO_REDRAW", parse_redraw, &mut self.redraw);
+        apply(
+            &var,
+            "MOTORO_MSAA",
+            |value| value.parse().ok(),
+            &mut self.msaa_samples,
+        );
+        apply(
+            &var,
+            "MOTORO_FPS_LIMIT",
+            |value| value.parse().ok().map(Some),
+            &mut self.fps_limit,
+        );
+        apply(&var, "MOTORO_STATS", parse_bool, &mut self.stats_overlay);
+        apply(
+            &var,
+            "MOTORO_HOT_RELOAD",
+            parse_bool,
+            &mut self.hot_reload_shaders,
+        );
+        apply(&var, "MOTORO_SRGB", parse_bool, &mut self.srgb);
+        apply(
+            &var,
+            "MOTORO_BRIGHTNESS",
+            |value| value.parse().ok(),
+            &mut self.brightness,
+        );
+        apply(
+            &var,
+            "MOTORO_GAMMA",
+            |value| value.parse().ok(),
+            &mut self.gamma,
+        );
+        self
+    }
+}
+
+fn parse_pair<T: FromStr>(value: &str, separator: char) -> Option<[T; 2]> {
+    let (a, b) = value.split_once(separator)?;
+    Some([a.trim().parse().ok()?, b.trim().parse().ok()?])
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "1" | "on" | "yes" => Some(true),
+        "false" | "0" | "off" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_mode(value: &str) -> Option<GraphicsMode> {
+    match value.to_lowercase().as_str() {
+        "windowed" => Some(GraphicsMode::Windowed),
+        "fullscreen" => Some(GraphicsMode::Fullscreen),
+        "borderless" => Some(GraphicsMode::Borderless),
+        "hidden" => Some(GraphicsMode::Hidden),
+        _ => None,
+    }
+}
+
+fn parse_display(value: &str) -> Option<GraphicsDisplay> {
+    match value.parse() {
+        Ok(index) => Some(GraphicsDisplay::Index(index)),
+        Err(_) => Some(GraphicsDisplay::Name(value.to_string())),
+    }
+}
+
+fn parse_present_mode(value: &str) -> Option<PresentMode> {
+    match value.to_lowercase().as_str() {
+        "fifo" => Some(PresentMode::Fifo),
+        "fifo_relaxed" => Some(PresentMode::FifoRelaxed),
+        "mailbox" => Some(PresentMode::Mailbox),
+        "immediate" => Some(PresentMode::Immediate),
+        _ => None,
+    }
+}
+
+fn parse_redraw(value: &str) -> Option<RedrawMode> {
+    match value.to_lowercase().as_str() {
+        "continuous" => Some(RedrawMode::Continuous),
+        "on_demand" | "ondemand" => Some(RedrawMode::OnDemand),
+        _ => None,
+    }
 }
 
 #[derive(Clone, Copy, Debug, serde::Deserialize)]
@@ -52,6 +377,8 @@ pub enum GraphicsMode {
     Windowed,
     Fullscreen,
     Borderless,
+    /// Window is never shown, e.g. to render frames in automated tests.
+    Hidden,
 }
 
 impl Default for GraphicsMode {
@@ -60,6 +387,67 @@ impl Default for GraphicsMode {
     }
 }
 
+/// Determines how rendered frames are queued for display.
+///
+/// If mode is not supported by device, falls back to next mode without tearing:
+/// IMMEDIATE to MAILBOX, then any mode to FIFO which is always supported.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+pub enum PresentMode {
+    /// Waits for vertical blank, classic vsync without tearing.
+    Fifo,
+    /// Like FIFO, but presents late frame immediately, tearing instead of stutter.
+    FifoRelaxed,
+    /// Replaces queued frame by newer one, low latency without tearing.
+    Mailbox,
+    /// Presents immediately, lowest latency with tearing.
+    Immediate,
+}
+
+impl PresentMode {
+    pub(crate) fn to_vulkan(self) -> vk::PresentModeKHR {
+        match self {
+            PresentMode::Fifo => vk::PresentModeKHR::FIFO,
+            PresentMode::FifoRelaxed => vk::PresentModeKHR::FIFO_RELAXED,
+            PresentMode::Mailbox => vk::PresentModeKHR::MAILBOX,
+            PresentMode::Immediate => vk::PresentModeKHR::IMMEDIATE,
+        }
+    }
+}
+
+/// Selects monitor to open window on, e.g. `"display": 1` or `"display": "DELL"` in config.
+///
+/// Falls back to first display if there is no display with given index or name.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+#[serde(untagged)]
+pub enum GraphicsDisplay {
+    /// Display index as enumerated by SDL, 0 is primary display.
+    Index(i32),
+    /// Case-insensitive part of display name.
+    Name(String),
+}
+
+impl Default for GraphicsDisplay {
+    fn default() -> Self {
+        Self::Index(0)
+    }
+}
+
+/// Determines when application loop proceeds to next frame.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+pub enum RedrawMode {
+    /// Renders frames continuously, game-style loop.
+    Continuous,
+    /// Renders frame only when input or window event occurs, texture is loaded
+    /// or redraw is requested, so editor-like applications don't load GPU at idle.
+    OnDemand,
+}
+
+impl Default for RedrawMode {
+    fn default() -> Self {
+        Self::Continuous
+    }
+}
+
 fn default_title() -> String {
     "motoro".to_string()
 }
@@ -72,12 +460,41 @@ fn default_resolution() -> [u32; 2] {
     [1920, 1080]
 }
 
+fn default_hot_reload_shaders() -> bool {
+    cfg!(debug_assertions)
+}
+
+fn default_display_adjustment() -> f32 {
+    1.0
+}
+
+fn default_msaa_samples() -> u32 {
+    1
+}
+
+fn default_max_textures() -> u32 {
+    256
+}
+
+/// Default texture filtering, e.g. nearest for pixel-art and anisotropic for HD projects.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize)]
+pub enum TextureFiltering {
+    #[default]
+    Nearest,
+    Linear,
+    /// Linear filtering with anisotropy level, e.g. 16.0, keeps textures viewed
+    /// at steep angles sharp.
+    Anisotropic(f32),
+}
+
 #[derive(Clone, Debug, serde::Deserialize)]
 pub struct FontsConfig {
     #[serde(default = "default_fonts_cache")]
     pub cache: String,
     #[serde(default)]
     pub resolution_reference: Option<[u32; 2]>,
+    #[serde(default)]
+    pub msdf: bool,
 }
 
 impl Default for FontsConfig {
@@ -85,6 +502,7 @@ impl Default for FontsConfig {
         Self {
             cache: default_fonts_cache(),
             resolution_reference: None,
+            msdf: false,
         }
     }
 }
@@ -99,8 +517,36 @@ impl FontsConfig {
         self.resolution_reference = Some(resolution);
         self
     }
+
+    /// Enables multi-channel signed distance field atlases, so text stays crisp at any scale.
+    pub fn msdf(mut self, enabled: bool) -> Self {
+        self.msdf = enabled;
+        self
+    }
 }
 
 fn default_fonts_cache() -> String {
     "./assets/cache/fonts".to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{GraphicsConfig, GraphicsMode};
+
+    #[test]
+    pub fn test_environment_overrides_loaded_config() {
+        let vars = |name: &str| match name {
+            "MOTORO_MODE" => Some("Fullscreen".to_string()),
+            "MOTORO_RESOLUTION" => Some("1280x720".to_string()),
+            "MOTORO_VSYNC" => Some("0".to_string()),
+            "MOTORO_POSITION" => Some("invalid".to_string()),
+            _ => None,
+        };
+        let config = GraphicsConfig::default().position([10, 20]).overrides(vars);
+        assert!(matches!(config.mode, GraphicsMode::Fullscreen));
+        assert_eq!(config.resolution, [1280, 720]);
+        assert!(!config.vsync);
+        assert_eq!(config.position, Some([10, 20]));
+        assert_eq!(config.title, "motoro");
+    }
+}
@@ -1,5 +1,22 @@
+/// Display dpi platforms treat as 100% scale.
+pub const DEFAULT_DPI: f32 = 96.0;
+
+/// Returns drawable pixels per logical point of user interface.
+///
+/// Platforms scaling window coordinates (macOS, Wayland, Windows with per monitor
+/// awareness) report it as pixel ratio, otherwise window is in pixels, e.g. on X11,
+/// and scale is estimated by display dpi rounded to quarter steps.
+pub fn dpi_scale(pixel_ratio: f32, display_dpi: f32) -> f32 {
+    if pixel_ratio > 1.0 || cfg!(target_os = "macos") {
+        return pixel_ratio;
+    }
+    let scale = (display_dpi / DEFAULT_DPI * 4.0).round() / 4.0;
+    scale.max(1.0)
+}
+
 #[cfg(target_os = "windows")]
 pub mod native {
+    use log::info;
 
     #[link(name = "user32")]
     extern "C" {
@@ -8,13 +25,39 @@ pub mod native {
     }
 
     pub fn setup_process_dpi() {
-        unsafe {
-            SetProcessDPIAware();
+        let version = sdl2::version::version();
+        if (version.major, version.minor) >= (2, 24) {
+            info!("Enables per monitor DPI awareness");
+            // window stays in scaled coordinates while drawable size is in pixels
+            sdl2::hint::set("SDL_WINDOWS_DPI_AWARENESS", "permonitorv2");
+            sdl2::hint::set("SDL_WINDOWS_DPI_SCALING", "1");
+        } else {
+            unsafe {
+                SetProcessDPIAware();
+            }
         }
     }
 }
 
 #[cfg(not(target_os = "windows"))]
 pub mod native {
+    /// Scaling of macOS and Wayland is enabled by high DPI window.
     pub fn setup_process_dpi() {}
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::dpi::dpi_scale;
+
+    #[test]
+    pub fn test_dpi_scale_of_scaled_and_pixel_windows() {
+        assert_eq!(dpi_scale(2.0, 220.0), 2.0);
+        if cfg!(target_os = "macos") {
+            return;
+        }
+        assert_eq!(dpi_scale(1.0, 96.0), 1.0);
+        assert_eq!(dpi_scale(1.0, 144.0), 1.5);
+        assert_eq!(dpi_scale(1.0, 160.0), 1.75);
+        assert_eq!(dpi_scale(1.0, 72.0), 1.0);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::math::lerp;
+use std::f32::consts::PI;
+
+/// Easing curves mapping linear progress in range 0.0 to 1.0 to eased progress,
+/// see [easings.net](https://easings.net) for curve shapes.
+///
+/// Back and elastic curves overshoot range, so eased values go beyond tween bounds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => in_out(t, |t| t * t),
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => in_out(t, |t| t * t * t),
+            Easing::ExpoIn => expo_in(t),
+            Easing::ExpoOut => 1.0 - expo_in(1.0 - t),
+            Easing::ExpoInOut => in_out(t, expo_in),
+            Easing::BackIn => back_in(t),
+            Easing::BackOut => 1.0 - back_in(1.0 - t),
+            Easing::BackInOut => in_out(t, back_in),
+            Easing::ElasticIn => elastic_in(t),
+            Easing::ElasticOut => 1.0 - elastic_in(1.0 - t),
+            Easing::ElasticInOut => in_out(t, elastic_in),
+        }
+    }
+}
+
+/// Combines ease in curve for first half of progress and mirrored one for second half.
+fn in_out(t: f32, ease_in: impl Fn(f32) -> f32) -> f32 {
+    if t < 0.5 {
+        ease_in(t * 2.0) / 2.0
+    } else {
+        1.0 - ease_in((1.0 - t) * 2.0) / 2.0
+    }
+}
+
+fn expo_in(t: f32) -> f32 {
+    if t == 0.0 {
+        0.0
+    } else {
+        2.0f32.powf(10.0 * t - 10.0)
+    }
+}
+
+fn back_in(t: f32) -> f32 {
+    const OVERSHOOT: f32 = 1.70158;
+    (OVERSHOOT + 1.0) * t * t * t - OVERSHOOT * t * t
+}
+
+fn elastic_in(t: f32) -> f32 {
+    if t == 0.0 || t == 1.0 {
+        return t;
+    }
+    let period = 2.0 * PI / 3.0;
+    -(2.0f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * period).sin()
+}
+
+/// Value which can be interpolated by [`Tween`], e.g. zoom, position or color.
+pub trait Tweenable: Copy {
+    fn interpolate(self, to: Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn interpolate(self, to: Self, t: f32) -> Self {
+        lerp(self, to, t)
+    }
+}
+
+impl<const N: usize> Tweenable for [f32; N] {
+    fn interpolate(self, to: Self, t: f32) -> Self {
+        let mut result = self;
+        for i in 0..N {
+            result[i] = self[i].interpolate(to[i], t);
+        }
+        result
+    }
+}
+
+/// Animates value from start to end over duration in seconds by easing curve,
+/// e.g. camera zoom, UI slide or color fade.
+#[derive(Clone, Copy, Debug)]
+pub struct Tween<T: Tweenable> {
+    pub from: T,
+    pub to: T,
+    pub duration: f32,
+    pub easing: Easing,
+    elapsed: f32,
+}
+
+impl<T: Tweenable> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing: Easing::default(),
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Advances tween by frame time in seconds, returns current value.
+    pub fn update(&mut self, time: f32) -> T {
+        self.elapsed = (self.elapsed + time).min(self.duration);
+        self.value()
+    }
+
+    pub fn value(&self) -> T {
+        self.from
+            .interpolate(self.to, self.easing.apply(self.progress()))
+    }
+
+    /// Returns linear progress in range 0.0 to 1.0.
+    pub fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            1.0
+        } else {
+            self.elapsed / self.duration
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    /// Restarts tween from current value to new target, so animation retargeted
+    /// mid-way continues without jump.
+    pub fn retarget(&mut self, to: T, duration: f32) {
+        self.from = self.value();
+        self.to = to;
+        self.duration = duration;
+        self.elapsed = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::easing::{Easing, Tween};
+
+    #[test]
+    pub fn test_easings_start_at_zero_and_end_at_one() {
+        let easings = [
+            Easing::Linear,
+            Easing::QuadIn,
+            Easing::QuadOut,
+            Easing::QuadInOut,
+            Easing::CubicIn,
+            Easing::CubicOut,
+            Easing::CubicInOut,
+            Easing::ExpoIn,
+            Easing::ExpoOut,
+            Easing::ExpoInOut,
+            Easing::BackIn,
+            Easing::BackOut,
+            Easing::BackInOut,
+            Easing::ElasticIn,
+            Easing::ElasticOut,
+            Easing::ElasticInOut,
+        ];
+        for easing in easings {
+            assert!(easing.apply(0.0).abs() < 1e-3, "{easing:?} start");
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-3, "{easing:?} end");
+        }
+    }
+
+    #[test]
+    pub fn test_tween_reaches_target_color() {
+        let mut tween =
+            Tween::new([0.0, 0.0, 0.0, 1.0], [1.0, 0.5, 0.0, 1.0], 0.5).easing(Easing::CubicOut);
+        tween.update(0.25);
+        assert!(!tween.finished());
+        assert_eq!(tween.update(0.5), [1.0, 0.5, 0.0, 1.0]);
+        assert!(tween.finished());
+    }
+}
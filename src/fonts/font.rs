@@ -1,5 +1,6 @@
-use crate::math::Vec2;
-use fontdue::layout::{CoordinateSystem, Layout, TextStyle};
+use crate::math::{Vec2, Vec4};
+use crate::{Colors, FontIndex};
+use fontdue::layout::{CoordinateSystem, GlyphPosition, Layout, TextStyle};
 pub use fontdue::layout::{HorizontalAlign, LayoutSettings, VerticalAlign};
 use std::collections::HashMap;
 use std::io;
@@ -13,6 +14,10 @@ pub struct Font {
     pub resolution_scale: f32,
     pub line_height: f32,
     pub baseline: f32,
+    /// Atlas pixels around glyph cell reserved for distance field falloff.
+    pub padding: f32,
+    /// Distance range in atlas pixels, set if atlas is multi-channel signed distance field.
+    pub distance_range: Option<f32>,
 }
 
 #[repr(C)]
@@ -31,22 +36,31 @@ impl Font {
     /// NOTE: Resolution scale must be applied to layout coordinates for better kerning and spacing
     /// calculations in font engine. Result glyph x and y coordinates different depends on
     /// TextStyle size and layout settings. You can't just scale atlas texture with font letters!
-    pub fn layout(&self, text: &str, mut settings: LayoutSettings) -> Vec<Char> {
+    pub fn layout(&self, text: &str, settings: impl Into<TextLayout>) -> Vec<Char> {
+        self.layout_truncated(text, settings).0
+    }
+
+    /// Lays out text like [`Font::layout`], also returns true if text is truncated
+    /// to max lines of layout.
+    pub fn layout_truncated(
+        &self,
+        text: &str,
+        settings: impl Into<TextLayout>,
+    ) -> (Vec<Char>, bool) {
+        let settings = settings.into();
         let scale = self.resolution_scale;
         let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
-        settings.max_width = settings.max_width.map(|width| width * scale);
-        settings.max_height = settings.max_height.map(|height| height * scale);
-        layout.reset(&settings);
-        let text = TextStyle::new(text, self.size, 0);
         let fonts = [&self.font];
-        layout.append(&fonts, &text);
+        let truncated = settings.layout(&mut layout, scale, |layout, cut| match cut {
+            None => layout.append(&fonts, &TextStyle::new(text, self.size, 0)),
+            Some(cut) => {
+                let text = ellipsize(text, cut);
+                layout.append(&fonts, &TextStyle::new(&text, self.size, 0));
+            }
+        });
         let mut draws = vec![];
-        for glyph in layout.glyphs() {
-            let mut draw = match self.charset.get(&glyph.parent) {
-                Some(char) => *char,
-                None => self.missing_char,
-            };
-            draw.position = [glyph.x / scale, (glyph.y - draw.glyph_offset) / scale].into();
+        for glyph in settings.aligned(&layout, &fonts, scale) {
+            let draw = self.place(&glyph);
             // let char = glyph.parent;
             // if char == '$' || char == '&' || char == ',' || char == '+' || char == 'j' {
             //     println!(
@@ -56,12 +70,319 @@ impl Font {
             // }
             draws.push(draw);
         }
-        draws
+        (draws, truncated)
+    }
+
+    /// Returns atlas char placed at glyph position produced by layout.
+    pub(crate) fn place<U: Copy>(&self, glyph: &GlyphPosition<U>) -> Char {
+        let scale = self.resolution_scale;
+        let mut draw = match self.charset.get(&glyph.parent) {
+            Some(char) => *char,
+            None => self.missing_char,
+        };
+        draw.position = [
+            (glyph.x - self.padding) / scale,
+            (glyph.y - draw.glyph_offset) / scale,
+        ]
+        .into();
+        draw
+    }
+}
+
+/// Horizontal alignment of text lines, extends [`HorizontalAlign`] with justification.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretches spaces so wrapped lines fill max width, last line of paragraph is left aligned.
+    Justify,
+}
+
+/// Describes text paragraph layout.
+///
+/// Lines are aligned within max width and max height if set, otherwise around text position.
+/// So centered label can be drawn at its center point without measuring text.
+#[derive(Clone, Copy, Default)]
+pub struct TextLayout {
+    pub settings: LayoutSettings,
+    pub align: TextAlign,
+    /// Text exceeding max lines is cut and ends with ellipsis.
+    pub max_lines: Option<usize>,
+    /// Extra space after every char (tracking), in pixels.
+    pub letter_spacing: f32,
+    /// Extra space after every space char, in pixels.
+    pub word_spacing: f32,
+    /// Applies kerning pairs of font, disabled by default.
+    pub kerning: bool,
+}
+
+impl From<LayoutSettings> for TextLayout {
+    fn from(settings: LayoutSettings) -> Self {
+        let align = match settings.horizontal_align {
+            HorizontalAlign::Left => TextAlign::Left,
+            HorizontalAlign::Center => TextAlign::Center,
+            HorizontalAlign::Right => TextAlign::Right,
+        };
+        Self {
+            settings,
+            align,
+            max_lines: None,
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+            kerning: false,
+        }
+    }
+}
+
+impl TextLayout {
+    pub fn max_width(mut self, width: f32) -> Self {
+        self.settings.max_width = Some(width);
+        self
+    }
+
+    pub fn max_height(mut self, height: f32) -> Self {
+        self.settings.max_height = Some(height);
+        self
+    }
+
+    pub fn align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn vertical_align(mut self, align: VerticalAlign) -> Self {
+        self.settings.vertical_align = align;
+        self
+    }
+
+    /// Truncates text to given number of lines, text is wrapped by max width.
+    pub fn max_lines(mut self, lines: usize) -> Self {
+        self.max_lines = Some(lines.max(1));
+        self
+    }
+
+    /// Sets tracking, e.g. wide spacing of stylized titles. Negative spacing tightens text.
+    ///
+    /// Spacing is applied after wrapping by max width, so wide tracking of wrapped
+    /// paragraph may exceed max width.
+    pub fn letter_spacing(mut self, spacing: f32) -> Self {
+        self.letter_spacing = spacing;
+        self
+    }
+
+    pub fn word_spacing(mut self, spacing: f32) -> Self {
+        self.word_spacing = spacing;
+        self
+    }
+
+    pub fn kerning(mut self, enabled: bool) -> Self {
+        self.kerning = enabled;
+        self
+    }
+
+    /// Lays out text appended by given function, if text exceeds max lines lays it out
+    /// again cut to fewer chars until it fits with ellipsis. Returns true if text is truncated.
+    ///
+    /// Append function receives number of chars to keep, if set text must be cut
+    /// and followed by ellipsis, see [`ellipsize`].
+    pub(crate) fn layout<U: Copy>(
+        &self,
+        layout: &mut Layout<U>,
+        scale: f32,
+        append: impl Fn(&mut Layout<U>, Option<usize>),
+    ) -> bool {
+        let settings = self.scaled(scale);
+        layout.reset(&settings);
+        append(layout, None);
+        let max_lines = match self.max_lines {
+            Some(max_lines) => max_lines,
+            None => return false,
+        };
+        let lines = |layout: &Layout<U>| layout.lines().map(Vec::len).unwrap_or(0);
+        if lines(layout) <= max_lines {
+            return false;
+        }
+        // layout produces glyph for every char, so glyph index is char index of text
+        let keep = layout
+            .lines()
+            .map(|lines| lines[max_lines - 1].glyph_end + 1)
+            .unwrap_or(0);
+        for cut in (0..=keep).rev() {
+            layout.reset(&settings);
+            append(layout, Some(cut));
+            if lines(layout) <= max_lines {
+                break;
+            }
+        }
+        true
+    }
+
+    /// Returns settings of font engine in resolution scaled coordinates,
+    /// horizontal alignment is done by [`TextLayout::aligned`] instead.
+    pub(crate) fn scaled(&self, scale: f32) -> LayoutSettings {
+        let mut settings = self.settings;
+        settings.max_width = settings.max_width.map(|width| width * scale);
+        settings.max_height = settings.max_height.map(|height| height * scale);
+        settings.horizontal_align = HorizontalAlign::Left;
+        settings
+    }
+
+    /// Returns laid out glyphs spaced and shifted to align lines horizontally and paragraph
+    /// vertically around text position if there are no bounds to align within.
+    ///
+    /// Fonts must be the same as appended to layout, glyph font index refers to them.
+    pub(crate) fn aligned<U: Copy>(
+        &self,
+        layout: &Layout<U>,
+        fonts: &[&fontdue::Font],
+        scale: f32,
+    ) -> Vec<GlyphPosition<U>> {
+        let mut glyphs = layout.glyphs().clone();
+        let lines = match layout.lines() {
+            Some(lines) => lines,
+            None => return glyphs,
+        };
+        let bounds = self.settings.max_width.map(|width| width * scale);
+        let left = self.settings.x;
+        for (index, line) in lines.iter().enumerate() {
+            let line_glyphs = &mut glyphs[line.glyph_start..=line.glyph_end];
+            self.space(line_glyphs, fonts, scale);
+            let right = line_glyphs
+                .iter()
+                .filter(|glyph| !glyph.parent.is_whitespace())
+                .map(|glyph| glyph.x + glyph.width as f32)
+                .fold(f32::NAN, f32::max);
+            if right.is_nan() {
+                continue;
+            }
+            let width = right - left;
+            let space = bounds.unwrap_or(0.0) - width;
+            let offset = match self.align {
+                TextAlign::Left | TextAlign::Justify => 0.0,
+                TextAlign::Center => (space / 2.0).floor(),
+                TextAlign::Right => space.floor(),
+            };
+            let last = index == lines.len() - 1
+                || line_glyphs.last().map(|glyph| glyph.parent) == Some('\n');
+            if self.align == TextAlign::Justify && bounds.is_some() && !last {
+                justify(line_glyphs, space);
+            }
+            for glyph in line_glyphs {
+                glyph.x += offset;
+            }
+        }
+        if self.settings.max_height.is_none() {
+            let offset = match self.settings.vertical_align {
+                VerticalAlign::Top => 0.0,
+                VerticalAlign::Middle => (layout.height() / 2.0).floor(),
+                VerticalAlign::Bottom => layout.height(),
+            };
+            for glyph in glyphs.iter_mut() {
+                glyph.y -= offset;
+            }
+        }
+        glyphs
+    }
+
+    /// Shifts glyphs of line by kerning, letter and word spacing accumulated before them.
+    fn space<U: Copy>(
+        &self,
+        glyphs: &mut [GlyphPosition<U>],
+        fonts: &[&fontdue::Font],
+        scale: f32,
+    ) {
+        if !self.kerning && self.letter_spacing == 0.0 && self.word_spacing == 0.0 {
+            return;
+        }
+        let mut shift = 0.0;
+        let mut previous: Option<(char, usize)> = None;
+        for glyph in glyphs {
+            if let Some((left, font_index)) = previous {
+                if self.kerning && font_index == glyph.font_index {
+                    let kern = fonts
+                        .get(font_index)
+                        .and_then(|font| font.horizontal_kern(left, glyph.parent, glyph.key.px));
+                    shift += kern.unwrap_or(0.0);
+                }
+            }
+            glyph.x += shift.round();
+            shift += self.letter_spacing * scale;
+            if glyph.parent == ' ' {
+                shift += self.word_spacing * scale;
+            }
+            previous = Some((glyph.parent, glyph.font_index));
+        }
+    }
+}
+
+/// Returns text cut to given number of chars and trailing whitespace followed by ellipsis.
+pub(crate) fn ellipsize(text: &str, chars: usize) -> String {
+    let mut text: String = text.chars().take(chars).collect();
+    text.truncate(text.trim_end().len());
+    text.push(ELLIPSIS);
+    text
+}
+
+/// Distributes free space of line between inner spaces, trailing spaces are kept.
+fn justify<U: Copy>(glyphs: &mut [GlyphPosition<U>], space: f32) {
+    let end = match glyphs
+        .iter()
+        .rposition(|glyph| !glyph.parent.is_whitespace())
+    {
+        Some(end) => end,
+        None => return,
+    };
+    let gaps = glyphs[..end]
+        .iter()
+        .filter(|glyph| glyph.parent == ' ')
+        .count();
+    if gaps == 0 || space <= 0.0 {
+        return;
+    }
+    let gap = space / gaps as f32;
+    let mut shift = 0.0;
+    for glyph in &mut glyphs[..=end] {
+        if glyph.parent == ' ' {
+            shift += gap;
+        }
+        glyph.x += shift.floor();
+    }
+}
+
+/// Run of text with own font and color, laid out as part of one paragraph.
+///
+/// Weight, style and size of span are determined by font, see [`crate::FontLoader::match_font`].
+#[derive(Clone, Copy, Debug)]
+pub struct TextSpan<'a> {
+    pub text: &'a str,
+    pub font: FontIndex,
+    pub color: Vec4,
+}
+
+impl<'a> TextSpan<'a> {
+    pub fn new(text: &'a str, font: FontIndex, color: impl Colors) -> Self {
+        Self {
+            text,
+            font,
+            color: color.to_vec4(),
+        }
     }
 }
 
+/// Represents char of rich text with color and font of its span.
+#[derive(Clone, Copy, Debug)]
+pub struct SpanChar {
+    pub char: Char,
+    pub color: Vec4,
+    pub font: FontIndex,
+}
+
 pub const MISSING_CHAR: char = '□';
 
+pub const ELLIPSIS: char = '…';
+
 #[derive(Debug)]
 pub struct FontError(pub String);
 
@@ -76,3 +397,50 @@ impl From<io::Error> for FontError {
         FontError(error.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{TextAlign, TextLayout};
+    use fontdue::layout::{CoordinateSystem, Layout, TextStyle};
+    use fontdue::FontSettings;
+
+    #[test]
+    pub fn test_justified_lines_fill_max_width() {
+        let data = include_bytes!("./builtin/Roboto/Roboto-Regular.ttf");
+        let font = fontdue::Font::from_bytes(&data[..], FontSettings::default()).unwrap();
+        let settings = TextLayout::default()
+            .max_width(60.0)
+            .align(TextAlign::Justify);
+        let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
+        layout.reset(&settings.scaled(1.0));
+        layout.append(&[&font], &TextStyle::new("aa bb cc dd ee ff gg", 16.0, 0));
+        let glyphs = settings.aligned(&layout, &[&font], 1.0);
+        let lines = layout.lines().unwrap();
+        assert!(lines.len() > 1);
+        let first = &glyphs[lines[0].glyph_start..=lines[0].glyph_end];
+        let right = first
+            .iter()
+            .filter(|glyph| !glyph.parent.is_whitespace())
+            .map(|glyph| glyph.x + glyph.width as f32)
+            .fold(0.0, f32::max);
+        assert!(right > 58.0 && right <= 60.0, "right edge {right}");
+    }
+
+    #[test]
+    pub fn test_letter_and_word_spacing_shift_following_glyphs() {
+        let data = include_bytes!("./builtin/Roboto/Roboto-Regular.ttf");
+        let font = fontdue::Font::from_bytes(&data[..], FontSettings::default()).unwrap();
+        let positions = |settings: TextLayout| {
+            let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
+            layout.reset(&settings.scaled(1.0));
+            layout.append(&[&font], &TextStyle::new("ab cd", 16.0, 0));
+            let glyphs = settings.aligned(&layout, &[&font], 1.0);
+            glyphs.iter().map(|glyph| glyph.x).collect::<Vec<_>>()
+        };
+        let base = positions(TextLayout::default());
+        let spaced = positions(TextLayout::default().letter_spacing(4.0).word_spacing(10.0));
+        assert_eq!(spaced[0], base[0]);
+        assert_eq!(spaced[2] - base[2], 8.0);
+        assert_eq!(spaced[3] - base[3], 22.0);
+    }
+}
@@ -1,8 +1,11 @@
-use crate::fonts::rasterize_font_to_image_file;
+use crate::fonts::{ellipsize, rasterize_font_to_image_file, rasterize_font_to_msdf_file};
 use crate::math::{Vec3, VecArith, VecMagnitude};
-use crate::{Font, FontError, MISSING_CHAR};
+use crate::watcher::same_file;
+use crate::{Font, FontError, SpanChar, TextLayout, TextSpan, ELLIPSIS, MISSING_CHAR};
+use fontdue::layout::{CoordinateSystem, Layout, TextStyle};
 use log::info;
 use std::fs;
+use std::path::Path;
 use std::sync::{Arc, RwLock};
 
 struct Record {
@@ -10,6 +13,10 @@ struct Record {
     weight: u16,
     style: String,
     size: f32,
+    alphabet: String,
+    data: Vec<u8>,
+    /// Font file path if font is loaded from file, so it can be reloaded on change.
+    path: Option<String>,
     font: Font,
 }
 
@@ -38,31 +45,35 @@ pub struct FontLoader {
     resolution_scale: f32,
     registry: Vec<Record>,
     cache: String,
+    msdf: bool,
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct FontIndex(usize);
 
 impl FontLoader {
-    pub fn new(cache: &str, resolution_scale: f32) -> FontLoaderHandle {
+    pub fn new(
+        cache: &str,
+        resolution_scale: f32,
+        msdf: bool,
+    ) -> Result<FontLoaderHandle, FontError> {
         info!("Creates font loader");
         let default = include_bytes!("builtin/Roboto/Roboto-Regular.ttf");
         let mut loader = Self {
             resolution_scale,
             registry: vec![],
             cache: cache.to_string(),
+            msdf,
         };
-        loader
-            .load_font(
-                "system-ui",
-                400,
-                "normal",
-                16.0,
-                &(ascii() + &cyrillic()),
-                default,
-            )
-            .expect("default font must be loaded");
-        Arc::new(RwLock::new(loader))
+        loader.load_font(
+            "system-ui",
+            400,
+            "normal",
+            16.0,
+            &(ascii() + &cyrillic()),
+            default,
+        )?;
+        Ok(Arc::new(RwLock::new(loader)))
     }
 
     pub fn load_font_file(
@@ -75,7 +86,37 @@ impl FontLoader {
         path: &str,
     ) -> Result<&Font, FontError> {
         let data = fs::read(path).map_err(|error| FontError(error.to_string()))?;
-        self.load_font(family, weight, style, size, alphabet, &data)
+        self.load_font(family, weight, style, size, alphabet, &data)?;
+        let index = self.registry.len() - 1;
+        self.registry[index].path = Some(path.to_string());
+        Ok(&self.registry[index].font)
+    }
+
+    /// Re-rasterizes fonts loaded from changed file, returns atlas textures of replaced fonts.
+    pub fn reload_font_file(&mut self, path: &Path) -> Result<Vec<String>, FontError> {
+        let mut replaced = vec![];
+        for index in 0..self.registry.len() {
+            let record = &self.registry[index];
+            match &record.path {
+                Some(record_path) if same_file(record_path, path) => {}
+                _ => continue,
+            }
+            info!("Reloads font {} from {path:?}", record.family);
+            let data = fs::read(path).map_err(|error| FontError(error.to_string()))?;
+            let name = format!("{}-{}-{}", record.family, record.weight, record.style);
+            let font = self.rasterize(
+                &data,
+                &name,
+                &record.alphabet,
+                record.size,
+                self.resolution_scale,
+            )?;
+            let record = &mut self.registry[index];
+            record.data = data;
+            let previous = std::mem::replace(&mut record.font, font);
+            replaced.push(previous.texture);
+        }
+        Ok(replaced)
     }
 
     pub fn load_font(
@@ -87,24 +128,82 @@ impl FontLoader {
         alphabet: &str,
         data: &[u8],
     ) -> Result<&Font, FontError> {
-        let font = rasterize_font_to_image_file(
-            data,
-            &self.cache,
-            &format!("{family}-{weight}-{style}"),
-            alphabet,
-            size,
-            self.resolution_scale,
-        )?;
+        let name = format!("{family}-{weight}-{style}");
+        let font = self.rasterize(data, &name, alphabet, size, self.resolution_scale)?;
         self.registry.push(Record {
             family: family.to_string(),
             weight,
             style: style.to_string(),
             size,
+            alphabet: alphabet.to_string(),
+            data: data.to_vec(),
+            path: None,
             font,
         });
         Ok(&self.registry[self.registry.len() - 1].font)
     }
 
+    fn rasterize(
+        &self,
+        data: &[u8],
+        name: &str,
+        alphabet: &str,
+        size: f32,
+        resolution_scale: f32,
+    ) -> Result<Font, FontError> {
+        let rasterize = if self.msdf {
+            rasterize_font_to_msdf_file
+        } else {
+            rasterize_font_to_image_file
+        };
+        rasterize(data, &self.cache, name, alphabet, size, resolution_scale)
+    }
+
+    pub fn resolution_scale(&self) -> f32 {
+        self.resolution_scale
+    }
+
+    /// Re-rasterizes all loaded fonts at new resolution scale, e.g. when window moves
+    /// to monitor with different DPI. Returns atlas textures of replaced fonts.
+    ///
+    /// Font indices stay valid, MSDF fonts are not re-rasterized because they scale without loss.
+    pub fn set_resolution_scale(
+        &mut self,
+        resolution_scale: f32,
+    ) -> Result<Vec<String>, FontError> {
+        if !resolution_scale.is_finite() || resolution_scale <= 0.0 {
+            return Err(FontError(format!(
+                "invalid resolution scale {resolution_scale}"
+            )));
+        }
+        if (self.resolution_scale - resolution_scale).abs() < 0.01 {
+            return Ok(vec![]);
+        }
+        info!(
+            "Changes font resolution scale {} -> {resolution_scale}",
+            self.resolution_scale
+        );
+        self.resolution_scale = resolution_scale;
+        if self.msdf {
+            return Ok(vec![]);
+        }
+        let mut replaced = vec![];
+        for index in 0..self.registry.len() {
+            let record = &self.registry[index];
+            let name = format!("{}-{}-{}", record.family, record.weight, record.style);
+            let font = self.rasterize(
+                &record.data,
+                &name,
+                &record.alphabet,
+                record.size,
+                resolution_scale,
+            )?;
+            let previous = std::mem::replace(&mut self.registry[index].font, font);
+            replaced.push(previous.texture);
+        }
+        Ok(replaced)
+    }
+
     pub fn match_font(&self, family: &str, weight: u16, style: &str, size: f32) -> FontIndex {
         let mut best = 0;
         let mut best_diff = f32::INFINITY;
@@ -127,10 +226,77 @@ impl FontLoader {
     pub fn get_font(&self, index: FontIndex) -> &Font {
         &self.registry[index.0].font
     }
+
+    /// Lays out spans as one paragraph, glyphs of different fonts and sizes share line baselines.
+    ///
+    /// All fonts are rasterized with loader resolution scale, so it is applied to whole layout.
+    pub fn layout_spans(
+        &self,
+        spans: &[TextSpan],
+        settings: impl Into<TextLayout>,
+    ) -> Vec<SpanChar> {
+        self.layout_spans_truncated(spans, settings).0
+    }
+
+    /// Lays out spans like [`FontLoader::layout_spans`], also returns true if text
+    /// is truncated to max lines of layout. Ellipsis is drawn by font of last kept span.
+    pub fn layout_spans_truncated(
+        &self,
+        spans: &[TextSpan],
+        settings: impl Into<TextLayout>,
+    ) -> (Vec<SpanChar>, bool) {
+        let settings = settings.into();
+        let scale = self.resolution_scale;
+        let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
+        let mut indices: Vec<FontIndex> = vec![];
+        for span in spans {
+            if !indices.contains(&span.font) {
+                indices.push(span.font);
+            }
+        }
+        let fonts: Vec<&fontdue::Font> = indices
+            .iter()
+            .map(|index| &self.get_font(*index).font)
+            .collect();
+        let truncated = settings.layout(&mut layout, scale, |layout, cut| {
+            let mut remaining = cut.unwrap_or(usize::MAX);
+            for (span_index, span) in spans.iter().enumerate() {
+                let font_index = indices
+                    .iter()
+                    .position(|index| *index == span.font)
+                    .unwrap_or_default();
+                let size = self.get_font(span.font).size;
+                let count = span.text.chars().count();
+                if cut.is_none() || remaining > count {
+                    let style = TextStyle::with_user_data(span.text, size, font_index, span_index);
+                    layout.append(&fonts, &style);
+                    remaining = remaining.saturating_sub(count);
+                } else {
+                    let text = ellipsize(span.text, remaining);
+                    let style = TextStyle::with_user_data(&text, size, font_index, span_index);
+                    layout.append(&fonts, &style);
+                    break;
+                }
+            }
+        });
+        let chars = settings
+            .aligned(&layout, &fonts, scale)
+            .iter()
+            .map(|glyph| {
+                let span = &spans[glyph.user_data];
+                SpanChar {
+                    char: self.get_font(span.font).place(glyph),
+                    color: span.color,
+                    font: span.font,
+                }
+            })
+            .collect();
+        (chars, truncated)
+    }
 }
 
 pub fn ascii() -> String {
-    let mut string = String::from(MISSING_CHAR);
+    let mut string = String::from_iter([MISSING_CHAR, ELLIPSIS]);
     for code in 0x20..=0x7e {
         string.push(code as u8 as char);
     }
@@ -1,9 +1,10 @@
 pub use font::*;
 pub use loader::*;
-
+pub use msdf::*;
 pub use raster::*;
 
 mod font;
 mod loader;
 mod metrics;
+mod msdf;
 mod raster;
@@ -0,0 +1,526 @@
+use crate::fonts::{round_up_pow_2, MISSING_CHAR};
+use crate::math::{bezier_cubic, bezier_quadratic, Vec2};
+use crate::{Char, Font, FontError};
+use fontdue::FontSettings;
+use log::{error, info};
+use std::collections::HashMap;
+use std::fs;
+use ttf_parser::{Face, GlyphId, OutlineBuilder};
+use zune_png::zune_core::bit_depth::BitDepth;
+use zune_png::zune_core::colorspace::ColorSpace;
+use zune_png::zune_core::options::EncoderOptions;
+use zune_png::PngEncoder;
+
+/// Distance range of MSDF atlas in atlas pixels, must match `range` in text shader.
+pub const MSDF_DISTANCE_RANGE: f32 = 4.0;
+
+const RED: u8 = 1;
+const GREEN: u8 = 2;
+const BLUE: u8 = 4;
+const YELLOW: u8 = RED | GREEN;
+const MAGENTA: u8 = RED | BLUE;
+const CYAN: u8 = GREEN | BLUE;
+const WHITE: u8 = RED | GREEN | BLUE;
+
+/// Represents outline edge (line or curve) flattened to polyline.
+struct Edge {
+    points: Vec<Vec2>,
+    color: u8,
+}
+
+impl Edge {
+    fn start_direction(&self) -> Vec2 {
+        sub(self.points[1], self.points[0])
+    }
+
+    fn end_direction(&self) -> Vec2 {
+        let n = self.points.len();
+        sub(self.points[n - 1], self.points[n - 2])
+    }
+}
+
+/// Represents glyph outline in atlas pixel space, y axis up.
+#[derive(Default)]
+pub struct Shape {
+    contours: Vec<Vec<Edge>>,
+    current: Vec<Edge>,
+    cursor: Vec2,
+    start: Vec2,
+    scale: f32,
+}
+
+impl Shape {
+    pub fn from_glyph(face: &Face, glyph: GlyphId, size: f32) -> Self {
+        let mut shape = Shape {
+            scale: size / face.units_per_em() as f32,
+            ..Default::default()
+        };
+        face.outline_glyph(glyph, &mut shape);
+        shape.close();
+        shape
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contours.is_empty()
+    }
+
+    fn push(&mut self, points: Vec<Vec2>) {
+        let mut polyline: Vec<Vec2> = Vec::with_capacity(points.len());
+        for point in points {
+            if polyline.last().map(|last| length(sub(point, *last)) > 1e-6) != Some(false) {
+                polyline.push(point);
+            }
+        }
+        if polyline.len() > 1 {
+            self.cursor = polyline[polyline.len() - 1];
+            self.current.push(Edge {
+                points: polyline,
+                color: WHITE,
+            });
+        }
+    }
+
+    fn flatten(&self, control: &[Vec2], to: Vec2) -> Vec<Vec2> {
+        let mut hull = self.cursor;
+        let mut len = 0.0;
+        for point in control.iter().chain([&to]) {
+            len += length(sub(*point, hull));
+            hull = *point;
+        }
+        let segments = (len / 1.5).ceil().clamp(1.0, 24.0) as usize;
+        let p0 = self.cursor;
+        (0..=segments)
+            .map(|step| {
+                let t = step as f32 / segments as f32;
+                match control {
+                    [p1] => bezier_quadratic(p0, *p1, to, t),
+                    [p1, p2] => bezier_cubic(p0, *p1, *p2, to, t),
+                    _ => to,
+                }
+            })
+            .collect()
+    }
+
+    /// Assigns channel colors to edges, so that edges meeting at sharp corner
+    /// always have different colors and corner is preserved by median of channels.
+    fn color_edges(&mut self) {
+        // sin of 3 radians, corner threshold proposed by original MSDF paper
+        let threshold = 3.0f32.sin();
+        for contour in &mut self.contours {
+            let n = contour.len();
+            let corners: Vec<usize> = (0..n)
+                .filter(|index| {
+                    let a = normalize(contour[(index + n - 1) % n].end_direction());
+                    let b = normalize(contour[*index].start_direction());
+                    dot(a, b) <= 0.0 || cross(a, b).abs() > threshold
+                })
+                .collect();
+            match corners.len() {
+                0 => {}
+                1 if n >= 3 => {
+                    // teardrop, split smooth part into three colored parts
+                    let colors = [MAGENTA, WHITE, YELLOW];
+                    let corner = corners[0];
+                    for index in 0..n {
+                        let part = index * 3 / n;
+                        contour[(corner + index) % n].color = colors[part];
+                    }
+                }
+                1 => {}
+                _ => {
+                    let start = corners[0];
+                    let mut color = CYAN;
+                    let mut spline = 0;
+                    for index in 0..n {
+                        let edge = (start + index) % n;
+                        if spline + 1 < corners.len() && corners[spline + 1] == edge {
+                            spline += 1;
+                            color = next_color(color);
+                            if spline == corners.len() - 1 && color == CYAN {
+                                // last spline meets first one
+                                color = next_color(color);
+                            }
+                        }
+                        contour[edge].color = color;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns true if point inside shape by non-zero winding rule.
+    fn contains(&self, point: Vec2) -> bool {
+        let mut winding = 0;
+        for edge in self.contours.iter().flatten() {
+            for segment in edge.points.windows(2) {
+                let [a, b] = [segment[0], segment[1]];
+                if a[1] <= point[1] {
+                    if b[1] > point[1] && cross(sub(b, a), sub(point, a)) > 0.0 {
+                        winding += 1;
+                    }
+                } else if b[1] <= point[1] && cross(sub(b, a), sub(point, a)) < 0.0 {
+                    winding -= 1;
+                }
+            }
+        }
+        winding != 0
+    }
+
+    /// Returns positive value if contours are counter clockwise (inside on the left).
+    fn orientation(&self) -> f32 {
+        let mut area = 0.0;
+        for edge in self.contours.iter().flatten() {
+            for segment in edge.points.windows(2) {
+                area += cross(segment[0], segment[1]);
+            }
+        }
+        if area < 0.0 {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns signed pseudo distances for red, green and blue channels, positive inside.
+    fn distance(&self, point: Vec2, orientation: f32) -> [f32; 3] {
+        let mut channels = [Closest::default(); 3];
+        for edge in self.contours.iter().flatten() {
+            let last = edge.points.len() - 2;
+            for (index, segment) in edge.points.windows(2).enumerate() {
+                let [a, b] = [segment[0], segment[1]];
+                let direction = sub(b, a);
+                let relative = sub(point, a);
+                let t = dot(relative, direction) / dot(direction, direction);
+                let closest = add(a, scale(direction, t.clamp(0.0, 1.0)));
+                let offset = sub(point, closest);
+                let distance = length(offset);
+                let orthogonality = if distance > 0.0 {
+                    cross(normalize(direction), scale(offset, 1.0 / distance)).abs()
+                } else {
+                    1.0
+                };
+                let side = cross(direction, relative).signum() * orientation;
+                // pseudo distance extends edge ends to infinite lines
+                let extends = (index == 0 && t < 0.0) || (index == last && t > 1.0);
+                let pseudo = if extends {
+                    cross(normalize(direction), relative).abs()
+                } else {
+                    distance
+                };
+                for (channel, color) in [RED, GREEN, BLUE].into_iter().enumerate() {
+                    if edge.color & color != 0 {
+                        channels[channel].select(distance, orthogonality, side * pseudo);
+                    }
+                }
+            }
+        }
+        channels.map(|closest| closest.signed)
+    }
+}
+
+impl OutlineBuilder for Shape {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.cursor = [x * self.scale, y * self.scale];
+        self.start = self.cursor;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let to = [x * self.scale, y * self.scale];
+        self.push(vec![self.cursor, to]);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let control = [[x1 * self.scale, y1 * self.scale]];
+        let points = self.flatten(&control, [x * self.scale, y * self.scale]);
+        self.push(points);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let control = [
+            [x1 * self.scale, y1 * self.scale],
+            [x2 * self.scale, y2 * self.scale],
+        ];
+        let points = self.flatten(&control, [x * self.scale, y * self.scale]);
+        self.push(points);
+    }
+
+    fn close(&mut self) {
+        if length(sub(self.cursor, self.start)) > 1e-6 {
+            self.push(vec![self.cursor, self.start]);
+        }
+        if !self.current.is_empty() {
+            let contour = std::mem::take(&mut self.current);
+            self.contours.push(contour);
+        }
+        self.cursor = self.start;
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Closest {
+    distance: f32,
+    orthogonality: f32,
+    signed: f32,
+}
+
+impl Default for Closest {
+    fn default() -> Self {
+        Self {
+            distance: f32::INFINITY,
+            orthogonality: 0.0,
+            signed: -f32::INFINITY,
+        }
+    }
+}
+
+impl Closest {
+    #[inline(always)]
+    fn select(&mut self, distance: f32, orthogonality: f32, signed: f32) {
+        let tie = (distance - self.distance).abs() < 1e-4;
+        if (tie && orthogonality > self.orthogonality) || (!tie && distance < self.distance) {
+            self.distance = distance;
+            self.orthogonality = orthogonality;
+            self.signed = signed;
+        }
+    }
+}
+
+/// Generates multi-channel signed distance field of shape as RGBA pixels,
+/// alpha channel contains true signed distance.
+///
+/// Origin is shape space position of top-left pixel corner.
+pub fn generate_msdf(
+    shape: &mut Shape,
+    width: usize,
+    height: usize,
+    origin: Vec2,
+    range: f32,
+) -> Vec<[u8; 4]> {
+    let mut pixels = vec![[0u8; 4]; width * height];
+    if shape.is_empty() {
+        return pixels;
+    }
+    shape.color_edges();
+    let orientation = shape.orientation();
+    let encode = |distance: f32| ((distance / range + 0.5).clamp(0.0, 1.0) * 255.0).round() as u8;
+    for y in 0..height {
+        for x in 0..width {
+            let point = [origin[0] + x as f32 + 0.5, origin[1] - y as f32 - 0.5];
+            let [r, g, b] = shape.distance(point, orientation);
+            let inside = shape.contains(point);
+            let exact = r.abs().min(g.abs()).min(b.abs());
+            let exact = if inside { exact } else { -exact };
+            let median = r.min(g).max(r.max(g).min(b));
+            // channels clash where edge coloring can't resolve contour, fall back to true distance
+            let [r, g, b] = if (median > 0.0) != inside {
+                [exact; 3]
+            } else {
+                [r, g, b]
+            };
+            pixels[y * width + x] = [encode(r), encode(g), encode(b), encode(exact)];
+        }
+    }
+    pixels
+}
+
+/// Rasterizes font glyphs to multi-channel signed distance field atlas.
+///
+/// Unlike bitmap atlas, text stays crisp at any scale. Fragment shader reconstructs
+/// glyph coverage from median of channels:
+///
+/// ```glsl
+/// float median(vec3 value) {
+///     return max(min(value.r, value.g), min(max(value.r, value.g), value.b));
+/// }
+///
+/// vec3 msdf = texture(textures[texture], uv).rgb;
+/// vec2 unit = range / vec2(textureSize(textures[texture], 0));
+/// float screen_range = max(0.5 * dot(unit, 1.0 / fwidth(uv)), 1.0);
+/// float opacity = clamp(screen_range * (median(msdf) - 0.5) + 0.5, 0.0, 1.0);
+/// ```
+pub fn rasterize_font_to_msdf_file(
+    input: &[u8],
+    cache: &str,
+    name: &str,
+    alphabet: &str,
+    size: f32,
+    resolution_scale: f32,
+) -> Result<Font, FontError> {
+    let key = format!(
+        "{name}-{}-{}-msdf.png",
+        (size) as u32,
+        (resolution_scale * 100.0) as u32
+    );
+    let texture = format!("{cache}/{key}");
+
+    let size = size * resolution_scale;
+    info!("Starts font {texture} loading");
+    let font_settings = FontSettings {
+        collection_index: 0,
+        scale: size,
+        load_substitutions: true,
+    };
+    let font = fontdue::Font::from_bytes(input, font_settings)?;
+    let face = Face::parse(input, 0).map_err(|error| FontError(error.to_string()))?;
+
+    let range = MSDF_DISTANCE_RANGE;
+    let padding = range as usize;
+    let w = (512.0 * resolution_scale) as usize;
+    let h = (512.0 * resolution_scale) as usize;
+    let mut data = vec![0; w * h * 4];
+    let mut offset_x = 0usize;
+    let mut offset_y = 0usize;
+    let line_metrics = font
+        .horizontal_line_metrics(size)
+        .ok_or(FontError("line metrics unavailable".into()))?;
+    let line_height = line_metrics.new_line_size.ceil();
+    let baseline = line_height + line_metrics.descent.ceil();
+    let step_y = round_up_pow_2(line_height as usize + 2 * padding);
+    let mut charset = HashMap::new();
+    let mut missing_char = Char::default();
+    for char in alphabet.chars() {
+        let glyph = font.metrics(char, size);
+        let step_x = round_up_pow_2(glyph.width + 2 * padding);
+        if offset_x + step_x >= w {
+            offset_x = 0;
+            offset_y += step_y;
+        }
+        if offset_y + step_y > h {
+            error!("unable to render glyph [{}], atlas is full", char);
+            break;
+        }
+        if glyph.height > line_height as usize
+            || (glyph.height as i32 + glyph.ymin) > baseline as i32
+        {
+            error!(
+                "unable to render glyph [{}], height greater than line height",
+                char
+            );
+            continue;
+        }
+        let glyph_offset = (baseline as i32 - (glyph.height as i32 + glyph.ymin)) as usize;
+        let index = face.glyph_index(char).unwrap_or(GlyphId(0));
+        let mut shape = Shape::from_glyph(&face, index, size);
+        let origin = [
+            glyph.xmin as f32 - padding as f32,
+            (glyph.ymin + glyph.height as i32) as f32 + (padding + glyph_offset) as f32,
+        ];
+        let pixels = generate_msdf(&mut shape, step_x, step_y, origin, range);
+        for (index, pixel) in pixels.iter().enumerate() {
+            let y = offset_y + index / step_x;
+            let x = offset_x + index % step_x;
+            let offset = (y * w * 4) + x * 4;
+            data[offset..offset + 4].copy_from_slice(pixel);
+        }
+        let constants = Char {
+            position: [0.0; 2],
+            image: [w as f32, h as f32],
+            src: [offset_x as f32 / w as f32, offset_y as f32 / h as f32],
+            uv: [step_x as f32 / w as f32, step_y as f32 / h as f32],
+            size: [
+                step_x as f32 / resolution_scale,
+                step_y as f32 / resolution_scale,
+            ],
+            glyph_offset: (glyph_offset + padding) as f32,
+            glyph_width: glyph.width as f32 / resolution_scale,
+        };
+        charset.insert(char, constants);
+        if char == MISSING_CHAR {
+            missing_char = constants;
+        }
+        offset_x += step_x;
+    }
+
+    let options = EncoderOptions::new(w, h, ColorSpace::RGBA, BitDepth::Eight);
+    let mut encoder = PngEncoder::new(&data, options);
+    fs::write(&texture, encoder.encode())?;
+
+    info!(
+        "Creates MSDF font prefab {texture} charset={}",
+        charset.len()
+    );
+    Ok(Font {
+        texture,
+        charset,
+        font,
+        size,
+        missing_char,
+        resolution_scale,
+        line_height: line_height / resolution_scale,
+        baseline: baseline / resolution_scale,
+        padding: padding as f32,
+        distance_range: Some(range),
+    })
+}
+
+#[inline(always)]
+fn next_color(color: u8) -> u8 {
+    match color {
+        CYAN => MAGENTA,
+        MAGENTA => YELLOW,
+        _ => CYAN,
+    }
+}
+
+#[inline(always)]
+fn add(a: Vec2, b: Vec2) -> Vec2 {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+#[inline(always)]
+fn sub(a: Vec2, b: Vec2) -> Vec2 {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+#[inline(always)]
+fn scale(a: Vec2, k: f32) -> Vec2 {
+    [a[0] * k, a[1] * k]
+}
+
+#[inline(always)]
+fn dot(a: Vec2, b: Vec2) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+#[inline(always)]
+fn cross(a: Vec2, b: Vec2) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+#[inline(always)]
+fn length(a: Vec2) -> f32 {
+    dot(a, a).sqrt()
+}
+
+#[inline(always)]
+fn normalize(a: Vec2) -> Vec2 {
+    let length = length(a);
+    if length > 0.0 {
+        scale(a, 1.0 / length)
+    } else {
+        a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fonts::msdf::{generate_msdf, Shape};
+    use ttf_parser::Face;
+
+    #[test]
+    pub fn test_msdf_sign_matches_glyph_coverage() {
+        let data = include_bytes!("./builtin/Roboto/Roboto-Regular.ttf");
+        let face = Face::parse(data, 0).unwrap();
+        let glyph = face.glyph_index('H').unwrap();
+        let mut shape = Shape::from_glyph(&face, glyph, 64.0);
+        let pixels = generate_msdf(&mut shape, 64, 64, [0.0, 64.0], 4.0);
+        let median = |[r, g, b, _]: [u8; 4]| r.min(g).max(r.max(g).min(b));
+        // left stem of H
+        assert!(median(pixels[40 * 64 + 10]) > 127);
+        // gap between stems above crossbar
+        assert!(median(pixels[20 * 64 + 24]) < 128);
+        // outside of glyph
+        assert!(median(pixels[2 * 64 + 2]) < 128);
+    }
+}
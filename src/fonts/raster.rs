@@ -19,7 +19,11 @@ pub fn rasterize_font_to_image_file(
     size: f32,
     resolution_scale: f32,
 ) -> Result<Font, FontError> {
-    let key = format!("{name}-{}-{}.png", (size) as u32, (resolution_scale) as u32);
+    let key = format!(
+        "{name}-{}-{}.png",
+        (size) as u32,
+        (resolution_scale * 100.0) as u32
+    );
     let texture = format!("{cache}/{key}");
 
     let size = size * resolution_scale;
@@ -126,10 +130,12 @@ pub fn rasterize_font_to_image_file(
         resolution_scale,
         line_height: line_height / resolution_scale,
         baseline: baseline / resolution_scale,
+        padding: 0.0,
+        distance_range: None,
     })
 }
 
-fn round_up_pow_2(value: usize) -> usize {
+pub(crate) fn round_up_pow_2(value: usize) -> usize {
     if value == 0 {
         return 1;
     }
@@ -0,0 +1,98 @@
+use crate::math::Vec2;
+use crate::renderers::{embedded_shader, ShapeRenderer, SpriteRenderer, TextEffects, TextRenderer};
+use crate::{CameraHandle, Colors, FontIndex, Graphics, LayoutSettings, ProgramError, Texture};
+use log::error;
+
+/// Provides simplest way to draw rectangles, sprites and text without setting up
+/// programs, storages and descriptors.
+///
+/// Shaders are embedded in binary, their GLSL sources are in `src/renderers/builtin`.
+/// Coordinates are given in pixels of default camera, rectangles are drawn first,
+/// then sprites, then text on top.
+///
+/// ```ignore
+/// let mut graphics = Graphics::create(GraphicsConfig::default())?;
+/// let mut frame = graphics.create_frame()?;
+/// loop {
+///     graphics.capture_user_input();
+///     graphics.clear([0.1, 0.1, 0.1, 1.0]);
+///     frame.draw_rect([10.0, 10.0], [200.0, 40.0], [0.2, 0.4, 0.8, 1.0]);
+///     frame.draw_text(&mut graphics, "Hello, world!", [20.0, 20.0], [1.0; 4]);
+///     frame.render();
+///     graphics.present();
+/// }
+/// ```
+pub struct Frame {
+    pub camera: CameraHandle,
+    pub font: FontIndex,
+    shapes: ShapeRenderer,
+    sprites: SpriteRenderer,
+    text: TextRenderer,
+}
+
+impl Frame {
+    pub fn create(graphics: &mut Graphics) -> Result<Self, ProgramError> {
+        let camera = graphics.camera();
+        let shapes = ShapeRenderer::create(
+            graphics,
+            embedded_shader!("shapes.vert"),
+            embedded_shader!("shapes.frag"),
+        )?;
+        let sprites = SpriteRenderer::create(
+            graphics,
+            embedded_shader!("sprites.vert"),
+            embedded_shader!("sprites.frag"),
+        )?;
+        let text = TextRenderer::create(
+            graphics,
+            embedded_shader!("text.vert"),
+            embedded_shader!("text.frag"),
+        )?;
+        Ok(Self {
+            camera,
+            // default font is always loaded first
+            font: FontIndex::default(),
+            shapes,
+            sprites,
+            text,
+        })
+    }
+
+    pub fn draw_rect(&mut self, position: Vec2, size: Vec2, color: impl Colors) {
+        if let Err(error) = self.shapes.draw_rect(position, size, color) {
+            error!("unable to draw rect, {error:?}");
+        }
+    }
+
+    pub fn draw_sprite(&mut self, texture: Texture, position: Vec2, size: Vec2) {
+        if let Err(error) = self.sprites.draw_sprite(texture, position, size, [1.0; 4]) {
+            error!("unable to draw sprite, {error:?}");
+        }
+    }
+
+    pub fn draw_text(
+        &mut self,
+        graphics: &mut Graphics,
+        text: &str,
+        position: Vec2,
+        color: impl Colors,
+    ) {
+        let settings = LayoutSettings::default();
+        let effects = TextEffects::default();
+        let result = self.text.draw_text(
+            graphics, self.font, text, position, color, settings, effects,
+        );
+        if let Err(error) = result {
+            error!("unable to draw text, {error:?}");
+        }
+    }
+
+    /// Renders everything drawn since previous render, must be called between
+    /// `clear` and `present`.
+    pub fn render(&mut self) {
+        let camera = self.camera.read().expect("camera must be readable");
+        self.shapes.render(&camera);
+        self.sprites.render(&camera);
+        self.text.render(&camera);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::math::Vec4;
+use crate::{Colors, Graphics};
+use std::time::Duration;
+
+/// Drives application loop with fixed timestep updates and variable rate rendering.
+///
+/// Scaled frame time of [`crate::Clock`] is accumulated and consumed by update steps
+/// of fixed duration, so simulation doesn't depend on frame rate and stops while clock
+/// is paused. Render receives interpolation alpha in range 0.0 to 1.0, fraction of step
+/// accumulated after last update, to blend previous and current states.
+///
+/// ```ignore
+/// let mut graphics = Graphics::create(GraphicsConfig::default())?;
+/// let mut world = World::default();
+/// GameLoop::new(60.0).clear_color([0.1, 0.1, 0.1, 1.0]).run(
+///     &mut graphics,
+///     &mut world,
+///     |world, graphics, step| world.update(&graphics.input, step),
+///     |world, graphics, alpha| world.render(graphics, alpha),
+/// );
+/// ```
+///
+/// Input captured in frame is seen by every update step of this frame, but by none
+/// if frame is shorter than step. One-frame flags like `pressed` should be consumed
+/// in render or latched by application.
+pub struct GameLoop {
+    step: Duration,
+    accumulator: Duration,
+    max_steps: usize,
+    clear_color: Vec4,
+}
+
+impl GameLoop {
+    /// Creates loop with given number of update steps per second.
+    pub fn new(rate: f32) -> Self {
+        Self {
+            step: Duration::from_secs_f32(1.0 / rate),
+            accumulator: Duration::ZERO,
+            max_steps: 5,
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+
+    /// Limits update steps per frame, so slow frame doesn't cause even slower next frame.
+    /// Time beyond limit is dropped and simulation slows down instead.
+    pub fn max_steps(mut self, steps: usize) -> Self {
+        self.max_steps = steps.max(1);
+        self
+    }
+
+    pub fn clear_color(mut self, color: impl Colors) -> Self {
+        self.clear_color = color.to_vec4();
+        self
+    }
+
+    /// Returns fixed step duration in seconds.
+    pub fn step(&self) -> f32 {
+        self.step.as_secs_f32()
+    }
+
+    /// Returns fraction of step accumulated after last update.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+
+    /// Accumulates frame time, returns number of update steps to run.
+    pub fn advance(&mut self, time: Duration) -> usize {
+        self.accumulator += time;
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        if steps > self.max_steps {
+            steps = self.max_steps;
+            self.accumulator = Duration::ZERO;
+        }
+        steps
+    }
+
+    /// Runs one frame: captures input, updates simulation by fixed steps
+    /// and renders between `clear` and `present`.
+    pub fn frame<S, U, R>(
+        &mut self,
+        graphics: &mut Graphics,
+        state: &mut S,
+        mut update: U,
+        render: R,
+    ) where
+        U: FnMut(&mut S, &mut Graphics, f32),
+        R: FnOnce(&mut S, &mut Graphics, f32),
+    {
+        graphics.capture_user_input();
+        let time = Duration::from_secs_f32(graphics.input.clock.delta());
+        let steps = self.advance(time);
+        for _ in 0..steps {
+            update(state, graphics, self.step());
+        }
+        graphics.clear(self.clear_color);
+        render(state, graphics, self.alpha());
+        graphics.present();
+    }
+
+    /// Runs frames until application quits.
+    pub fn run<S, U, R>(
+        mut self,
+        graphics: &mut Graphics,
+        state: &mut S,
+        mut update: U,
+        mut render: R,
+    ) -> !
+    where
+        U: FnMut(&mut S, &mut Graphics, f32),
+        R: FnMut(&mut S, &mut Graphics, f32),
+    {
+        loop {
+            self.frame(graphics, state, &mut update, &mut render);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GameLoop;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_accumulated_time_runs_fixed_steps() {
+        let mut game_loop = GameLoop::new(50.0).max_steps(3);
+        assert_eq!(game_loop.advance(Duration::from_millis(10)), 0);
+        assert_eq!(game_loop.advance(Duration::from_millis(35)), 2);
+        assert!((game_loop.alpha() - 0.25).abs() < 1e-3);
+        assert_eq!(game_loop.advance(Duration::from_secs(1)), 3);
+        assert_eq!(game_loop.alpha(), 0.0);
+    }
+}
@@ -0,0 +1,281 @@
+use crate::math::Vec4;
+use crate::{Colors, Graphics, RenderTarget, Texture};
+use log::info;
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq)]
+pub enum RenderGraphError {
+    UnknownInput { pass: String, input: String },
+    DuplicateOutput { output: String },
+    Cycle { passes: Vec<String> },
+}
+
+/// Declares pass of render graph, pass without output draws to screen.
+///
+/// ```ignore
+/// let pass = GraphPass::new("bloom").input("scene").output("bloom").clear("black");
+/// ```
+#[derive(Debug, Clone)]
+pub struct GraphPass {
+    name: String,
+    inputs: Vec<String>,
+    output: Option<String>,
+    clear: Vec4,
+}
+
+impl GraphPass {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            inputs: vec![],
+            output: None,
+            clear: [0.0; 4],
+        }
+    }
+
+    /// Declares attachment sampled by pass, pass is executed after pass writing it.
+    pub fn input(mut self, attachment: &str) -> Self {
+        self.inputs.push(attachment.to_string());
+        self
+    }
+
+    /// Declares attachment written by pass, offscreen target of screen size is created for it.
+    pub fn output(mut self, attachment: &str) -> Self {
+        self.output = Some(attachment.to_string());
+        self
+    }
+
+    pub fn clear(mut self, color: impl Colors) -> Self {
+        self.clear = color.to_vec4();
+        self
+    }
+}
+
+/// Textures of attachments declared as pass inputs.
+pub struct PassContext<'a> {
+    pub name: &'a str,
+    inputs: Vec<(&'a str, Texture)>,
+}
+
+impl PassContext<'_> {
+    pub fn input(&self, attachment: &str) -> Texture {
+        self.inputs
+            .iter()
+            .find(|(name, _)| *name == attachment)
+            .map(|(_, texture)| *texture)
+            .expect("attachment must be declared as pass input")
+    }
+}
+
+/// Orders offscreen passes by attachments they read and write, so lighting, post effects
+/// and other targets compose without manual ordering.
+///
+/// Image layout transitions and synchronization between passes are done by
+/// render passes of targets: attachment is ready for sampling once its pass ends.
+///
+/// ```ignore
+/// let mut graph = RenderGraph::default();
+/// graph.add_pass(GraphPass::new("scene").output("scene").clear("black"));
+/// graph.add_pass(GraphPass::new("blur").input("scene").output("blur"));
+/// graph.add_pass(GraphPass::new("compose").input("scene").input("blur"));
+/// graph.compile()?;
+/// // every frame between clear and present
+/// graph.execute(&mut graphics, |graphics, pass| match pass.name {
+///     "scene" => scene.render(&camera),
+///     "blur" => blur.draw(pass.input("scene")),
+///     _ => compose.draw(pass.input("scene"), pass.input("blur")),
+/// })?;
+/// ```
+#[derive(Default)]
+pub struct RenderGraph {
+    passes: Vec<GraphPass>,
+    order: Option<Vec<usize>>,
+    targets: HashMap<String, RenderTarget>,
+}
+
+impl RenderGraph {
+    pub fn add_pass(&mut self, pass: GraphPass) {
+        self.passes.push(pass);
+        self.order = None;
+    }
+
+    /// Validates passes and resolves execution order.
+    pub fn compile(&mut self) -> Result<(), RenderGraphError> {
+        let order = order_passes(&self.passes)?;
+        let names: Vec<&str> = order
+            .iter()
+            .map(|index| self.passes[*index].name.as_str())
+            .collect();
+        info!("Compiles render graph {names:?}");
+        self.order = Some(order);
+        Ok(())
+    }
+
+    /// Returns pass names in execution order, empty if graph is not compiled.
+    pub fn order(&self) -> Vec<&str> {
+        self.order
+            .iter()
+            .flatten()
+            .map(|index| self.passes[*index].name.as_str())
+            .collect()
+    }
+
+    /// Returns texture of attachment, it is created on first execution.
+    pub fn texture(&self, attachment: &str) -> Option<Texture> {
+        self.targets.get(attachment).map(|target| target.texture)
+    }
+
+    /// Executes passes in order, draws recorded by callback go to pass output.
+    ///
+    /// Must be called between `clear` and `present`.
+    pub fn execute<F>(
+        &mut self,
+        graphics: &mut Graphics,
+        mut record: F,
+    ) -> Result<(), RenderGraphError>
+    where
+        F: FnMut(&mut Graphics, &PassContext),
+    {
+        if self.order.is_none() {
+            self.compile()?;
+        }
+        let order = self.order.as_ref().expect("render graph must be compiled");
+        let extent = graphics.vulkan.swapchain.extent;
+        for index in order {
+            let pass = &self.passes[*index];
+            let inputs = pass
+                .inputs
+                .iter()
+                .map(|input| {
+                    let target = self
+                        .targets
+                        .get(input)
+                        .expect("input target must be created");
+                    (input.as_str(), target.texture)
+                })
+                .collect();
+            let context = PassContext {
+                name: &pass.name,
+                inputs,
+            };
+            let output = match pass.output.as_ref() {
+                Some(output) => output,
+                None => {
+                    record(graphics, &context);
+                    continue;
+                }
+            };
+            let resize = match self.targets.get(output) {
+                Some(target) => target.extent != extent,
+                None => true,
+            };
+            if resize {
+                if let Some(target) = self.targets.remove(output) {
+                    graphics.destroy_render_target(target);
+                }
+                let target = graphics.create_render_target(extent.width, extent.height);
+                self.targets.insert(output.clone(), target);
+            }
+            let target = self
+                .targets
+                .get_mut(output)
+                .expect("output target must be created");
+            graphics.begin_render_target(target, pass.clear);
+            record(graphics, &context);
+            graphics.end_render_target(target);
+        }
+        Ok(())
+    }
+
+    pub fn destroy(self, graphics: &mut Graphics) {
+        for target in self.targets.into_values() {
+            graphics.destroy_render_target(target);
+        }
+    }
+}
+
+/// Returns pass indices sorted so every pass goes after passes writing its inputs,
+/// independent passes keep declaration order.
+fn order_passes(passes: &[GraphPass]) -> Result<Vec<usize>, RenderGraphError> {
+    let mut writers = HashMap::new();
+    for (index, pass) in passes.iter().enumerate() {
+        if let Some(output) = pass.output.as_ref() {
+            if writers.insert(output.as_str(), index).is_some() {
+                return Err(RenderGraphError::DuplicateOutput {
+                    output: output.clone(),
+                });
+            }
+        }
+    }
+    let mut dependencies = Vec::with_capacity(passes.len());
+    for pass in passes {
+        let mut writers_of_inputs = vec![];
+        for input in &pass.inputs {
+            match writers.get(input.as_str()) {
+                Some(writer) => writers_of_inputs.push(*writer),
+                None => {
+                    return Err(RenderGraphError::UnknownInput {
+                        pass: pass.name.clone(),
+                        input: input.clone(),
+                    })
+                }
+            }
+        }
+        dependencies.push(writers_of_inputs);
+    }
+    let mut order = Vec::with_capacity(passes.len());
+    let mut done = vec![false; passes.len()];
+    while order.len() < passes.len() {
+        let ready = (0..passes.len())
+            .find(|index| !done[*index] && dependencies[*index].iter().all(|writer| done[*writer]));
+        match ready {
+            Some(index) => {
+                done[index] = true;
+                order.push(index);
+            }
+            None => {
+                let passes = (0..passes.len())
+                    .filter(|index| !done[*index])
+                    .map(|index| passes[index].name.clone())
+                    .collect();
+                return Err(RenderGraphError::Cycle { passes });
+            }
+        }
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::order_passes;
+    use crate::{GraphPass, RenderGraphError};
+
+    #[test]
+    pub fn test_passes_ordered_by_attachments() {
+        let passes = vec![
+            GraphPass::new("compose").input("scene").input("lights"),
+            GraphPass::new("lights").input("scene").output("lights"),
+            GraphPass::new("scene").output("scene"),
+            GraphPass::new("ui"),
+        ];
+        assert_eq!(order_passes(&passes), Ok(vec![2, 1, 0, 3]));
+        let passes = vec![
+            GraphPass::new("a").input("b").output("a"),
+            GraphPass::new("b").input("a").output("b"),
+        ];
+        assert_eq!(
+            order_passes(&passes),
+            Err(RenderGraphError::Cycle {
+                passes: vec!["a".to_string(), "b".to_string()]
+            })
+        );
+        let passes = vec![GraphPass::new("blur").input("scene")];
+        assert_eq!(
+            order_passes(&passes),
+            Err(RenderGraphError::UnknownInput {
+                pass: "blur".to_string(),
+                input: "scene".to_string()
+            })
+        );
+    }
+}
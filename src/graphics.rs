@@ -1,105 +1,894 @@
-use crate::input::{poll_event, UserInput};
+use crate::input::{poll_event, wait_event, UserInput};
+use crate::limiter::FrameLimiter;
+use crate::math::{Vec2, VecCast};
 
+use crate::system::set_crash_context;
 use crate::textures::TexturesManager;
+use crate::vulkan::memory::{memory_stats, MemoryMetrics, MemoryStats};
+use crate::vulkan::retire::Retired;
+use crate::vulkan::stats::{take_frame_stats, FrameMetrics, FrameStats};
 use crate::vulkan::Vulkan;
-use crate::{dpi, Colors, FontLoader, FontLoaderHandle, GraphicsConfig, GraphicsMode};
-use log::info;
-use sdl2::event::Event;
+use crate::watcher::AssetWatcher;
+#[cfg(feature = "svg")]
+use crate::SvgRasterizer;
+use crate::{
+    dpi, log_validation_summary, read_texture_from_data, Colors, FontError, FontLoader,
+    FontLoaderHandle, GraphicsConfig, GraphicsDisplay, GraphicsMode, Picking, PresentMode,
+    RedrawMode, SamplerOptions, TextureError, TextureFiltering, TextureSlotsRegistry,
+};
+use log::{error, info, warn};
+use sdl2::controller::{GameController, GameControllerSubsystem};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::mouse::{Cursor, SystemCursor};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::Surface;
 
-use sdl2::video::{FullscreenType, Window, WindowPos};
+use sdl2::video::{DisplayMode, FullscreenType, Window, WindowPos};
+use sdl2::VideoSubsystem;
+use std::cell::RefCell;
+use std::fs;
 use std::fs::create_dir_all;
+use std::io;
+use std::mem::take;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 use vulkanalia::vk;
 
+/// Interval at which blocking waits are interrupted to check textures loaded
+/// in background threads without events.
+const WAKE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Delay after last window resize before fonts and images are re-rasterized,
+/// so dragging window border doesn't re-rasterize them every frame.
+const RESIZE_SETTLE_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Display window is on, updated when window is moved to another display.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Monitor {
+    pub display: i32,
+    pub name: String,
+    /// Diagonal dpi reported by display, 96 if unknown.
+    pub dpi: f32,
+    /// Drawable pixels per logical point, see [`Graphics::dpi_scale`].
+    pub dpi_scale: f32,
+    /// Refresh rate in Hz, 0 if unknown.
+    pub refresh_rate: i32,
+}
+
 /// Provides the context for the rendering graphics on screen.
+///
+/// Graphics must stay on thread which created it, because SDL window and its events are
+/// bound to that thread, so that thread renders frames. Game logic can run on another
+/// thread and send snapshots of game state to render thread, camera handles can be
+/// updated from any thread. Renderers are not `Send`, create them on render thread.
+///
+/// ```ignore
+/// let mut graphics = Graphics::create(GraphicsConfig::default())?;
+/// let mut frame = graphics.create_frame()?;
+/// let camera = frame.camera.clone();
+/// let (sender, receiver) = std::sync::mpsc::sync_channel::<Vec<Vec2>>(1);
+/// std::thread::spawn(move || loop {
+///     let (player, enemies) = simulate();
+///     camera.write().expect("camera must be writable").look_at(player);
+///     sender.send(enemies).expect("render thread must receive snapshot");
+/// });
+/// let mut enemies = vec![];
+/// loop {
+///     graphics.capture_user_input();
+///     graphics.clear([0.1, 0.1, 0.1, 1.0]);
+///     // latest snapshot is drawn, logic never waits for frame present
+///     if let Ok(snapshot) = receiver.try_recv() {
+///         enemies = snapshot;
+///     }
+///     for enemy in &enemies {
+///         frame.draw_rect(*enemy, [16.0, 16.0], [0.8, 0.2, 0.2, 1.0]);
+///     }
+///     frame.render();
+///     graphics.present();
+/// }
+/// ```
 pub struct Graphics {
     pub(crate) window: Window,
     pub(crate) vulkan: Vulkan,
     pub textures: TexturesManager,
     pub fonts: FontLoaderHandle,
+    /// Rasterizes SVG images to textures in fonts cache directory.
+    #[cfg(feature = "svg")]
+    pub svg: SvgRasterizer,
     pub input: UserInput,
+    redraw: RedrawMode,
+    redraw_requested: bool,
+    fonts_resolution_reference: Option<[u32; 2]>,
+    /// Time resolution scale is recomputed at once window resize settles.
+    resolution_scale_deadline: Option<Instant>,
+    display_dpi: f32,
+    monitor: Monitor,
+    /// Not available on systems without game controller support, e.g. some CI machines.
+    controller_subsystem: Option<GameControllerSubsystem>,
+    controllers: Vec<GameController>,
+    cursor: Option<Cursor>,
+    mouse_grab: bool,
+    deferred: Vec<Box<dyn FnOnce(&mut Graphics)>>,
+    pub(crate) filtering: TextureFiltering,
+    present_mode: PresentMode,
+    mode: GraphicsMode,
+    pub(crate) limiter: FrameLimiter,
+    frame_stats: FrameStats,
+    frame_metrics: FrameMetrics,
+    memory_metrics: MemoryMetrics,
+    pub(crate) stats_overlay: bool,
+    watcher: Option<AssetWatcher>,
+    brightness: f32,
+    gamma: f32,
+    picking: Rc<RefCell<Picking>>,
+    pub(crate) texture_slots: TextureSlotsRegistry,
+    /// Capacity of bindless texture arrays, configured and clamped to device limits.
+    pub(crate) max_textures: u32,
+    pub(crate) samplers: RefCell<Vec<(SamplerOptions, vk::Sampler)>>,
+}
+
+/// Handle to value created by [`Graphics::create_deferred`] once current frame is presented.
+pub struct Deferred<T> {
+    value: Rc<RefCell<Option<T>>>,
+}
+
+impl<T> Deferred<T> {
+    /// Takes created value, returns `None` if frame is not presented yet or value already taken.
+    pub fn take(&self) -> Option<T> {
+        self.value.borrow_mut().take()
+    }
+}
+
+#[derive(Debug)]
+pub enum GraphicsError {
+    Sdl(String),
+    Vulkan(String),
+    Io(io::Error),
+    Font(FontError),
+    Texture(TextureError),
+}
+
+impl From<io::Error> for GraphicsError {
+    fn from(error: io::Error) -> Self {
+        GraphicsError::Io(error)
+    }
+}
+
+impl From<FontError> for GraphicsError {
+    fn from(error: FontError) -> Self {
+        GraphicsError::Font(error)
+    }
+}
+
+impl From<TextureError> for GraphicsError {
+    fn from(error: TextureError) -> Self {
+        GraphicsError::Texture(error)
+    }
 }
 
 impl Graphics {
-    pub fn create(config: GraphicsConfig) -> Self {
+    pub fn create(config: GraphicsConfig) -> Result<Self, GraphicsError> {
+        let config = config.env_overrides();
+        set_crash_context("config", format!("{config:?}"));
         dpi::native::setup_process_dpi();
-        let system = sdl2::init().expect("SDL2 must be initialized");
-        let video = system.video().expect("SDL2 video must be initialized");
-        let display = 0;
-        let bounds = video
-            .display_bounds(display)
-            .expect("display bounds must be determined");
-        let dpi = video
-            .display_dpi(display)
-            .expect("display dpi must be determined");
-        info!("SDL first display bounds is {bounds:?} dpi is {dpi:?}");
-        let [width, height] = config.resolution;
-        let mut window = video
-            .window(&config.title, width, height)
-            .vulkan()
-            //.allow_highdpi()
-            .resizable()
-            .build()
-            .expect("SDL2 window must be created");
-        match config.mode {
-            GraphicsMode::Windowed => {}
-            GraphicsMode::Fullscreen => {
-                window
-                    .set_fullscreen(FullscreenType::True)
-                    .expect("fullscreen mode must be set");
-            }
-            GraphicsMode::Borderless => {
-                window.set_bordered(false);
+        let system = sdl2::init().map_err(GraphicsError::Sdl)?;
+        let video = system.video().map_err(GraphicsError::Sdl)?;
+        let controller_subsystem = match system.game_controller() {
+            Ok(subsystem) => Some(subsystem),
+            Err(error) => {
+                error!("unable to initialize game controllers, {error}");
+                None
             }
+        };
+        let display = select_display(&video, &config.display)?;
+        let bounds = video.display_bounds(display).map_err(GraphicsError::Sdl)?;
+        let dpi = video.display_dpi(display).map_err(GraphicsError::Sdl)?;
+        info!("SDL display {display} bounds is {bounds:?} dpi is {dpi:?}");
+        let [width, height] = config.resolution;
+        let mut builder = video.window(&config.title, width, height);
+        // otherwise high DPI displays get swapchain in points upscaled by compositor
+        builder.vulkan().resizable().allow_highdpi();
+        if let GraphicsMode::Hidden = config.mode {
+            builder.hidden();
         }
-        if let Some([x, y]) = config.position {
-            window.set_position(WindowPos::Positioned(x), WindowPos::Positioned(y));
-        }
+        let mut window = builder
+            .build()
+            .map_err(|error| GraphicsError::Sdl(error.to_string()))?;
+        // window must be moved to display before fullscreen switch to take that display
+        let [x, y] = match config.position {
+            Some([x, y]) => [bounds.x() + x, bounds.y() + y],
+            None => [
+                bounds.x() + (bounds.width() as i32 - width as i32) / 2,
+                bounds.y() + (bounds.height() as i32 - height as i32) / 2,
+            ],
+        };
+        window.set_position(WindowPos::Positioned(x), WindowPos::Positioned(y));
+        apply_mode(&mut window, config.mode)?;
         let drawable = window.vulkan_drawable_size();
         let window_size = window.size();
-        let dpi_scale = drawable.1 as f32 / window_size.1 as f32;
-        info!("SDL window size is {window_size:?} drawable is {drawable:?} dpi scale={dpi_scale}");
-        let present_mode = if config.vsync {
-            vk::PresentModeKHR::FIFO
-        } else {
-            vk::PresentModeKHR::IMMEDIATE
+        let pixel_ratio = get_pixel_ratio(&window).unwrap_or(1.0);
+        let monitor = get_monitor(&window, pixel_ratio);
+        info!("SDL window size is {window_size:?} drawable is {drawable:?} monitor is {monitor:?}");
+        let present_mode = config.get_present_mode();
+        let mut vulkan = unsafe {
+            Vulkan::create(
+                &window,
+                present_mode.to_vulkan(),
+                config.msaa_samples,
+                config.srgb,
+                config.validation,
+            )?
         };
-        let vulkan = unsafe { Vulkan::create(&window, present_mode) };
+        if vulkan.is_srgb() && config.fonts.msdf {
+            warn!("MSDF font atlases are decoded as sRGB, distance shader must compensate");
+        }
+        vulkan.hot_reload_shaders = config.hot_reload_shaders;
+        let max_textures = config
+            .max_textures
+            .clamp(1, vulkan.max_bindless_textures.max(1));
+        if max_textures != config.max_textures {
+            warn!(
+                "Clamps max textures {} to {max_textures} supported by device",
+                config.max_textures
+            );
+        }
         info!("Configures asset loaders");
-        create_dir_all(&config.fonts.cache).expect("all cache sub directories must be created");
+        create_dir_all(&config.fonts.cache)?;
         let textures = vulkan.create_texture_loader_device();
         let textures = TexturesManager::new(textures);
+        let display_dpi = monitor.dpi;
         let fonts_resolution_scale = match config.fonts.resolution_reference {
             None => 1.0,
             Some([_, height]) => drawable.1 as f32 / height as f32,
         };
-        let fonts = FontLoader::new(&config.fonts.cache, fonts_resolution_scale);
-        let input = UserInput::default();
-        Self {
+        let fonts = FontLoader::new(
+            &config.fonts.cache,
+            fonts_resolution_scale,
+            config.fonts.msdf,
+        )?;
+        let mut input = UserInput::default();
+        let (x, y) = window.position();
+        input.window.position = [x, y];
+        input.window.size = [window_size.0, window_size.1];
+        input.window.pixel_ratio = pixel_ratio;
+        input.window.display = display;
+        let watcher = if config.watch_assets.is_empty() {
+            None
+        } else {
+            Some(AssetWatcher::new(config.watch_assets.clone()))
+        };
+        Ok(Self {
             window,
             vulkan,
             textures,
             fonts,
+            #[cfg(feature = "svg")]
+            svg: SvgRasterizer::new(&config.fonts.cache, fonts_resolution_scale),
             input,
-        }
+            redraw: config.redraw,
+            // first frame must be drawn regardless of events
+            redraw_requested: true,
+            fonts_resolution_reference: config.fonts.resolution_reference,
+            resolution_scale_deadline: None,
+            display_dpi,
+            monitor,
+            controller_subsystem,
+            controllers: vec![],
+            cursor: None,
+            mouse_grab: false,
+            deferred: vec![],
+            filtering: config.filtering,
+            present_mode,
+            mode: config.mode,
+            limiter: FrameLimiter::new(config.fps_limit),
+            frame_stats: FrameStats::default(),
+            frame_metrics: FrameMetrics::new(),
+            memory_metrics: MemoryMetrics::new(),
+            stats_overlay: config.stats_overlay,
+            watcher,
+            brightness: config.brightness.max(0.0),
+            gamma: config.gamma.max(0.01),
+            picking: Rc::new(RefCell::new(Picking::default())),
+            texture_slots: TextureSlotsRegistry::default(),
+            max_textures,
+            samplers: RefCell::new(vec![]),
+        })
     }
 
     pub fn clear(&mut self, color: impl Colors) {
         self.vulkan.update();
+        self.reload_changed_assets();
+        for texture in self.textures.retired.drain(..) {
+            self.texture_slots.release(texture);
+            self.vulkan.retire(Retired::Texture(texture));
+        }
+        self.texture_slots.next_frame();
+        if self.minimized() {
+            // input may be captured by application itself, frame must not be prepared anyway
+            self.wait_restored();
+        }
         self.vulkan.prepare(&self.window, color.to_vec4());
+        self.picking.borrow_mut().next_frame();
     }
 
     pub fn present(&mut self) {
         self.vulkan.present();
+        self.record_frame_stats();
+        self.create_deferred_values();
+        self.limiter.wait();
+    }
+
+    /// Returns draw calls, uploads and texture bindings submitted in last presented frame.
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
+    /// Returns device memory allocated for buffers and images and resource counts,
+    /// e.g. to detect leaks of dynamically created render targets.
+    pub fn memory_stats(&self) -> MemoryStats {
+        memory_stats()
+    }
+
+    pub(crate) fn record_frame_stats(&mut self) {
+        self.frame_stats = take_frame_stats();
+        self.frame_metrics.record(&self.frame_stats);
+        self.memory_metrics.record(memory_stats());
+    }
+
+    /// Limits frames per second, e.g. lower limit in menus or while window is out of focus,
+    /// None removes limit.
+    pub fn set_fps_limit(&mut self, fps: Option<f32>) {
+        self.limiter.set_fps(fps);
+    }
+
+    pub fn fps_limit(&self) -> Option<f32> {
+        self.limiter.fps()
+    }
+
+    /// Enables recreation of programs when shader files change, e.g. to live-edit
+    /// shaders in release build or to skip per-frame file checks in debug build.
+    pub fn set_hot_reload_shaders(&mut self, enabled: bool) {
+        self.vulkan.hot_reload_shaders = enabled;
+    }
+
+    pub fn hot_reload_shaders(&self) -> bool {
+        self.vulkan.hot_reload_shaders
+    }
+
+    /// Sets multiplier of final colors, e.g. from accessibility settings of dark game.
+    pub fn set_brightness(&mut self, brightness: f32) {
+        self.brightness = brightness.max(0.0);
+    }
+
+    pub fn brightness(&self) -> f32 {
+        self.brightness
+    }
+
+    pub fn set_gamma(&mut self, gamma: f32) {
+        // zero gamma makes every color white
+        self.gamma = gamma.max(0.01);
+    }
+
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    /// Enables recording of elements tagged by renderers, see [`Graphics::pick`].
+    pub fn set_picking(&mut self, enabled: bool) {
+        self.picking.borrow_mut().set_enabled(enabled);
+    }
+
+    /// Returns id of topmost tagged element at point in window pixels,
+    /// elements are taken from previous frame.
+    pub fn pick(&self, point: Vec2) -> Option<u64> {
+        self.picking.borrow().pick(point)
+    }
+
+    pub fn pick_under_cursor(&self) -> Option<u64> {
+        self.pick(self.input.mouse.raw.cast())
+    }
+
+    /// Returns picking shared with renderers, so they record elements at render time.
+    pub(crate) fn picking(&self) -> Rc<RefCell<Picking>> {
+        self.picking.clone()
+    }
+
+    /// Queues creation of programs, storages or whole renderers until current frame
+    /// is presented, so games can spawn renderers for content loaded mid-play
+    /// without touching resources of frame being recorded.
+    pub fn create_deferred<T, F>(&mut self, create: F) -> Deferred<T>
+    where
+        T: 'static,
+        F: FnOnce(&mut Graphics) -> T + 'static,
+    {
+        let value = Rc::new(RefCell::new(None));
+        let target = value.clone();
+        self.deferred.push(Box::new(move |graphics| {
+            *target.borrow_mut() = Some(create(graphics));
+        }));
+        Deferred { value }
     }
 
+    pub(crate) fn create_deferred_values(&mut self) {
+        for create in take(&mut self.deferred) {
+            create(self);
+        }
+    }
+
+    /// Captures pending input events. In on-demand redraw mode blocks until event occurs,
+    /// texture is loaded or redraw is requested.
+    ///
+    /// Blocks while window is minimized, so application doesn't render in background.
     pub fn capture_user_input(&mut self) {
         self.input.clear();
+        if self.redraw == RedrawMode::OnDemand {
+            self.wait_redraw();
+        }
         while let Some(event) = poll_event() {
-            if let Event::Quit { .. } = event {
-                std::process::exit(0);
-            } else {
-                self.input.handle(event);
+            self.handle_event(event);
+        }
+        if self.minimized() {
+            self.wait_restored();
+        }
+        if let Some(deadline) = self.resolution_scale_deadline {
+            if Instant::now() >= deadline {
+                self.resolution_scale_deadline = None;
+                self.update_resolution_scale();
             }
         }
     }
+
+    /// Returns true if window is minimized or has zero drawable size,
+    /// swapchain can't be created in this state.
+    pub fn minimized(&self) -> bool {
+        let (width, height) = self.window.vulkan_drawable_size();
+        self.input.window.minimized || width == 0 || height == 0
+    }
+
+    /// Hides cursor and reports unbounded mouse motion while enabled, e.g. for
+    /// twin-stick aiming or camera dragging. Motion is accumulated in `input.mouse.motion`.
+    pub fn set_relative_mouse_mode(&mut self, enabled: bool) {
+        self.window
+            .subsystem()
+            .sdl()
+            .mouse()
+            .set_relative_mouse_mode(enabled);
+    }
+
+    pub fn relative_mouse_mode(&self) -> bool {
+        self.window.subsystem().sdl().mouse().relative_mouse_mode()
+    }
+
+    /// Confines cursor to window, e.g. for edge scrolling in fullscreen strategy games.
+    ///
+    /// Grab is released while window is out of focus (e.g. after Alt+Tab),
+    /// so user is never locked out of other windows, and restored on focus gain.
+    pub fn set_mouse_grab(&mut self, enabled: bool) {
+        self.mouse_grab = enabled;
+        self.window.set_mouse_grab(enabled);
+    }
+
+    pub fn mouse_grab(&self) -> bool {
+        self.mouse_grab
+    }
+
+    /// Replaces system cursor by RGBA image, hotspot is click point in image pixels.
+    ///
+    /// Cursor is drawn by OS, so image is taken from memory instead of GPU texture.
+    pub fn set_cursor(
+        &mut self,
+        width: u32,
+        height: u32,
+        rgba: &[u8],
+        hotspot: [i32; 2],
+    ) -> Result<(), GraphicsError> {
+        let mut data = rgba.to_vec();
+        let surface =
+            Surface::from_data(&mut data, width, height, width * 4, PixelFormatEnum::RGBA32)
+                .map_err(GraphicsError::Sdl)?;
+        let [x, y] = hotspot;
+        let cursor = Cursor::from_surface(surface, x, y).map_err(GraphicsError::Sdl)?;
+        cursor.set();
+        self.cursor = Some(cursor);
+        Ok(())
+    }
+
+    /// Replaces system cursor by RGBA PNG image, see [`Graphics::set_cursor`].
+    pub fn set_cursor_from_file(
+        &mut self,
+        path: &str,
+        hotspot: [i32; 2],
+    ) -> Result<(), GraphicsError> {
+        let data = fs::read(path)?;
+        let (info, data) = read_texture_from_data(&data)?;
+        let (width, height) = (info.width as u32, info.height as u32);
+        if data.len() != (width * height * 4) as usize {
+            return Err(TextureError::from("cursor image must be RGBA").into());
+        }
+        self.set_cursor(width, height, &data, hotspot)
+    }
+
+    /// Restores system arrow cursor.
+    pub fn reset_cursor(&mut self) -> Result<(), GraphicsError> {
+        let cursor = Cursor::from_system(SystemCursor::Arrow).map_err(GraphicsError::Sdl)?;
+        cursor.set();
+        self.cursor = Some(cursor);
+        Ok(())
+    }
+
+    pub fn show_cursor(&mut self, visible: bool) {
+        self.window.subsystem().sdl().mouse().show_cursor(visible);
+    }
+
+    /// Changes present mode, swapchain is recreated with new mode on next frame,
+    /// e.g. when player toggles vsync in settings menu.
+    pub fn set_present_mode(&mut self, mode: PresentMode) {
+        if self.present_mode != mode {
+            info!(
+                "Changes present mode from {:?} to {mode:?}",
+                self.present_mode
+            );
+            self.present_mode = mode;
+            self.vulkan.set_present_mode(mode.to_vulkan());
+            self.redraw_requested = true;
+        }
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        self.present_mode
+    }
+
+    /// Switches between FIFO (vsync) and IMMEDIATE present modes.
+    pub fn set_vsync(&mut self, vsync: bool) {
+        let mode = if vsync {
+            PresentMode::Fifo
+        } else {
+            PresentMode::Immediate
+        };
+        self.set_present_mode(mode);
+    }
+
+    /// Returns true if present mode waits for vertical blank.
+    pub fn vsync(&self) -> bool {
+        self.present_mode != PresentMode::Immediate
+    }
+
+    /// Switches window between windowed, borderless and fullscreen modes,
+    /// swapchain and cameras are updated to new size on next frame.
+    pub fn set_mode(&mut self, mode: GraphicsMode) -> Result<(), GraphicsError> {
+        info!("Changes graphics mode from {:?} to {mode:?}", self.mode);
+        apply_mode(&mut self.window, mode)?;
+        self.mode = mode;
+        self.vulkan.request_resize();
+        self.redraw_requested = true;
+        Ok(())
+    }
+
+    pub fn mode(&self) -> GraphicsMode {
+        self.mode
+    }
+
+    /// Changes window size, in fullscreen mode changes display resolution instead.
+    pub fn set_window_size(&mut self, width: u32, height: u32) -> Result<(), GraphicsError> {
+        info!("Changes window size to {width}x{height}");
+        if let GraphicsMode::Fullscreen = self.mode {
+            let mode = self.window.display_mode().map_err(GraphicsError::Sdl)?;
+            let mode =
+                DisplayMode::new(mode.format, width as i32, height as i32, mode.refresh_rate);
+            self.window
+                .set_display_mode(mode)
+                .map_err(GraphicsError::Sdl)?;
+        }
+        self.window
+            .set_size(width, height)
+            .map_err(|error| GraphicsError::Sdl(error.to_string()))?;
+        self.vulkan.request_resize();
+        self.redraw_requested = true;
+        Ok(())
+    }
+
+    pub fn window_size(&self) -> [u32; 2] {
+        let (width, height) = self.window.size();
+        [width, height]
+    }
+
+    /// Returns drawable pixels per logical point of display window is on,
+    /// e.g. 2.0 on Retina display or 1.5 on display scaled to 150%.
+    ///
+    /// User interface sized in points should be multiplied by it to stay sharp
+    /// and keep physical size on every platform.
+    pub fn dpi_scale(&self) -> f32 {
+        self.monitor.dpi_scale
+    }
+
+    /// Returns display window is on, see [`crate::WindowInput::display_changed`]
+    /// to adapt once window is moved to another display.
+    pub fn monitor(&self) -> &Monitor {
+        &self.monitor
+    }
+
+    /// Schedules next frame in on-demand redraw mode, e.g. to continue animation.
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+
+    fn wait_redraw(&mut self) {
+        while !take(&mut self.redraw_requested) {
+            if let Some(event) = wait_event(WAKE_INTERVAL) {
+                self.handle_event(event);
+                break;
+            }
+            if self.textures.update() {
+                break;
+            }
+            // fonts re-rasterized after resize settles must be redrawn
+            if let Some(deadline) = self.resolution_scale_deadline {
+                if Instant::now() >= deadline {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn wait_restored(&mut self) {
+        info!("Suspends rendering while window is minimized");
+        while self.minimized() {
+            if let Some(event) = wait_event(WAKE_INTERVAL) {
+                self.handle_event(event);
+            }
+            self.textures.update();
+        }
+        info!("Resumes rendering after window is restored");
+        // surface extent may be changed while minimized
+        self.vulkan.request_resize();
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match &event {
+            Event::Quit { .. } => {
+                log_validation_summary();
+                std::process::exit(0)
+            }
+            Event::Window {
+                win_event: WindowEvent::DisplayChanged(_),
+                ..
+            } => {
+                self.update_display();
+                self.update_resolution_scale();
+            }
+            Event::Window {
+                win_event: WindowEvent::SizeChanged(..),
+                ..
+            } => {
+                if self.update_display() {
+                    self.update_resolution_scale();
+                } else if self.fonts_resolution_reference.is_some() {
+                    // scale follows drawable height, updated once resize settles
+                    self.resolution_scale_deadline = Some(Instant::now() + RESIZE_SETTLE_INTERVAL);
+                }
+            }
+            Event::Window {
+                win_event: WindowEvent::Moved(..),
+                ..
+            } => {
+                // older SDL versions do not report display change of moved window
+                if self.update_display() {
+                    self.update_resolution_scale();
+                }
+            }
+            Event::Display { .. } => {
+                // display connection or orientation may change dpi of display window is on
+                self.update_display();
+                self.update_resolution_scale();
+            }
+            Event::Window {
+                win_event: WindowEvent::FocusLost,
+                ..
+            } => self.window.set_mouse_grab(false),
+            Event::Window {
+                win_event: WindowEvent::FocusGained,
+                ..
+            } => self.window.set_mouse_grab(self.mouse_grab),
+            Event::ControllerDeviceAdded { which, .. } => {
+                if let Some(subsystem) = self.controller_subsystem.as_ref() {
+                    match subsystem.open(*which) {
+                        Ok(controller) => {
+                            info!("Opens game controller {}", controller.name());
+                            self.controllers.push(controller);
+                        }
+                        Err(error) => error!("unable to open game controller {which}, {error}"),
+                    }
+                }
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.controllers
+                    .retain(|controller| controller.instance_id() != *which);
+            }
+            _ => {}
+        }
+        self.input.handle(event);
+    }
+
+    fn reload_changed_assets(&mut self) {
+        let changes = match self.watcher.as_ref() {
+            Some(watcher) => watcher.changes(),
+            None => return,
+        };
+        for path in changes {
+            self.textures.reload_texture(&path);
+            let mut fonts = self.fonts.write().expect("fonts must be writable");
+            match fonts.reload_font_file(&path) {
+                Ok(replaced) => {
+                    for texture in replaced {
+                        self.textures.evict_texture(&texture);
+                    }
+                }
+                Err(error) => error!("unable to reload font {path:?}, {error:?}"),
+            }
+        }
+    }
+
+    /// Updates display and ratio of drawable pixels to window coordinates, returns true
+    /// if any of them changed.
+    ///
+    /// Swapchain is recreated on next frame once drawable size differs from its extent,
+    /// e.g. window moved from Retina display, without waiting for out of date error.
+    fn update_display(&mut self) -> bool {
+        let mut changed = false;
+        if let Ok(display) = self.window.display_index() {
+            if display != self.input.window.display {
+                info!("Moves window to display {display}");
+                self.input.window.display = display;
+                self.input.window.display_changed = true;
+                changed = true;
+            }
+        }
+        if let Some(ratio) = get_pixel_ratio(&self.window) {
+            if ratio != self.input.window.pixel_ratio {
+                info!("Changes pixel ratio to {ratio}");
+                self.input.window.pixel_ratio = ratio;
+                changed = true;
+            }
+        }
+        let monitor = get_monitor(&self.window, self.input.window.pixel_ratio);
+        if monitor != self.monitor {
+            info!("Changes monitor to {monitor:?}");
+            self.monitor = monitor;
+            self.input.window.display_changed = true;
+            changed = true;
+        }
+        let (width, height) = self.window.vulkan_drawable_size();
+        let extent = self.vulkan.swapchain.extent;
+        if width != extent.width || height != extent.height {
+            self.vulkan.request_resize();
+        }
+        changed
+    }
+
+    /// Recomputes resolution scale after window moved to another display or resized,
+    /// fonts are re-rasterized and cameras are updated if scale changed.
+    ///
+    /// Skipped while window has no drawable area, e.g. minimized.
+    fn update_resolution_scale(&mut self) {
+        let scale = match self.fonts_resolution_reference {
+            // drawable size already reflects display pixel density
+            Some([_, height]) => self.window.vulkan_drawable_size().1 as f32 / height as f32,
+            None => self.monitor.dpi / self.display_dpi,
+        };
+        if !scale.is_finite() || scale <= 0.0 {
+            warn!("Skips resolution scale update, invalid scale {scale}");
+            return;
+        }
+        let mut fonts = self.fonts.write().expect("fonts must be writable");
+        match fonts.set_resolution_scale(scale) {
+            Ok(replaced) => {
+                for texture in replaced {
+                    self.textures.evict_texture(&texture);
+                }
+            }
+            Err(error) => error!("unable to re-rasterize fonts, {error:?}"),
+        }
+        #[cfg(feature = "svg")]
+        match self.svg.set_resolution_scale(scale) {
+            Ok(replaced) => {
+                for texture in replaced {
+                    self.textures.evict_texture(&texture);
+                }
+            }
+            Err(error) => error!("unable to re-rasterize svg images, {error:?}"),
+        }
+        let resize_requested = self.vulkan.is_resize_requested();
+        for camera in self.vulkan.cameras() {
+            let mut camera = camera.write().expect("camera must be writable");
+            camera.set_dpi_scale(self.monitor.dpi_scale);
+            // otherwise camera is updated once swapchain is recreated with new extent
+            if !resize_requested {
+                camera.update(&self.vulkan);
+            }
+        }
+    }
+}
+
+/// Returns drawable pixels per window coordinate, None while window has no drawable area.
+fn get_pixel_ratio(window: &Window) -> Option<f32> {
+    let (width, _) = window.size();
+    let (drawable, _) = window.vulkan_drawable_size();
+    if width == 0 || drawable == 0 {
+        return None;
+    }
+    Some(drawable as f32 / width as f32)
+}
+
+/// Applies window mode, previous mode changes are reverted, so modes can be switched at runtime.
+fn apply_mode(window: &mut Window, mode: GraphicsMode) -> Result<(), GraphicsError> {
+    let fullscreen = match mode {
+        GraphicsMode::Fullscreen => FullscreenType::True,
+        _ => FullscreenType::Off,
+    };
+    window
+        .set_fullscreen(fullscreen)
+        .map_err(GraphicsError::Sdl)?;
+    window.set_bordered(!matches!(mode, GraphicsMode::Borderless));
+    match mode {
+        GraphicsMode::Hidden => window.hide(),
+        _ => window.show(),
+    }
+    Ok(())
+}
+
+/// Returns index of display selected by config, first display if there is no such display.
+fn select_display(video: &VideoSubsystem, select: &GraphicsDisplay) -> Result<i32, GraphicsError> {
+    let displays = video.num_video_displays().map_err(GraphicsError::Sdl)?;
+    let names: Vec<String> = (0..displays)
+        .map(|index| video.display_name(index).unwrap_or_default())
+        .collect();
+    info!("SDL displays {names:?}");
+    let display = match select {
+        GraphicsDisplay::Index(index) => Some(*index).filter(|index| (0..displays).contains(index)),
+        GraphicsDisplay::Name(name) => {
+            let name = name.to_lowercase();
+            names
+                .iter()
+                .position(|display| display.to_lowercase().contains(&name))
+                .map(|index| index as i32)
+        }
+    };
+    Ok(display.unwrap_or_else(|| {
+        warn!("Unable to find display {select:?}, uses first display");
+        0
+    }))
+}
+
+/// Returns display window is on, defaults are used for values platform does not report.
+fn get_monitor(window: &Window, pixel_ratio: f32) -> Monitor {
+    let video = window.subsystem();
+    let display = window.display_index().unwrap_or(0);
+    let dpi = get_display_dpi(window);
+    let refresh_rate = match video.current_display_mode(display) {
+        Ok(mode) => mode.refresh_rate,
+        Err(error) => {
+            error!("unable to determine display refresh rate, {error}");
+            0
+        }
+    };
+    Monitor {
+        display,
+        name: video.display_name(display).unwrap_or_default(),
+        dpi,
+        dpi_scale: dpi::dpi_scale(pixel_ratio, dpi),
+        refresh_rate,
+    }
+}
+
+fn get_display_dpi(window: &Window) -> f32 {
+    let dpi = window
+        .display_index()
+        .and_then(|display| window.subsystem().display_dpi(display));
+    match dpi {
+        Ok((diagonal, _, _)) => diagonal,
+        Err(error) => {
+            error!("unable to determine display dpi, {error}");
+            96.0
+        }
+    }
 }
@@ -1,10 +1,11 @@
-use crate::math::{VecArith, VecCast, VecComponents, VecMagnitude};
-use crate::Camera;
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use crate::math::{Vec2, VecArith, VecCast, VecComponents, VecMagnitude};
+use crate::{Camera, Clock};
+use sdl2::controller::{Axis, Button};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Scancode};
 use sdl2::mouse::MouseButton;
 use sdl2::sys;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::mem;
 use std::time::{Duration, Instant};
 
@@ -13,8 +14,12 @@ pub struct UserInput {
     pub counter: usize,
     pub mouse: MouseInput,
     pub keys: KeysInput,
+    pub gamepad: GamepadInput,
+    pub window: WindowInput,
     pub events: Vec<Event>,
+    /// Real time elapsed since previous frame, see [`Clock`] for scaled time.
     pub time: Duration,
+    pub clock: Clock,
     timestamp: Instant,
 }
 
@@ -24,8 +29,11 @@ impl Default for UserInput {
             counter: 0,
             mouse: MouseInput::default(),
             keys: KeysInput::default(),
+            gamepad: GamepadInput::default(),
+            window: WindowInput::default(),
             events: vec![],
             time: Duration::default(),
+            clock: Clock::default(),
             timestamp: Instant::now(),
         }
     }
@@ -36,30 +44,57 @@ impl UserInput {
         self.counter += 1;
         self.time = self.timestamp.elapsed();
         self.timestamp = Instant::now();
+        self.clock.tick(self.time);
         self.mouse.left.click = false;
         self.mouse.right.click = false;
         self.mouse.wheel = [0.0; 2];
+        self.mouse.motion = [0.0; 2];
         self.keys.pressed.clear();
+        self.keys.scancodes_pressed.clear();
+        self.window.focus_changed = false;
+        self.window.minimized_changed = false;
+        self.window.moved = false;
+        self.window.resized = false;
+        self.window.display_changed = false;
         self.events.clear();
     }
 
     pub(crate) fn handle(&mut self, event: Event) {
         match &event {
             Event::KeyDown {
-                keycode: Some(keycode),
-                ..
+                keycode, scancode, ..
             } => {
-                self.keys.down.push(*keycode);
+                if let Some(keycode) = keycode {
+                    self.keys.down.push(*keycode);
+                }
+                if let Some(scancode) = scancode {
+                    if !self.keys.scancodes.contains(scancode) {
+                        self.keys.scancodes.push(*scancode);
+                    }
+                }
             }
             Event::KeyUp {
-                keycode: Some(keycode),
-                ..
+                keycode, scancode, ..
             } => {
-                self.keys.down.retain(|down| down != keycode);
-                self.keys.pressed.insert(*keycode);
+                if let Some(keycode) = keycode {
+                    self.keys.down.retain(|down| down != keycode);
+                    self.keys.pressed.insert(*keycode);
+                }
+                if let Some(scancode) = scancode {
+                    self.keys.scancodes.retain(|down| down != scancode);
+                    self.keys.scancodes_pressed.insert(*scancode);
+                }
             }
-            Event::MouseMotion { x, y, .. } => {
-                self.mouse.raw = [*x, *y];
+            Event::MouseMotion {
+                x, y, xrel, yrel, ..
+            } => {
+                // SDL reports mouse in window coordinates, rendering is in drawable pixels
+                let ratio = self.window.pixel_ratio;
+                self.mouse.raw = [(*x as f32 * ratio) as i32, (*y as f32 * ratio) as i32];
+                self.mouse.motion = self
+                    .mouse
+                    .motion
+                    .add([*xrel as f32 * ratio, *yrel as f32 * ratio]);
             }
             Event::MouseButtonDown { mouse_btn, .. } => match mouse_btn {
                 MouseButton::Left => {
@@ -70,20 +105,40 @@ impl UserInput {
                 }
                 _ => {}
             },
-            Event::MouseButtonUp { mouse_btn, .. } => match mouse_btn {
+            Event::MouseButtonUp {
+                mouse_btn, clicks, ..
+            } => match mouse_btn {
                 MouseButton::Left => {
                     self.mouse.left.down = false;
                     self.mouse.left.click = true;
+                    self.mouse.left.clicks = *clicks;
                 }
                 MouseButton::Right => {
                     self.mouse.right.down = false;
                     self.mouse.right.click = true;
+                    self.mouse.right.clicks = *clicks;
                 }
                 _ => {}
             },
             Event::MouseWheel { x, y, .. } => {
                 self.mouse.wheel = [*x as f32, *y as f32];
             }
+            Event::ControllerButtonDown { which, button, .. } => {
+                self.gamepad.press(*which, *button);
+            }
+            Event::ControllerButtonUp { which, button, .. } => {
+                self.gamepad.release(*which, *button);
+            }
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => {
+                self.gamepad
+                    .move_axis(*which, *axis, *value as f32 / i16::MAX as f32);
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.gamepad.remove(*which);
+            }
+            Event::Window { win_event, .. } => self.window.handle(win_event),
             _ => {}
         }
         self.events.push(event);
@@ -94,6 +149,10 @@ impl UserInput {
 pub struct KeysInput {
     pub down: Vec<Keycode>,
     pub pressed: HashSet<Keycode>,
+    /// Physical keys down, independent of keyboard layout. Scancodes are named by
+    /// US layout, so `Scancode::W` is the key labeled `Z` on AZERTY keyboard.
+    pub scancodes: Vec<Scancode>,
+    pub scancodes_pressed: HashSet<Scancode>,
 }
 
 impl KeysInput {
@@ -106,13 +165,23 @@ impl KeysInput {
         false
     }
 
+    pub fn scancode_down(&self, scancodes: &[Scancode]) -> bool {
+        for scancode in scancodes {
+            if self.scancodes.contains(scancode) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Uses physical positions of WASD keys, so it works on any keyboard layout.
     pub fn wasd_first_down(&self) -> Option<[f32; 2]> {
-        for keycode in &self.down {
-            let direction = match keycode {
-                Keycode::W => [0.0, -1.0],
-                Keycode::S => [0.0, 1.0],
-                Keycode::A => [-1.0, 0.0],
-                Keycode::D => [1.0, 0.0],
+        for scancode in &self.scancodes {
+            let direction = match scancode {
+                Scancode::W => [0.0, -1.0],
+                Scancode::S => [0.0, 1.0],
+                Scancode::A => [-1.0, 0.0],
+                Scancode::D => [1.0, 0.0],
                 _ => continue,
             };
             return Some(direction);
@@ -120,18 +189,19 @@ impl KeysInput {
         None
     }
 
+    /// Uses physical positions of WASD keys, so it works on any keyboard layout.
     pub fn wasd_xy_direction(&self) -> [f32; 2] {
         let mut delta = [0.0, 0.0];
-        if self.down.contains(&Keycode::W) {
+        if self.scancodes.contains(&Scancode::W) {
             delta[1] -= 1.0;
         }
-        if self.down.contains(&Keycode::A) {
+        if self.scancodes.contains(&Scancode::A) {
             delta[0] -= 1.0;
         }
-        if self.down.contains(&Keycode::S) {
+        if self.scancodes.contains(&Scancode::S) {
             delta[1] += 1.0;
         }
-        if self.down.contains(&Keycode::D) {
+        if self.scancodes.contains(&Scancode::D) {
             delta[0] += 1.0;
         };
         delta.normal()
@@ -141,6 +211,9 @@ impl KeysInput {
 #[derive(Debug, Default, Clone)]
 pub struct MouseInput {
     pub raw: [i32; 2],
+    /// Mouse movement since previous frame in window pixels, unbounded by window
+    /// edges in relative mouse mode, see [`crate::Graphics::set_relative_mouse_mode`].
+    pub motion: [f32; 2],
     pub wheel: [f32; 2],
     pub left: MouseButtonInput,
     pub right: MouseButtonInput,
@@ -148,18 +221,208 @@ pub struct MouseInput {
 
 impl MouseInput {
     pub fn position(&self, camera: &Camera) -> [f32; 2] {
-        self.raw
-            .cast()
+        let raw: Vec2 = self.raw.cast();
+        raw.sub(camera.origin)
             .div(camera.resolution_scale)
             .div(camera.zoom)
             .add(camera.eye.xy())
     }
 }
 
+/// State of application window, e.g. to pause game on focus loss or minimization.
+///
+/// Change flags are set in frame when window event occurs.
+#[derive(Debug, Clone)]
+pub struct WindowInput {
+    pub focused: bool,
+    pub minimized: bool,
+    /// Window position in screen coordinates.
+    pub position: [i32; 2],
+    /// Window size in screen coordinates, may differ from drawable size on high DPI displays.
+    pub size: [u32; 2],
+    /// Display index window is on, changes when window is moved to another monitor.
+    pub display: i32,
+    /// Drawable pixels per window coordinate, e.g. 2.0 on Retina displays.
+    /// Mouse position and motion are scaled by it to drawable pixels.
+    pub pixel_ratio: f32,
+    pub focus_changed: bool,
+    pub minimized_changed: bool,
+    pub moved: bool,
+    pub resized: bool,
+    /// Window moved to another display or dpi scale or refresh rate of display changed.
+    pub display_changed: bool,
+}
+
+impl Default for WindowInput {
+    fn default() -> Self {
+        Self {
+            focused: true,
+            minimized: false,
+            position: [0, 0],
+            size: [0, 0],
+            display: 0,
+            pixel_ratio: 1.0,
+            focus_changed: false,
+            minimized_changed: false,
+            moved: false,
+            resized: false,
+            display_changed: false,
+        }
+    }
+}
+
+impl WindowInput {
+    fn handle(&mut self, event: &WindowEvent) {
+        match *event {
+            WindowEvent::FocusGained => {
+                self.focused = true;
+                self.focus_changed = true;
+            }
+            WindowEvent::FocusLost => {
+                self.focused = false;
+                self.focus_changed = true;
+            }
+            WindowEvent::Minimized => {
+                self.minimized = true;
+                self.minimized_changed = true;
+            }
+            WindowEvent::Restored | WindowEvent::Maximized if self.minimized => {
+                self.minimized = false;
+                self.minimized_changed = true;
+            }
+            WindowEvent::Moved(x, y) => {
+                self.position = [x, y];
+                self.moved = true;
+            }
+            WindowEvent::SizeChanged(width, height) => {
+                self.size = [width.max(0) as u32, height.max(0) as u32];
+                self.resized = true;
+            }
+            WindowEvent::DisplayChanged(display) => {
+                self.display = display;
+                self.display_changed = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Window gained focus in current frame.
+    pub fn focus_gained(&self) -> bool {
+        self.focus_changed && self.focused
+    }
+
+    /// Window lost focus in current frame.
+    pub fn focus_lost(&self) -> bool {
+        self.focus_changed && !self.focused
+    }
+
+    /// Window was restored from minimized state in current frame.
+    pub fn restored(&self) -> bool {
+        self.minimized_changed && !self.minimized
+    }
+}
+
+/// State of connected game controllers, inputs of all controllers are merged.
+#[derive(Debug, Default, Clone)]
+pub struct GamepadInput {
+    pub buttons: HashSet<Button>,
+    /// Axis values in range -1.0 to 1.0, triggers in range 0.0 to 1.0.
+    pub axes: HashMap<Axis, f32>,
+    /// Inputs of every controller by instance id, so removed controller releases its own.
+    controllers: HashMap<u32, ControllerInput>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct ControllerInput {
+    buttons: HashSet<Button>,
+    axes: HashMap<Axis, f32>,
+}
+
+impl GamepadInput {
+    /// Axis values below dead zone are reported as zero, so worn sticks don't drift.
+    pub const DEAD_ZONE: f32 = 0.15;
+
+    pub(crate) fn press(&mut self, controller: u32, button: Button) {
+        let input = self.controllers.entry(controller).or_default();
+        input.buttons.insert(button);
+        self.merge();
+    }
+
+    pub(crate) fn release(&mut self, controller: u32, button: Button) {
+        if let Some(input) = self.controllers.get_mut(&controller) {
+            input.buttons.remove(&button);
+        }
+        self.merge();
+    }
+
+    pub(crate) fn move_axis(&mut self, controller: u32, axis: Axis, value: f32) {
+        let input = self.controllers.entry(controller).or_default();
+        input.axes.insert(axis, value);
+        self.merge();
+    }
+
+    pub(crate) fn remove(&mut self, controller: u32) {
+        self.controllers.remove(&controller);
+        self.merge();
+    }
+
+    /// Merges inputs of controllers, axis takes value of controller deflecting it most.
+    fn merge(&mut self) {
+        self.buttons.clear();
+        self.axes.clear();
+        for input in self.controllers.values() {
+            self.buttons.extend(input.buttons.iter().copied());
+            for (axis, value) in &input.axes {
+                let merged = self.axes.entry(*axis).or_insert(*value);
+                if value.abs() > merged.abs() {
+                    *merged = *value;
+                }
+            }
+        }
+    }
+
+    pub fn axis(&self, axis: Axis) -> f32 {
+        let value = self.axes.get(&axis).copied().unwrap_or(0.0);
+        if value.abs() < Self::DEAD_ZONE {
+            0.0
+        } else {
+            value
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct MouseButtonInput {
     pub click: bool,
     pub down: bool,
+    /// Number of consecutive clicks at the same position ending with last click,
+    /// as counted by SDL within system double click time.
+    pub clicks: u8,
+}
+
+impl MouseButtonInput {
+    pub fn double_click(&self) -> bool {
+        self.click && self.clicks == 2
+    }
+
+    pub fn triple_click(&self) -> bool {
+        self.click && self.clicks == 3
+    }
+}
+
+/// Blocks until next event or timeout, returns None if timeout expired.
+pub(crate) fn wait_event(timeout: Duration) -> Option<Event> {
+    unsafe {
+        let mut raw = mem::MaybeUninit::uninit();
+        let timeout = timeout.as_millis() as i32;
+        let has_pending = sys::SDL_WaitEventTimeout(raw.as_mut_ptr(), timeout) == 1;
+
+        if has_pending {
+            Some(Event::from_ll(raw.assume_init()))
+        } else {
+            None
+        }
+    }
 }
 
 pub(crate) fn poll_event() -> Option<Event> {
@@ -174,3 +437,23 @@ pub(crate) fn poll_event() -> Option<Event> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::GamepadInput;
+    use sdl2::controller::{Axis, Button};
+
+    #[test]
+    pub fn test_removed_controller_keeps_other_controllers_input() {
+        let mut gamepad = GamepadInput::default();
+        gamepad.press(1, Button::A);
+        gamepad.press(2, Button::B);
+        gamepad.move_axis(1, Axis::LeftX, -0.8);
+        gamepad.move_axis(2, Axis::LeftX, 0.5);
+        assert_eq!(gamepad.axis(Axis::LeftX), -0.8);
+        gamepad.remove(1);
+        assert!(!gamepad.buttons.contains(&Button::A));
+        assert!(gamepad.buttons.contains(&Button::B));
+        assert_eq!(gamepad.axis(Axis::LeftX), 0.5);
+    }
+}
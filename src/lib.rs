@@ -1,23 +1,47 @@
+extern crate self as motoro;
+
+pub use actions::*;
 pub use api::*;
 pub use camera::*;
+pub use clock::*;
 pub use config::*;
 pub use fonts::*;
+pub use frame::*;
+pub use game_loop::*;
+pub use graph::*;
 pub use graphics::*;
 pub use input::*;
+pub use picking::*;
+pub use preload::*;
+pub use skeleton::*;
 
+mod actions;
 mod api;
 mod camera;
+mod clock;
 mod colors;
 mod config;
 mod dpi;
+pub mod easing;
 mod fonts;
+mod frame;
+mod game_loop;
+mod graph;
 mod graphics;
 mod input;
+mod limiter;
 pub mod math;
+mod picking;
+mod preload;
 pub mod renderers;
+mod skeleton;
 pub mod system;
+pub mod testing;
 mod textures;
 mod vulkan;
+mod watcher;
+#[cfg(feature = "winit")]
+mod winit_input;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,58 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Caps frame rate by waiting after present, independent of present mode.
+///
+/// OS sleep is too coarse to hold frame interval precisely, so limiter sleeps until
+/// short time before deadline and spins the rest.
+pub(crate) struct FrameLimiter {
+    interval: Option<Duration>,
+    deadline: Instant,
+}
+
+impl FrameLimiter {
+    /// Remaining time before deadline spent in spin loop instead of sleep.
+    const SPIN_THRESHOLD: Duration = Duration::from_millis(2);
+
+    pub fn new(fps: Option<f32>) -> Self {
+        let mut limiter = Self {
+            interval: None,
+            deadline: Instant::now(),
+        };
+        limiter.set_fps(fps);
+        limiter
+    }
+
+    pub fn set_fps(&mut self, fps: Option<f32>) {
+        self.interval = fps
+            .filter(|fps| *fps > 0.0)
+            .map(|fps| Duration::from_secs_f32(1.0 / fps));
+        self.deadline = Instant::now();
+    }
+
+    pub fn fps(&self) -> Option<f32> {
+        self.interval.map(|interval| 1.0 / interval.as_secs_f32())
+    }
+
+    /// Blocks until frame interval is elapsed since previous frame.
+    pub fn wait(&mut self) {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return,
+        };
+        self.deadline += interval;
+        let now = Instant::now();
+        if self.deadline <= now {
+            // frame is late, schedule next one from now instead of catching up by burst
+            self.deadline = now;
+            return;
+        }
+        let remaining = self.deadline - now;
+        if remaining > Self::SPIN_THRESHOLD {
+            thread::sleep(remaining - Self::SPIN_THRESHOLD);
+        }
+        while Instant::now() < self.deadline {
+            std::hint::spin_loop();
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::{PI, TAU};
+use std::hash::Hash;
 use std::ops::{Add, Div, Mul, Neg, Range, Sub};
 
 /// Math module is designed for simple vector and matrix processing.
@@ -34,6 +37,77 @@ impl VecMovement for Vec2 {
     }
 }
 
+#[inline]
+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Returns interpolation factor of value between a and b, inverse of [`lerp`].
+/// Result is not clamped, returns 0.0 if a equals b.
+#[inline]
+pub fn inverse_lerp(a: f32, b: f32, value: f32) -> f32 {
+    if a == b {
+        0.0
+    } else {
+        (value - a) / (b - a)
+    }
+}
+
+/// Maps value from one range to another, e.g. health to bar width.
+#[inline]
+pub fn remap(value: f32, from: Range<f32>, to: Range<f32>) -> f32 {
+    lerp(to.start, to.end, inverse_lerp(from.start, from.end, value))
+}
+
+/// Clamps value like [`f32::clamp`], but doesn't panic if min is greater than max
+/// (e.g. camera bounds smaller than screen), returns min then.
+#[inline]
+pub fn clamp(value: f32, min: f32, max: f32) -> f32 {
+    value.min(max).max(min)
+}
+
+/// Moves value towards target by no more than max delta without overshooting.
+pub fn move_towards(current: f32, target: f32, max_delta: f32) -> f32 {
+    let distance = target - current;
+    if distance.abs() <= max_delta {
+        target
+    } else {
+        current + distance.signum() * max_delta
+    }
+}
+
+/// Returns shortest signed difference between angles in radians, in range -PI to PI,
+/// e.g. to rotate turret by shortest arc.
+pub fn angle_delta(from: f32, to: f32) -> f32 {
+    let delta = (to - from).rem_euclid(TAU);
+    if delta > PI {
+        delta - TAU
+    } else {
+        delta
+    }
+}
+
+/// Interpolates angles in radians by shortest arc.
+pub fn lerp_angle(from: f32, to: f32, t: f32) -> f32 {
+    from + angle_delta(from, to) * t
+}
+
+/// Rotates angle towards target by shortest arc, no more than max delta in radians.
+pub fn move_towards_angle(current: f32, target: f32, max_delta: f32) -> f32 {
+    let delta = angle_delta(current, target);
+    if delta.abs() <= max_delta {
+        target
+    } else {
+        current + delta.signum() * max_delta
+    }
+}
+
+/// Returns signed angle in radians from vector a to vector b, in range -PI to PI,
+/// positive angle is counter-clockwise in Y up coordinates.
+pub fn angle_between(a: Vec2, b: Vec2) -> f32 {
+    cross2(a, b).atan2(a.dot(b))
+}
+
 pub trait VecRange<T> {
     fn range(self) -> Range<T>;
 }
@@ -210,6 +284,7 @@ where
     }
 }
 
+#[deprecated(note = "use Rect::from_points instead")]
 pub fn vec2_aabb(points: &[Vec2]) -> (Vec2, Vec2) {
     let mut min_x = f32::MAX;
     let mut max_x = f32::MIN;
@@ -383,6 +458,7 @@ pub trait VecSpace<T> {
 
     fn has(&self, target: Self) -> bool;
 
+    #[deprecated(note = "use Rect::contains instead")]
     fn in_rect(&self, left_top: Self, size: Self) -> bool;
 }
 
@@ -703,6 +779,17 @@ pub fn mat4_from_translation(delta: Vec3) -> Mat4 {
     ]
 }
 
+/// Returns counter-clockwise rotation around Z axis, angle in radians.
+pub fn mat4_from_rotation_z(angle: f32) -> Mat4 {
+    let (sin, cos) = angle.sin_cos();
+    [
+        [cos, sin, 0.0, 0.0],
+        [-sin, cos, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
 pub fn mat4_row(matrix: Mat4, row: usize) -> Vec4 {
     [
         matrix[0][row],
@@ -730,6 +817,138 @@ pub fn mat4_mul_col(a: Mat4, col: Vec4) -> Vec4 {
     ]
 }
 
+/// Returns inverse of matrix or `None` if matrix is singular (e.g. has zero scale).
+///
+/// Uses Gauss-Jordan elimination with partial pivoting, so works for any transform
+/// including projections, not only for affine ones.
+pub fn mat4_inverse(matrix: Mat4) -> Option<Mat4> {
+    // inverse of transposed matrix is transposed inverse, so elimination can be done
+    // on columns as rows without changing storage order
+    let mut a = matrix;
+    let mut inverse = mat4_identity();
+    for column in 0..4 {
+        let pivot = (column..4)
+            .max_by(|i, j| a[*i][column].abs().total_cmp(&a[*j][column].abs()))
+            .unwrap_or(column);
+        if a[pivot][column].abs() < f32::EPSILON {
+            return None;
+        }
+        a.swap(column, pivot);
+        inverse.swap(column, pivot);
+        let factor = 1.0 / a[column][column];
+        for k in 0..4 {
+            a[column][k] *= factor;
+            inverse[column][k] *= factor;
+        }
+        for row in 0..4 {
+            if row != column {
+                let factor = a[row][column];
+                for k in 0..4 {
+                    a[row][k] -= factor * a[column][k];
+                    inverse[row][k] -= factor * inverse[column][k];
+                }
+            }
+        }
+    }
+    Some(inverse)
+}
+
+/// Transforms point, result is divided by w, so projections are supported.
+pub fn mat4_transform_point(matrix: Mat4, point: Vec3) -> Vec3 {
+    let [x, y, z, w] = mat4_mul_col(matrix, [point[0], point[1], point[2], 1.0]);
+    if w == 0.0 || w == 1.0 {
+        [x, y, z]
+    } else {
+        [x / w, y / w, z / w]
+    }
+}
+
+/// Transforms direction, translation is not applied.
+pub fn mat4_transform_vector(matrix: Mat4, vector: Vec3) -> Vec3 {
+    let [x, y, z, _] = mat4_mul_col(matrix, [vector[0], vector[1], vector[2], 0.0]);
+    [x, y, z]
+}
+
+/// Transforms 2D point lying on z = 0 plane, e.g. to unproject mouse position
+/// by inverse of camera transform.
+pub fn mat4_transform_vec2(matrix: Mat4, point: Vec2) -> Vec2 {
+    let [x, y, _] = mat4_transform_point(matrix, [point[0], point[1], 0.0]);
+    [x, y]
+}
+
+/// Represents rotation as unit quaternion `[x, y, z, w]`, same layout as GLSL `vec4`.
+pub type Quat = [f32; 4];
+
+pub fn quat_identity() -> Quat {
+    [0.0, 0.0, 0.0, 1.0]
+}
+
+/// Returns rotation around axis by angle in radians, axis is normalized.
+pub fn quat_from_axis_angle(axis: Vec3, angle: f32) -> Quat {
+    let [x, y, z] = axis.normal();
+    let (sin, cos) = (angle / 2.0).sin_cos();
+    [x * sin, y * sin, z * sin, cos]
+}
+
+/// Returns product of quaternions, `b` rotation is applied first.
+pub fn quat_mul(a: Quat, b: Quat) -> Quat {
+    let [ax, ay, az, aw] = a;
+    let [bx, by, bz, bw] = b;
+    [
+        aw * bx + ax * bw + ay * bz - az * by,
+        aw * by - ax * bz + ay * bw + az * bx,
+        aw * bz + ax * by - ay * bx + az * bw,
+        aw * bw - ax * bx - ay * by - az * bz,
+    ]
+}
+
+/// Returns inverse rotation of unit quaternion.
+pub fn quat_conjugate(q: Quat) -> Quat {
+    [-q[0], -q[1], -q[2], q[3]]
+}
+
+pub fn quat_rotate(q: Quat, vector: Vec3) -> Vec3 {
+    let [x, y, z, _] = quat_mul(
+        quat_mul(q, [vector[0], vector[1], vector[2], 0.0]),
+        quat_conjugate(q),
+    );
+    [x, y, z]
+}
+
+/// Interpolates rotations by shortest arc with constant angular velocity.
+pub fn quat_slerp(a: Quat, b: Quat, t: f32) -> Quat {
+    let mut cos = a.dot(b);
+    // q and -q are the same rotation, negation takes shortest arc
+    let b = if cos < 0.0 {
+        cos = -cos;
+        b.neg()
+    } else {
+        b
+    };
+    // nearly parallel rotations are interpolated linearly to avoid division by zero sin
+    if cos > 0.9995 {
+        return a.add(b.sub(a).mul(t)).normal();
+    }
+    let angle = cos.acos();
+    let sin = angle.sin();
+    let wa = ((1.0 - t) * angle).sin() / sin;
+    let wb = (t * angle).sin() / sin;
+    a.mul(wa).add(b.mul(wb))
+}
+
+pub fn mat4_from_quat(q: Quat) -> Mat4 {
+    let [x, y, z, w] = q;
+    let (xx, yy, zz) = (x * x, y * y, z * z);
+    let (xy, xz, yz) = (x * y, x * z, y * z);
+    let (wx, wy, wz) = (w * x, w * y, w * z);
+    [
+        [1.0 - 2.0 * (yy + zz), 2.0 * (xy + wz), 2.0 * (xz - wy), 0.0],
+        [2.0 * (xy - wz), 1.0 - 2.0 * (xx + zz), 2.0 * (yz + wx), 0.0],
+        [2.0 * (xz + wy), 2.0 * (yz - wx), 1.0 - 2.0 * (xx + yy), 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
 /// based on [Microsoft Matrix.LookAtRH](https://learn.microsoft.com/en-us/previous-versions/windows/desktop/bb281711(v=vs.85))
 pub fn mat4_look_at_rh(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 {
     let z = eye.sub(target).normal();
@@ -785,3 +1004,910 @@ pub fn mat4_prepend_scale(matrix: &mut Mat4, scale: Vec3) {
     matrix[1][1] *= scale[1];
     matrix[1][2] *= scale[2];
 }
+
+/// Represents axis-aligned rectangle, position is top left corner in Y down coordinates.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+impl Rect {
+    pub fn new(position: Vec2, size: Vec2) -> Self {
+        Self { position, size }
+    }
+
+    pub fn from_min_max(min: Vec2, max: Vec2) -> Self {
+        Self {
+            position: min,
+            size: [max[0] - min[0], max[1] - min[1]],
+        }
+    }
+
+    /// Returns bounding box of points, empty rect at origin if there are no points.
+    pub fn from_points(points: &[Vec2]) -> Self {
+        let mut points = points.iter();
+        let first = match points.next() {
+            Some(point) => *point,
+            None => return Self::default(),
+        };
+        let (min, max) = points.fold((first, first), |(min, max), point| {
+            (
+                [min[0].min(point[0]), min[1].min(point[1])],
+                [max[0].max(point[0]), max[1].max(point[1])],
+            )
+        });
+        Self::from_min_max(min, max)
+    }
+
+    pub fn min(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn max(&self) -> Vec2 {
+        [
+            self.position[0] + self.size[0],
+            self.position[1] + self.size[1],
+        ]
+    }
+
+    pub fn center(&self) -> Vec2 {
+        [
+            self.position[0] + self.size[0] / 2.0,
+            self.position[1] + self.size[1] / 2.0,
+        ]
+    }
+
+    /// Returns true if point is inside rect, edges are inclusive.
+    pub fn contains(&self, point: Vec2) -> bool {
+        let [x, y] = self.max();
+        point[0] >= self.position[0]
+            && point[1] >= self.position[1]
+            && point[0] <= x
+            && point[1] <= y
+    }
+
+    /// Returns true if rects overlap, touching edges are not overlap.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        let [ax, ay] = self.max();
+        let [bx, by] = other.max();
+        self.position[0] < bx
+            && other.position[0] < ax
+            && self.position[1] < by
+            && other.position[1] < ay
+    }
+
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let [ax, ay] = self.max();
+        let [bx, by] = other.max();
+        let min = [
+            self.position[0].max(other.position[0]),
+            self.position[1].max(other.position[1]),
+        ];
+        Some(Self::from_min_max(min, [ax.min(bx), ay.min(by)]))
+    }
+
+    /// Returns smallest rect containing both rects.
+    pub fn union(&self, other: &Rect) -> Rect {
+        let [ax, ay] = self.max();
+        let [bx, by] = other.max();
+        let min = [
+            self.position[0].min(other.position[0]),
+            self.position[1].min(other.position[1]),
+        ];
+        Self::from_min_max(min, [ax.max(bx), ay.max(by)])
+    }
+
+    /// Returns rect grown by amount on every side, negative amount shrinks rect.
+    pub fn inflate(&self, amount: f32) -> Rect {
+        Self {
+            position: [self.position[0] - amount, self.position[1] - amount],
+            size: [
+                (self.size[0] + amount * 2.0).max(0.0),
+                (self.size[1] + amount * 2.0).max(0.0),
+            ],
+        }
+    }
+
+    /// Returns nearest point inside rect.
+    pub fn clamp(&self, point: Vec2) -> Vec2 {
+        let [x, y] = self.max();
+        [
+            point[0].clamp(self.position[0], x.max(self.position[0])),
+            point[1].clamp(self.position[1], y.max(self.position[1])),
+        ]
+    }
+}
+
+impl From<Vec4> for Rect {
+    /// Converts x, y, width, height vector.
+    fn from(value: Vec4) -> Self {
+        let [x, y, width, height] = value;
+        Self::new([x, y], [width, height])
+    }
+}
+
+impl From<Rect> for Vec4 {
+    fn from(rect: Rect) -> Self {
+        let [x, y] = rect.position;
+        let [width, height] = rect.size;
+        [x, y, width, height]
+    }
+}
+
+/// Small seedable pseudo random generator (xoshiro128**), gives the same sequence
+/// for the same seed on every platform, so particle emitters and procedural effects
+/// are reproducible across runs.
+///
+/// Not suitable for cryptography.
+#[derive(Clone, Debug)]
+pub struct Random {
+    state: [u32; 4],
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        // splitmix64 spreads seed bits, so close seeds give unrelated sequences
+        // and state is never all zeros
+        let mut seed = seed;
+        let mut next = || {
+            seed = seed.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            z ^ (z >> 31)
+        };
+        let [a, b] = [next(), next()];
+        Self {
+            state: [a as u32, (a >> 32) as u32, b as u32, (b >> 32) as u32],
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 9;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(11);
+        result
+    }
+
+    /// Returns value in range 0.0 (inclusive) to 1.0 (exclusive).
+    pub fn next_f32(&mut self) -> f32 {
+        // upper 24 bits fit f32 mantissa exactly
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Returns value in range, end is exclusive.
+    pub fn range(&mut self, range: Range<f32>) -> f32 {
+        lerp(range.start, range.end, self.next_f32())
+    }
+
+    /// Returns integer in range, end is exclusive. Returns start if range is empty.
+    pub fn range_i32(&mut self, range: Range<i32>) -> i32 {
+        if range.end <= range.start {
+            return range.start;
+        }
+        let span = range.end.abs_diff(range.start) as u64;
+        let offset = (self.next_u32() as u64 * span) >> 32;
+        range.start.wrapping_add(offset as i32)
+    }
+
+    /// Returns true with given probability in range 0.0 to 1.0.
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        let index = self.range_i32(0..items.len() as i32) as usize;
+        items.get(index)
+    }
+
+    /// Returns random direction of unit length.
+    pub fn unit_vector(&mut self) -> Vec2 {
+        let (sin, cos) = self.range(0.0..TAU).sin_cos();
+        [cos, sin]
+    }
+
+    /// Returns point uniformly distributed inside circle of radius centered at origin.
+    pub fn inside_circle(&mut self, radius: f32) -> Vec2 {
+        // square root compensates area growth with distance, otherwise points cluster at center
+        let distance = radius * self.next_f32().sqrt();
+        self.unit_vector().mul(distance)
+    }
+
+    pub fn inside_rect(&mut self, rect: &Rect) -> Vec2 {
+        let [x, y] = rect.max();
+        [
+            self.range(rect.position[0]..x),
+            self.range(rect.position[1]..y),
+        ]
+    }
+}
+
+/// Returns intersection point of segments a and b, `None` if segments don't cross
+/// or are parallel (including collinear overlap).
+pub fn segment_intersection(a: [Vec2; 2], b: [Vec2; 2]) -> Option<Vec2> {
+    let r = a[1].sub(a[0]);
+    let s = b[1].sub(b[0]);
+    let denominator = cross2(r, s);
+    if denominator.abs() < f32::EPSILON {
+        return None;
+    }
+    let offset = b[0].sub(a[0]);
+    let t = cross2(offset, s) / denominator;
+    let u = cross2(offset, r) / denominator;
+    if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+        Some(a[0].add(r.mul(t)))
+    } else {
+        None
+    }
+}
+
+/// Returns distance along ray to first hit of rect in units of direction length,
+/// 0.0 if ray starts inside rect.
+pub fn ray_rect_intersection(origin: Vec2, direction: Vec2, rect: &Rect) -> Option<f32> {
+    let min = rect.min();
+    let max = rect.max();
+    let mut near = 0.0f32;
+    let mut far = f32::INFINITY;
+    for axis in 0..2 {
+        if direction[axis] == 0.0 {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let a = (min[axis] - origin[axis]) / direction[axis];
+        let b = (max[axis] - origin[axis]) / direction[axis];
+        near = near.max(a.min(b));
+        far = far.min(a.max(b));
+        if near > far {
+            return None;
+        }
+    }
+    Some(near)
+}
+
+/// Returns distance along ray to first hit of circle in units of direction length,
+/// 0.0 if ray starts inside circle.
+pub fn ray_circle_intersection(
+    origin: Vec2,
+    direction: Vec2,
+    center: Vec2,
+    radius: f32,
+) -> Option<f32> {
+    let offset = origin.sub(center);
+    let a = direction.dot(direction);
+    let c = offset.dot(offset) - radius * radius;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+    if a == 0.0 {
+        return None;
+    }
+    let b = offset.dot(direction);
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t = (-b - discriminant.sqrt()) / a;
+    if t >= 0.0 {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// Returns true if point is inside polygon by even-odd rule, so works for concave
+/// and self-intersecting polygons. Polygon is closed implicitly.
+pub fn polygon_contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    let mut previous = match polygon.last() {
+        Some(vertex) => *vertex,
+        None => return false,
+    };
+    for vertex in polygon {
+        let [x0, y0] = previous;
+        let [x1, y1] = *vertex;
+        if (y1 > point[1]) != (y0 > point[1]) {
+            let x = x1 + (point[1] - y1) * (x0 - x1) / (y0 - y1);
+            if point[0] < x {
+                inside = !inside;
+            }
+        }
+        previous = *vertex;
+    }
+    inside
+}
+
+#[inline]
+fn cross2(a: Vec2, b: Vec2) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+/// Represents 2D affine transform in homogeneous coordinates, column-major like [`Mat4`].
+pub type Mat3 = [[f32; 3]; 3];
+
+pub fn mat3_identity() -> Mat3 {
+    [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+}
+
+/// Returns counter-clockwise rotation, angle in radians.
+pub fn mat3_from_rotation(angle: f32) -> Mat3 {
+    let (sin, cos) = angle.sin_cos();
+    [[cos, sin, 0.0], [-sin, cos, 0.0], [0.0, 0.0, 1.0]]
+}
+
+pub fn mat3_from_translation(delta: Vec2) -> Mat3 {
+    [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [delta[0], delta[1], 1.0]]
+}
+
+pub fn mat3_from_scale(scale: Vec2) -> Mat3 {
+    [[scale[0], 0.0, 0.0], [0.0, scale[1], 0.0], [0.0, 0.0, 1.0]]
+}
+
+/// Returns product of matrices, `b` is applied first.
+pub fn mat3_mul(a: Mat3, b: Mat3) -> Mat3 {
+    let mut result = [[0.0; 3]; 3];
+    for column in 0..3 {
+        for row in 0..3 {
+            result[column][row] = (0..3).map(|k| a[k][row] * b[column][k]).sum();
+        }
+    }
+    result
+}
+
+pub fn mat3_transform_point(matrix: Mat3, point: Vec2) -> Vec2 {
+    let [x, y] = point;
+    [
+        matrix[0][0] * x + matrix[1][0] * y + matrix[2][0],
+        matrix[0][1] * x + matrix[1][1] * y + matrix[2][1],
+    ]
+}
+
+/// Returns 2D transform extended to 3D space, Z axis is kept as is.
+pub fn mat4_from_mat3(matrix: Mat3) -> Mat4 {
+    [
+        [matrix[0][0], matrix[0][1], 0.0, 0.0],
+        [matrix[1][0], matrix[1][1], 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [matrix[2][0], matrix[2][1], 0.0, 1.0],
+    ]
+}
+
+pub fn bezier_quadratic(p0: Vec2, p1: Vec2, p2: Vec2, t: f32) -> Vec2 {
+    let s = 1.0 - t;
+    p0.mul(s * s).add(p1.mul(2.0 * s * t)).add(p2.mul(t * t))
+}
+
+pub fn bezier_cubic(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, t: f32) -> Vec2 {
+    let s = 1.0 - t;
+    p0.mul(s * s * s)
+        .add(p1.mul(3.0 * s * s * t))
+        .add(p2.mul(3.0 * s * t * t))
+        .add(p3.mul(t * t * t))
+}
+
+/// Returns polyline approximating quadratic curve, points deviate from curve
+/// by no more than tolerance. First and last points are curve ends.
+pub fn bezier_quadratic_flatten(p0: Vec2, p1: Vec2, p2: Vec2, tolerance: f32) -> Vec<Vec2> {
+    // quadratic curve is exactly representable by cubic one
+    let c1 = p0.add(p1.sub(p0).mul(2.0 / 3.0));
+    let c2 = p2.add(p1.sub(p2).mul(2.0 / 3.0));
+    bezier_cubic_flatten(p0, c1, c2, p2, tolerance)
+}
+
+/// Returns polyline approximating cubic curve, points deviate from curve
+/// by no more than tolerance. First and last points are curve ends.
+pub fn bezier_cubic_flatten(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, tolerance: f32) -> Vec<Vec2> {
+    let mut points = vec![p0];
+    flatten_cubic(&mut points, [p0, p1, p2, p3], tolerance.max(1e-4), 0);
+    points
+}
+
+/// Subdivides curve by de Casteljau algorithm until control points are close enough to chord.
+fn flatten_cubic(points: &mut Vec<Vec2>, curve: [Vec2; 4], tolerance: f32, depth: usize) {
+    const MAX_DEPTH: usize = 16;
+    let [p0, p1, p2, p3] = curve;
+    let flat = distance_to_line(p1, p0, p3).max(distance_to_line(p2, p0, p3)) <= tolerance;
+    if flat || depth >= MAX_DEPTH {
+        points.push(p3);
+        return;
+    }
+    let p01 = p0.add(p1).mul(0.5);
+    let p12 = p1.add(p2).mul(0.5);
+    let p23 = p2.add(p3).mul(0.5);
+    let p012 = p01.add(p12).mul(0.5);
+    let p123 = p12.add(p23).mul(0.5);
+    let middle = p012.add(p123).mul(0.5);
+    flatten_cubic(points, [p0, p01, p012, middle], tolerance, depth + 1);
+    flatten_cubic(points, [middle, p123, p23, p3], tolerance, depth + 1);
+}
+
+/// Returns distance from point to line through a and b, or to a if line is degenerate.
+fn distance_to_line(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let line = b.sub(a);
+    let offset = point.sub(a);
+    let length = line.magnitude();
+    if length < f32::EPSILON {
+        offset.magnitude()
+    } else {
+        (line[0] * offset[1] - line[1] * offset[0]).abs() / length
+    }
+}
+
+/// Evaluates uniform Catmull-Rom segment between p1 and p2, p0 and p3 are neighbour points.
+pub fn catmull_rom(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, t: f32) -> Vec2 {
+    let [c1, c2] = catmull_rom_controls(p0, p1, p2, p3);
+    bezier_cubic(p1, c1, c2, p2, t)
+}
+
+/// Returns polyline of smooth curve passing through all points, e.g. path of patrolling unit.
+/// Curve points deviate from spline by no more than tolerance.
+pub fn catmull_rom_flatten(points: &[Vec2], tolerance: f32) -> Vec<Vec2> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let last = points.len() - 1;
+    let mut polyline = vec![points[0]];
+    for i in 0..last {
+        // end points are duplicated to give end segments tangent
+        let p0 = points[i.saturating_sub(1)];
+        let p3 = points[(i + 2).min(last)];
+        let [c1, c2] = catmull_rom_controls(p0, points[i], points[i + 1], p3);
+        let segment = bezier_cubic_flatten(points[i], c1, c2, points[i + 1], tolerance);
+        polyline.extend_from_slice(&segment[1..]);
+    }
+    polyline
+}
+
+/// Returns Bezier control points of Catmull-Rom segment between p1 and p2.
+fn catmull_rom_controls(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2) -> [Vec2; 2] {
+    [p1.add(p2.sub(p0).div(6.0)), p2.sub(p3.sub(p1).div(6.0))]
+}
+
+/// Uniform grid spatial index of rects, for culling, picking and broad-phase collision.
+///
+/// Each key is stored in every cell its rect overlaps, so cell size should be close to
+/// typical rect size: too small cells multiply entries, too large cells give more candidates.
+#[derive(Clone, Debug)]
+pub struct SpatialHash<K> {
+    cell_size: f32,
+    cells: HashMap<Vec2i, Vec<K>>,
+    bounds: HashMap<K, Rect>,
+}
+
+impl<K: Copy + Eq + Hash> SpatialHash<K> {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(f32::EPSILON),
+            cells: HashMap::new(),
+            bounds: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.bounds.clear();
+    }
+
+    pub fn get(&self, key: K) -> Option<Rect> {
+        self.bounds.get(&key).copied()
+    }
+
+    /// Inserts key with bounds, bounds of already inserted key are replaced.
+    pub fn insert(&mut self, key: K, bounds: Rect) {
+        self.remove(key);
+        for cell in self.cells_of(&bounds) {
+            self.cells.entry(cell).or_default().push(key);
+        }
+        self.bounds.insert(key, bounds);
+    }
+
+    pub fn remove(&mut self, key: K) -> Option<Rect> {
+        let bounds = self.bounds.remove(&key)?;
+        for cell in self.cells_of(&bounds) {
+            if let Some(keys) = self.cells.get_mut(&cell) {
+                keys.retain(|other| *other != key);
+                if keys.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+        Some(bounds)
+    }
+
+    /// Returns keys with bounds overlapping area, see [`Rect::intersects`].
+    pub fn query(&self, area: &Rect) -> Vec<K> {
+        self.query_by(area, |bounds| bounds.intersects(area))
+    }
+
+    /// Returns keys with bounds containing point, e.g. elements under cursor.
+    pub fn query_point(&self, point: Vec2) -> Vec<K> {
+        let area = Rect::new(point, [0.0, 0.0]);
+        self.query_by(&area, |bounds| bounds.contains(point))
+    }
+
+    fn query_by<F>(&self, area: &Rect, test: F) -> Vec<K>
+    where
+        F: Fn(&Rect) -> bool,
+    {
+        let mut visited = HashSet::new();
+        let mut keys = vec![];
+        for cell in self.cells_of(area) {
+            for key in self.cells.get(&cell).into_iter().flatten() {
+                if visited.insert(*key) && test(&self.bounds[key]) {
+                    keys.push(*key);
+                }
+            }
+        }
+        keys
+    }
+
+    fn cells_of(&self, rect: &Rect) -> impl Iterator<Item = Vec2i> {
+        let cell = |value: f32| (value / self.cell_size).floor() as i32;
+        let [min_x, min_y] = rect.min().map(cell);
+        let [max_x, max_y] = rect.max().map(cell);
+        (min_y..=max_y).flat_map(move |y| (min_x..=max_x).map(move |x| [x, y]))
+    }
+}
+
+/// Converts float to IEEE 754 half precision bits rounding to nearest even,
+/// e.g. for RGBA16F texture data.
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x007f_ffff;
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x0200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    let (half, remainder, midpoint) = if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        // subnormal half, implicit leading bit becomes explicit
+        let mantissa = mantissa | 0x0080_0000;
+        let shift = (14 - exponent) as u32;
+        let midpoint = 1 << (shift - 1);
+        (mantissa >> shift, mantissa & ((1 << shift) - 1), midpoint)
+    } else {
+        let half = ((exponent as u32) << 10) | (mantissa >> 13);
+        (half, mantissa & 0x1fff, 0x1000)
+    };
+    let round = remainder > midpoint || (remainder == midpoint && half & 1 == 1);
+    // rounding carry may overflow mantissa into exponent, up to infinity
+    sign | (half + round as u32) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::{
+        angle_between, angle_delta, bezier_cubic, bezier_cubic_flatten, catmull_rom_flatten,
+        f32_to_f16, inverse_lerp, lerp_angle, mat3_from_rotation, mat3_from_scale,
+        mat3_from_translation, mat3_mul, mat3_transform_point, mat4_from_mat3, mat4_from_quat,
+        mat4_from_rotation_z, mat4_from_scale, mat4_from_translation, mat4_identity, mat4_inverse,
+        mat4_mul, mat4_orthographic, mat4_transform_point, mat4_transform_vec2,
+        mat4_transform_vector, move_towards_angle, polygon_contains, quat_from_axis_angle,
+        quat_identity, quat_mul, quat_rotate, quat_slerp, ray_circle_intersection,
+        ray_rect_intersection, remap, segment_intersection, Mat4, Random, Rect, SpatialHash,
+    };
+    use std::f32::consts::{PI, TAU};
+
+    fn assert_mat4_eq(a: Mat4, b: Mat4) {
+        for column in 0..4 {
+            for row in 0..4 {
+                let (a, b) = (a[column][row], b[column][row]);
+                assert!((a - b).abs() < 1e-4, "{a} != {b} at {column},{row}");
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_inverse_of_transform_gives_identity() {
+        let transform = mat4_mul(
+            mat4_from_translation([10.0, -4.0, 0.5]),
+            mat4_mul(mat4_from_rotation_z(0.7), mat4_from_scale([2.0, 3.0, 1.0])),
+        );
+        let inverse = mat4_inverse(transform).expect("transform must be invertible");
+        assert_mat4_eq(mat4_mul(transform, inverse), mat4_identity());
+        assert_mat4_eq(mat4_mul(inverse, transform), mat4_identity());
+    }
+
+    #[test]
+    pub fn test_singular_matrix_has_no_inverse() {
+        assert!(mat4_inverse(mat4_from_scale([1.0, 0.0, 1.0])).is_none());
+    }
+
+    #[test]
+    pub fn test_unproject_point_through_orthographic_projection() {
+        let proj = mat4_orthographic(0.0, 800.0, 0.0, 600.0, 0.0, 1.0);
+        let model = mat4_mul(
+            mat4_from_scale([2.0, 2.0, 1.0]),
+            mat4_from_translation([-50.0, -20.0, 0.0]),
+        );
+        let transform = mat4_mul(proj, model);
+        let point = [120.0, 80.0];
+        let projected = mat4_transform_vec2(transform, point);
+        let inverse = mat4_inverse(transform).expect("transform must be invertible");
+        let [x, y] = mat4_transform_vec2(inverse, projected);
+        assert!(
+            (x - 120.0).abs() < 1e-3 && (y - 80.0).abs() < 1e-3,
+            "{x},{y}"
+        );
+    }
+
+    #[test]
+    pub fn test_vector_transform_ignores_translation() {
+        let transform = mat4_from_translation([5.0, 5.0, 5.0]);
+        assert_eq!(
+            mat4_transform_vector(transform, [1.0, 2.0, 3.0]),
+            [1.0, 2.0, 3.0]
+        );
+        assert_eq!(
+            mat4_transform_point(transform, [1.0, 2.0, 3.0]),
+            [6.0, 7.0, 8.0]
+        );
+    }
+
+    #[test]
+    pub fn test_mat3_rotates_scaled_point_around_pivot() {
+        let pivot = [10.0, 10.0];
+        let transform = mat3_mul(
+            mat3_from_translation(pivot),
+            mat3_mul(
+                mat3_from_rotation(std::f32::consts::FRAC_PI_2),
+                mat3_mul(
+                    mat3_from_scale([2.0, 2.0]),
+                    mat3_from_translation([-10.0, -10.0]),
+                ),
+            ),
+        );
+        let [x, y] = mat3_transform_point(transform, [11.0, 10.0]);
+        assert!(
+            (x - 10.0).abs() < 1e-5 && (y - 12.0).abs() < 1e-5,
+            "{x},{y}"
+        );
+        let [x, y, _] = mat4_transform_point(mat4_from_mat3(transform), [11.0, 10.0, 0.0]);
+        assert!(
+            (x - 10.0).abs() < 1e-5 && (y - 12.0).abs() < 1e-5,
+            "{x},{y}"
+        );
+        let rotation = mat4_from_mat3(mat3_from_rotation(0.3));
+        assert_mat4_eq(rotation, mat4_from_rotation_z(0.3));
+    }
+
+    #[test]
+    pub fn test_rect_intersection_and_union() {
+        let a = Rect::new([0.0, 0.0], [10.0, 10.0]);
+        let b = Rect::new([5.0, 8.0], [10.0, 10.0]);
+        assert!(a.intersects(&b));
+        assert_eq!(a.intersection(&b), Some(Rect::new([5.0, 8.0], [5.0, 2.0])));
+        assert_eq!(a.union(&b), Rect::new([0.0, 0.0], [15.0, 18.0]));
+        let touching = Rect::new([10.0, 0.0], [5.0, 5.0]);
+        assert!(!a.intersects(&touching));
+        assert_eq!(a.intersection(&touching), None);
+        assert!(a.contains([10.0, 10.0]));
+        assert_eq!(a.clamp([-3.0, 12.0]), [0.0, 10.0]);
+        assert_eq!(a.inflate(1.0), Rect::new([-1.0, -1.0], [12.0, 12.0]));
+        let points = [[3.0, 4.0], [-1.0, 6.0], [2.0, -2.0]];
+        assert_eq!(
+            Rect::from_points(&points),
+            Rect::new([-1.0, -2.0], [4.0, 8.0])
+        );
+    }
+
+    #[test]
+    pub fn test_flattened_cubic_stays_within_tolerance() {
+        let curve = [[0.0, 0.0], [0.0, 100.0], [100.0, 100.0], [100.0, 0.0]];
+        let [p0, p1, p2, p3] = curve;
+        let polyline = bezier_cubic_flatten(p0, p1, p2, p3, 0.25);
+        assert_eq!(polyline.first(), Some(&p0));
+        assert_eq!(polyline.last(), Some(&p3));
+        assert!(polyline.len() > 8);
+        for step in 0..=100 {
+            let point = bezier_cubic(p0, p1, p2, p3, step as f32 / 100.0);
+            let distance = polyline
+                .windows(2)
+                .map(|segment| {
+                    let [a, b] = [segment[0], segment[1]];
+                    let line = [b[0] - a[0], b[1] - a[1]];
+                    let t = ((point[0] - a[0]) * line[0] + (point[1] - a[1]) * line[1])
+                        / (line[0] * line[0] + line[1] * line[1]);
+                    let t = t.clamp(0.0, 1.0);
+                    let closest = [a[0] + line[0] * t, a[1] + line[1] * t];
+                    ((point[0] - closest[0]).powi(2) + (point[1] - closest[1]).powi(2)).sqrt()
+                })
+                .fold(f32::INFINITY, f32::min);
+            assert!(distance <= 0.25, "{point:?} is {distance} away");
+        }
+    }
+
+    #[test]
+    pub fn test_catmull_rom_passes_through_points() {
+        let points = [[0.0, 0.0], [10.0, 20.0], [30.0, -5.0], [50.0, 10.0]];
+        let polyline = catmull_rom_flatten(&points, 0.1);
+        for point in points {
+            assert!(polyline.contains(&point), "{point:?} is not on spline");
+        }
+    }
+
+    #[test]
+    pub fn test_angles_wrap_by_shortest_arc() {
+        let (near_pi, near_minus_pi) = (3.0, -3.0);
+        let delta = angle_delta(near_pi, near_minus_pi);
+        assert!((delta - (TAU - 6.0)).abs() < 1e-5);
+        assert!((lerp_angle(near_pi, near_minus_pi, 0.5).abs() - PI).abs() < 1e-5);
+        let rotated = move_towards_angle(near_pi, near_minus_pi, 0.1);
+        assert!((rotated - 3.1).abs() < 1e-5);
+        assert_eq!(
+            move_towards_angle(near_pi, near_minus_pi, 1.0),
+            near_minus_pi
+        );
+        assert!((angle_between([1.0, 0.0], [0.0, 1.0]) - PI / 2.0).abs() < 1e-5);
+        assert!((angle_between([1.0, 0.0], [0.0, -1.0]) + PI / 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    pub fn test_remap_between_ranges() {
+        assert_eq!(remap(75.0, 0.0..100.0, 0.0..200.0), 150.0);
+        assert_eq!(remap(0.5, 1.0..0.0, -1.0..1.0), 0.0);
+        assert_eq!(inverse_lerp(2.0, 2.0, 5.0), 0.0);
+    }
+
+    #[test]
+    pub fn test_segment_and_ray_intersections() {
+        let crossing = segment_intersection([[0.0, 0.0], [10.0, 10.0]], [[0.0, 10.0], [10.0, 0.0]]);
+        assert_eq!(crossing, Some([5.0, 5.0]));
+        let apart = segment_intersection([[0.0, 0.0], [1.0, 1.0]], [[0.0, 10.0], [10.0, 0.0]]);
+        assert_eq!(apart, None);
+        let rect = Rect::new([10.0, -5.0], [10.0, 10.0]);
+        assert_eq!(
+            ray_rect_intersection([0.0, 0.0], [2.0, 0.0], &rect),
+            Some(5.0)
+        );
+        assert_eq!(ray_rect_intersection([0.0, 0.0], [-1.0, 0.0], &rect), None);
+        assert_eq!(
+            ray_rect_intersection([15.0, 0.0], [0.0, 1.0], &rect),
+            Some(0.0)
+        );
+        assert_eq!(
+            ray_circle_intersection([0.0, 0.0], [1.0, 0.0], [10.0, 0.0], 2.0),
+            Some(8.0)
+        );
+        assert_eq!(
+            ray_circle_intersection([0.0, 0.0], [0.0, 1.0], [10.0, 0.0], 2.0),
+            None
+        );
+    }
+
+    #[test]
+    pub fn test_point_in_concave_polygon() {
+        let polygon = [
+            [0.0, 0.0],
+            [10.0, 0.0],
+            [10.0, 10.0],
+            [5.0, 2.0],
+            [0.0, 10.0],
+        ];
+        assert!(polygon_contains(&polygon, [2.0, 2.0]));
+        assert!(!polygon_contains(&polygon, [5.0, 8.0]));
+        assert!(!polygon_contains(&polygon, [-1.0, 1.0]));
+        assert!(!polygon_contains(&[], [0.0, 0.0]));
+    }
+
+    #[test]
+    pub fn test_quaternion_rotation_matches_matrix() {
+        let rotation = quat_from_axis_angle([0.0, 0.0, 2.0], 0.7);
+        assert_mat4_eq(mat4_from_quat(rotation), mat4_from_rotation_z(0.7));
+        let [x, y, z] = quat_rotate(rotation, [1.0, 0.0, 0.0]);
+        assert!((x - 0.7f32.cos()).abs() < 1e-5 && (y - 0.7f32.sin()).abs() < 1e-5);
+        assert!(z.abs() < 1e-5);
+        let twice = quat_mul(rotation, rotation);
+        assert_mat4_eq(mat4_from_quat(twice), mat4_from_rotation_z(1.4));
+    }
+
+    #[test]
+    pub fn test_slerp_halfway_and_shortest_arc() {
+        let axis = [0.0, 1.0, 0.0];
+        let a = quat_identity();
+        let b = quat_from_axis_angle(axis, PI / 2.0);
+        let halfway = quat_slerp(a, b, 0.5);
+        assert_mat4_eq(
+            mat4_from_quat(halfway),
+            mat4_from_quat(quat_from_axis_angle(axis, PI / 4.0)),
+        );
+        // 350 degrees is reached by -10 degrees arc
+        let far = quat_from_axis_angle(axis, PI * 2.0 - 0.2);
+        let near = quat_slerp(a, far, 0.5);
+        assert_mat4_eq(
+            mat4_from_quat(near),
+            mat4_from_quat(quat_from_axis_angle(axis, -0.1)),
+        );
+        assert_eq!(quat_slerp(a, b, 0.0), a);
+    }
+
+    #[test]
+    pub fn test_random_is_reproducible_and_in_range() {
+        let mut a = Random::new(42);
+        let mut b = Random::new(42);
+        let mut c = Random::new(43);
+        let sequence: Vec<u32> = (0..8).map(|_| a.next_u32()).collect();
+        assert_eq!(sequence, (0..8).map(|_| b.next_u32()).collect::<Vec<_>>());
+        assert_ne!(sequence, (0..8).map(|_| c.next_u32()).collect::<Vec<_>>());
+        for _ in 0..1000 {
+            let value = a.range(-2.0..3.0);
+            assert!((-2.0..3.0).contains(&value));
+            let value = a.range_i32(-5..5);
+            assert!((-5..5).contains(&value));
+            let point = a.inside_circle(10.0);
+            assert!(point[0].hypot(point[1]) <= 10.0);
+            let direction = a.unit_vector();
+            assert!((direction[0].hypot(direction[1]) - 1.0).abs() < 1e-5);
+        }
+        assert_eq!(a.pick::<u8>(&[]), None);
+        assert_eq!(a.range_i32(3..3), 3);
+    }
+
+    #[test]
+    pub fn test_spatial_hash_insert_remove_query() {
+        let mut index = SpatialHash::new(10.0);
+        index.insert(1, Rect::new([0.0, 0.0], [5.0, 5.0]));
+        index.insert(2, Rect::new([8.0, 8.0], [15.0, 15.0]));
+        index.insert(3, Rect::new([-30.0, -30.0], [4.0, 4.0]));
+        let mut keys = index.query(&Rect::new([4.0, 4.0], [6.0, 6.0]));
+        keys.sort();
+        assert_eq!(keys, vec![1, 2]);
+        assert_eq!(
+            index.query(&Rect::new([20.0, 0.0], [5.0, 5.0])),
+            Vec::<i32>::new()
+        );
+        assert_eq!(index.query_point([-28.0, -28.0]), vec![3]);
+        index.insert(1, Rect::new([100.0, 100.0], [5.0, 5.0]));
+        assert_eq!(index.query_point([2.0, 2.0]), Vec::<i32>::new());
+        assert_eq!(index.query_point([101.0, 101.0]), vec![1]);
+        assert_eq!(index.remove(2), Some(Rect::new([8.0, 8.0], [15.0, 15.0])));
+        assert_eq!(
+            index.query(&Rect::new([0.0, 0.0], [50.0, 50.0])),
+            Vec::<i32>::new()
+        );
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    pub fn test_f32_to_f16_rounds_to_nearest_even() {
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(0.5), 0x3800);
+        assert_eq!(f32_to_f16(0.1), 0x2e66);
+        assert_eq!(f32_to_f16(65504.0), 0x7bff);
+        assert_eq!(f32_to_f16(65536.0), 0x7c00);
+        assert_eq!(f32_to_f16(f32::NEG_INFINITY), 0xfc00);
+        assert_eq!(f32_to_f16(f32::NAN) & 0x7c00, 0x7c00);
+        assert_eq!(f32_to_f16(2.0f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16(2.0f32.powi(-26)), 0x0000);
+        assert_eq!(f32_to_f16(1.0 + 2.0f32.powi(-11)), 0x3c00);
+        assert_eq!(f32_to_f16(1.0 + 3.0 * 2.0f32.powi(-11)), 0x3c02);
+    }
+}
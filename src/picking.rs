@@ -0,0 +1,92 @@
+use crate::math::{Rect, Vec2};
+
+/// Element tagged by renderer for picking, bounds are in screen pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PickTarget {
+    pub id: u64,
+    pub bounds: Rect,
+}
+
+/// CPU hit-testing of rendered elements, answers what is under the cursor.
+///
+/// Renderers record bounds of tagged elements in draw order while frame is rendered,
+/// queries are answered from last completed frame, so results match what is on screen.
+#[derive(Debug, Default)]
+pub struct Picking {
+    enabled: bool,
+    recording: Vec<PickTarget>,
+    targets: Vec<PickTarget>,
+}
+
+impl Picking {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.recording.clear();
+            self.targets.clear();
+        }
+    }
+
+    /// Records element drawn over previously recorded ones, ignored if picking disabled.
+    pub fn record(&mut self, id: u64, bounds: Rect) {
+        if self.enabled {
+            self.recording.push(PickTarget { id, bounds });
+        }
+    }
+
+    /// Completes recording of frame, its elements are used by next queries.
+    pub fn next_frame(&mut self) {
+        self.targets = std::mem::take(&mut self.recording);
+    }
+
+    pub fn targets(&self) -> &[PickTarget] {
+        &self.targets
+    }
+
+    /// Returns id of topmost element containing point.
+    pub fn pick(&self, point: Vec2) -> Option<u64> {
+        self.targets
+            .iter()
+            .rev()
+            .find(|target| target.bounds.contains(point))
+            .map(|target| target.id)
+    }
+
+    /// Returns ids of all elements containing point, topmost first.
+    pub fn pick_all(&self, point: Vec2) -> Vec<u64> {
+        self.targets
+            .iter()
+            .rev()
+            .filter(|target| target.bounds.contains(point))
+            .map(|target| target.id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::Rect;
+    use crate::Picking;
+
+    #[test]
+    pub fn test_topmost_element_of_previous_frame_is_picked() {
+        let mut picking = Picking::default();
+        picking.record(1, Rect::new([0.0, 0.0], [100.0, 100.0]));
+        assert!(picking.targets().is_empty());
+        picking.set_enabled(true);
+        picking.record(1, Rect::new([0.0, 0.0], [100.0, 100.0]));
+        picking.record(2, Rect::new([40.0, 40.0], [20.0, 20.0]));
+        assert_eq!(picking.pick([50.0, 50.0]), None);
+        picking.next_frame();
+        assert_eq!(picking.pick([50.0, 50.0]), Some(2));
+        assert_eq!(picking.pick([10.0, 10.0]), Some(1));
+        assert_eq!(picking.pick([150.0, 10.0]), None);
+        assert_eq!(picking.pick_all([50.0, 50.0]), vec![2, 1]);
+        picking.next_frame();
+        assert_eq!(picking.pick([50.0, 50.0]), None);
+    }
+}
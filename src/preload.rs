@@ -0,0 +1,163 @@
+use crate::{Graphics, Shader};
+use log::{error, info};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Describes an asset that should be loaded before the application starts using it.
+#[derive(Clone, Debug)]
+pub enum AssetRef {
+    Texture(String),
+    Font {
+        family: String,
+        weight: u16,
+        style: String,
+        size: f32,
+        alphabet: String,
+        path: String,
+    },
+    Shader(String),
+}
+
+impl AssetRef {
+    pub fn texture(path: &str) -> Self {
+        AssetRef::Texture(path.to_string())
+    }
+
+    pub fn shader(path: &str) -> Self {
+        AssetRef::Shader(path.to_string())
+    }
+
+    pub fn font(
+        family: &str,
+        weight: u16,
+        style: &str,
+        size: f32,
+        alphabet: &str,
+        path: &str,
+    ) -> Self {
+        AssetRef::Font {
+            family: family.to_string(),
+            weight,
+            style: style.to_string(),
+            size,
+            alphabet: alphabet.to_string(),
+            path: path.to_string(),
+        }
+    }
+}
+
+/// Tracks cooperative loading of assets requested via [`Graphics::preload`].
+///
+/// Textures are loaded by background reader threads, fonts and shaders are processed
+/// on the calling thread in small steps limited by time budget.
+pub struct PreloadHandle {
+    pending: VecDeque<AssetRef>,
+    textures: Vec<String>,
+    total: usize,
+    completed: usize,
+    progress: f32,
+}
+
+impl PreloadHandle {
+    /// Returns aggregated loading progress from 0.0 to 1.0, usable to draw a loading bar.
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.progress >= 1.0
+    }
+
+    /// Processes pending assets until the budget is used up and updates progress.
+    pub fn update(&mut self, graphics: &mut Graphics, budget: Duration) -> f32 {
+        let time = Instant::now();
+        while let Some(asset) = self.pending.pop_front() {
+            load_asset(graphics, asset);
+            self.completed += 1;
+            if time.elapsed() >= budget {
+                break;
+            }
+        }
+        graphics.textures.update();
+        // failed textures are completed too, they are drawn as fallback
+        let textures = self
+            .textures
+            .iter()
+            .filter(|path| !graphics.textures.is_loading(path))
+            .count();
+        self.progress = if self.total == 0 {
+            1.0
+        } else {
+            (self.completed + textures) as f32 / self.total as f32
+        };
+        self.progress
+    }
+
+    /// Keeps presenting frames while loading, so the window stays responsive.
+    pub fn block_until_done(&mut self, graphics: &mut Graphics, budget_per_frame: Duration) {
+        info!("Starts blocking preload of {} assets", self.total);
+        let time = Instant::now();
+        loop {
+            graphics.capture_user_input();
+            if self.update(graphics, budget_per_frame) >= 1.0 {
+                break;
+            }
+            graphics.clear([0.0, 0.0, 0.0, 1.0]);
+            graphics.present();
+        }
+        info!("Completes preload in {:?}", time.elapsed());
+    }
+}
+
+fn load_asset(graphics: &mut Graphics, asset: AssetRef) {
+    match asset {
+        AssetRef::Texture(path) => {
+            graphics.textures.get_texture(&path);
+        }
+        AssetRef::Font {
+            family,
+            weight,
+            style,
+            size,
+            alphabet,
+            path,
+        } => {
+            let mut fonts = graphics.fonts.write().expect("fonts must be writable");
+            if let Err(error) =
+                fonts.load_font_file(&family, weight, &style, size, &alphabet, &path)
+            {
+                error!("unable to preload font {path}, {error:?}");
+            }
+        }
+        AssetRef::Shader(path) => {
+            if let Err(error) = Shader::preload(&path) {
+                error!("unable to preload shader {path}, {error:?}");
+            }
+        }
+    }
+}
+
+impl Graphics {
+    /// Starts loading of given assets. Texture reading begins immediately in background,
+    /// fonts and shaders are loaded by subsequent [`PreloadHandle::update`] calls.
+    pub fn preload(&mut self, assets: &[AssetRef]) -> PreloadHandle {
+        let mut pending = VecDeque::new();
+        let mut textures = vec![];
+        for asset in assets {
+            match asset {
+                AssetRef::Texture(path) => {
+                    self.textures.get_texture(path);
+                    textures.push(path.clone());
+                }
+                asset => pending.push_back(asset.clone()),
+            }
+        }
+        PreloadHandle {
+            total: assets.len(),
+            pending,
+            textures,
+            completed: 0,
+            progress: 0.0,
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::renderers::{embedded_shader, LayerTarget};
+use crate::{
+    range, Colors, Graphics, Program, ProgramError, ProgramOptions, Textures, VariableError,
+};
+use vulkanalia::vk;
+
+/// Represents GLSL push constants of display adjustment program.
+///
+/// ```glsl
+/// layout (push_constant) uniform Adjustment {
+///     float brightness;
+///     float gamma;
+///     uint texture;
+/// };
+/// // color.rgb = pow(color.rgb * brightness, vec3(1.0 / gamma));
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct AdjustmentConstants {
+    brightness: f32,
+    gamma: f32,
+    texture: u32,
+    _padding: u32,
+}
+
+/// Applies brightness and gamma of [`Graphics`] as final fullscreen pass,
+/// standard accessibility option for dark games.
+///
+/// Whole frame, including UI, is rendered into layer target between `begin` and `end`,
+/// then drawn adjusted to screen. Shaders are embedded in binary, their GLSL sources are
+/// in `src/renderers/builtin`.
+///
+/// ```ignore
+/// adjustment.begin(&mut graphics, "black");
+/// // render scene and UI
+/// adjustment.end(&mut graphics);
+/// adjustment.draw(&graphics)?;
+/// ```
+pub struct DisplayAdjustment {
+    program: Box<Program>,
+    textures: Textures,
+    sampler: vk::Sampler,
+    layer: LayerTarget,
+}
+
+impl DisplayAdjustment {
+    pub fn create(graphics: &mut Graphics) -> Result<Self, ProgramError> {
+        let textures = graphics.textures(0, 0);
+        let sampler = graphics.create_pixel_perfect_sampler();
+        let program = graphics.create_program(
+            "adjustment",
+            embedded_shader!("screen.vert"),
+            embedded_shader!("adjustment.frag"),
+            vec![range::<AdjustmentConstants>()],
+            sampler,
+            vec![textures.layout()],
+            None,
+            ProgramOptions::default(),
+        )?;
+        Ok(Self {
+            program,
+            textures,
+            sampler,
+            layer: LayerTarget::new(),
+        })
+    }
+
+    /// Starts rendering of adjusted layer, draws go to layer target until `end`.
+    ///
+    /// Must be called between `clear` and `present`, once per frame.
+    pub fn begin(&mut self, graphics: &mut Graphics, color: impl Colors) {
+        self.layer.resize_to_screen(graphics);
+        self.layer.begin(graphics, color);
+    }
+
+    pub fn end(&mut self, graphics: &mut Graphics) {
+        self.layer.end(graphics);
+    }
+
+    /// Draws adjusted layer over whole screen in current render pass.
+    pub fn draw(&mut self, graphics: &Graphics) -> Result<(), VariableError> {
+        let texture = self
+            .textures
+            .store(self.layer.target().texture, self.sampler)?;
+        let constants = AdjustmentConstants {
+            brightness: graphics.brightness(),
+            gamma: graphics.gamma(),
+            texture,
+            _padding: 0,
+        };
+        self.program.bind_pipeline();
+        self.program.bind_textures(&self.textures);
+        self.program.push_constants(&constants);
+        self.program.draw(6, 1);
+        Ok(())
+    }
+}
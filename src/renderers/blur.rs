@@ -0,0 +1,166 @@
+use crate::math::{Vec2, Vec4};
+use crate::renderers::{embedded_shader, LayerTarget};
+use crate::{
+    range, Colors, Graphics, Program, ProgramError, ProgramOptions, Texture, Textures,
+    VariableError,
+};
+use vulkanalia::vk;
+
+/// Represents GLSL push constants of blur program.
+///
+/// ```glsl
+/// layout (push_constant) uniform Blur {
+///     vec4 region;
+///     vec4 uv;
+///     vec2 screen;
+///     vec2 direction;
+///     vec2 texel;
+///     float radius;
+///     float sigma;
+///     uint texture;
+/// };
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct BlurConstants {
+    region: Vec4,
+    uv: Vec4,
+    screen: Vec2,
+    direction: Vec2,
+    texel: Vec2,
+    radius: f32,
+    sigma: f32,
+    texture: u32,
+}
+
+/// Applies separable gaussian blur to screen region of source texture or to whole frame.
+///
+/// Horizontal pass renders region into intermediate target, vertical pass
+/// samples intermediate target and draws result over region in current render pass.
+/// Shaders are embedded in binary, their GLSL sources are in `src/renderers/builtin`.
+///
+/// Whole frame, e.g. scene under pause menu, is rendered into layer target between
+/// `begin` and `end`, then drawn blurred to screen:
+///
+/// ```ignore
+/// blur.begin(&mut graphics, "black");
+/// // render scene
+/// blur.end(&mut graphics);
+/// blur.draw(&mut graphics, 8.0)?;
+/// // render pause menu
+/// ```
+pub struct BlurPass {
+    program: Box<Program>,
+    textures: Textures,
+    sampler: vk::Sampler,
+    intermediate: LayerTarget,
+    layer: LayerTarget,
+}
+
+impl BlurPass {
+    pub fn create(graphics: &mut Graphics) -> Result<Self, ProgramError> {
+        let textures = graphics.textures(0, 0);
+        let sampler = graphics.create_linear_sampler();
+        let program = graphics.create_program(
+            "blur",
+            embedded_shader!("blur.vert"),
+            embedded_shader!("blur.frag"),
+            vec![range::<BlurConstants>()],
+            sampler,
+            vec![textures.layout()],
+            None,
+            ProgramOptions::default(),
+        )?;
+        Ok(Self {
+            program,
+            textures,
+            sampler,
+            intermediate: LayerTarget::new(),
+            layer: LayerTarget::new(),
+        })
+    }
+
+    /// Starts rendering of blurred frame, draws go to layer target until `end`.
+    ///
+    /// Must be called between `clear` and `present`, once per frame.
+    pub fn begin(&mut self, graphics: &mut Graphics, color: impl Colors) {
+        self.layer.resize_to_screen(graphics);
+        self.layer.begin(graphics, color);
+    }
+
+    pub fn end(&mut self, graphics: &mut Graphics) {
+        self.layer.end(graphics);
+    }
+
+    /// Draws layer blurred by radius in pixels over whole screen in current render pass.
+    pub fn draw(&mut self, graphics: &mut Graphics, radius: f32) -> Result<(), VariableError> {
+        let target = self.layer.target();
+        let texture = target.texture;
+        let region = [
+            0.0,
+            0.0,
+            target.extent.width as f32,
+            target.extent.height as f32,
+        ];
+        self.apply(graphics, texture, region, radius)
+    }
+
+    /// Blurs `region` (x, y, width, height in pixels) of `source`, radius is given in pixels.
+    ///
+    /// Must be called between `clear` and `present`, outside of other render targets.
+    pub fn apply(
+        &mut self,
+        graphics: &mut Graphics,
+        source: Texture,
+        region: Vec4,
+        radius: f32,
+    ) -> Result<(), VariableError> {
+        let [_, _, width, height] = region;
+        let size = [width.max(1.0) as u32, height.max(1.0) as u32];
+        let intermediate_texture = self.intermediate.resize(graphics, size[0], size[1]).texture;
+        let source_index = self.textures.store(source, self.sampler)?;
+        let intermediate_index = self.textures.store(intermediate_texture, self.sampler)?;
+        let sigma = (radius / 2.0).max(0.5);
+        let [x, y, _, _] = region;
+        // horizontal pass reads source region and fills whole intermediate target
+        let [source_width, source_height] = source.size;
+        let [source_width, source_height] = [source_width as f32, source_height as f32];
+        self.intermediate.begin(graphics, [0.0; 4]);
+        self.draw(BlurConstants {
+            region: [0.0, 0.0, width, height],
+            uv: [
+                x / source_width,
+                y / source_height,
+                width / source_width,
+                height / source_height,
+            ],
+            screen: [width, height],
+            direction: [1.0, 0.0],
+            texel: [1.0 / source_width, 1.0 / source_height],
+            radius,
+            sigma,
+            texture: source_index,
+        });
+        self.intermediate.end(graphics);
+        // vertical pass reads intermediate target and draws result over region
+        let extent = graphics.vulkan.swapchain.extent;
+        self.draw(BlurConstants {
+            region,
+            uv: [0.0, 0.0, 1.0, 1.0],
+            screen: [extent.width as f32, extent.height as f32],
+            direction: [0.0, 1.0],
+            texel: [1.0 / width, 1.0 / height],
+            radius,
+            sigma,
+            texture: intermediate_index,
+        });
+        Ok(())
+    }
+
+    fn draw(&mut self, constants: BlurConstants) {
+        self.program.bind_pipeline();
+        self.program.bind_textures(&self.textures);
+        self.program.push_constants(&constants);
+        self.program.draw(6, 1);
+    }
+}
@@ -0,0 +1,19 @@
+#version 450
+#extension GL_EXT_nonuniform_qualifier: require
+
+layout (set = 0, binding = 0) uniform sampler2D textures[];
+
+layout (push_constant) uniform Adjustment {
+    float brightness;
+    float gamma;
+    uint texture;
+};
+
+layout (location = 0) in vec2 fragment_uv;
+
+layout (location = 0) out vec4 color;
+
+void main() {
+    vec4 texel = texture(textures[texture], fragment_uv);
+    color = vec4(pow(texel.rgb * brightness, vec3(1.0 / gamma)), texel.a);
+}
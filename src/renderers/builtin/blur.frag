@@ -0,0 +1,32 @@
+#version 450
+#extension GL_EXT_nonuniform_qualifier: require
+
+layout (set = 0, binding = 0) uniform sampler2D textures[];
+
+layout (push_constant) uniform Blur {
+    vec4 region;
+    vec4 uv;
+    vec2 screen;
+    vec2 direction;
+    vec2 texel;
+    float radius;
+    float sigma;
+    uint texture;
+};
+
+layout (location = 0) in vec2 fragment_uv;
+
+layout (location = 0) out vec4 color;
+
+void main() {
+    vec4 sum = vec4(0.0);
+    float weights = 0.0;
+    float extent = ceil(radius);
+    // one dimensional gaussian kernel along pass direction
+    for (float i = -extent; i <= extent; i += 1.0) {
+        float weight = exp(-(i * i) / (2.0 * sigma * sigma));
+        sum += textureLod(textures[texture], fragment_uv + direction * texel * i, 0.0) * weight;
+        weights += weight;
+    }
+    color = sum / max(weights, 0.0001);
+}
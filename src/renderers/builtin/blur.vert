@@ -0,0 +1,22 @@
+#version 450
+
+layout (push_constant) uniform Blur {
+    vec4 region;
+    vec4 uv;
+    vec2 screen;
+    vec2 direction;
+    vec2 texel;
+    float radius;
+    float sigma;
+    uint texture;
+};
+
+layout (location = 0) out vec2 fragment_uv;
+
+void main() {
+    // quad triangles take corners 0-1-2 and 0-2-3 of region rectangle
+    vec2 corner = vec2((0x16 >> gl_VertexIndex) & 1, (0x34 >> gl_VertexIndex) & 1);
+    vec2 position = region.xy + corner * region.zw;
+    gl_Position = vec4(position / screen * 2.0 - 1.0, 0.0, 1.0);
+    fragment_uv = uv.xy + corner * uv.zw;
+}
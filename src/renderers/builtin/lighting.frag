@@ -0,0 +1,23 @@
+#version 450
+#extension GL_EXT_nonuniform_qualifier: require
+
+layout (set = 0, binding = 0) uniform sampler2D textures[];
+
+layout (push_constant) uniform Lighting {
+    vec4 ambient;
+    vec2 screen;
+    uint light_count;
+    uint normals;
+    uint scene;
+    uint accumulation;
+};
+
+layout (location = 0) in vec2 fragment_uv;
+
+layout (location = 0) out vec4 color;
+
+void main() {
+    vec4 scene_color = texture(textures[scene], fragment_uv);
+    vec3 light = texture(textures[accumulation], fragment_uv).rgb;
+    color = vec4(scene_color.rgb * light, scene_color.a);
+}
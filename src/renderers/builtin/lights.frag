@@ -0,0 +1,52 @@
+#version 450
+#extension GL_EXT_nonuniform_qualifier: require
+
+struct Light {
+    vec4 color;
+    vec2 position;
+    vec2 direction;
+    float radius;
+    float intensity;
+    float cone;
+    uint kind;
+};
+
+layout (set = 0, binding = 0) uniform sampler2D textures[];
+
+layout (std430, set = 1, binding = 0) readonly buffer Lights {
+    Light lights[];
+};
+
+layout (push_constant) uniform Lighting {
+    vec4 ambient;
+    vec2 screen;
+    uint light_count;
+    uint normals;
+    uint scene;
+    uint accumulation;
+};
+
+layout (location = 0) in vec2 fragment_uv;
+
+layout (location = 0) out vec4 color;
+
+void main() {
+    vec3 normal = normalize(texture(textures[normals], fragment_uv).rgb * 2.0 - 1.0);
+    vec2 pixel = fragment_uv * screen;
+    vec3 sum = ambient.rgb;
+    for (uint i = 0; i < light_count; i++) {
+        Light light = lights[i];
+        vec2 delta = light.position - pixel;
+        float distance = length(delta);
+        float attenuation = clamp(1.0 - distance / light.radius, 0.0, 1.0);
+        // point lights have cone -1.0 and zero direction, so every pixel is inside
+        vec2 to_pixel = -delta / max(distance, 0.0001);
+        float inside = step(light.cone, dot(to_pixel, light.direction));
+        // light is lifted above scene, so surfaces facing camera are lit too
+        vec3 direction = normalize(vec3(delta, light.radius * 0.5));
+        float diffuse = max(dot(normal, direction), 0.0);
+        float factor = attenuation * attenuation * light.intensity * inside * diffuse;
+        sum += light.color.rgb * factor;
+    }
+    color = vec4(sum, 1.0);
+}
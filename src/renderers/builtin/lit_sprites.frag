@@ -0,0 +1,20 @@
+#version 450
+#extension GL_EXT_nonuniform_qualifier: require
+
+layout (set = 1, binding = 0) uniform sampler2D textures[];
+
+layout (location = 0) in vec2 fragment_uv;
+layout (location = 1) flat in int fragment_texture;
+layout (location = 2) flat in int fragment_normal;
+
+layout (location = 0) out vec4 color;
+
+void main() {
+    vec4 albedo = texture(textures[nonuniformEXT(fragment_texture)], fragment_uv);
+    // normal map is sampled unconditionally, so derivatives stay in uniform control flow
+    bool mapped = fragment_normal >= 0;
+    int normal_index = mapped ? fragment_normal : fragment_texture;
+    vec3 normal = texture(textures[nonuniformEXT(normal_index)], fragment_uv).rgb;
+    // sprites without normal map face camera
+    color = vec4(mapped ? normal : vec3(0.5, 0.5, 1.0), albedo.a);
+}
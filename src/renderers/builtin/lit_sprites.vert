@@ -0,0 +1,33 @@
+#version 450
+
+struct Sprite {
+    vec2 position;
+    vec2 size;
+    int texture;
+    int normal;
+};
+
+layout (set = 0, binding = 0) uniform Transform {
+    mat4 model;
+    mat4 view;
+    mat4 proj;
+} transform;
+
+layout (std430, set = 2, binding = 0) readonly buffer Sprites {
+    Sprite sprites[];
+};
+
+layout (location = 0) out vec2 fragment_uv;
+layout (location = 1) flat out int fragment_texture;
+layout (location = 2) flat out int fragment_normal;
+
+void main() {
+    Sprite sprite = sprites[gl_InstanceIndex];
+    // quad triangles take corners 0-1-2 and 0-2-3 of sprite rectangle
+    vec2 corner = vec2((0x16 >> gl_VertexIndex) & 1, (0x34 >> gl_VertexIndex) & 1);
+    vec2 position = sprite.position + corner * sprite.size;
+    gl_Position = transform.proj * transform.view * transform.model * vec4(position, 0.0, 1.0);
+    fragment_uv = corner;
+    fragment_texture = sprite.texture;
+    fragment_normal = sprite.normal;
+}
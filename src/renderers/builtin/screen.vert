@@ -0,0 +1,10 @@
+#version 450
+
+layout (location = 0) out vec2 fragment_uv;
+
+void main() {
+    // quad triangles take corners 0-1-2 and 0-2-3 of screen rectangle
+    vec2 corner = vec2((0x16 >> gl_VertexIndex) & 1, (0x34 >> gl_VertexIndex) & 1);
+    gl_Position = vec4(corner * 2.0 - 1.0, 0.0, 1.0);
+    fragment_uv = corner;
+}
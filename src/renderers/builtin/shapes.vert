@@ -0,0 +1,18 @@
+#version 450
+
+layout (location = 0) in vec2 position;
+layout (location = 1) in vec4 color;
+layout (location = 2) in vec2 uv;
+
+layout (set = 0, binding = 0) uniform Transform {
+    mat4 model;
+    mat4 view;
+    mat4 proj;
+} transform;
+
+layout (location = 0) out vec4 fragment_color;
+
+void main() {
+    gl_Position = transform.proj * transform.view * transform.model * vec4(position, 0.0, 1.0);
+    fragment_color = color;
+}
@@ -0,0 +1,16 @@
+#version 450
+#extension GL_EXT_nonuniform_qualifier: require
+
+layout (set = 1, binding = 0) uniform sampler2D textures[];
+
+layout (location = 0) in vec4 fragment_color;
+layout (location = 1) in vec2 fragment_uv;
+layout (location = 2) flat in int fragment_texture;
+// not used by builtin shader, custom shaders read it for per-sprite effects
+layout (location = 3) flat in vec4 fragment_params;
+
+layout (location = 0) out vec4 color;
+
+void main() {
+    color = texture(textures[nonuniformEXT(fragment_texture)], fragment_uv) * fragment_color;
+}
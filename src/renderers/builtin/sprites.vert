@@ -0,0 +1,40 @@
+#version 450
+
+struct Sprite {
+    vec4 color;
+    vec4 params;
+    vec2 src;
+    vec2 uv;
+    vec2 position;
+    vec2 size;
+    int texture;
+    float depth;
+};
+
+layout (set = 0, binding = 0) uniform Transform {
+    mat4 model;
+    mat4 view;
+    mat4 proj;
+} transform;
+
+layout (std430, set = 2, binding = 0) readonly buffer Sprites {
+    Sprite sprites[];
+};
+
+layout (location = 0) out vec4 fragment_color;
+layout (location = 1) out vec2 fragment_uv;
+layout (location = 2) flat out int fragment_texture;
+layout (location = 3) flat out vec4 fragment_params;
+
+void main() {
+    Sprite sprite = sprites[gl_InstanceIndex];
+    // quad triangles take corners 0-1-2 and 0-2-3 of sprite rectangle
+    vec2 corner = vec2((0x16 >> gl_VertexIndex) & 1, (0x34 >> gl_VertexIndex) & 1);
+    vec2 position = sprite.position + corner * sprite.size;
+    gl_Position = transform.proj * transform.view * transform.model * vec4(position, 0.0, 1.0);
+    gl_Position.z = sprite.depth * gl_Position.w;
+    fragment_color = sprite.color;
+    fragment_uv = sprite.src + corner * sprite.uv;
+    fragment_texture = sprite.texture;
+    fragment_params = sprite.params;
+}
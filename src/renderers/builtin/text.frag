@@ -0,0 +1,78 @@
+#version 450
+#extension GL_EXT_nonuniform_qualifier: require
+
+layout (set = 1, binding = 0) uniform sampler2D textures[];
+
+layout (location = 0) in vec4 fragment_color;
+layout (location = 1) in vec2 fragment_uv;
+layout (location = 2) flat in int fragment_texture;
+layout (location = 3) flat in float fragment_distance_range;
+// glyph region in atlas, size of pixel in uv, shadow softness and glow radius
+layout (location = 4) flat in vec4 fragment_region;
+layout (location = 5) flat in vec4 fragment_effects;
+layout (location = 6) flat in vec2 fragment_shadow_offset;
+layout (location = 7) flat in vec4 fragment_shadow_color;
+layout (location = 8) flat in vec4 fragment_glow_color;
+layout (location = 9) flat in vec4 fragment_outline_color;
+layout (location = 10) flat in float fragment_outline_width;
+
+layout (location = 0) out vec4 color;
+
+const vec2 KERNEL[16] = vec2[](
+    vec2(1.0, 0.0), vec2(0.7071, 0.7071), vec2(0.0, 1.0), vec2(-0.7071, 0.7071),
+    vec2(-1.0, 0.0), vec2(-0.7071, -0.7071), vec2(0.0, -1.0), vec2(0.7071, -0.7071),
+    vec2(0.5, 0.0), vec2(0.35355, 0.35355), vec2(0.0, 0.5), vec2(-0.35355, 0.35355),
+    vec2(-0.5, 0.0), vec2(-0.35355, -0.35355), vec2(0.0, -0.5), vec2(0.35355, -0.35355)
+);
+
+float median(vec3 texel) {
+    return max(min(texel.r, texel.g), min(max(texel.r, texel.g), texel.b));
+}
+
+// coverage of bitmap glyph is alpha, of MSDF glyph is median distance,
+// atlas outside of glyph region belongs to other glyphs
+float coverage(vec2 uv, float px) {
+    vec4 texel = texture(textures[nonuniformEXT(fragment_texture)], uv);
+    float msdf = clamp((median(texel.rgb) - 0.5) * px + 0.5, 0.0, 1.0);
+    vec2 inside = step(fragment_region.xy, uv) * step(uv, fragment_region.zw);
+    return (fragment_distance_range > 0.0 ? msdf : texel.a) * inside.x * inside.y;
+}
+
+// average coverage of samples within radius in pixels around uv
+float blurred(vec2 uv, float radius, float px) {
+    float sum = coverage(uv, px);
+    for (int i = 0; i < 16; i++) {
+        sum += coverage(uv + KERNEL[i] * radius * fragment_effects.xy, px);
+    }
+    return sum / 17.0;
+}
+
+// max coverage of samples within radius in pixels around uv, dilates glyph by radius
+float dilated(vec2 uv, float radius, float px) {
+    float result = coverage(uv, px);
+    for (int i = 0; i < 16; i++) {
+        result = max(result, coverage(uv + KERNEL[i] * radius * fragment_effects.xy, px));
+    }
+    return result;
+}
+
+vec4 over(vec4 src, vec4 dst) {
+    float alpha = src.a + dst.a * (1.0 - src.a);
+    vec3 rgb = src.rgb * src.a + dst.rgb * dst.a * (1.0 - src.a);
+    return vec4(rgb / max(alpha, 0.0001), alpha);
+}
+
+void main() {
+    vec4 texel = texture(textures[nonuniformEXT(fragment_texture)], fragment_uv);
+    float distance = median(texel.rgb) - 0.5;
+    // distance to screen pixels scale, taken once for all samples
+    float px = 1.0 / max(fwidth(distance), 0.0001);
+    float fill = coverage(fragment_uv, px);
+    float shadow = blurred(fragment_uv - fragment_shadow_offset, fragment_effects.z, px);
+    float glow = blurred(fragment_uv, fragment_effects.w, px);
+    float outline = dilated(fragment_uv, fragment_outline_width, px);
+    vec4 result = vec4(fragment_shadow_color.rgb, fragment_shadow_color.a * shadow);
+    result = over(vec4(fragment_glow_color.rgb, fragment_glow_color.a * glow), result);
+    result = over(vec4(fragment_outline_color.rgb, fragment_outline_color.a * outline), result);
+    color = over(vec4(fragment_color.rgb, fragment_color.a * fill), result);
+}
@@ -0,0 +1,63 @@
+#version 450
+
+struct Glyph {
+    vec4 color;
+    vec4 shadow_color;
+    vec4 glow_color;
+    vec4 outline_color;
+    vec2 position;
+    vec2 size;
+    vec2 src;
+    vec2 uv;
+    vec2 shadow_offset;
+    float shadow_softness;
+    float glow_radius;
+    int texture;
+    float distance_range;
+    float margin;
+    float outline_width;
+};
+
+layout (set = 0, binding = 0) uniform Transform {
+    mat4 model;
+    mat4 view;
+    mat4 proj;
+} transform;
+
+layout (std430, set = 2, binding = 0) readonly buffer Glyphs {
+    Glyph glyphs[];
+};
+
+layout (location = 0) out vec4 fragment_color;
+layout (location = 1) out vec2 fragment_uv;
+layout (location = 2) flat out int fragment_texture;
+layout (location = 3) flat out float fragment_distance_range;
+layout (location = 4) flat out vec4 fragment_region;
+layout (location = 5) flat out vec4 fragment_effects;
+layout (location = 6) flat out vec2 fragment_shadow_offset;
+layout (location = 7) flat out vec4 fragment_shadow_color;
+layout (location = 8) flat out vec4 fragment_glow_color;
+layout (location = 9) flat out vec4 fragment_outline_color;
+layout (location = 10) flat out float fragment_outline_width;
+
+void main() {
+    Glyph glyph = glyphs[gl_InstanceIndex];
+    // quad triangles take corners 0-1-2 and 0-2-3 of glyph rectangle
+    vec2 corner = vec2((0x16 >> gl_VertexIndex) & 1, (0x34 >> gl_VertexIndex) & 1);
+    // quad and uv are expanded by margin, so effects around glyph are not clipped
+    vec2 pixel = glyph.uv / max(glyph.size, vec2(0.0001));
+    vec2 position = glyph.position - glyph.margin + corner * (glyph.size + 2.0 * glyph.margin);
+    gl_Position = transform.proj * transform.view * transform.model * vec4(position, 0.0, 1.0);
+    fragment_color = glyph.color;
+    vec2 uv = glyph.uv + 2.0 * glyph.margin * pixel;
+    fragment_uv = glyph.src - glyph.margin * pixel + corner * uv;
+    fragment_texture = glyph.texture;
+    fragment_distance_range = glyph.distance_range;
+    fragment_region = vec4(glyph.src, glyph.src + glyph.uv);
+    fragment_effects = vec4(pixel, glyph.shadow_softness, glyph.glow_radius);
+    fragment_shadow_offset = glyph.shadow_offset * pixel;
+    fragment_shadow_color = glyph.shadow_color;
+    fragment_glow_color = glyph.glow_color;
+    fragment_outline_color = glyph.outline_color;
+    fragment_outline_width = glyph.outline_width;
+}
@@ -0,0 +1,186 @@
+use crate::math::Rect;
+use crate::{
+    compute_range, Camera, ComputeProgram, Graphics, Program, ProgramError, Shader, Storage,
+    Variable, VariableError,
+};
+use vulkanalia::vk;
+
+/// Represents GLSL push constants of culling program, view is visible world rect.
+///
+/// ```glsl
+/// layout (local_size_x = 64) in;
+/// layout (push_constant) uniform Culling {
+///     vec2 position;
+///     vec2 size;
+///     uint count;
+/// };
+/// layout (std430, set = 0, binding = 0) readonly buffer Instances {
+///     Sprite instances[];
+/// };
+/// layout (std430, set = 1, binding = 0) writeonly buffer Visible {
+///     Sprite visible[];
+/// };
+/// layout (std430, set = 2, binding = 0) buffer Command {
+///     uint vertexCount;
+///     uint instanceCount;
+///     uint firstVertex;
+///     uint firstInstance;
+/// };
+/// // if (overlaps(instances[i])) visible[atomicAdd(instanceCount, 1)] = instances[i];
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct CullingConstants {
+    position: [f32; 2],
+    size: [f32; 2],
+    count: u32,
+    _padding: [u32; 3],
+}
+
+/// Culls static instances, e.g. sprites or tiles of large map, against camera on GPU.
+///
+/// Instances are uploaded once, compute pass writes visible instances to compacted
+/// storage and instance count to indirect draw command, so there is no per-frame
+/// storage rebuild on CPU.
+///
+/// ```ignore
+/// let mut culling = InstanceCulling::<Sprite>::create(&mut graphics, shader, 1 << 20, 6)?;
+/// let visible = culling.visible_layout(2, 0);
+/// culling.set_instances(&tiles)?;
+/// // every frame
+/// culling.cull(&mut graphics, &camera);
+/// program.bind_variable(&visible);
+/// culling.draw(&program);
+/// ```
+pub struct InstanceCulling<T> {
+    program: Box<ComputeProgram>,
+    instances: Storage<T>,
+    instances_variable: Variable,
+    visible: Storage<T>,
+    visible_variable: Variable,
+    commands: Storage<vk::DrawIndirectCommand>,
+    commands_variable: Variable,
+    collection: Vec<T>,
+    capacity: usize,
+    vertex_count: usize,
+    /// Frames in flight whose instances storage must be updated before next cull.
+    outdated: Vec<bool>,
+}
+
+impl<T: Default + Clone + Copy> InstanceCulling<T> {
+    pub const WORKGROUP_SIZE: usize = 64;
+
+    /// Creates culling of up to capacity instances, each drawn with vertex count vertices.
+    pub fn create(
+        graphics: &mut Graphics,
+        shader: Shader,
+        capacity: usize,
+        vertex_count: usize,
+    ) -> Result<Self, ProgramError> {
+        let instances = graphics.storage::<T>(capacity);
+        let instances_variable = instances.layout(0, 0);
+        let visible = graphics.storage::<T>(capacity);
+        let visible_variable = visible.layout(1, 0);
+        let commands = graphics.storage::<vk::DrawIndirectCommand>(1);
+        let commands_variable = commands.layout(2, 0);
+        let program = graphics.create_compute_program(
+            "culling",
+            shader,
+            vec![compute_range::<CullingConstants>()],
+            vec![
+                instances_variable.layout,
+                visible_variable.layout,
+                commands_variable.layout,
+            ],
+        )?;
+        let frames = instances.buffers.len();
+        Ok(Self {
+            program,
+            instances,
+            instances_variable,
+            visible,
+            visible_variable,
+            commands,
+            commands_variable,
+            collection: vec![],
+            capacity,
+            vertex_count,
+            outdated: vec![false; frames],
+        })
+    }
+
+    /// Creates variable of visible instances to bind in program drawing them.
+    pub fn visible_layout(&self, set: u32, binding: u32) -> Variable {
+        self.visible.layout(set, binding)
+    }
+
+    /// Replaces instances, storages of frames in flight are updated on their next cull.
+    pub fn set_instances(&mut self, instances: &[T]) -> Result<(), VariableError> {
+        if instances.len() > self.capacity {
+            return Err(VariableError::StorageOverflow {
+                limit: self.capacity,
+            });
+        }
+        self.collection = instances.to_vec();
+        self.outdated.fill(true);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.collection.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.collection.is_empty()
+    }
+
+    /// Submits culling of instances against visible rect of camera.
+    ///
+    /// Must be called between `clear` and `present`, before draw of the same frame.
+    pub fn cull(&mut self, graphics: &mut Graphics, camera: &Camera) {
+        let frame = graphics.frame();
+        if self.outdated[frame] {
+            self.instances.update_from(frame, &self.collection);
+            self.outdated[frame] = false;
+        }
+        // instance count is accumulated by shader, so it is reset every frame
+        let command = vk::DrawIndirectCommand {
+            vertex_count: self.vertex_count as u32,
+            instance_count: 0,
+            first_vertex: 0,
+            first_instance: 0,
+        };
+        self.commands.update_from(frame, &[command]);
+        if self.collection.is_empty() {
+            return;
+        }
+        let Rect { position, size } = camera.visible_rect();
+        let constants = CullingConstants {
+            position,
+            size,
+            count: self.collection.len() as u32,
+            _padding: [0; 3],
+        };
+        let groups = self.collection.len().div_ceil(Self::WORKGROUP_SIZE) as u32;
+        let program = &self.program;
+        let variables = [
+            &self.instances_variable,
+            &self.visible_variable,
+            &self.commands_variable,
+        ];
+        graphics.submit_async_compute(|commands| {
+            program.bind_pipeline(commands);
+            for variable in variables {
+                program.bind_descriptor(commands, variable.set, variable.descriptor(frame));
+            }
+            program.push_constants(commands, &constants);
+            program.dispatch(commands, groups, 1, 1);
+        });
+    }
+
+    /// Draws visible instances by indirect command, program pipeline and variables
+    /// must be bound.
+    pub fn draw(&self, program: &Program) {
+        program.draw_indirect(&self.commands, 1);
+    }
+}
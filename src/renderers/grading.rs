@@ -0,0 +1,143 @@
+use crate::renderers::LayerTarget;
+use crate::{
+    range, Colors, Graphics, Program, ProgramError, ProgramOptions, Shader, Texture, Textures,
+    VariableError,
+};
+use vulkanalia::vk;
+
+/// Represents GLSL push constants of color grading program.
+///
+/// LUT is horizontal strip of `size` square slices, e.g. 256x16 texture for size 16.
+/// Blue channel selects slice, red and green select texel in slice, shader interpolates
+/// between neighbour slices. Graded color of `lut` is mixed with `next_lut` by `blend`,
+/// then with source color by `intensity`.
+///
+/// ```glsl
+/// layout (push_constant) uniform ColorGrading {
+///     float size;
+///     float blend;
+///     float intensity;
+///     uint texture;
+///     uint lut;
+///     uint next_lut;
+/// };
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct ColorGradingConstants {
+    size: f32,
+    blend: f32,
+    intensity: f32,
+    texture: u32,
+    lut: u32,
+    next_lut: u32,
+}
+
+/// Renders layer into target and draws it to screen through color lookup table,
+/// LUTs can be swapped or blended at runtime, e.g. day to night or damage effects.
+///
+/// ```ignore
+/// grading.begin(&mut graphics, "black");
+/// // render scene
+/// grading.end(&mut graphics);
+/// grading.transition(night, time_of_day);
+/// grading.draw()?;
+/// // render UI
+/// ```
+pub struct ColorGrading {
+    program: Box<Program>,
+    textures: Textures,
+    sampler: vk::Sampler,
+    layer: LayerTarget,
+    lut: Texture,
+    next_lut: Option<(Texture, f32)>,
+    /// Mix of graded color with source color, 0.0 disables grading.
+    pub intensity: f32,
+}
+
+impl ColorGrading {
+    pub fn create(
+        graphics: &mut Graphics,
+        vert: Shader,
+        frag: Shader,
+        lut: Texture,
+    ) -> Result<Self, ProgramError> {
+        let textures = graphics.textures(0, 0);
+        let sampler = graphics.create_linear_sampler();
+        let program = graphics.create_program(
+            "grading",
+            vert,
+            frag,
+            vec![range::<ColorGradingConstants>()],
+            sampler,
+            vec![textures.layout()],
+            None,
+            ProgramOptions::default(),
+        )?;
+        Ok(Self {
+            program,
+            textures,
+            sampler,
+            layer: LayerTarget::new(),
+            lut,
+            next_lut: None,
+            intensity: 1.0,
+        })
+    }
+
+    pub fn lut(&self) -> Texture {
+        self.lut
+    }
+
+    /// Replaces LUT immediately, cancels transition.
+    pub fn set_lut(&mut self, lut: Texture) {
+        self.lut = lut;
+        self.next_lut = None;
+    }
+
+    /// Blends current LUT to next by factor, next LUT replaces current once blend reaches 1.0.
+    pub fn transition(&mut self, next: Texture, blend: f32) {
+        if blend >= 1.0 {
+            self.set_lut(next);
+        } else {
+            self.next_lut = Some((next, blend.max(0.0)));
+        }
+    }
+
+    /// Starts rendering of graded layer, draws go to layer target until `end`.
+    ///
+    /// Must be called between `clear` and `present`, once per frame.
+    pub fn begin(&mut self, graphics: &mut Graphics, color: impl Colors) {
+        self.layer.resize_to_screen(graphics);
+        self.layer.begin(graphics, color);
+    }
+
+    pub fn end(&mut self, graphics: &mut Graphics) {
+        self.layer.end(graphics);
+    }
+
+    /// Draws graded layer over whole screen in current render pass.
+    pub fn draw(&mut self) -> Result<(), VariableError> {
+        let texture = self
+            .textures
+            .store(self.layer.target().texture, self.sampler)?;
+        let lut = self.textures.store(self.lut, self.sampler)?;
+        let (next_lut, blend) = match self.next_lut {
+            Some((next, blend)) => (self.textures.store(next, self.sampler)?, blend),
+            None => (lut, 0.0),
+        };
+        let constants = ColorGradingConstants {
+            size: self.lut.size[1] as f32,
+            blend,
+            intensity: self.intensity,
+            texture,
+            lut,
+            next_lut,
+        };
+        self.program.bind_pipeline();
+        self.program.bind_textures(&self.textures);
+        self.program.push_constants(&constants);
+        self.program.draw(6, 1);
+        Ok(())
+    }
+}
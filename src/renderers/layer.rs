@@ -0,0 +1,65 @@
+use crate::{Colors, Graphics, RenderTarget};
+use vulkanalia::vk;
+
+/// Offscreen target of post-processing layer, created on first use and recreated
+/// when requested size changes, e.g. on window resize.
+///
+/// ```ignore
+/// layer.resize_to_screen(&mut graphics);
+/// layer.begin(&mut graphics, "black");
+/// // render layer
+/// layer.end(&mut graphics);
+/// let texture = layer.target().texture;
+/// ```
+#[derive(Default)]
+pub struct LayerTarget {
+    target: Option<RenderTarget>,
+}
+
+impl LayerTarget {
+    pub fn new() -> Self {
+        Self { target: None }
+    }
+
+    /// Returns target of given size, previous target of other size is destroyed.
+    pub fn resize(
+        &mut self,
+        graphics: &mut Graphics,
+        width: u32,
+        height: u32,
+    ) -> &mut RenderTarget {
+        let extent = vk::Extent2D { width, height };
+        let resize = match self.target.as_ref() {
+            Some(target) => target.extent != extent,
+            None => true,
+        };
+        if resize {
+            if let Some(target) = self.target.take() {
+                graphics.destroy_render_target(target);
+            }
+            self.target = Some(graphics.create_render_target(width, height));
+        }
+        self.target.as_mut().expect("layer target must be set")
+    }
+
+    /// Returns target of swapchain size.
+    pub fn resize_to_screen(&mut self, graphics: &mut Graphics) -> &mut RenderTarget {
+        let extent = graphics.vulkan.swapchain.extent;
+        self.resize(graphics, extent.width, extent.height)
+    }
+
+    /// Starts rendering into target, draws go to target until `end`.
+    pub fn begin(&mut self, graphics: &mut Graphics, color: impl Colors) {
+        let target = self.target.as_mut().expect("layer target must be set");
+        graphics.begin_render_target(target, color);
+    }
+
+    pub fn end(&mut self, graphics: &mut Graphics) {
+        let target = self.target.as_mut().expect("layer target must be set");
+        graphics.end_render_target(target);
+    }
+
+    pub fn target(&self) -> &RenderTarget {
+        self.target.as_ref().expect("layer target must be set")
+    }
+}
@@ -0,0 +1,318 @@
+use crate::math::{Vec2, Vec4, VecMagnitude};
+use crate::renderers::{embedded_shader, LayerTarget};
+use crate::{
+    range, Camera, Colors, Graphics, Program, ProgramError, ProgramOptions, Storage, Texture,
+    Textures, Transform, Uniform, Variable, VariableError,
+};
+use vulkanalia::vk;
+
+/// Represents GLSL light structure stored in lights storage buffer.
+///
+/// Position and radius are given in world coordinates, they are converted to screen
+/// pixels on render.
+///
+/// ```glsl
+/// struct Light {
+///     vec4 color;
+///     vec2 position;
+///     vec2 direction;
+///     float radius;
+///     float intensity;
+///     float cone;
+///     uint kind;
+/// };
+/// layout (std430, set = 1, binding = 0) readonly buffer Lights {
+///     Light lights[];
+/// };
+/// ```
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct Light {
+    pub color: Vec4,
+    pub position: Vec2,
+    pub direction: Vec2,
+    pub radius: f32,
+    pub intensity: f32,
+    /// Cosine of cone half angle, used by cone lights only.
+    pub cone: f32,
+    pub kind: u32,
+}
+
+impl Light {
+    pub const POINT: u32 = 0;
+    pub const CONE: u32 = 1;
+
+    pub fn point(position: Vec2, radius: f32, color: Vec4) -> Self {
+        Self {
+            color,
+            position,
+            direction: [0.0, 0.0],
+            radius,
+            intensity: 1.0,
+            cone: -1.0,
+            kind: Self::POINT,
+        }
+    }
+
+    pub fn cone(position: Vec2, direction: Vec2, angle: f32, radius: f32, color: Vec4) -> Self {
+        Self {
+            color,
+            position,
+            direction,
+            radius,
+            intensity: 1.0,
+            cone: (angle * 0.5).cos(),
+            kind: Self::CONE,
+        }
+    }
+
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+}
+
+/// Represents GLSL lit sprite structure, normal map index is -1 if sprite has no normal map.
+///
+/// Normal maps are encoded in screen space, green channel points down.
+///
+/// ```glsl
+/// struct Sprite {
+///     vec2 position;
+///     vec2 size;
+///     int texture;
+///     int normal;
+/// };
+/// ```
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct LitSprite {
+    pub position: Vec2,
+    pub size: Vec2,
+    pub texture: i32,
+    pub normal: i32,
+}
+
+/// Represents GLSL push constants of light accumulation and composite programs.
+///
+/// ```glsl
+/// layout (push_constant) uniform Lighting {
+///     vec4 ambient;
+///     vec2 screen;
+///     uint light_count;
+///     uint normals;
+///     uint scene;
+///     uint accumulation;
+/// };
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct LightingConstants {
+    ambient: Vec4,
+    screen: Vec2,
+    lights: u32,
+    normals: u32,
+    scene: u32,
+    accumulation: u32,
+    _padding: [u32; 2],
+}
+
+/// Renders 2D lighting of scene by point and cone lights, sprites with normal maps
+/// are shaded by their surface.
+///
+/// Scene is rendered into layer target between `begin` and `end`. On render, normals of
+/// lit sprites are drawn into normals target, lights are accumulated per pixel into
+/// accumulation target starting from ambient, then scene multiplied by accumulated light
+/// is drawn to screen. Shaders are embedded in binary, their GLSL sources are
+/// in `src/renderers/builtin`.
+///
+/// ```ignore
+/// lighting.add_light(Light::point(torch.position, 160.0, [1.0, 0.8, 0.5, 1.0]))?;
+/// lighting.draw_sprite(knight, Some(knight_normals), unit.position, [32.0, 32.0])?;
+/// lighting.begin(&mut graphics, "black");
+/// sprites.render(&camera);
+/// lighting.end(&mut graphics);
+/// lighting.render(&mut graphics, &camera)?;
+/// ```
+pub struct LightingRenderer {
+    sprites_program: Box<Program>,
+    lights_program: Box<Program>,
+    composite_program: Box<Program>,
+    transform: Uniform<Transform>,
+    textures: Textures,
+    layers: Textures,
+    sampler: vk::Sampler,
+    sprites: Storage<LitSprite>,
+    sprites_variable: Variable,
+    lights: Storage<Light>,
+    lights_variable: Variable,
+    scene: LayerTarget,
+    normals: LayerTarget,
+    accumulation: LayerTarget,
+    pub ambient: Vec4,
+}
+
+impl LightingRenderer {
+    pub const MAX_SPRITES: usize = 4096;
+    pub const MAX_LIGHTS: usize = 256;
+    /// Normal of surface facing camera, normals target is cleared to it.
+    pub const FLAT_NORMAL: Vec4 = [0.5, 0.5, 1.0, 1.0];
+
+    pub fn create(graphics: &mut Graphics) -> Result<Self, ProgramError> {
+        let transform = graphics.uniform::<Transform>(0, 0);
+        let textures = graphics.textures(1, 0);
+        let layers = graphics.textures(0, 0);
+        let sprites = graphics.storage::<LitSprite>(Self::MAX_SPRITES);
+        let sprites_variable = sprites.layout(2, 0);
+        let lights = graphics.storage::<Light>(Self::MAX_LIGHTS);
+        let lights_variable = lights.layout(1, 0);
+        let sampler = graphics.create_pixel_perfect_sampler();
+        let sprites_program = graphics.create_program(
+            "lit_sprites",
+            embedded_shader!("lit_sprites.vert"),
+            embedded_shader!("lit_sprites.frag"),
+            vec![],
+            sampler,
+            vec![
+                transform.layout(),
+                textures.layout(),
+                sprites_variable.layout,
+            ],
+            None,
+            ProgramOptions::default(),
+        )?;
+        let lights_program = graphics.create_program(
+            "lights",
+            embedded_shader!("screen.vert"),
+            embedded_shader!("lights.frag"),
+            vec![range::<LightingConstants>()],
+            sampler,
+            vec![layers.layout(), lights_variable.layout],
+            None,
+            ProgramOptions::default(),
+        )?;
+        let composite_program = graphics.create_program(
+            "lighting",
+            embedded_shader!("screen.vert"),
+            embedded_shader!("lighting.frag"),
+            vec![range::<LightingConstants>()],
+            sampler,
+            vec![layers.layout()],
+            None,
+            ProgramOptions::default(),
+        )?;
+        Ok(Self {
+            sprites_program,
+            lights_program,
+            composite_program,
+            transform,
+            textures,
+            layers,
+            sampler,
+            sprites,
+            sprites_variable,
+            lights,
+            lights_variable,
+            scene: LayerTarget::new(),
+            normals: LayerTarget::new(),
+            accumulation: LayerTarget::new(),
+            ambient: [0.1, 0.1, 0.1, 1.0],
+        })
+    }
+
+    pub fn add_light(&mut self, light: Light) -> Result<(), VariableError> {
+        self.lights.push(light)?;
+        Ok(())
+    }
+
+    /// Draws sprite into normals target, sprite without normal map faces camera.
+    /// Sprite itself must be rendered into scene as usual, e.g. by sprite renderer.
+    pub fn draw_sprite(
+        &mut self,
+        texture: Texture,
+        normal: Option<Texture>,
+        position: Vec2,
+        size: Vec2,
+    ) -> Result<(), VariableError> {
+        let texture = self.textures.store(texture, self.sampler)? as i32;
+        let normal = match normal {
+            Some(normal) => self.textures.store(normal, self.sampler)? as i32,
+            None => -1,
+        };
+        self.sprites.push(LitSprite {
+            position,
+            size,
+            texture,
+            normal,
+        })?;
+        Ok(())
+    }
+
+    /// Starts rendering of lit scene, draws go to scene target until `end`.
+    ///
+    /// Must be called between `clear` and `present`, once per frame.
+    pub fn begin(&mut self, graphics: &mut Graphics, color: impl Colors) {
+        self.scene.resize_to_screen(graphics);
+        self.scene.begin(graphics, color);
+    }
+
+    pub fn end(&mut self, graphics: &mut Graphics) {
+        self.scene.end(graphics);
+    }
+
+    /// Accumulates lights drawn since previous render and draws lit scene over whole
+    /// screen in current render pass.
+    ///
+    /// Must be called after `end`, outside of other render targets.
+    pub fn render(
+        &mut self,
+        graphics: &mut Graphics,
+        camera: &Camera,
+    ) -> Result<(), VariableError> {
+        let frame = self.sprites_program.frame();
+        let [scale, _, _] = camera.scaling();
+        for light in self.lights.written_mut() {
+            light.position = camera.world_to_screen(light.position);
+            light.direction = light.direction.normal();
+            light.radius *= scale;
+        }
+        let lights = self.lights.take_and_update(frame);
+        let sprites = self.sprites.take_and_update(frame);
+        let normals = self.normals.resize_to_screen(graphics).texture;
+        let accumulation = self.accumulation.resize_to_screen(graphics).texture;
+        let extent = graphics.vulkan.swapchain.extent;
+        let constants = LightingConstants {
+            ambient: self.ambient,
+            screen: [extent.width as f32, extent.height as f32],
+            lights: lights as u32,
+            normals: self.layers.store(normals, self.sampler)?,
+            scene: self
+                .layers
+                .store(self.scene.target().texture, self.sampler)?,
+            accumulation: self.layers.store(accumulation, self.sampler)?,
+            _padding: [0; 2],
+        };
+        self.normals.begin(graphics, Self::FLAT_NORMAL);
+        if sprites > 0 {
+            self.transform.update(frame, &camera.get_transform());
+            self.sprites_program.bind_pipeline();
+            self.sprites_program.bind_uniform(&self.transform);
+            self.sprites_program.bind_textures(&self.textures);
+            self.sprites_program.bind_variable(&self.sprites_variable);
+            self.sprites_program.draw(6, sprites);
+        }
+        self.normals.end(graphics);
+        self.accumulation.begin(graphics, self.ambient);
+        self.lights_program.bind_pipeline();
+        self.lights_program.bind_textures(&self.layers);
+        self.lights_program.bind_variable(&self.lights_variable);
+        self.lights_program.push_constants(&constants);
+        self.lights_program.draw(6, 1);
+        self.accumulation.end(graphics);
+        self.composite_program.bind_pipeline();
+        self.composite_program.bind_textures(&self.layers);
+        self.composite_program.push_constants(&constants);
+        self.composite_program.draw(6, 1);
+        Ok(())
+    }
+}
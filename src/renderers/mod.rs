@@ -1 +1,99 @@
+pub use adjustment::*;
+pub use blur::*;
+pub use culling::*;
+pub use grading::*;
+pub use layer::*;
+pub use lighting::*;
+pub use overlay::*;
+pub use particles::*;
+pub use resolution::*;
+pub use shapes::*;
+pub use skeleton::*;
+pub use sprites::*;
+pub use text::*;
+use crate::math::{Rect, SpatialHash};
+use crate::Camera;
+use std::cmp::Ordering;
+use std::hash::Hash;
 
+mod adjustment;
+mod blur;
+mod culling;
+mod grading;
+mod layer;
+mod lighting;
+mod overlay;
+mod particles;
+mod resolution;
+mod shapes;
+mod skeleton;
+mod sprites;
+mod text;
+
+/// Creates shader of SPIR-V compiled from GLSL source in `src/renderers/builtin`.
+macro_rules! embedded_shader {
+    ($name:literal) => {
+        crate::Shader::embedded(
+            $name,
+            include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/src/renderers/builtin/",
+                $name,
+                ".spv"
+            )),
+        )
+    };
+}
+pub(crate) use embedded_shader;
+
+/// Sorts elements by depth from far to near, so transparent elements blend correctly
+/// when drawn with depth test enabled but without depth write.
+///
+/// Depth follows orthographic camera convention: 0.0 is near plane, 1.0 is far plane.
+/// Sorting is stable, elements with equal depth keep submission order.
+pub fn sort_back_to_front<T, F>(elements: &mut [T], depth: F)
+where
+    F: Fn(&T) -> f32,
+{
+    elements.sort_by(|a, b| {
+        depth(b)
+            .partial_cmp(&depth(a))
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+/// Keeps only elements with world bounds overlapping camera visible rect,
+/// so large maps push into storage only what is on screen.
+///
+/// Relative order of kept elements is preserved.
+pub fn retain_visible<T, F>(elements: &mut Vec<T>, camera: &Camera, bounds: F)
+where
+    F: Fn(&T) -> Rect,
+{
+    let view = camera.visible_rect();
+    elements.retain(|element| view.intersects(&bounds(element)));
+}
+
+/// Returns iterator over elements with world bounds overlapping view rect,
+/// see [`Camera::visible_rect`].
+pub fn filter_visible<'a, T, F>(
+    elements: &'a [T],
+    view: Rect,
+    bounds: F,
+) -> impl Iterator<Item = &'a T>
+where
+    F: Fn(&T) -> Rect + 'a,
+{
+    elements
+        .iter()
+        .filter(move |element| view.intersects(&bounds(element)))
+}
+
+/// Returns keys of spatial index visible by camera, so static map elements are not
+/// tested one by one every frame.
+pub fn query_visible<K>(index: &SpatialHash<K>, camera: &Camera) -> Vec<K>
+where
+    K: Copy + Eq + Hash,
+{
+    index.query(&camera.visible_rect())
+}
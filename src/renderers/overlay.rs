@@ -0,0 +1,168 @@
+use crate::math::{Vec2, Vec4};
+use crate::renderers::{ShapeRenderer, TextEffects, TextRenderer};
+use crate::{CameraHandle, FontIndex, Graphics, LayoutSettings, ProgramError, Shader};
+use log::error;
+use sdl2::keyboard::Keycode;
+use std::collections::VecDeque;
+
+/// Draws FPS, frame time graph, draw calls and texture memory over the scene,
+/// so performance regressions are visible without external tools.
+///
+/// Shown on start if `stats_overlay` is enabled in config, toggled by F3.
+/// Shaders are loaded from given directory like [`crate::Frame`] does.
+///
+/// ```ignore
+/// let mut overlay = StatsOverlay::create(&mut graphics, "./shaders")?;
+/// loop {
+///     graphics.capture_user_input();
+///     graphics.clear([0.0, 0.0, 0.0, 1.0]);
+///     // render scene
+///     overlay.update(&mut graphics);
+///     overlay.render();
+///     graphics.present();
+/// }
+/// ```
+pub struct StatsOverlay {
+    pub camera: CameraHandle,
+    pub visible: bool,
+    pub toggle_key: Option<Keycode>,
+    font: FontIndex,
+    shapes: ShapeRenderer,
+    text: TextRenderer,
+    frame_times: VecDeque<f32>,
+}
+
+impl StatsOverlay {
+    /// Number of frames shown in frame time graph.
+    pub const HISTORY: usize = 120;
+    /// Frame time drawn at full graph height, in seconds.
+    const GRAPH_SCALE: f32 = 1.0 / 20.0;
+    const GRAPH_HEIGHT: f32 = 60.0;
+    const BAR_WIDTH: f32 = 2.0;
+    const PADDING: f32 = 8.0;
+
+    pub fn create(graphics: &mut Graphics, shaders: &str) -> Result<Self, ProgramError> {
+        let shader =
+            |name: &str, stage: &str| Shader::new(&format!("{shaders}/{name}.{stage}.spv"));
+        let camera = graphics.camera();
+        let shapes =
+            ShapeRenderer::create(graphics, shader("shapes", "vert"), shader("shapes", "frag"))?;
+        let text = TextRenderer::create(graphics, shader("text", "vert"), shader("text", "frag"))?;
+        Ok(Self {
+            camera,
+            visible: graphics.stats_overlay,
+            toggle_key: Some(Keycode::F3),
+            // default font is always loaded first
+            font: FontIndex::default(),
+            shapes,
+            text,
+            frame_times: VecDeque::with_capacity(Self::HISTORY),
+        })
+    }
+
+    pub fn toggle_key(mut self, key: Option<Keycode>) -> Self {
+        self.toggle_key = key;
+        self
+    }
+
+    /// Records frame time and draws overlay if visible, must be called once per frame
+    /// between `clear` and `present`.
+    pub fn update(&mut self, graphics: &mut Graphics) {
+        if let Some(key) = self.toggle_key {
+            if graphics.input.keys.pressed.contains(&key) {
+                self.visible = !self.visible;
+            }
+        }
+        if self.frame_times.len() == Self::HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times
+            .push_back(graphics.input.time.as_secs_f32());
+        if self.visible {
+            self.draw(graphics);
+        }
+    }
+
+    /// Returns frames per second averaged over graph history.
+    pub fn fps(&self) -> f32 {
+        let total: f32 = self.frame_times.iter().sum();
+        if total > 0.0 {
+            self.frame_times.len() as f32 / total
+        } else {
+            0.0
+        }
+    }
+
+    fn draw(&mut self, graphics: &mut Graphics) {
+        let stats = graphics.frame_stats();
+        let frame_time = self.frame_times.back().copied().unwrap_or_default();
+        let texture_memory = graphics.textures.memory_usage() as f32 / (1024.0 * 1024.0);
+        let lines = [
+            format!("{:.0} fps {:.2} ms", self.fps(), frame_time * 1000.0),
+            format!(
+                "{} draw calls {} instances {} vertices",
+                stats.draw_calls, stats.instances, stats.vertices
+            ),
+            format!(
+                "{} KB uploaded {} textures bound",
+                stats.bytes_uploaded / 1024,
+                stats.textures_bound
+            ),
+            format!("{texture_memory:.1} MB textures"),
+        ];
+        let padding = Self::PADDING;
+        let width = Self::HISTORY as f32 * Self::BAR_WIDTH;
+        let line_height = 18.0;
+        let text_height = lines.len() as f32 * line_height;
+        let height = Self::GRAPH_HEIGHT + text_height + padding * 3.0;
+        draw_rect(
+            &mut self.shapes,
+            [padding, padding],
+            [width + padding * 2.0, height],
+            [0.0, 0.0, 0.0, 0.6],
+        );
+        for (index, line) in lines.iter().enumerate() {
+            let position = [padding * 2.0, padding * 2.0 + index as f32 * line_height];
+            let result = self.text.draw_text(
+                graphics,
+                self.font,
+                line,
+                position,
+                [1.0; 4],
+                LayoutSettings::default(),
+                TextEffects::default(),
+            );
+            if let Err(error) = result {
+                error!("unable to draw stats overlay text, {error:?}");
+            }
+        }
+        let bottom = padding * 3.0 + text_height + Self::GRAPH_HEIGHT;
+        for (index, &time) in self.frame_times.iter().enumerate() {
+            let height = (time / Self::GRAPH_SCALE).min(1.0) * Self::GRAPH_HEIGHT;
+            let color = if time <= 1.0 / 55.0 {
+                [0.2, 0.8, 0.2, 1.0]
+            } else if time <= 1.0 / 28.0 {
+                [0.9, 0.8, 0.2, 1.0]
+            } else {
+                [0.9, 0.2, 0.2, 1.0]
+            };
+            let x = padding * 2.0 + index as f32 * Self::BAR_WIDTH;
+            let size = [Self::BAR_WIDTH, height];
+            draw_rect(&mut self.shapes, [x, bottom - height], size, color);
+        }
+    }
+
+    /// Renders overlay drawn by update, must be called after scene rendering
+    /// between `clear` and `present`.
+    pub fn render(&mut self) {
+        let camera = self.camera.read().expect("camera must be readable");
+        self.shapes.render(&camera);
+        self.text.render(&camera);
+    }
+}
+
+fn draw_rect(shapes: &mut ShapeRenderer, position: Vec2, size: Vec2, color: Vec4) {
+    if let Err(error) = shapes.draw_rect(position, size, color) {
+        error!("unable to draw stats overlay rect, {error:?}");
+    }
+}
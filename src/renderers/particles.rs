@@ -0,0 +1,267 @@
+use crate::math::{Vec2, Vec4};
+use crate::{
+    compute_range, Camera, Colors, ComputeProgram, DeviceStorage, Graphics, Program, ProgramError,
+    ProgramOptions, Shader, Storage, Transform, Uniform, Variable, VariableError,
+};
+use vulkanalia::vk;
+use vulkanalia::vk::{DeviceV1_0, HasBuilder};
+
+/// Represents GLSL particle structure, particle is dead once life reaches zero.
+///
+/// ```glsl
+/// struct Particle {
+///     vec4 color;
+///     vec2 position;
+///     vec2 velocity;
+///     float size;
+///     float life;
+/// };
+/// ```
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct Particle {
+    pub color: Vec4,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub size: f32,
+    /// Remaining life time in seconds.
+    pub life: f32,
+    _padding: [f32; 2],
+}
+
+impl Particle {
+    pub fn new(position: Vec2, velocity: Vec2, size: f32, life: f32, color: impl Colors) -> Self {
+        Self {
+            color: color.to_vec4(),
+            position,
+            velocity,
+            size,
+            life,
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+/// Represents GLSL push constants of particle simulation program.
+///
+/// Particles are kept in ring of `capacity` slots, spawned particles replace slots
+/// starting from `cursor`, so oldest particles are replaced once ring is full.
+///
+/// ```glsl
+/// layout (local_size_x = 64) in;
+/// layout (push_constant) uniform Simulation {
+///     vec2 gravity;
+///     float delta;
+///     uint cursor;
+///     uint spawns;
+///     uint capacity;
+/// };
+/// layout (std430, set = 0, binding = 0) buffer State {
+///     Particle particles[];
+/// };
+/// layout (std430, set = 1, binding = 0) readonly buffer Spawns {
+///     Particle spawned[];
+/// };
+/// layout (std430, set = 2, binding = 0) writeonly buffer Alive {
+///     Particle alive[];
+/// };
+/// layout (std430, set = 3, binding = 0) buffer Command {
+///     uint vertexCount;
+///     uint instanceCount;
+///     uint firstVertex;
+///     uint firstInstance;
+/// };
+/// // uint offset = (index + capacity - cursor) % capacity;
+/// // if (offset < spawns) particles[index] = spawned[offset];
+/// // simulate, then if (life > 0) alive[atomicAdd(instanceCount, 1)] = particle;
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct SimulationConstants {
+    gravity: Vec2,
+    delta: f32,
+    cursor: u32,
+    spawns: u32,
+    capacity: u32,
+    _padding: [u32; 2],
+}
+
+/// Simulates particles on GPU, particle state stays in device local memory
+/// and alive particles are drawn by indirect draw, so CPU only uploads spawned particles.
+///
+/// ```ignore
+/// let mut sparks = ParticleSystem::create(&mut graphics, simulation, vert, frag, 1 << 18)?;
+/// sparks.emit(Particle::new(position, velocity, 2.0, 1.5, "orange"))?;
+/// // every frame between clear and present
+/// sparks.update(&mut graphics, input.clock.delta());
+/// sparks.render(&camera);
+/// ```
+pub struct ParticleSystem {
+    simulation: Box<ComputeProgram>,
+    program: Box<Program>,
+    transform: Uniform<Transform>,
+    state: DeviceStorage<Particle>,
+    state_variable: Variable,
+    spawns: Storage<Particle>,
+    spawns_variable: Variable,
+    alive: DeviceStorage<Particle>,
+    alive_simulation_variable: Variable,
+    alive_variable: Variable,
+    command: DeviceStorage<vk::DrawIndirectCommand>,
+    command_variable: Variable,
+    capacity: usize,
+    cursor: usize,
+    initialized: bool,
+    /// Acceleration applied to all particles, in units per second squared.
+    pub gravity: Vec2,
+}
+
+impl ParticleSystem {
+    pub const MAX_SPAWNS: usize = 16384;
+    pub const WORKGROUP_SIZE: usize = 64;
+
+    pub fn create(
+        graphics: &mut Graphics,
+        simulation: Shader,
+        vert: Shader,
+        frag: Shader,
+        capacity: usize,
+    ) -> Result<Self, ProgramError> {
+        let state = graphics.device_storage::<Particle>(capacity, false);
+        let state_variable = state.layout(0, 0);
+        let spawns = graphics.storage::<Particle>(Self::MAX_SPAWNS);
+        let spawns_variable = spawns.layout(1, 0);
+        // alive particles of previous frame may still be drawn while next frame simulates
+        let alive = graphics.device_storage::<Particle>(capacity, true);
+        let alive_simulation_variable = alive.layout(2, 0);
+        let command = graphics.device_storage::<vk::DrawIndirectCommand>(1, true);
+        let command_variable = command.layout(3, 0);
+        let simulation = graphics.create_compute_program(
+            "particles simulation",
+            simulation,
+            vec![compute_range::<SimulationConstants>()],
+            vec![
+                state_variable.layout,
+                spawns_variable.layout,
+                alive_simulation_variable.layout,
+                command_variable.layout,
+            ],
+        )?;
+        let transform = graphics.uniform::<Transform>(0, 0);
+        let alive_variable = alive.layout(1, 0);
+        let sampler = graphics.create_linear_sampler();
+        let program = graphics.create_program(
+            "particles",
+            vert,
+            frag,
+            vec![],
+            sampler,
+            vec![transform.layout(), alive_variable.layout],
+            None,
+            ProgramOptions::default(),
+        )?;
+        Ok(Self {
+            simulation,
+            program,
+            transform,
+            state,
+            state_variable,
+            spawns,
+            spawns_variable,
+            alive,
+            alive_simulation_variable,
+            alive_variable,
+            command,
+            command_variable,
+            capacity,
+            cursor: 0,
+            initialized: false,
+            gravity: [0.0, 0.0],
+        })
+    }
+
+    /// Spawns particle on next update.
+    pub fn emit(&mut self, particle: Particle) -> Result<(), VariableError> {
+        self.spawns.push(particle)?;
+        Ok(())
+    }
+
+    /// Submits simulation step, must be called between `clear` and `present` before render.
+    pub fn update(&mut self, graphics: &mut Graphics, delta: f32) {
+        let frame = graphics.frame();
+        let spawns = self.spawns.take_and_update(frame).min(self.capacity);
+        let constants = SimulationConstants {
+            gravity: self.gravity,
+            delta,
+            cursor: self.cursor as u32,
+            spawns: spawns as u32,
+            capacity: self.capacity as u32,
+            _padding: [0; 2],
+        };
+        self.cursor = (self.cursor + spawns) % self.capacity.max(1);
+        let command = vk::DrawIndirectCommand {
+            vertex_count: 6,
+            instance_count: 0,
+            first_vertex: 0,
+            first_instance: 0,
+        };
+        let initialized = self.initialized;
+        self.initialized = true;
+        let groups = self.capacity.div_ceil(Self::WORKGROUP_SIZE) as u32;
+        let simulation = &self.simulation;
+        let state = &self.state;
+        let command_storage = &self.command;
+        let variables = [
+            &self.state_variable,
+            &self.spawns_variable,
+            &self.alive_simulation_variable,
+            &self.command_variable,
+        ];
+        graphics.submit_async_compute(|commands| {
+            let device = &simulation.device;
+            if !initialized {
+                // zero life marks slot as dead
+                state.fill(commands, frame, 0);
+            }
+            command_storage.update(commands, frame, &[command]);
+            // previous simulation step writes state, transfers write state and command
+            let barrier = vk::MemoryBarrier::builder()
+                .src_access_mask(vk::AccessFlags::SHADER_WRITE | vk::AccessFlags::TRANSFER_WRITE)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE);
+            unsafe {
+                device.cmd_pipeline_barrier(
+                    commands,
+                    vk::PipelineStageFlags::COMPUTE_SHADER | vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::COMPUTE_SHADER,
+                    vk::DependencyFlags::empty(),
+                    &[barrier],
+                    &[] as &[vk::BufferMemoryBarrier],
+                    &[] as &[vk::ImageMemoryBarrier],
+                );
+            }
+            simulation.bind_pipeline(commands);
+            for variable in variables {
+                simulation.bind_descriptor(commands, variable.set, variable.descriptor(frame));
+            }
+            simulation.push_constants(commands, &constants);
+            simulation.dispatch(commands, groups, 1, 1);
+        });
+    }
+
+    pub fn render(&mut self, camera: &Camera) {
+        let frame = self.program.frame();
+        self.transform.update(frame, &camera.get_transform());
+        self.program.bind_pipeline();
+        self.program.bind_uniform(&self.transform);
+        self.program.bind_variable(&self.alive_variable);
+        self.program
+            .draw_indirect_buffer(self.command.buffer(frame), 1);
+    }
+
+    /// Destroys device local storages, must be called once frames in flight complete.
+    pub fn destroy(&self) {
+        self.state.destroy();
+        self.alive.destroy();
+        self.command.destroy();
+    }
+}
@@ -0,0 +1,150 @@
+use crate::math::Vec2;
+use crate::renderers::LayerTarget;
+use crate::{
+    range, Colors, Graphics, Program, ProgramError, ProgramOptions, Shader, Textures, VariableError,
+};
+use std::time::Instant;
+use vulkanalia::vk;
+
+/// Represents GLSL push constants of upscale program.
+///
+/// ```glsl
+/// layout (push_constant) uniform Upscale {
+///     vec2 uv;
+///     uint texture;
+/// };
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+struct UpscaleConstants {
+    uv: Vec2,
+    texture: u32,
+    _padding: u32,
+}
+
+/// Renders layer (e.g. world) at reduced internal resolution and upscales it to screen,
+/// so UI rendered after it stays at native resolution.
+///
+/// Layer target is allocated at native resolution, scale only limits rendered region,
+/// so scale can be changed every frame without target recreation. If target frame time
+/// is set, scale is adjusted by measured frame time to hold frame rate on weak GPUs.
+pub struct DynamicResolution {
+    program: Box<Program>,
+    textures: Textures,
+    sampler: vk::Sampler,
+    layer: LayerTarget,
+    scale: f32,
+    min_scale: f32,
+    max_scale: f32,
+    target_frame_time: Option<f32>,
+    last_frame: Option<Instant>,
+}
+
+impl DynamicResolution {
+    /// Scale change per frame when frame time is out of target range.
+    const SCALE_STEP: f32 = 0.02;
+
+    pub fn create(
+        graphics: &mut Graphics,
+        vert: Shader,
+        frag: Shader,
+    ) -> Result<Self, ProgramError> {
+        let textures = graphics.textures(0, 0);
+        let sampler = graphics.create_linear_sampler();
+        let program = graphics.create_program(
+            "upscale",
+            vert,
+            frag,
+            vec![range::<UpscaleConstants>()],
+            sampler,
+            vec![textures.layout()],
+            None,
+            ProgramOptions::default(),
+        )?;
+        Ok(Self {
+            program,
+            textures,
+            sampler,
+            layer: LayerTarget::new(),
+            scale: 1.0,
+            min_scale: 0.5,
+            max_scale: 1.0,
+            target_frame_time: None,
+            last_frame: None,
+        })
+    }
+
+    /// Limits scale adjusted by frame time feedback.
+    pub fn scale_range(mut self, min: f32, max: f32) -> Self {
+        self.min_scale = min.clamp(0.1, 1.0);
+        self.max_scale = max.clamp(self.min_scale, 1.0);
+        self.scale = self.scale.clamp(self.min_scale, self.max_scale);
+        self
+    }
+
+    /// Enables scale adjustment to hold given frame time in seconds, e.g. `1.0 / 60.0`.
+    pub fn target_frame_time(mut self, seconds: f32) -> Self {
+        self.target_frame_time = Some(seconds);
+        self
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.clamp(self.min_scale, self.max_scale);
+    }
+
+    /// Starts rendering of layer at current scale, draws go to layer target until `end`.
+    ///
+    /// Must be called between `clear` and `present`, once per frame.
+    pub fn begin(&mut self, graphics: &mut Graphics, color: impl Colors) {
+        self.adjust_scale();
+        let extent = graphics.vulkan.swapchain.extent;
+        let target = self.layer.resize_to_screen(graphics);
+        target.set_viewport(
+            (extent.width as f32 * self.scale).round() as u32,
+            (extent.height as f32 * self.scale).round() as u32,
+        );
+        self.layer.begin(graphics, color);
+    }
+
+    pub fn end(&mut self, graphics: &mut Graphics) {
+        self.layer.end(graphics);
+    }
+
+    /// Draws upscaled layer over whole screen in current render pass.
+    pub fn draw(&mut self) -> Result<(), VariableError> {
+        let target = self.layer.target();
+        let texture = self.textures.store(target.texture, self.sampler)?;
+        let viewport = target.viewport();
+        let constants = UpscaleConstants {
+            uv: [
+                viewport.width as f32 / target.extent.width as f32,
+                viewport.height as f32 / target.extent.height as f32,
+            ],
+            texture,
+            _padding: 0,
+        };
+        self.program.bind_pipeline();
+        self.program.bind_textures(&self.textures);
+        self.program.push_constants(&constants);
+        self.program.draw(6, 1);
+        Ok(())
+    }
+
+    fn adjust_scale(&mut self) {
+        let now = Instant::now();
+        let last_frame = self.last_frame.replace(now);
+        if let (Some(target), Some(last_frame)) = (self.target_frame_time, last_frame) {
+            let frame_time = now.duration_since(last_frame).as_secs_f32();
+            // tolerance band prevents scale oscillation around target
+            if frame_time > target * 1.05 {
+                self.set_scale(self.scale - Self::SCALE_STEP);
+            } else if frame_time < target * 0.85 {
+                self.set_scale(self.scale + Self::SCALE_STEP);
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::math::Vec2;
+use crate::{
+    Camera, Colors, Graphics, Mesh, Program, ProgramError, ProgramOptions, Shader, Transform,
+    Uniform, VariableError, Vertices,
+};
+
+/// Renders colored polygons, vertices are collected into dynamic mesh every frame.
+///
+/// ```glsl
+/// layout (location = 0) in vec2 position;
+/// layout (location = 1) in vec4 color;
+/// layout (location = 2) in vec2 uv;
+///
+/// layout (set = 0, binding = 0) uniform Transform {
+///     mat4 model;
+///     mat4 view;
+///     mat4 proj;
+/// } transform;
+/// ```
+pub struct ShapeRenderer {
+    program: Box<Program>,
+    transform: Uniform<Transform>,
+    mesh: Mesh,
+}
+
+impl ShapeRenderer {
+    pub const MAX_VERTICES: usize = 65536;
+
+    pub fn create(
+        graphics: &mut Graphics,
+        vert: Shader,
+        frag: Shader,
+    ) -> Result<Self, ProgramError> {
+        let transform = graphics.uniform::<Transform>(0, 0);
+        // mesh grows on demand, most frames draw far fewer vertices
+        let mut mesh = graphics.mesh(Self::MAX_VERTICES / 16);
+        mesh.set_limit(Some(Self::MAX_VERTICES));
+        let sampler = graphics.create_pixel_perfect_sampler();
+        let program = graphics.create_program(
+            "shapes",
+            vert,
+            frag,
+            vec![],
+            sampler,
+            vec![transform.layout()],
+            mesh.input_state(),
+            ProgramOptions::default(),
+        )?;
+        Ok(Self {
+            program,
+            transform,
+            mesh,
+        })
+    }
+
+    pub fn draw_rect(
+        &mut self,
+        position: Vec2,
+        size: Vec2,
+        color: impl Colors,
+    ) -> Result<Vertices, VariableError> {
+        let limit = Self::MAX_VERTICES;
+        self.mesh
+            .add_rect(position, size, color)
+            .ok_or(VariableError::StorageOverflow { limit })
+    }
+
+    pub fn draw_polygon(
+        &mut self,
+        vertices: &[Vec2],
+        color: impl Colors,
+    ) -> Result<Vertices, VariableError> {
+        let limit = Self::MAX_VERTICES;
+        self.mesh
+            .add_polygon(vertices, color)
+            .ok_or(VariableError::StorageOverflow { limit })
+    }
+
+    pub fn render(&mut self, camera: &Camera) {
+        let frame = self.program.frame();
+        let len = self.mesh.update(frame);
+        if len == 0 {
+            return;
+        }
+        self.transform.update(frame, &camera.get_transform());
+        self.program.bind_pipeline();
+        self.program.bind_uniform(&self.transform);
+        self.program.bind_mesh(&self.mesh);
+        self.program.draw_sub_mesh(Vertices { ptr: 0, len });
+    }
+}
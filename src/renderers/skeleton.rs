@@ -0,0 +1,250 @@
+use crate::math::{Vec2, Vec4};
+use crate::{
+    AttachmentData, AttachmentKind, BoneMatrix, Camera, Graphics, Program, ProgramError,
+    ProgramOptions, Shader, Skeleton, Storage, Textures, Transform, Uniform, Variable,
+    VariableError,
+};
+use vulkanalia::vk;
+
+/// Represents GLSL skinned vertex structure, vertex position is sum of positions in
+/// bone space transformed by bones and multiplied by weights.
+///
+/// Vertices and bones are pulled by index in vertex shader, skeletons share single draw call.
+///
+/// ```glsl
+/// struct SkinVertex {
+///     vec4 weights;
+///     ivec4 bones;
+///     vec2 positions[4];
+///     vec4 color;
+///     vec2 uv;
+///     int texture;
+/// };
+/// layout (std430, set = 2, binding = 0) readonly buffer Bones {
+///     Bone bones[];
+/// };
+/// layout (std430, set = 3, binding = 0) readonly buffer Vertices {
+///     SkinVertex vertices[];
+/// };
+/// layout (std430, set = 4, binding = 0) readonly buffer Indices {
+///     uint indices[];
+/// };
+/// // SkinVertex vertex = vertices[indices[gl_VertexIndex]];
+/// ```
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct SkinVertex {
+    pub weights: Vec4,
+    pub bones: [i32; 4],
+    pub positions: [Vec2; 4],
+    pub color: Vec4,
+    pub uv: Vec2,
+    pub texture: i32,
+    _padding: i32,
+}
+
+impl SkinVertex {
+    /// Maximum number of bones affecting single vertex, less significant bones are dropped.
+    pub const MAX_INFLUENCES: usize = 4;
+}
+
+/// Renders Spine skeletons posed by [`Skeleton`] with skinning on GPU.
+///
+/// Attachment textures are resolved by attachment path or name, so atlas regions
+/// should be registered as texture aliases.
+pub struct SkeletonRenderer {
+    program: Box<Program>,
+    transform: Uniform<Transform>,
+    textures: Textures,
+    sampler: vk::Sampler,
+    bones: Storage<BoneMatrix>,
+    bones_variable: Variable,
+    vertices: Storage<SkinVertex>,
+    vertices_variable: Variable,
+    indices: Storage<u32>,
+    indices_variable: Variable,
+}
+
+impl SkeletonRenderer {
+    pub const MAX_BONES: usize = 4096;
+    pub const MAX_VERTICES: usize = 65536;
+    pub const MAX_INDICES: usize = 196608;
+
+    pub fn create(
+        graphics: &mut Graphics,
+        vert: Shader,
+        frag: Shader,
+    ) -> Result<Self, ProgramError> {
+        let transform = graphics.uniform::<Transform>(0, 0);
+        let textures = graphics.textures(1, 0);
+        let bones = graphics.storage::<BoneMatrix>(Self::MAX_BONES);
+        let bones_variable = bones.layout(2, 0);
+        let vertices = graphics.storage::<SkinVertex>(Self::MAX_VERTICES);
+        let vertices_variable = vertices.layout(3, 0);
+        let indices = graphics.storage::<u32>(Self::MAX_INDICES);
+        let indices_variable = indices.layout(4, 0);
+        let sampler = graphics.create_pixel_perfect_sampler();
+        let program = graphics.create_program(
+            "skeleton",
+            vert,
+            frag,
+            vec![],
+            sampler,
+            vec![
+                transform.layout(),
+                textures.layout(),
+                bones_variable.layout,
+                vertices_variable.layout,
+                indices_variable.layout,
+            ],
+            None,
+            ProgramOptions::default(),
+        )?;
+        Ok(Self {
+            program,
+            transform,
+            textures,
+            sampler,
+            bones,
+            bones_variable,
+            vertices,
+            vertices_variable,
+            indices,
+            indices_variable,
+        })
+    }
+
+    /// Draws visible attachments of skeleton in draw order, skeleton must be updated.
+    pub fn draw(
+        &mut self,
+        graphics: &mut Graphics,
+        skeleton: &Skeleton,
+    ) -> Result<(), VariableError> {
+        let bones = self.bones.extend(skeleton.bone_matrices())? as i32;
+        for visible in skeleton.visible_attachments() {
+            let attachment = visible.attachment;
+            let (mut vertices, triangles) = match attachment.kind {
+                AttachmentKind::Region => skin_region(attachment, visible.bone),
+                AttachmentKind::Mesh => skin_mesh(attachment, visible.bone),
+                AttachmentKind::Unsupported => continue,
+            };
+            let path = attachment.path.as_deref().unwrap_or(visible.name);
+            let region = graphics.textures.get_texture_region(path);
+            let texture = self.textures.store(region.texture, self.sampler)? as i32;
+            for vertex in vertices.iter_mut() {
+                vertex.bones = vertex.bones.map(|bone| bones + bone);
+                vertex.color = visible.color;
+                vertex.texture = texture;
+                vertex.uv = [
+                    region.src[0] + vertex.uv[0] * region.uv[0],
+                    region.src[1] + vertex.uv[1] * region.uv[1],
+                ];
+            }
+            let base = self.vertices.extend(&vertices)?;
+            let indices: Vec<u32> = triangles.iter().map(|index| base + index).collect();
+            self.indices.extend(&indices)?;
+        }
+        Ok(())
+    }
+
+    pub fn render(&mut self, camera: &Camera) {
+        let frame = self.program.frame();
+        self.bones.take_and_update(frame);
+        self.vertices.take_and_update(frame);
+        let indices = self.indices.take_and_update(frame);
+        if indices == 0 {
+            return;
+        }
+        self.transform.update(frame, &camera.get_transform());
+        self.program.bind_pipeline();
+        self.program.bind_uniform(&self.transform);
+        self.program.bind_textures(&self.textures);
+        self.program.bind_variable(&self.bones_variable);
+        self.program.bind_variable(&self.vertices_variable);
+        self.program.bind_variable(&self.indices_variable);
+        self.program.draw(indices, 1);
+    }
+}
+
+fn rigid_vertex(bone: usize, position: Vec2, uv: Vec2) -> SkinVertex {
+    SkinVertex {
+        weights: [1.0, 0.0, 0.0, 0.0],
+        bones: [bone as i32, 0, 0, 0],
+        positions: [position, [0.0; 2], [0.0; 2], [0.0; 2]],
+        uv,
+        ..SkinVertex::default()
+    }
+}
+
+/// Returns quad of region attachment in slot bone space.
+fn skin_region(attachment: &AttachmentData, bone: usize) -> (Vec<SkinVertex>, Vec<u32>) {
+    let local = BoneMatrix::new(
+        [attachment.x, attachment.y],
+        attachment.rotation,
+        [attachment.scale_x, attachment.scale_y],
+    );
+    let w = attachment.width * 0.5;
+    let h = attachment.height * 0.5;
+    // Spine y axis points up, texture v axis points down
+    let corners = [
+        ([-w, -h], [0.0, 1.0]),
+        ([w, -h], [1.0, 1.0]),
+        ([w, h], [1.0, 0.0]),
+        ([-w, h], [0.0, 0.0]),
+    ];
+    let vertices = corners
+        .into_iter()
+        .map(|(corner, uv)| rigid_vertex(bone, local.transform(corner), uv))
+        .collect();
+    (vertices, vec![0, 1, 2, 2, 3, 0])
+}
+
+/// Returns mesh vertices weighted to slot bone, or to bones listed in mesh if weighted.
+fn skin_mesh(attachment: &AttachmentData, bone: usize) -> (Vec<SkinVertex>, Vec<u32>) {
+    let uvs: Vec<Vec2> = attachment
+        .uvs
+        .chunks_exact(2)
+        .map(|uv| [uv[0], uv[1]])
+        .collect();
+    let data = &attachment.vertices;
+    if data.len() == attachment.uvs.len() {
+        let vertices = data
+            .chunks_exact(2)
+            .zip(uvs)
+            .map(|(position, uv)| rigid_vertex(bone, [position[0], position[1]], uv))
+            .collect();
+        return (vertices, attachment.triangles.clone());
+    }
+    let mut vertices = Vec::with_capacity(uvs.len());
+    let mut cursor = 0;
+    for uv in uvs {
+        let count = data.get(cursor).copied().unwrap_or(0.0) as usize;
+        cursor += 1;
+        let mut influences: Vec<(f32, i32, Vec2)> = data
+            .get(cursor..)
+            .unwrap_or_default()
+            .chunks_exact(4)
+            .take(count)
+            .map(|influence| {
+                let [bone, x, y, weight] = [influence[0], influence[1], influence[2], influence[3]];
+                (weight, bone as i32, [x, y])
+            })
+            .collect();
+        cursor += count * 4;
+        influences.sort_by(|a, b| b.0.total_cmp(&a.0));
+        influences.truncate(SkinVertex::MAX_INFLUENCES);
+        let total: f32 = influences.iter().map(|influence| influence.0).sum();
+        let mut vertex = SkinVertex {
+            weights: [0.0; 4],
+            uv,
+            ..SkinVertex::default()
+        };
+        for (index, (weight, bone, position)) in influences.into_iter().enumerate() {
+            vertex.weights[index] = if total > 0.0 { weight / total } else { 0.0 };
+            vertex.bones[index] = bone;
+            vertex.positions[index] = position;
+        }
+        vertices.push(vertex);
+    }
+    (vertices, attachment.triangles.clone())
+}
@@ -0,0 +1,337 @@
+use crate::math::{Rect, Vec2, Vec4};
+use crate::renderers::sort_back_to_front;
+use crate::{
+    Camera, Colors, Graphics, PickTarget, Picking, Program, ProgramError, ProgramOptions, Shader,
+    Storage, Texture, TextureRegion, Textures, Transform, Uniform, Variable, VariableError,
+};
+use log::warn;
+use std::cell::RefCell;
+use std::rc::Rc;
+use vulkanalia::vk;
+
+/// Represents GLSL sprite structure stored in sprites storage buffer.
+///
+/// Texture is sampled in region from `src` of `uv` size in normalized coordinates,
+/// sampled color is multiplied by `color`. Depth is written to `gl_Position.z`.
+///
+/// Params are passed to fragment shader as is, for per-sprite effects like
+/// flash amount, dissolve threshold or outline width without separate programs.
+///
+/// ```glsl
+/// struct Sprite {
+///     vec4 color;
+///     vec4 params;
+///     vec2 src;
+///     vec2 uv;
+///     vec2 position;
+///     vec2 size;
+///     int texture;
+///     float depth;
+/// };
+/// layout (std430, set = 2, binding = 0) readonly buffer Sprites {
+///     Sprite sprites[];
+/// };
+/// ```
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct Sprite {
+    pub color: Vec4,
+    pub params: Vec4,
+    pub src: Vec2,
+    pub uv: Vec2,
+    pub position: Vec2,
+    pub size: Vec2,
+    pub texture: i32,
+    pub depth: f32,
+    _padding: [i32; 2],
+}
+
+/// Renders tinted images and image regions of atlases or sprite sheets.
+///
+/// Opacity groups fade whole subtree of UI, e.g. panel or tooltip, by single multiplier:
+///
+/// ```ignore
+/// sprites.push_opacity(tooltip.fade);
+/// sprites.draw_image(&mut graphics, "tooltip", position, size, "white")?;
+/// sprites.draw_image(&mut graphics, "icons/sword", icon, [32.0, 32.0], "white")?;
+/// sprites.pop_opacity();
+/// ```
+///
+/// Layers share program and textures but have separate sprites and transforms,
+/// e.g. world-space UI and screen-space HUD, layers are drawn in order of render calls:
+///
+/// ```ignore
+/// let hud = sprites.create_layer(&mut graphics);
+/// sprites.set_layer(SpriteRenderer::DEFAULT_LAYER)?;
+/// sprites.draw_image(&mut graphics, "health", unit.position, [32.0, 4.0], "red")?;
+/// sprites.set_layer(hud)?;
+/// sprites.draw_image(&mut graphics, "minimap", [0.0, 0.0], [256.0, 256.0], "white")?;
+/// sprites.render_layer(SpriteRenderer::DEFAULT_LAYER, &world_camera);
+/// sprites.render_layer(hud, &screen_camera);
+/// ```
+///
+/// Sprites are drawn with depth test, so they interleave with geometry written to depth
+/// buffer, e.g. tilemaps. Sprites of layer are sorted back-to-front before render,
+/// sprites of equal depth keep draw order:
+///
+/// ```ignore
+/// sprites.set_depth(0.5);
+/// sprites.draw_image(&mut graphics, "trees/oak", tree.position, [64.0, 96.0], "white")?;
+/// sprites.set_depth(0.0);
+/// ```
+///
+/// Params are attached to sprites same way, e.g. hit flash read by custom fragment shader:
+///
+/// ```ignore
+/// sprites.set_params([unit.flash, 0.0, 0.0, 0.0]);
+/// sprites.draw_image(&mut graphics, "units/knight", unit.position, [32.0, 32.0], "white")?;
+/// sprites.set_params([0.0; 4]);
+/// ```
+///
+/// Tagged sprites can be picked by cursor, see [`Graphics::pick`]:
+///
+/// ```ignore
+/// graphics.set_picking(true);
+/// sprites.set_pick_id(Some(unit.id));
+/// sprites.draw_image(&mut graphics, "units/knight", unit.position, [32.0, 32.0], "white")?;
+/// sprites.set_pick_id(None);
+/// let selected = graphics.pick_under_cursor();
+/// ```
+pub struct SpriteRenderer {
+    program: Box<Program>,
+    textures: Textures,
+    sampler: vk::Sampler,
+    layers: Vec<SpriteLayer>,
+    layer: usize,
+    /// Accumulated opacity of nested groups, current opacity is last.
+    opacity: Vec<f32>,
+    picking: Rc<RefCell<Picking>>,
+    pick_id: Option<u64>,
+    depth: f32,
+    params: Vec4,
+}
+
+struct SpriteLayer {
+    transform: Uniform<Transform>,
+    sprites: Storage<Sprite>,
+    sprites_variable: Variable,
+    /// Tagged sprites in world coordinates, recorded to picking on render.
+    picks: Vec<PickTarget>,
+}
+
+impl SpriteLayer {
+    fn create(graphics: &mut Graphics) -> Self {
+        let transform = graphics.uniform::<Transform>(0, 0);
+        let sprites = graphics.storage::<Sprite>(SpriteRenderer::MAX_SPRITES);
+        let sprites_variable = sprites.layout(2, 0);
+        Self {
+            transform,
+            sprites,
+            sprites_variable,
+            picks: vec![],
+        }
+    }
+}
+
+impl SpriteRenderer {
+    pub const MAX_SPRITES: usize = 16384;
+    pub const DEFAULT_LAYER: usize = 0;
+
+    pub fn create(
+        graphics: &mut Graphics,
+        vert: Shader,
+        frag: Shader,
+    ) -> Result<Self, ProgramError> {
+        let layer = SpriteLayer::create(graphics);
+        let textures = graphics.textures(1, 0);
+        let sampler = graphics.create_pixel_perfect_sampler();
+        let program = graphics.create_program(
+            "sprites",
+            vert,
+            frag,
+            vec![],
+            sampler,
+            vec![
+                layer.transform.layout(),
+                textures.layout(),
+                layer.sprites_variable.layout,
+            ],
+            None,
+            // sprites are transparent, so they are sorted instead of writing depth
+            ProgramOptions::default().depth(true, false),
+        )?;
+        Ok(Self {
+            program,
+            textures,
+            sampler,
+            layers: vec![layer],
+            layer: Self::DEFAULT_LAYER,
+            opacity: vec![],
+            picking: graphics.picking(),
+            pick_id: None,
+            depth: 0.0,
+            params: [0.0; 4],
+        })
+    }
+
+    /// Creates new layer with its own sprites and transform, returns layer index.
+    pub fn create_layer(&mut self, graphics: &mut Graphics) -> usize {
+        self.layers.push(SpriteLayer::create(graphics));
+        self.layers.len() - 1
+    }
+
+    /// Selects layer sprites are drawn to, current layer is kept if layer is not created.
+    pub fn set_layer(&mut self, layer: usize) -> Result<(), VariableError> {
+        if layer >= self.layers.len() {
+            return Err(VariableError::LayerNotCreated { layer });
+        }
+        self.layer = layer;
+        Ok(())
+    }
+
+    pub fn layer(&self) -> usize {
+        self.layer
+    }
+
+    /// Starts opacity group, opacity of sprites drawn until `pop_opacity` is multiplied
+    /// by given opacity and opacity of outer groups.
+    pub fn push_opacity(&mut self, opacity: f32) {
+        let current = self.opacity();
+        self.opacity.push(current * opacity.clamp(0.0, 1.0));
+    }
+
+    pub fn pop_opacity(&mut self) {
+        if self.opacity.pop().is_none() {
+            warn!("Unable to pop opacity, no opacity group started");
+        }
+    }
+
+    /// Returns opacity of current group, 1.0 outside of groups.
+    pub fn opacity(&self) -> f32 {
+        self.opacity.last().copied().unwrap_or(1.0)
+    }
+
+    /// Tags sprites drawn until next call with id for picking, `None` stops tagging.
+    pub fn set_pick_id(&mut self, id: Option<u64>) {
+        self.pick_id = id;
+    }
+
+    /// Sets depth of sprites drawn until next call, 0.0 is near plane and 1.0 is far plane.
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth.clamp(0.0, 1.0);
+    }
+
+    pub fn depth(&self) -> f32 {
+        self.depth
+    }
+
+    /// Sets params of sprites drawn until next call, passed to fragment shader as is.
+    pub fn set_params(&mut self, params: Vec4) {
+        self.params = params;
+    }
+
+    pub fn params(&self) -> Vec4 {
+        self.params
+    }
+
+    /// Draws whole texture multiplied by tint, alpha of tint is opacity.
+    pub fn draw_sprite(
+        &mut self,
+        texture: Texture,
+        position: Vec2,
+        size: Vec2,
+        tint: impl Colors,
+    ) -> Result<(), VariableError> {
+        let region = TextureRegion {
+            texture,
+            src: [0.0, 0.0],
+            uv: [1.0, 1.0],
+        };
+        self.draw_region(region, position, size, tint)
+    }
+
+    /// Draws texture region, e.g. frame of sprite sheet, multiplied by tint.
+    pub fn draw_region(
+        &mut self,
+        region: TextureRegion,
+        position: Vec2,
+        size: Vec2,
+        tint: impl Colors,
+    ) -> Result<(), VariableError> {
+        let texture = self.textures.store(region.texture, self.sampler)? as i32;
+        let [r, g, b, a] = tint.to_vec4();
+        let opacity = self.opacity();
+        let layer = &mut self.layers[self.layer];
+        layer.sprites.push(Sprite {
+            color: [r, g, b, a * opacity],
+            params: self.params,
+            src: region.src,
+            uv: region.uv,
+            position,
+            size,
+            texture,
+            depth: self.depth,
+            _padding: [0; 2],
+        })?;
+        if let Some(id) = self.pick_id {
+            if self.picking.borrow().is_enabled() {
+                let bounds = Rect::new(position, size);
+                layer.picks.push(PickTarget { id, bounds });
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws image by logical name, region of atlas is resolved from texture alias.
+    pub fn draw_image(
+        &mut self,
+        graphics: &mut Graphics,
+        name: &str,
+        position: Vec2,
+        size: Vec2,
+        tint: impl Colors,
+    ) -> Result<(), VariableError> {
+        let region = graphics.textures.get_texture_region(name);
+        self.draw_region(region, position, size, tint)
+    }
+
+    /// Renders default layer.
+    pub fn render(&mut self, camera: &Camera) {
+        self.render_layer(Self::DEFAULT_LAYER, camera);
+    }
+
+    /// Renders sprites drawn to layer since its previous render, nothing is rendered
+    /// if layer is not created.
+    pub fn render_layer(&mut self, layer: usize, camera: &Camera) {
+        if !self.opacity.is_empty() {
+            warn!(
+                "Renders sprites with {} opacity groups not popped",
+                self.opacity.len()
+            );
+            self.opacity.clear();
+        }
+        let frame = self.program.frame();
+        let layer = match self.layers.get_mut(layer) {
+            Some(layer) => layer,
+            None => {
+                warn!("Unable to render sprite layer {layer}, layer is not created");
+                return;
+            }
+        };
+        sort_back_to_front(layer.sprites.written_mut(), |sprite| sprite.depth);
+        let sprites = layer.sprites.take_and_update(frame);
+        let mut picking = self.picking.borrow_mut();
+        for pick in layer.picks.drain(..) {
+            picking.record(pick.id, camera.world_to_screen_rect(&pick.bounds));
+        }
+        if sprites == 0 {
+            return;
+        }
+        layer.transform.update(frame, &camera.get_transform());
+        self.program.bind_pipeline();
+        self.program.bind_uniform(&layer.transform);
+        self.program.bind_textures(&self.textures);
+        self.program.bind_variable(&layer.sprites_variable);
+        self.program.draw(6, sprites);
+    }
+}
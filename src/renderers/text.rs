@@ -0,0 +1,251 @@
+use crate::math::{Vec2, Vec4};
+use crate::{
+    range, Camera, Colors, FontIndex, Graphics, Program, ProgramError, ProgramOptions, Shader,
+    SpanChar, Storage, TextLayout, TextSpan, Textures, Transform, Uniform, Variable, VariableError,
+};
+use vulkanalia::vk;
+
+/// Drop shadow drawn under glyph in the same draw call.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct TextShadow {
+    pub offset: Vec2,
+    pub softness: f32,
+    pub color: Vec4,
+}
+
+/// Halo around glyph outline, fades out over given radius.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct TextGlow {
+    pub radius: f32,
+    pub color: Vec4,
+}
+
+/// Stroke around glyph outline, drawn under fill and over shadow and glow.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct TextOutline {
+    pub width: f32,
+    pub color: Vec4,
+}
+
+/// Per-text effects applied by text fragment shader, keeps HUD text readable
+/// over bright scenes without second render pass.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct TextEffects {
+    pub shadow: Option<TextShadow>,
+    pub glow: Option<TextGlow>,
+    pub outline: Option<TextOutline>,
+}
+
+impl TextEffects {
+    pub fn shadow(mut self, offset: Vec2, softness: f32, color: Vec4) -> Self {
+        self.shadow = Some(TextShadow {
+            offset,
+            softness,
+            color,
+        });
+        self
+    }
+
+    pub fn glow(mut self, radius: f32, color: Vec4) -> Self {
+        self.glow = Some(TextGlow { radius, color });
+        self
+    }
+
+    pub fn outline(mut self, width: f32, color: Vec4) -> Self {
+        self.outline = Some(TextOutline { width, color });
+        self
+    }
+}
+
+/// Represents GLSL glyph structure stored in glyphs storage buffer.
+///
+/// Glyph quad is expanded by margin to fit outline, shadow and glow. Fragment shader takes
+/// fill from glyph coverage at uv, shadow from coverage around uv shifted by shadow offset
+/// averaged within softness, glow from coverage averaged within glow radius and outline
+/// from max coverage within outline width. Layers are blended from shadow to fill.
+///
+/// Distance range is zero for bitmap fonts, then coverage is taken from alpha channel,
+/// otherwise from median of MSDF channels.
+///
+/// ```glsl
+/// struct Glyph {
+///     vec4 color;
+///     vec4 shadow_color;
+///     vec4 glow_color;
+///     vec4 outline_color;
+///     vec2 position;
+///     vec2 size;
+///     vec2 src;
+///     vec2 uv;
+///     vec2 shadow_offset;
+///     float shadow_softness;
+///     float glow_radius;
+///     int texture;
+///     float distance_range;
+///     float margin;
+///     float outline_width;
+/// };
+/// layout (std430, set = 2, binding = 0) readonly buffer Glyphs {
+///     Glyph glyphs[];
+/// };
+/// ```
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct Glyph {
+    pub color: Vec4,
+    pub shadow_color: Vec4,
+    pub glow_color: Vec4,
+    pub outline_color: Vec4,
+    pub position: Vec2,
+    pub size: Vec2,
+    pub src: Vec2,
+    pub uv: Vec2,
+    pub shadow_offset: Vec2,
+    pub shadow_softness: f32,
+    pub glow_radius: f32,
+    pub texture: i32,
+    pub distance_range: f32,
+    pub margin: f32,
+    pub outline_width: f32,
+}
+
+/// Renders text laid out by fonts in screen space.
+pub struct TextRenderer {
+    program: Box<Program>,
+    transform: Uniform<Transform>,
+    textures: Textures,
+    sampler: vk::Sampler,
+    distance_sampler: vk::Sampler,
+    glyphs: Storage<Glyph>,
+    glyphs_variable: Variable,
+}
+
+impl TextRenderer {
+    pub const MAX_GLYPHS: usize = 16384;
+
+    pub fn create(
+        graphics: &mut Graphics,
+        vert: Shader,
+        frag: Shader,
+    ) -> Result<Self, ProgramError> {
+        let transform = graphics.uniform::<Transform>(0, 0);
+        let textures = graphics.textures(1, 0);
+        let glyphs = graphics.storage::<Glyph>(Self::MAX_GLYPHS);
+        let glyphs_variable = glyphs.layout(2, 0);
+        let sampler = graphics.create_pixel_perfect_sampler();
+        let distance_sampler = graphics.create_linear_sampler();
+        let program = graphics.create_program(
+            "text",
+            vert,
+            frag,
+            vec![range::<Vec2>()],
+            sampler,
+            vec![
+                transform.layout(),
+                textures.layout(),
+                glyphs_variable.layout,
+            ],
+            None,
+            ProgramOptions::default(),
+        )?;
+        Ok(Self {
+            program,
+            transform,
+            textures,
+            sampler,
+            distance_sampler,
+            glyphs,
+            glyphs_variable,
+        })
+    }
+
+    /// Draws text, returns true if text is truncated to max lines of layout settings.
+    pub fn draw_text(
+        &mut self,
+        graphics: &mut Graphics,
+        font: FontIndex,
+        text: &str,
+        position: Vec2,
+        color: impl Colors,
+        settings: impl Into<TextLayout>,
+        effects: TextEffects,
+    ) -> Result<bool, VariableError> {
+        let spans = [TextSpan::new(text, font, color)];
+        self.draw_rich_text(graphics, &spans, position, settings, effects)
+    }
+
+    /// Draws paragraph of spans with own fonts and colors, see [`crate::FontLoader::layout_spans`].
+    /// Returns true if text is truncated to max lines of layout settings.
+    pub fn draw_rich_text(
+        &mut self,
+        graphics: &mut Graphics,
+        spans: &[TextSpan],
+        position: Vec2,
+        settings: impl Into<TextLayout>,
+        effects: TextEffects,
+    ) -> Result<bool, VariableError> {
+        let fonts = graphics.fonts.read().expect("fonts must be readable");
+        let shadow = effects.shadow.unwrap_or_default();
+        let glow = effects.glow.unwrap_or_default();
+        let outline = effects.outline.unwrap_or_default();
+        let shadow_extent = shadow.offset[0].abs().max(shadow.offset[1].abs()) + shadow.softness;
+        let margin = shadow_extent.max(glow.radius).max(outline.width);
+        let mut current = None;
+        let (chars, truncated) = fonts.layout_spans_truncated(spans, settings);
+        for SpanChar { char, color, font } in chars {
+            let (texture, distance_range) = match current {
+                Some((index, texture, distance_range)) if index == font => {
+                    (texture, distance_range)
+                }
+                _ => {
+                    let font_ref = fonts.get_font(font);
+                    let texture = graphics.textures.get_texture(&font_ref.texture);
+                    let sampler = match font_ref.distance_range {
+                        Some(_) => self.distance_sampler,
+                        None => self.sampler,
+                    };
+                    let texture = self.textures.store(texture, sampler)? as i32;
+                    let distance_range = font_ref.distance_range.unwrap_or(0.0);
+                    current = Some((font, texture, distance_range));
+                    (texture, distance_range)
+                }
+            };
+            self.glyphs.push(Glyph {
+                color,
+                shadow_color: shadow.color,
+                glow_color: glow.color,
+                outline_color: outline.color,
+                position: [
+                    position[0] + char.position[0],
+                    position[1] + char.position[1],
+                ],
+                size: char.size,
+                src: char.src,
+                uv: char.uv,
+                shadow_offset: shadow.offset,
+                shadow_softness: shadow.softness,
+                glow_radius: glow.radius,
+                texture,
+                distance_range,
+                margin,
+                outline_width: outline.width,
+            })?;
+        }
+        Ok(truncated)
+    }
+
+    pub fn render(&mut self, camera: &Camera) {
+        let frame = self.program.frame();
+        let glyphs = self.glyphs.take_and_update(frame);
+        if glyphs == 0 {
+            return;
+        }
+        self.transform.update(frame, &camera.get_screen_transform());
+        self.program.bind_pipeline();
+        self.program.bind_uniform(&self.transform);
+        self.program.bind_textures(&self.textures);
+        self.program.bind_variable(&self.glyphs_variable);
+        self.program.push_constants(&camera.screen);
+        self.program.draw(6, glyphs);
+    }
+}
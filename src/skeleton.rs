@@ -0,0 +1,647 @@
+use crate::math::{Vec2, Vec4};
+use crate::Colors;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Skeleton exported from Spine as JSON, can be deserialized with any serde format.
+///
+/// Supported subset: bones without shear and inherit modes, region and mesh attachments,
+/// rotate, translate, scale, attachment and draw order timelines. Bezier curves are
+/// interpolated linearly, stepped curves are respected.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct SkeletonData {
+    /// Bones ordered from root, parent always precedes children.
+    #[serde(default)]
+    pub bones: Vec<BoneData>,
+    /// Slots in setup draw order, from back to front.
+    #[serde(default)]
+    pub slots: Vec<SlotData>,
+    #[serde(default)]
+    pub skins: Vec<SkinData>,
+    #[serde(default)]
+    pub animations: HashMap<String, AnimationData>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct BoneData {
+    pub name: String,
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    /// Counterclockwise rotation in degrees.
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default = "default_scale", rename = "scaleX")]
+    pub scale_x: f32,
+    #[serde(default = "default_scale", rename = "scaleY")]
+    pub scale_y: f32,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct SlotData {
+    pub name: String,
+    pub bone: String,
+    /// Attachment visible in setup pose.
+    #[serde(default)]
+    pub attachment: Option<String>,
+    /// Color as `rrggbbaa` hex string.
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct SkinData {
+    pub name: String,
+    /// Attachments by slot name and attachment name.
+    #[serde(default)]
+    pub attachments: HashMap<String, HashMap<String, AttachmentData>>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct AttachmentData {
+    #[serde(default, rename = "type")]
+    pub kind: AttachmentKind,
+    /// Texture name if differs from attachment name.
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default = "default_scale", rename = "scaleX")]
+    pub scale_x: f32,
+    #[serde(default = "default_scale", rename = "scaleY")]
+    pub scale_y: f32,
+    #[serde(default)]
+    pub width: f32,
+    #[serde(default)]
+    pub height: f32,
+    /// Mesh texture coordinates normalized to texture region.
+    #[serde(default)]
+    pub uvs: Vec<f32>,
+    #[serde(default)]
+    pub triangles: Vec<u32>,
+    /// Mesh positions in slot bone space, or `[bones, bone, x, y, weight, ...]` per vertex
+    /// if mesh is weighted to several bones.
+    #[serde(default)]
+    pub vertices: Vec<f32>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentKind {
+    #[default]
+    Region,
+    Mesh,
+    /// Bounding boxes, paths, clipping and other attachments not rendered by motoro.
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct AnimationData {
+    #[serde(default)]
+    pub bones: HashMap<String, BoneTimelines>,
+    #[serde(default)]
+    pub slots: HashMap<String, SlotTimelines>,
+    #[serde(default, rename = "drawOrder")]
+    pub draw_order: Vec<DrawOrderKey>,
+}
+
+/// Keys are relative to setup pose: rotation and translation are added,
+/// scale is multiplied.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct BoneTimelines {
+    #[serde(default)]
+    pub rotate: Vec<Keyframe>,
+    #[serde(default)]
+    pub translate: Vec<Keyframe>,
+    #[serde(default)]
+    pub scale: Vec<Keyframe>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct Keyframe {
+    #[serde(default)]
+    pub time: f32,
+    /// Rotation in degrees, named `angle` before Spine 4.
+    #[serde(default, alias = "angle")]
+    pub value: Option<f32>,
+    #[serde(default)]
+    pub x: Option<f32>,
+    #[serde(default)]
+    pub y: Option<f32>,
+    #[serde(default)]
+    pub curve: Option<Curve>,
+}
+
+impl Keyframe {
+    fn stepped(&self) -> bool {
+        matches!(&self.curve, Some(Curve::Named(name)) if name == "stepped")
+    }
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(untagged)]
+pub enum Curve {
+    Named(String),
+    Bezier(Vec<f32>),
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct SlotTimelines {
+    #[serde(default)]
+    pub attachment: Vec<AttachmentKey>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct AttachmentKey {
+    #[serde(default)]
+    pub time: f32,
+    /// Attachment shown from key time, slot is hidden if not set.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct DrawOrderKey {
+    #[serde(default)]
+    pub time: f32,
+    /// Slots moved from setup draw order, draw order is reset to setup if empty.
+    #[serde(default)]
+    pub offsets: Vec<DrawOrderOffset>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct DrawOrderOffset {
+    pub slot: String,
+    pub offset: i32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+#[derive(Debug)]
+pub struct SkeletonError(pub String);
+
+/// Represents GLSL bone structure, affine transform from bone space to world space.
+///
+/// ```glsl
+/// struct Bone {
+///     vec4 basis;
+///     vec2 translation;
+/// };
+/// // world = vec2(dot(bone.basis.xy, local), dot(bone.basis.zw, local)) + bone.translation;
+/// ```
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoneMatrix {
+    /// Rows of 2x2 matrix `[a, b, c, d]`.
+    pub basis: Vec4,
+    pub translation: Vec2,
+    _padding: Vec2,
+}
+
+impl Default for BoneMatrix {
+    fn default() -> Self {
+        Self::new([0.0, 0.0], 0.0, [1.0, 1.0])
+    }
+}
+
+impl BoneMatrix {
+    /// Creates transform of scale, then rotation in degrees, then translation.
+    pub fn new(position: Vec2, rotation: f32, scale: Vec2) -> Self {
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let [sx, sy] = scale;
+        Self {
+            basis: [cos * sx, -sin * sy, sin * sx, cos * sy],
+            translation: position,
+            _padding: [0.0; 2],
+        }
+    }
+
+    /// Returns transform applying local transform first, then this one.
+    pub fn multiply(&self, local: &BoneMatrix) -> BoneMatrix {
+        let [a, b, c, d] = self.basis;
+        let [la, lb, lc, ld] = local.basis;
+        Self {
+            basis: [
+                a * la + b * lc,
+                a * lb + b * ld,
+                c * la + d * lc,
+                c * lb + d * ld,
+            ],
+            translation: self.transform(local.translation),
+            _padding: [0.0; 2],
+        }
+    }
+
+    pub fn transform(&self, point: Vec2) -> Vec2 {
+        let [a, b, c, d] = self.basis;
+        let [x, y] = point;
+        let [tx, ty] = self.translation;
+        [a * x + b * y + tx, c * x + d * y + ty]
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct BonePose {
+    position: Vec2,
+    rotation: f32,
+    scale: Vec2,
+}
+
+impl BonePose {
+    fn setup(bone: &BoneData) -> Self {
+        Self {
+            position: [bone.x, bone.y],
+            rotation: bone.rotation,
+            scale: [bone.scale_x, bone.scale_y],
+        }
+    }
+}
+
+/// Attachment of slot visible in current pose, see [`Skeleton::visible_attachments`].
+pub struct VisibleAttachment<'a> {
+    /// Index of slot bone in skeleton bones.
+    pub bone: usize,
+    pub name: &'a str,
+    pub attachment: &'a AttachmentData,
+    pub color: Vec4,
+}
+
+/// Posed instance of skeleton data, animations are applied over setup pose
+/// and mixed by alpha, so transitions blend smoothly.
+///
+/// ```ignore
+/// skeleton.setup_pose();
+/// skeleton.apply("walk", time, true, 1.0)?;
+/// skeleton.apply("run", time, true, run_blend)?;
+/// skeleton.update();
+/// renderer.draw(&mut graphics, &skeleton)?;
+/// ```
+pub struct Skeleton {
+    data: Rc<SkeletonData>,
+    pub position: Vec2,
+    /// Negative y flips Spine y-up coordinates for y-down cameras.
+    pub scale: Vec2,
+    /// Tint multiplied by slot colors.
+    pub color: Vec4,
+    parents: Vec<Option<usize>>,
+    bones: Vec<BonePose>,
+    world: Vec<BoneMatrix>,
+    slot_bones: Vec<usize>,
+    slot_colors: Vec<Vec4>,
+    attachments: Vec<Option<String>>,
+    draw_order: Vec<usize>,
+    skin: Option<usize>,
+    bone_indices: HashMap<String, usize>,
+    slot_indices: HashMap<String, usize>,
+}
+
+impl Skeleton {
+    pub fn new(data: Rc<SkeletonData>) -> Result<Self, SkeletonError> {
+        let mut bone_indices = HashMap::new();
+        let mut parents = vec![];
+        for (index, bone) in data.bones.iter().enumerate() {
+            let parent = match &bone.parent {
+                Some(parent) => match bone_indices.get(parent) {
+                    Some(parent) => Some(*parent),
+                    None => {
+                        return Err(SkeletonError(format!(
+                            "bone {} parent {parent} not found before bone",
+                            bone.name
+                        )))
+                    }
+                },
+                None => None,
+            };
+            parents.push(parent);
+            bone_indices.insert(bone.name.clone(), index);
+        }
+        let mut slot_indices = HashMap::new();
+        let mut slot_bones = vec![];
+        let mut slot_colors = vec![];
+        for (index, slot) in data.slots.iter().enumerate() {
+            let bone = bone_indices.get(&slot.bone).ok_or_else(|| {
+                SkeletonError(format!("slot {} bone {} not found", slot.name, slot.bone))
+            })?;
+            slot_bones.push(*bone);
+            let color = match &slot.color {
+                Some(color) => format!("#{color}").as_str().to_vec4(),
+                None => [1.0; 4],
+            };
+            slot_colors.push(color);
+            slot_indices.insert(slot.name.clone(), index);
+        }
+        let skin = data.skins.iter().position(|skin| skin.name == "default");
+        let mut skeleton = Self {
+            position: [0.0, 0.0],
+            scale: [1.0, -1.0],
+            color: [1.0; 4],
+            parents,
+            bones: vec![],
+            world: vec![BoneMatrix::default(); data.bones.len()],
+            slot_bones,
+            slot_colors,
+            attachments: vec![],
+            draw_order: vec![],
+            skin,
+            bone_indices,
+            slot_indices,
+            data,
+        };
+        skeleton.setup_pose();
+        skeleton.update();
+        Ok(skeleton)
+    }
+
+    /// Selects skin attachments are taken from, default skin is used for missing ones.
+    pub fn set_skin(&mut self, name: &str) -> Result<(), SkeletonError> {
+        let skin = self.data.skins.iter().position(|skin| skin.name == name);
+        match skin {
+            Some(skin) => {
+                self.skin = Some(skin);
+                Ok(())
+            }
+            None => Err(SkeletonError(format!("skin {name} not found"))),
+        }
+    }
+
+    /// Resets bones, attachments and draw order to setup pose.
+    pub fn setup_pose(&mut self) {
+        self.bones = self.data.bones.iter().map(BonePose::setup).collect();
+        self.attachments = self
+            .data
+            .slots
+            .iter()
+            .map(|slot| slot.attachment.clone())
+            .collect();
+        self.draw_order = (0..self.data.slots.len()).collect();
+    }
+
+    /// Returns time of last key in animation.
+    pub fn duration(&self, animation: &str) -> Option<f32> {
+        let animation = self.data.animations.get(animation)?;
+        let bones = animation.bones.values().flat_map(|timelines| {
+            [&timelines.rotate, &timelines.translate, &timelines.scale]
+                .into_iter()
+                .flat_map(|keys| keys.iter().map(|key| key.time))
+        });
+        let slots = animation
+            .slots
+            .values()
+            .flat_map(|timelines| timelines.attachment.iter().map(|key| key.time));
+        let draw_order = animation.draw_order.iter().map(|key| key.time);
+        Some(bones.chain(slots).chain(draw_order).fold(0.0, f32::max))
+    }
+
+    /// Mixes animation pose at given time into current pose, alpha 1.0 replaces pose.
+    /// Attachments and draw order are switched if alpha is at least 0.5.
+    pub fn apply(
+        &mut self,
+        animation: &str,
+        time: f32,
+        looping: bool,
+        alpha: f32,
+    ) -> Result<(), SkeletonError> {
+        let data = self.data.clone();
+        let duration = self.duration(animation).unwrap_or(0.0);
+        let animation = data
+            .animations
+            .get(animation)
+            .ok_or_else(|| SkeletonError(format!("animation {animation} not found")))?;
+        let time = if looping && duration > 0.0 {
+            time % duration
+        } else {
+            time
+        };
+        for (name, timelines) in &animation.bones {
+            let index = match self.bone_indices.get(name) {
+                Some(index) => *index,
+                None => continue,
+            };
+            let setup = BonePose::setup(&data.bones[index]);
+            let pose = &mut self.bones[index];
+            if let Some([rotation, _]) = sample(&timelines.rotate, time, |key| {
+                [key.value.unwrap_or(0.0), 0.0]
+            }) {
+                let delta = setup.rotation + rotation - pose.rotation;
+                pose.rotation += wrap_degrees(delta) * alpha;
+            }
+            if let Some([x, y]) = sample(&timelines.translate, time, |key| {
+                [key.x.unwrap_or(0.0), key.y.unwrap_or(0.0)]
+            }) {
+                pose.position[0] += (setup.position[0] + x - pose.position[0]) * alpha;
+                pose.position[1] += (setup.position[1] + y - pose.position[1]) * alpha;
+            }
+            if let Some([x, y]) = sample(&timelines.scale, time, |key| {
+                [key.x.unwrap_or(1.0), key.y.unwrap_or(1.0)]
+            }) {
+                pose.scale[0] += (setup.scale[0] * x - pose.scale[0]) * alpha;
+                pose.scale[1] += (setup.scale[1] * y - pose.scale[1]) * alpha;
+            }
+        }
+        if alpha < 0.5 {
+            return Ok(());
+        }
+        for (name, timelines) in &animation.slots {
+            let index = match self.slot_indices.get(name) {
+                Some(index) => *index,
+                None => continue,
+            };
+            if let Some(key) = last_key(&timelines.attachment, time, |key| key.time) {
+                self.attachments[index] = key.name.clone();
+            }
+        }
+        if let Some(key) = last_key(&animation.draw_order, time, |key| key.time) {
+            self.draw_order = self.get_draw_order(&key.offsets);
+        }
+        Ok(())
+    }
+
+    fn get_draw_order(&self, offsets: &[DrawOrderOffset]) -> Vec<usize> {
+        let count = self.data.slots.len();
+        let mut order = vec![None; count];
+        let mut unchanged = vec![];
+        let mut original = 0;
+        for offset in offsets {
+            let slot = match self.slot_indices.get(&offset.slot) {
+                Some(slot) => *slot,
+                None => continue,
+            };
+            while original < slot {
+                unchanged.push(original);
+                original += 1;
+            }
+            let target = original as i32 + offset.offset;
+            if let Some(position) = order.get_mut(target.max(0) as usize) {
+                *position = Some(original);
+            }
+            original += 1;
+        }
+        unchanged.extend(original..count);
+        for position in order.iter_mut().rev() {
+            if position.is_none() {
+                *position = unchanged.pop();
+            }
+        }
+        order.into_iter().flatten().collect()
+    }
+
+    /// Computes world transforms of bones from current pose.
+    pub fn update(&mut self) {
+        let root = BoneMatrix::new(self.position, 0.0, self.scale);
+        for (index, pose) in self.bones.iter().enumerate() {
+            let local = BoneMatrix::new(pose.position, pose.rotation, pose.scale);
+            let parent = match self.parents[index] {
+                Some(parent) => self.world[parent],
+                None => root,
+            };
+            self.world[index] = parent.multiply(&local);
+        }
+    }
+
+    /// Returns world transforms of bones computed by [`Skeleton::update`].
+    pub fn bone_matrices(&self) -> &[BoneMatrix] {
+        &self.world
+    }
+
+    pub fn bone_matrix(&self, name: &str) -> Option<BoneMatrix> {
+        self.bone_indices.get(name).map(|index| self.world[*index])
+    }
+
+    /// Returns attachments of slots in draw order, from back to front.
+    pub fn visible_attachments(&self) -> Vec<VisibleAttachment<'_>> {
+        let mut visible = vec![];
+        for &slot in &self.draw_order {
+            let name = match &self.attachments[slot] {
+                Some(name) => name.as_str(),
+                None => continue,
+            };
+            let attachment = match self.find_attachment(slot, name) {
+                Some(attachment) => attachment,
+                None => continue,
+            };
+            let [r, g, b, a] = self.slot_colors[slot];
+            let [tr, tg, tb, ta] = self.color;
+            visible.push(VisibleAttachment {
+                bone: self.slot_bones[slot],
+                name,
+                attachment,
+                color: [r * tr, g * tg, b * tb, a * ta],
+            });
+        }
+        visible
+    }
+
+    fn find_attachment(&self, slot: usize, name: &str) -> Option<&AttachmentData> {
+        let slot = &self.data.slots[slot].name;
+        let default = self
+            .data
+            .skins
+            .iter()
+            .position(|skin| skin.name == "default");
+        [self.skin, default].into_iter().flatten().find_map(|skin| {
+            self.data.skins[skin]
+                .attachments
+                .get(slot)
+                .and_then(|attachments| attachments.get(name))
+        })
+    }
+}
+
+fn last_key<T>(keys: &[T], time: f32, key_time: impl Fn(&T) -> f32) -> Option<&T> {
+    keys.iter().rev().find(|key| key_time(key) <= time)
+}
+
+/// Interpolates keys at given time, returns None before first key.
+fn sample(keys: &[Keyframe], time: f32, value: impl Fn(&Keyframe) -> Vec2) -> Option<Vec2> {
+    let index = keys.iter().rposition(|key| key.time <= time)?;
+    let current = &keys[index];
+    let next = match keys.get(index + 1) {
+        Some(next) if !current.stepped() && next.time > current.time => next,
+        _ => return Some(value(current)),
+    };
+    let t = (time - current.time) / (next.time - current.time);
+    let [a, b] = value(current);
+    let [next_a, next_b] = value(next);
+    Some([a + (next_a - a) * t, b + (next_b - b) * t])
+}
+
+/// Returns angle in range -180 to 180 degrees, so rotations blend by shortest way.
+fn wrap_degrees(angle: f32) -> f32 {
+    (angle + 180.0).rem_euclid(360.0) - 180.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        AnimationData, BoneData, BoneTimelines, DrawOrderKey, DrawOrderOffset, Keyframe, Skeleton,
+        SkeletonData, SlotData,
+    };
+    use std::rc::Rc;
+
+    #[test]
+    pub fn test_animation_blending_and_draw_order() {
+        let bone = |name: &str, parent: Option<&str>, x: f32| BoneData {
+            name: name.to_string(),
+            parent: parent.map(str::to_string),
+            x,
+            y: 0.0,
+            rotation: 0.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+        };
+        let slot = |name: &str| SlotData {
+            name: name.to_string(),
+            bone: "root".to_string(),
+            attachment: None,
+            color: None,
+        };
+        let key = |time: f32, value: f32| Keyframe {
+            time,
+            value: Some(value),
+            ..Keyframe::default()
+        };
+        let mut animation = AnimationData::default();
+        animation.bones.insert(
+            "root".to_string(),
+            BoneTimelines {
+                rotate: vec![key(0.0, 0.0), key(1.0, 90.0)],
+                ..BoneTimelines::default()
+            },
+        );
+        animation.draw_order.push(DrawOrderKey {
+            time: 0.0,
+            offsets: vec![DrawOrderOffset {
+                slot: "a".to_string(),
+                offset: 2,
+            }],
+        });
+        let mut data = SkeletonData {
+            bones: vec![bone("root", None, 0.0), bone("hand", Some("root"), 10.0)],
+            slots: vec![slot("a"), slot("b"), slot("c")],
+            ..SkeletonData::default()
+        };
+        data.animations.insert("wave".to_string(), animation);
+        let mut skeleton = Skeleton::new(Rc::new(data)).unwrap();
+        skeleton.scale = [1.0, 1.0];
+        skeleton.apply("wave", 1.5, true, 1.0).unwrap();
+        skeleton.update();
+        let [x, y] = skeleton.bone_matrix("hand").unwrap().translation;
+        assert!((x - 7.071).abs() < 1e-3 && (y - 7.071).abs() < 1e-3);
+        assert_eq!(skeleton.draw_order, vec![1, 2, 0]);
+        skeleton.setup_pose();
+        skeleton.apply("wave", 1.0, false, 0.5).unwrap();
+        skeleton.update();
+        let [x, y] = skeleton.bone_matrix("hand").unwrap().translation;
+        assert!((x - 7.071).abs() < 1e-3 && (y - 7.071).abs() < 1e-3);
+    }
+}
@@ -1,14 +1,29 @@
 use std::backtrace::Backtrace;
-use std::env;
+use std::collections::{BTreeMap, VecDeque};
 use std::io::{Read, Write};
 use std::net::TcpListener;
 use std::ops::Deref;
-use std::time::Instant;
-use std::{panic, thread};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::{env, fs, panic, thread};
 
 use log::{error, info, set_boxed_logger, set_max_level, LevelFilter, Log, Metadata, Record};
 use mesura::get_metrics;
 
+/// Number of last log lines written to crash report.
+const CRASH_REPORT_LOG_LINES: usize = 200;
+
+static LOG_HISTORY: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static CRASH_CONTEXT: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// Sets value written to crash report, e.g. GPU name or current level,
+/// value replaces previous one with the same key.
+pub fn set_crash_context(key: &str, value: impl ToString) {
+    if let Ok(mut context) = CRASH_CONTEXT.lock() {
+        context.insert(key.to_string(), value.to_string());
+    }
+}
+
 struct BasicLogger {
     start: Instant,
 }
@@ -28,13 +43,20 @@ impl Log for BasicLogger {
 
     fn log(&self, record: &Record) {
         let timestamp = Instant::now().duration_since(self.start).as_secs_f32();
-        println!(
+        let line = format!(
             "{:.4} {} [{}] {}",
             timestamp,
             record.level(),
             record.module_path().unwrap_or("unknown"),
             record.args()
-        )
+        );
+        println!("{line}");
+        if let Ok(mut history) = LOG_HISTORY.lock() {
+            if history.len() == CRASH_REPORT_LOG_LINES {
+                history.pop_front();
+            }
+            history.push_back(line);
+        }
     }
 
     fn flush(&self) {}
@@ -65,12 +87,45 @@ pub fn setup_basic_logging(level: LevelFilter) {
             });
 
         error!("thread {name} panic! at {}:{}: {}", file, line, reason);
-        info!("{}", Backtrace::force_capture());
+        let backtrace = Backtrace::force_capture();
+        info!("{}", backtrace);
+        let message = format!("thread {name} panic! at {file}:{line}: {reason}");
+        write_crash_report(&message, &backtrace);
     }));
 
     info!("Starts logging");
 }
 
+/// Writes crash report with panic message, backtrace, crash context and last log lines
+/// to directory specified by `CRASH_REPORTS` env variable or working directory,
+/// so users can attach it to bug reports.
+fn write_crash_report(message: &str, backtrace: &Backtrace) {
+    let directory = env::var("CRASH_REPORTS").unwrap_or_else(|_| ".".to_string());
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let path = format!("{directory}/crash-{timestamp}.txt");
+    let mut report = format!("{message}\n\nBacktrace:\n{backtrace}\n\nContext:\n");
+    // panic may occur while lock is held, report is written without poisoned parts
+    if let Ok(context) = CRASH_CONTEXT.try_lock() {
+        for (key, value) in context.iter() {
+            report += &format!("{key}: {value}\n");
+        }
+    }
+    report += "\nLog:\n";
+    if let Ok(history) = LOG_HISTORY.try_lock() {
+        for line in history.iter() {
+            report += line;
+            report += "\n";
+        }
+    }
+    match fs::create_dir_all(&directory).and_then(|_| fs::write(&path, report)) {
+        Ok(_) => error!("Crash report written to {path}"),
+        Err(error) => error!("unable to write crash report {path}, {error}"),
+    }
+}
+
 pub fn setup_basic_monitoring() {
     let host = env::var("MONITORING_PORT")
         .map(|port| format!("0.0.0.0:{port}"))
@@ -0,0 +1,333 @@
+//! Harness to run example scenes headlessly, e.g. from integration tests:
+//!
+//! ```ignore
+//! #[test]
+//! fn test_post_passes_example() {
+//!     motoro::testing::run_example::<PostPassesExample>("post passes", 10);
+//! }
+//! ```
+//!
+//! Bundled scenes cover frame, sprites, text and post passes. Every scene renders into
+//! offscreen layer target first, only final composite is drawn to swapchain.
+use crate::renderers::{
+    embedded_shader, BlurPass, DisplayAdjustment, Light, LightingRenderer, SpriteRenderer,
+    TextEffects, TextRenderer,
+};
+use crate::{
+    CameraHandle, FontIndex, Frame, Graphics, GraphicsConfig, GraphicsMode, LayoutSettings,
+    RedrawMode, TextSpan, Texture,
+};
+use std::sync::Mutex;
+
+/// Represents example scene which can be run by [`run_example`].
+pub trait Example {
+    fn create(graphics: &mut Graphics) -> Self;
+
+    /// Updates and renders whole frame, from clear to present.
+    fn update(&mut self, graphics: &mut Graphics);
+}
+
+/// SDL allows single context per process, so examples run one at a time.
+static EXAMPLES: Mutex<()> = Mutex::new(());
+
+/// Runs example in hidden window for given number of frames with validation layer enabled.
+///
+/// Panics if example panics or validation layer reports any error while example runs.
+/// Examples of parallel tests wait for each other.
+pub fn run_example<E: Example>(name: &str, frames: usize) {
+    // example panicked in other test is reported by that test
+    let _guard = EXAMPLES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let config = GraphicsConfig::default()
+        .title(name)
+        .mode(GraphicsMode::Hidden)
+        .redraw(RedrawMode::Continuous)
+        .validation(true);
+    let mut graphics = Graphics::create(config).expect("graphics must be created");
+    let mut example = E::create(&mut graphics);
+    for _ in 0..frames {
+        graphics.capture_user_input();
+        example.update(&mut graphics);
+    }
+    graphics.vulkan.wait_idle();
+    let errors = graphics.vulkan.validation_errors();
+    assert_eq!(
+        errors, 0,
+        "example {name} must run {frames} frames without validation errors"
+    );
+}
+
+/// Creates checkerboard texture of two colors, 8x8 pixels.
+fn checker_texture(graphics: &Graphics, a: [u8; 4], b: [u8; 4]) -> Texture {
+    let mut data = Vec::with_capacity(8 * 8 * 4);
+    for y in 0..8 {
+        for x in 0..8 {
+            let color = if (x + y) % 2 == 0 { a } else { b };
+            data.extend_from_slice(&color);
+        }
+    }
+    graphics.texture_from(8, 8, &data)
+}
+
+/// Draws rectangles, sprite and text of [`Frame`] into offscreen output,
+/// output is drawn to screen by [`DisplayAdjustment`].
+pub struct FrameExample {
+    frame: Frame,
+    texture: Texture,
+    output: DisplayAdjustment,
+}
+
+impl Example for FrameExample {
+    fn create(graphics: &mut Graphics) -> Self {
+        let frame = graphics.create_frame().expect("frame must be created");
+        let texture = checker_texture(graphics, [255; 4], [64, 64, 64, 255]);
+        let output = DisplayAdjustment::create(graphics).expect("adjustment must be created");
+        Self {
+            frame,
+            texture,
+            output,
+        }
+    }
+
+    fn update(&mut self, graphics: &mut Graphics) {
+        graphics.clear([0.0, 0.0, 0.0, 1.0]);
+        self.output.begin(graphics, [0.1, 0.1, 0.1, 1.0]);
+        self.frame
+            .draw_rect([10.0, 10.0], [200.0, 40.0], [0.2, 0.4, 0.8, 1.0]);
+        self.frame
+            .draw_sprite(self.texture, [220.0, 10.0], [40.0, 40.0]);
+        self.frame
+            .draw_text(graphics, "Hello, world!", [20.0, 20.0], [1.0; 4]);
+        self.frame.render();
+        self.output.end(graphics);
+        self.output.draw(graphics).expect("output must be drawn");
+        graphics.present();
+    }
+}
+
+/// Draws sprites of builtin sprite renderer with opacity groups, depth, params
+/// and second layer into offscreen output.
+pub struct SpritesExample {
+    sprites: SpriteRenderer,
+    camera: CameraHandle,
+    texture: Texture,
+    overlay: usize,
+    output: DisplayAdjustment,
+}
+
+impl Example for SpritesExample {
+    fn create(graphics: &mut Graphics) -> Self {
+        let mut sprites = SpriteRenderer::create(
+            graphics,
+            embedded_shader!("sprites.vert"),
+            embedded_shader!("sprites.frag"),
+        )
+        .expect("sprites must be created");
+        let overlay = sprites.create_layer(graphics);
+        let camera = graphics.camera();
+        let texture = checker_texture(graphics, [255, 128, 0, 255], [0, 0, 0, 0]);
+        let output = DisplayAdjustment::create(graphics).expect("adjustment must be created");
+        Self {
+            sprites,
+            camera,
+            texture,
+            overlay,
+            output,
+        }
+    }
+
+    fn update(&mut self, graphics: &mut Graphics) {
+        graphics.clear([0.0, 0.0, 0.0, 1.0]);
+        for y in 0..8 {
+            for x in 0..8 {
+                let position = [16.0 + x as f32 * 36.0, 16.0 + y as f32 * 36.0];
+                self.sprites.set_depth((x + y) as f32 / 16.0);
+                self.sprites.set_params([x as f32, y as f32, 0.0, 0.0]);
+                self.sprites.push_opacity(0.5 + y as f32 / 16.0);
+                self.sprites
+                    .draw_sprite(self.texture, position, [32.0, 32.0], [1.0; 4])
+                    .expect("sprite must be drawn");
+                self.sprites.pop_opacity();
+            }
+        }
+        self.sprites
+            .set_layer(self.overlay)
+            .expect("overlay layer must be created");
+        self.sprites
+            .draw_sprite(self.texture, [100.0, 100.0], [96.0, 96.0], "white")
+            .expect("overlay sprite must be drawn");
+        self.sprites
+            .set_layer(SpriteRenderer::DEFAULT_LAYER)
+            .expect("default layer must be created");
+        self.output.begin(graphics, [0.1, 0.1, 0.1, 1.0]);
+        let camera = self.camera.read().expect("camera must be readable");
+        self.sprites.render(&camera);
+        self.sprites.render_layer(self.overlay, &camera);
+        drop(camera);
+        self.output.end(graphics);
+        self.output.draw(graphics).expect("output must be drawn");
+        graphics.present();
+    }
+}
+
+/// Draws plain text, rich text and text with shadow, glow and outline of builtin
+/// text renderer into offscreen output.
+pub struct TextExample {
+    text: TextRenderer,
+    camera: CameraHandle,
+    output: DisplayAdjustment,
+}
+
+impl Example for TextExample {
+    fn create(graphics: &mut Graphics) -> Self {
+        let text = TextRenderer::create(
+            graphics,
+            embedded_shader!("text.vert"),
+            embedded_shader!("text.frag"),
+        )
+        .expect("text must be created");
+        let camera = graphics.camera();
+        let output = DisplayAdjustment::create(graphics).expect("adjustment must be created");
+        Self {
+            text,
+            camera,
+            output,
+        }
+    }
+
+    fn update(&mut self, graphics: &mut Graphics) {
+        graphics.clear([0.0, 0.0, 0.0, 1.0]);
+        let font = FontIndex::default();
+        let settings = LayoutSettings::default();
+        let effects = TextEffects::default()
+            .shadow([2.0, 2.0], 1.5, [0.0, 0.0, 0.0, 0.8])
+            .glow(4.0, [1.0, 0.8, 0.2, 0.5])
+            .outline(1.0, [0.0, 0.0, 0.0, 1.0]);
+        self.text
+            .draw_text(
+                graphics,
+                font,
+                "Plain text",
+                [20.0, 20.0],
+                [1.0; 4],
+                settings,
+                TextEffects::default(),
+            )
+            .expect("text must be drawn");
+        self.text
+            .draw_text(
+                graphics,
+                font,
+                "Text effects",
+                [20.0, 60.0],
+                [1.0; 4],
+                settings,
+                effects,
+            )
+            .expect("text with effects must be drawn");
+        let spans = [
+            TextSpan::new("Rich ", font, [1.0, 0.4, 0.4, 1.0]),
+            TextSpan::new("text", font, [0.4, 0.4, 1.0, 1.0]),
+        ];
+        self.text
+            .draw_rich_text(graphics, &spans, [20.0, 100.0], settings, effects)
+            .expect("rich text must be drawn");
+        self.output.begin(graphics, [0.3, 0.3, 0.3, 1.0]);
+        let camera = self.camera.read().expect("camera must be readable");
+        self.text.render(&camera);
+        drop(camera);
+        self.output.end(graphics);
+        self.output.draw(graphics).expect("output must be drawn");
+        graphics.present();
+    }
+}
+
+/// Renders lit scene into lighting layers, then pause menu into blur layer drawn
+/// blurred over lit scene.
+pub struct PostPassesExample {
+    frame: Frame,
+    lighting: LightingRenderer,
+    blur: BlurPass,
+    texture: Texture,
+    normal: Texture,
+}
+
+impl Example for PostPassesExample {
+    fn create(graphics: &mut Graphics) -> Self {
+        let frame = graphics.create_frame().expect("frame must be created");
+        let lighting = LightingRenderer::create(graphics).expect("lighting must be created");
+        let blur = BlurPass::create(graphics).expect("blur must be created");
+        let texture = checker_texture(graphics, [200; 4], [120, 120, 120, 255]);
+        let normal = checker_texture(graphics, [128, 128, 255, 255], [200, 128, 200, 255]);
+        Self {
+            frame,
+            lighting,
+            blur,
+            texture,
+            normal,
+        }
+    }
+
+    fn update(&mut self, graphics: &mut Graphics) {
+        graphics.clear([0.0, 0.0, 0.0, 1.0]);
+        let lights = [
+            Light::point([100.0, 100.0], 160.0, [1.0, 0.8, 0.5, 1.0]),
+            Light::cone([300.0, 60.0], [0.0, 1.0], 0.8, 240.0, [0.5, 0.6, 1.0, 1.0]).intensity(2.0),
+        ];
+        for light in lights {
+            self.lighting.add_light(light).expect("light must be added");
+        }
+        let sprites = [([60.0, 60.0], Some(self.normal)), ([220.0, 120.0], None)];
+        for (position, normal) in sprites {
+            self.lighting
+                .draw_sprite(self.texture, normal, position, [64.0, 64.0])
+                .expect("lit sprite must be drawn");
+            self.frame.draw_sprite(self.texture, position, [64.0, 64.0]);
+        }
+        self.lighting.begin(graphics, [0.0, 0.0, 0.0, 1.0]);
+        self.frame.render();
+        self.lighting.end(graphics);
+        let camera = self.frame.camera.read().expect("camera must be readable");
+        self.lighting
+            .render(graphics, &camera)
+            .expect("lighting must be rendered");
+        drop(camera);
+        self.blur.begin(graphics, [0.0; 4]);
+        self.frame
+            .draw_rect([100.0, 80.0], [200.0, 60.0], [0.0, 0.0, 0.0, 0.6]);
+        self.frame
+            .draw_text(graphics, "Paused", [120.0, 100.0], [1.0; 4]);
+        self.frame.render();
+        self.blur.end(graphics);
+        self.blur.draw(graphics, 4.0).expect("blur must be drawn");
+        graphics.present();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{
+        run_example, FrameExample, PostPassesExample, SpritesExample, TextExample,
+    };
+
+    #[test]
+    pub fn test_frame_example() {
+        run_example::<FrameExample>("frame", 10);
+    }
+
+    #[test]
+    pub fn test_sprites_example() {
+        run_example::<SpritesExample>("sprites", 10);
+    }
+
+    #[test]
+    pub fn test_text_example() {
+        run_example::<TextExample>("text", 10);
+    }
+
+    #[test]
+    pub fn test_post_passes_example() {
+        run_example::<PostPassesExample>("post passes", 10);
+    }
+}
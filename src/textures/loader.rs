@@ -1,10 +1,15 @@
 use crate::handle_reader_thread;
-use crate::textures::{Texture, TextureError, TextureLoaderMetrics, TexturePrefabMetrics};
+use crate::textures::{
+    Texture, TextureAlias, TextureError, TextureFormat, TextureLoaderMetrics, TextureManifest,
+    TexturePrefabMetrics,
+};
 use crate::vulkan::textures::VulkanTextureLoaderDevice;
+use crate::watcher::same_file;
 use log::{debug, error, info};
 use mesura::GaugeValue;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::mem::take;
+use std::path::Path;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
@@ -17,10 +22,21 @@ pub trait TextureLoaderDevice: Clone + Send {
 pub struct TextureRecord {
     pub current: Texture,
     pub loading: Option<Texture>,
+    /// Texture file can not be read, it is read again only once file changes.
+    pub failed: bool,
+}
+
+/// Texture with normalized region offset and size, resolved from alias.
+#[derive(Debug, Copy, Clone)]
+pub struct TextureRegion {
+    pub texture: Texture,
+    pub src: [f32; 2],
+    pub uv: [f32; 2],
 }
 
 pub struct TexturesManager {
     pub records: HashMap<String, TextureRecord>,
+    pub aliases: HashMap<String, TextureAlias>,
     pub responses: Receiver<TextureLoaderResponse>,
     pub readers: Vec<Sender<(String, Texture)>>,
     pub readers_index: usize,
@@ -28,14 +44,21 @@ pub struct TexturesManager {
     pub fallback: Texture,
     pub blank: Texture,
     pub device: VulkanTextureLoaderDevice,
+    /// Textures no longer referenced by records, destroyed by graphics after frames in flight.
+    pub retired: Vec<Texture>,
+    /// Previous textures of reloading records, restored if reload fails.
+    pub reloading: HashMap<String, Texture>,
 }
 
 pub enum TextureLoaderRequest {
     Load(String, Texture, usize, usize, Vec<u8>),
+    /// Texture file can not be read or decoded, handle is returned unchanged.
+    Fail(String, Texture),
 }
 
 pub enum TextureLoaderResponse {
     Loaded(String, Texture),
+    Failed(String, Texture),
 }
 
 pub fn handle_loader_thread(
@@ -63,6 +86,12 @@ pub fn handle_loader_thread(
                     break;
                 }
             }
+            TextureLoaderRequest::Fail(path, handle) => {
+                if let Err(error) = manager.send(TextureLoaderResponse::Failed(path, handle)) {
+                    error!("unable to send manager response, {error:?}");
+                    break;
+                }
+            }
         }
     }
 }
@@ -101,6 +130,7 @@ impl TexturesManager {
             .expect("loader thread spawned");
         Self {
             records: HashMap::new(),
+            aliases: HashMap::new(),
             responses,
             readers,
             readers_index,
@@ -108,6 +138,8 @@ impl TexturesManager {
             fallback,
             blank,
             device: manager_device,
+            retired: vec![],
+            reloading: HashMap::new(),
         }
     }
 
@@ -115,17 +147,37 @@ impl TexturesManager {
         self.device.create_texture(width, height, data)
     }
 
+    /// Creates texture of data in given format, e.g. single channel mask.
+    pub fn create_texture_with_format(
+        &self,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        data: &[u8],
+    ) -> Texture {
+        self.device
+            .create_texture_with_format(width, height, format, data)
+    }
+
     pub fn create_dynamic_texture(&mut self, width: usize, height: usize, data: Vec<u8>) -> String {
         let path = format!("memory:{}", self.records.len());
         let record = TextureRecord {
             current: self.fallback,
             loading: Some(self.fallback),
+            failed: false,
         };
         self.records.insert(path.clone(), record);
         self.update_dynamic_texture(&path, width, height, data);
         path
     }
 
+    /// Returns true if dynamic texture can be updated, i.e. previous update is applied.
+    pub fn is_dynamic_texture_ready(&self, path: &str) -> bool {
+        self.records
+            .get(path)
+            .is_some_and(|record| record.loading.is_some())
+    }
+
     pub fn update_dynamic_texture(
         &mut self,
         path: &str,
@@ -153,7 +205,40 @@ impl TexturesManager {
         }
     }
 
+    /// Registers manifest aliases, existing aliases with same names are replaced.
+    pub fn register_manifest(&mut self, manifest: TextureManifest) {
+        info!("Registers {} texture aliases", manifest.aliases.len());
+        self.aliases.extend(manifest.aliases);
+    }
+
+    pub fn load_manifest(&mut self, path: &str) -> Result<(), TextureError> {
+        let manifest = TextureManifest::load(path)?;
+        self.register_manifest(manifest);
+        Ok(())
+    }
+
+    /// Returns texture and its region by logical name or path if alias not registered.
+    pub fn get_texture_region(&mut self, name: &str) -> TextureRegion {
+        let region = self.aliases.get(name).and_then(|alias| alias.region);
+        let texture = self.get_texture(name);
+        let [width, height] = texture.size.map(|value| value as f32);
+        let (src, uv) = match region {
+            Some([x, y, w, h]) => (
+                [x as f32 / width, y as f32 / height],
+                [w as f32 / width, h as f32 / height],
+            ),
+            None => ([0.0, 0.0], [1.0, 1.0]),
+        };
+        TextureRegion { texture, src, uv }
+    }
+
+    /// Returns texture by logical name registered via manifest or by file path.
     pub fn get_texture(&mut self, path: &str) -> Texture {
+        let path = match self.aliases.get(path) {
+            Some(alias) => alias.path.clone(),
+            None => path.to_string(),
+        };
+        let path = path.as_str();
         if path == Texture::FALLBACK {
             return self.fallback;
         }
@@ -168,9 +253,10 @@ impl TexturesManager {
             .or_insert_with(|| TextureRecord {
                 current: self.fallback,
                 loading: Some(self.fallback),
+                failed: false,
             });
 
-        if !path.starts_with("memory:") && record.current == self.fallback {
+        if !path.starts_with("memory:") && record.current == self.fallback && !record.failed {
             if let Some(handle) = take(&mut record.loading) {
                 self.readers_index = (self.readers_index + 1) % self.readers.len();
                 let request = (path.to_string(), handle);
@@ -185,22 +271,112 @@ impl TexturesManager {
         record.current
     }
 
-    pub fn update(&mut self) {
+    /// Returns true if texture requested by path is being read or loaded,
+    /// so it is neither loaded nor failed yet.
+    pub fn is_loading(&self, path: &str) -> bool {
+        let path = match self.aliases.get(path) {
+            Some(alias) => alias.path.as_str(),
+            None => path,
+        };
+        match self.records.get(path) {
+            Some(record) => record.loading.is_none(),
+            None => false,
+        }
+    }
+
+    /// Estimates device memory of loaded textures in bytes, 4 bytes per pixel.
+    pub fn memory_usage(&self) -> usize {
+        let mut images = HashSet::new();
+        self.records
+            .values()
+            .map(|record| record.current)
+            .filter(|texture| *texture != self.fallback && images.insert(texture.image))
+            .map(|texture| texture.size[0] as usize * texture.size[1] as usize * 4)
+            .sum()
+    }
+
+    /// Reads texture file again and replaces loaded texture in place, texture size may change.
+    ///
+    /// Previous texture is kept until next reload because previous frames may still sample it.
+    pub fn reload_texture(&mut self, path: &Path) {
+        for (key, record) in self.records.iter_mut() {
+            if key.starts_with("memory:") || !same_file(key, path) {
+                continue;
+            }
+            let previous = match take(&mut record.loading) {
+                Some(previous) => previous,
+                None => {
+                    info!("Skips texture {key} reload, loading in progress");
+                    continue;
+                }
+            };
+            // previous texture is retired once reloaded texture replaces it
+            self.reloading.insert(key.clone(), previous);
+            record.failed = false;
+            info!("Reloads texture {key}");
+            self.readers_index = (self.readers_index + 1) % self.readers.len();
+            // fallback handle makes loader create new texture of new file size
+            let request = (key.clone(), self.fallback);
+            if let Err(error) = self.readers[self.readers_index].send(request) {
+                error!("unable to send reader request, {error:?}");
+            }
+        }
+    }
+
+    /// Removes texture record, so next request loads texture again.
+    ///
+    /// Texture handles are not destroyed immediately because previous frames may still sample them.
+    pub fn evict_texture(&mut self, path: &str) {
+        let path = match self.aliases.get(path) {
+            Some(alias) => alias.path.as_str(),
+            None => path,
+        };
+        if let Some(record) = self.records.remove(path) {
+            let handles = [
+                Some(record.current),
+                record.loading,
+                self.reloading.remove(path),
+            ];
+            for handle in handles.into_iter().flatten() {
+                if handle != self.fallback && !self.retired.contains(&handle) {
+                    self.retired.push(handle);
+                }
+            }
+        }
+    }
+
+    /// Applies loaded textures, returns true if any texture was replaced.
+    pub fn update(&mut self) -> bool {
+        let mut updated = false;
         for response in self.responses.try_iter() {
             match response {
                 TextureLoaderResponse::Loaded(path, handle) => {
                     let record = match self.records.get_mut(&path) {
                         Some(record) => record,
                         None => {
-                            // TODO: destroy handle
-                            error!("unable to update loaded texture {path}, record not found");
+                            // texture evicted while loading
+                            self.retired.push(handle);
                             continue;
                         }
                     };
+                    if let Some(previous) = self.reloading.remove(&path) {
+                        if previous != self.fallback && !self.retired.contains(&previous) {
+                            self.retired.push(previous);
+                        }
+                    }
                     record.loading = Some(record.current);
                     record.current = handle;
+                    updated = true;
+                }
+                TextureLoaderResponse::Failed(path, handle) => {
+                    if let Some(record) = self.records.get_mut(&path) {
+                        // failed reload keeps loaded texture and its previous one
+                        record.loading = Some(self.reloading.remove(&path).unwrap_or(handle));
+                        record.failed = true;
+                    }
                 }
             }
         }
+        updated
     }
 }
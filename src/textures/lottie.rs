@@ -0,0 +1,95 @@
+use crate::{TextureError, TexturesManager};
+use log::info;
+use rlottie::{Animation, Size, Surface};
+
+/// Lottie (bodymovin) animation rasterized frame by frame into dynamic texture,
+/// for animated UI like loading spinners and emotes.
+///
+/// ```ignore
+/// let mut spinner = LottieAnimation::load(&mut graphics.textures, "./assets/spinner.json", 1.0)?;
+/// loop {
+///     spinner.update(&mut graphics.textures, time);
+///     let texture = graphics.textures.get_texture(&spinner.texture);
+///     // draw texture
+/// }
+/// ```
+pub struct LottieAnimation {
+    animation: Animation,
+    surface: Surface,
+    frame: Option<usize>,
+    /// Dynamic texture path, see [`TexturesManager::get_texture`].
+    pub texture: String,
+    /// Texture size in pixels.
+    pub size: [usize; 2],
+}
+
+impl LottieAnimation {
+    /// Loads animation rasterized at its size multiplied by resolution scale.
+    pub fn load(
+        textures: &mut TexturesManager,
+        path: &str,
+        resolution_scale: f32,
+    ) -> Result<Self, TextureError> {
+        let animation = Animation::from_file(path)
+            .ok_or_else(|| TextureError(format!("unable to load lottie animation {path}")))?;
+        let size = animation.size();
+        let width = ((size.width as f32 * resolution_scale).ceil() as usize).max(1);
+        let height = ((size.height as f32 * resolution_scale).ceil() as usize).max(1);
+        let surface = Surface::new(Size::new(width, height));
+        let texture = textures.create_dynamic_texture(width, height, vec![0; width * height * 4]);
+        info!(
+            "Loads lottie animation {path} {width}x{height} frames={}",
+            animation.totalframe()
+        );
+        Ok(Self {
+            animation,
+            surface,
+            frame: None,
+            texture,
+            size: [width, height],
+        })
+    }
+
+    /// Returns duration in seconds.
+    pub fn duration(&self) -> f32 {
+        self.animation.duration() as f32
+    }
+
+    /// Rasterizes frame at time in seconds, animation is looped.
+    ///
+    /// Rasterization is skipped if frame is not changed or previous frame is still uploading.
+    pub fn update(&mut self, textures: &mut TexturesManager, time: f32) {
+        let duration = self.animation.duration();
+        let position = if duration > 0.0 {
+            (time as f64 % duration) / duration
+        } else {
+            0.0
+        };
+        let frame = self.animation.frame_at_pos(position);
+        if self.frame == Some(frame) || !textures.is_dynamic_texture_ready(&self.texture) {
+            return;
+        }
+        self.frame = Some(frame);
+        self.animation.render(frame, &mut self.surface);
+        // surface pixels are premultiplied BGRA, textures are blended with straight alpha
+        let data = self
+            .surface
+            .data()
+            .iter()
+            .flat_map(|pixel| {
+                let demultiply = |value: u8| match pixel.a {
+                    0 => 0,
+                    alpha => (value as u32 * 255 / alpha as u32).min(255) as u8,
+                };
+                [
+                    demultiply(pixel.r),
+                    demultiply(pixel.g),
+                    demultiply(pixel.b),
+                    pixel.a,
+                ]
+            })
+            .collect();
+        let [width, height] = self.size;
+        textures.update_dynamic_texture(&self.texture, width, height, data);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::textures::TextureError;
+use std::collections::HashMap;
+use std::fs;
+
+/// Logical texture name binding to image file and optional pixel region `[x, y, width, height]`.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct TextureAlias {
+    pub path: String,
+    #[serde(default)]
+    pub region: Option<[u32; 4]>,
+}
+
+/// Table of texture aliases, so game code requests textures by logical name.
+///
+/// Manifest can be deserialized with any serde format or parsed from plain text file:
+/// ```text
+/// # name = path [x y width height]
+/// hero_idle = ./assets/hero.png 0 0 32 32
+/// background = ./assets/background.png
+/// ```
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct TextureManifest {
+    #[serde(default)]
+    pub aliases: HashMap<String, TextureAlias>,
+}
+
+impl TextureManifest {
+    pub fn load(path: &str) -> Result<Self, TextureError> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, TextureError> {
+        let mut aliases = HashMap::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = line.split_once('=').ok_or_else(|| {
+                TextureError(format!("manifest line {} has no '='", number + 1))
+            })?;
+            let mut values = value.split_whitespace();
+            let path = values.next().ok_or_else(|| {
+                TextureError(format!("manifest line {} has no path", number + 1))
+            })?;
+            let region: Vec<u32> = values
+                .map(|value| value.parse::<u32>())
+                .collect::<Result<_, _>>()
+                .map_err(|error| TextureError(format!("manifest line {}, {error}", number + 1)))?;
+            let region = match region.as_slice() {
+                [] => None,
+                [x, y, w, h] => Some([*x, *y, *w, *h]),
+                _ => {
+                    return Err(TextureError(format!(
+                        "manifest line {} region must have 4 values",
+                        number + 1
+                    )))
+                }
+            };
+            let alias = TextureAlias {
+                path: path.to_string(),
+                region,
+            };
+            aliases.insert(name.trim().to_string(), alias);
+        }
+        Ok(Self { aliases })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::textures::{TextureAlias, TextureManifest};
+
+    #[test]
+    pub fn test_manifest_parsing() {
+        let manifest = TextureManifest::parse(
+            "# comment\nhero_idle = ./hero.png 0 16 32 32\n\nsky = ./sky.png\n",
+        )
+        .unwrap();
+        assert_eq!(
+            manifest.aliases["hero_idle"],
+            TextureAlias {
+                path: "./hero.png".to_string(),
+                region: Some([0, 16, 32, 32])
+            }
+        );
+        assert_eq!(manifest.aliases["sky"].region, None);
+        assert!(TextureManifest::parse("broken = ./a.png 1 2").is_err());
+    }
+}
@@ -1,9 +1,19 @@
 pub use loader::*;
+#[cfg(feature = "lottie")]
+pub use lottie::*;
+pub use manifest::*;
 pub use metrics::*;
 pub use reader::*;
+#[cfg(feature = "svg")]
+pub use svg::*;
 pub use texture::*;
 
 mod loader;
+#[cfg(feature = "lottie")]
+mod lottie;
+mod manifest;
 mod metrics;
 mod reader;
+#[cfg(feature = "svg")]
+mod svg;
 mod texture;
@@ -27,21 +27,16 @@ pub fn handle_reader_thread(
 ) {
     info!("Starts texture reader id={id}");
     for (path, handle) in files.iter() {
-        let data = match fs::read(&path) {
-            Ok(data) => data,
-            Err(error) => {
-                error!("unable to read texture file, {error:?}");
-                continue;
+        let data = fs::read(&path).map_err(TextureError::from);
+        let request = match data.and_then(|data| read_texture_from_data(&data)) {
+            Ok((info, data)) => {
+                TextureLoaderRequest::Load(path, handle, info.width, info.height, data)
             }
-        };
-        let (info, data) = match read_texture_from_data(&data) {
-            Ok(data) => data,
             Err(error) => {
-                error!("unable to read texture, {error:?}");
-                continue;
+                error!("unable to read texture {path}, {error:?}");
+                TextureLoaderRequest::Fail(path, handle)
             }
         };
-        let request = TextureLoaderRequest::Load(path, handle, info.width, info.height, data);
         if let Err(error) = loader.send(request) {
             error!("unable to send loader request, {error:?}");
             break;
@@ -0,0 +1,121 @@
+use crate::math::Vec2;
+use crate::TextureError;
+use log::info;
+use resvg::{tiny_skia, usvg};
+use std::fs;
+use zune_png::zune_core::bit_depth::BitDepth;
+use zune_png::zune_core::colorspace::ColorSpace;
+use zune_png::zune_core::options::EncoderOptions;
+use zune_png::PngEncoder;
+
+/// Vector image rasterized to texture file.
+#[derive(Debug, Clone)]
+pub struct SvgImage {
+    /// Texture path, stays the same when image is re-rasterized.
+    pub texture: String,
+    /// Size in SVG units, texture size is this size multiplied by resolution scale.
+    pub size: Vec2,
+}
+
+struct Record {
+    tree: usvg::Tree,
+    image: SvgImage,
+}
+
+/// Rasterizes SVG images to PNG files in cache directory at resolution scale like fonts,
+/// so UI icons are loaded as regular textures and stay sharp at any resolution.
+pub struct SvgRasterizer {
+    cache: String,
+    resolution_scale: f32,
+    registry: Vec<Record>,
+}
+
+impl SvgRasterizer {
+    pub fn new(cache: &str, resolution_scale: f32) -> Self {
+        Self {
+            cache: cache.to_string(),
+            resolution_scale,
+            registry: vec![],
+        }
+    }
+
+    pub fn load_svg_file(&mut self, path: &str) -> Result<SvgImage, TextureError> {
+        let data = fs::read(path)?;
+        let name = path.replace(['/', '\\', ':'], "_");
+        self.load_svg(&name, &data)
+    }
+
+    /// Rasterizes image, image previously loaded with the same name is replaced.
+    pub fn load_svg(&mut self, name: &str, data: &[u8]) -> Result<SvgImage, TextureError> {
+        let tree = usvg::Tree::from_data(data, &usvg::Options::default())
+            .map_err(|error| TextureError(format!("unable to parse svg {name}, {error}")))?;
+        let size = tree.size();
+        let image = SvgImage {
+            texture: format!("{}/{name}.png", self.cache),
+            size: [size.width(), size.height()],
+        };
+        rasterize_svg_to_image_file(&tree, &image.texture, self.resolution_scale)?;
+        self.registry
+            .retain(|record| record.image.texture != image.texture);
+        self.registry.push(Record {
+            tree,
+            image: image.clone(),
+        });
+        Ok(image)
+    }
+
+    /// Re-rasterizes images for new resolution scale, e.g. after window moved
+    /// to monitor with different DPI. Returns textures must be evicted to load new files.
+    pub fn set_resolution_scale(
+        &mut self,
+        resolution_scale: f32,
+    ) -> Result<Vec<String>, TextureError> {
+        if (self.resolution_scale - resolution_scale).abs() < 0.01 {
+            return Ok(vec![]);
+        }
+        info!(
+            "Changes svg resolution scale {} -> {resolution_scale}",
+            self.resolution_scale
+        );
+        self.resolution_scale = resolution_scale;
+        let mut replaced = vec![];
+        for record in &self.registry {
+            rasterize_svg_to_image_file(&record.tree, &record.image.texture, resolution_scale)?;
+            replaced.push(record.image.texture.clone());
+        }
+        Ok(replaced)
+    }
+}
+
+fn rasterize_svg_to_image_file(
+    tree: &usvg::Tree,
+    path: &str,
+    resolution_scale: f32,
+) -> Result<(), TextureError> {
+    let size = tree.size();
+    let width = (size.width() * resolution_scale).ceil().max(1.0) as u32;
+    let height = (size.height() * resolution_scale).ceil().max(1.0) as u32;
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| TextureError(format!("unable to allocate {width}x{height} pixmap")))?;
+    let transform = tiny_skia::Transform::from_scale(resolution_scale, resolution_scale);
+    resvg::render(tree, transform, &mut pixmap.as_mut());
+    // pixmap colors are premultiplied, textures are blended with straight alpha
+    let data: Vec<u8> = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    let options = EncoderOptions::new(
+        width as usize,
+        height as usize,
+        ColorSpace::RGBA,
+        BitDepth::Eight,
+    );
+    let mut encoder = PngEncoder::new(&data, options);
+    fs::write(path, encoder.encode())?;
+    info!("Creates svg image {path} {width}x{height}");
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use crate::math::f32_to_f16;
+use crate::vulkan::memory::free_memory;
 use std::io;
 
 use vulkanalia::vk::DeviceV1_0;
@@ -11,6 +13,7 @@ pub struct Texture {
     pub memory: vk::DeviceMemory,
     pub view: vk::ImageView,
     pub size: [u32; 2],
+    pub format: vk::Format,
 }
 
 impl Texture {
@@ -18,8 +21,73 @@ impl Texture {
     pub const BLANK: &'static str = "memory:blank";
 }
 
+/// Pixel format of texture data.
+///
+/// Single and dual channel formats take 4x and 2x less memory, e.g. for grayscale masks,
+/// font atlases and lightmaps, shaders read channels from `.r` and `.rg` of sampled value.
+/// Float formats keep data, e.g. height fields, flow maps and HDR light buffers,
+/// linear filtering of R32F is optional on devices, so it should be sampled with nearest.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    /// Color texture, SRGB if graphics configured with SRGB.
+    #[default]
+    Rgba8,
+    R8,
+    Rg8,
+    R32F,
+    Rgba16F,
+}
+
+impl TextureFormat {
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            TextureFormat::Rgba8 => 4,
+            TextureFormat::R8 => 1,
+            TextureFormat::Rg8 => 2,
+            TextureFormat::R32F => 4,
+            TextureFormat::Rgba16F => 8,
+        }
+    }
+
+    /// Encodes float values as texture data, None if format is not float.
+    pub fn encode_floats(&self, values: &[f32]) -> Option<Vec<u8>> {
+        match self {
+            TextureFormat::R32F => Some(
+                values
+                    .iter()
+                    .flat_map(|value| value.to_ne_bytes())
+                    .collect(),
+            ),
+            TextureFormat::Rgba16F => Some(
+                values
+                    .iter()
+                    .flat_map(|value| f32_to_f16(*value).to_ne_bytes())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns number of bytes of texture data of given size.
+    pub fn data_size(&self, width: u32, height: u32) -> usize {
+        width as usize * height as usize * self.bytes_per_pixel()
+    }
+
+    pub fn to_vulkan(&self, srgb: bool) -> vk::Format {
+        match self {
+            TextureFormat::Rgba8 if srgb => vk::Format::R8G8B8A8_SRGB,
+            TextureFormat::Rgba8 => vk::Format::R8G8B8A8_UNORM,
+            // non-color data is never decoded as sRGB
+            TextureFormat::R8 => vk::Format::R8_UNORM,
+            TextureFormat::Rg8 => vk::Format::R8G8_UNORM,
+            TextureFormat::R32F => vk::Format::R32_SFLOAT,
+            TextureFormat::Rgba16F => vk::Format::R16G16B16A16_SFLOAT,
+        }
+    }
+}
+
 #[derive(Debug)]
-pub struct TextureError(String);
+pub struct TextureError(pub String);
 
 impl From<&str> for TextureError {
     fn from(error: &str) -> Self {
@@ -44,7 +112,7 @@ impl Texture {
         unsafe {
             device.destroy_image_view(self.view, None);
             device.destroy_image(self.image, None);
-            device.free_memory(self.memory, None);
+            free_memory(device, self.memory);
         }
     }
 }
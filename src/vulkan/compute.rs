@@ -0,0 +1,576 @@
+use crate::vulkan::memory::{allocate_memory, free_memory, record_descriptor_sets, MemoryKind};
+use crate::vulkan::{
+    create_command_buffers, create_command_pool, create_image_view, create_shader_module,
+    get_memory_type_index, QueueIndex, Vulkan, FRAMES_PROCESSING_CONCURRENCY,
+};
+use crate::{ProgramError, Shader};
+use log::info;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use vulkanalia::vk::{DeviceV1_0, Handle, HasBuilder, InstanceV1_0, KhrTimelineSemaphoreExtension};
+use vulkanalia::{vk, Device};
+
+/// Represents compute pipeline, commands are recorded into buffer given by
+/// [`crate::Graphics::submit_async_compute`].
+pub struct ComputeProgram {
+    pub device: Device,
+    shared: Arc<ComputeProgramShared>,
+}
+
+/// Part of compute program updated by Vulkan registry, e.g. pipeline recreated
+/// after shader change.
+pub(crate) struct ComputeProgramShared {
+    name: String,
+    device: Device,
+    pipeline: AtomicU64,
+    pipeline_layout: AtomicU64,
+    source: Mutex<ComputeSource>,
+}
+
+/// Describes how to recreate compute pipeline.
+struct ComputeSource {
+    shader: Shader,
+    push_constants: Vec<vk::PushConstantRange>,
+    layouts: Vec<vk::DescriptorSetLayout>,
+}
+
+pub fn compute_range<T>() -> vk::PushConstantRange {
+    vk::PushConstantRange::builder()
+        .stage_flags(vk::ShaderStageFlags::COMPUTE)
+        .offset(0)
+        .size(std::mem::size_of::<T>() as u32)
+        .build()
+}
+
+impl ComputeProgramShared {
+    pub fn pipeline(&self) -> vk::Pipeline {
+        vk::Pipeline::from_raw(self.pipeline.load(Ordering::Relaxed))
+    }
+
+    pub fn pipeline_layout(&self) -> vk::PipelineLayout {
+        vk::PipelineLayout::from_raw(self.pipeline_layout.load(Ordering::Relaxed))
+    }
+
+    /// Returns true if shader file changed since pipeline creation.
+    pub fn changed(&self) -> bool {
+        let source = self.source.lock().expect("compute source must be lockable");
+        source.shader.changed()
+    }
+
+    pub unsafe fn destroy(&self) {
+        info!("Destroy compute: {} {:?}", self.name, self.pipeline());
+        self.device.destroy_pipeline(self.pipeline(), None);
+        self.device
+            .destroy_pipeline_layout(self.pipeline_layout(), None);
+    }
+
+    /// Recreates pipeline from renewed shader, keeps current pipeline if new one fails.
+    pub unsafe fn recreate(&self) -> Result<(), ProgramError> {
+        info!("Renew compute: {} {:?}", self.name, self.pipeline());
+        let mut source = self.source.lock().expect("compute source must be lockable");
+        source.shader = source.shader.renew();
+        let (pipeline_layout, pipeline) = create_compute_pipeline(
+            &self.device,
+            &source.shader.read()?,
+            &source.layouts,
+            &source.push_constants,
+        )?;
+        self.destroy();
+        self.pipeline.store(pipeline.as_raw(), Ordering::Relaxed);
+        self.pipeline_layout
+            .store(pipeline_layout.as_raw(), Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl ComputeProgram {
+    pub unsafe fn create(
+        name: &str,
+        device: &Device,
+        mut shader: Shader,
+        push_constants: Vec<vk::PushConstantRange>,
+        layouts: Vec<vk::DescriptorSetLayout>,
+    ) -> Result<Self, ProgramError> {
+        let (pipeline_layout, pipeline) =
+            create_compute_pipeline(device, &shader.read()?, &layouts, &push_constants)?;
+        info!("Creates compute {name} {:?}", pipeline);
+        let source = ComputeSource {
+            shader,
+            push_constants,
+            layouts,
+        };
+        let shared = ComputeProgramShared {
+            name: name.to_string(),
+            device: device.clone(),
+            pipeline: AtomicU64::new(pipeline.as_raw()),
+            pipeline_layout: AtomicU64::new(pipeline_layout.as_raw()),
+            source: Mutex::new(source),
+        };
+        Ok(Self {
+            device: device.clone(),
+            shared: Arc::new(shared),
+        })
+    }
+
+    /// Returns weak reference to state updated by Vulkan registry.
+    pub(crate) fn shared(&self) -> Weak<ComputeProgramShared> {
+        Arc::downgrade(&self.shared)
+    }
+
+    pub fn bind_pipeline(&self, commands: vk::CommandBuffer) {
+        unsafe {
+            self.device.cmd_bind_pipeline(
+                commands,
+                vk::PipelineBindPoint::COMPUTE,
+                self.shared.pipeline(),
+            );
+        }
+    }
+
+    pub fn bind_descriptor(&self, commands: vk::CommandBuffer, index: u32, set: vk::DescriptorSet) {
+        unsafe {
+            self.device.cmd_bind_descriptor_sets(
+                commands,
+                vk::PipelineBindPoint::COMPUTE,
+                self.shared.pipeline_layout(),
+                index,
+                &[set],
+                &[],
+            );
+        }
+    }
+
+    pub fn push_constants<T>(&self, commands: vk::CommandBuffer, value: &T) {
+        unsafe {
+            let size = std::mem::size_of::<T>();
+            let constants = std::slice::from_raw_parts(value as *const T as *const u8, size);
+            self.device.cmd_push_constants(
+                commands,
+                self.shared.pipeline_layout(),
+                vk::ShaderStageFlags::COMPUTE,
+                0,
+                constants,
+            );
+        }
+    }
+
+    pub fn dispatch(&self, commands: vk::CommandBuffer, x: u32, y: u32, z: u32) {
+        unsafe {
+            self.device.cmd_dispatch(commands, x, y, z);
+        }
+    }
+
+    pub unsafe fn destroy(&self) {
+        self.shared.destroy();
+    }
+
+    /// Recreates pipeline from renewed shader, keeps current pipeline if new one fails.
+    pub unsafe fn recreate(&self) -> Result<(), ProgramError> {
+        self.shared.recreate()
+    }
+}
+
+unsafe fn create_compute_pipeline(
+    device: &Device,
+    code: &[u8],
+    layouts: &[vk::DescriptorSetLayout],
+    push_constants: &[vk::PushConstantRange],
+) -> Result<(vk::PipelineLayout, vk::Pipeline), ProgramError> {
+    let module = create_shader_module(device, code)?;
+    let stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::COMPUTE)
+        .module(module)
+        .name(b"main\0");
+    let mut layout_info = vk::PipelineLayoutCreateInfo::builder().set_layouts(layouts);
+    if push_constants.len() > 0 {
+        layout_info = layout_info.push_constant_ranges(push_constants);
+    }
+    let pipeline_layout = match device.create_pipeline_layout(&layout_info, None) {
+        Ok(layout) => layout,
+        Err(error) => {
+            device.destroy_shader_module(module, None);
+            return Err(error.into());
+        }
+    };
+    let info = vk::ComputePipelineCreateInfo::builder()
+        .stage(stage)
+        .layout(pipeline_layout);
+    let pipelines = device.create_compute_pipelines(vk::PipelineCache::null(), &[info], None);
+    device.destroy_shader_module(module, None);
+    match pipelines {
+        Ok((pipelines, _)) => Ok((pipeline_layout, pipelines[0])),
+        Err(error) => {
+            device.destroy_pipeline_layout(pipeline_layout, None);
+            Err(error.into())
+        }
+    }
+}
+
+/// Submits compute work on dedicated queue, graphics submission of the current frame
+/// waits for it on timeline semaphore.
+///
+/// If device does not support timeline semaphores, compute work is submitted
+/// to the same queue and waited immediately.
+pub struct AsyncCompute {
+    queue: vk::Queue,
+    command_pools: Vec<vk::CommandPool>,
+    command_buffers: Vec<vk::CommandBuffer>,
+    semaphore: Option<vk::Semaphore>,
+    submitted: Vec<u64>,
+    value: u64,
+    pub(crate) pending: Option<u64>,
+}
+
+impl AsyncCompute {
+    pub unsafe fn create(device: &Device, index: QueueIndex, timeline: bool) -> Self {
+        info!("Creates async compute on queue {index:?} timeline={timeline}");
+        let queue = device.get_device_queue(index.family, index.queue);
+        let command_pools: Vec<_> = (0..FRAMES_PROCESSING_CONCURRENCY)
+            .map(|_| create_command_pool(device, index))
+            .collect();
+        let command_buffers = create_command_buffers(device, &command_pools);
+        let semaphore = if timeline {
+            let mut semaphore_type = vk::SemaphoreTypeCreateInfo::builder()
+                .semaphore_type(vk::SemaphoreType::TIMELINE)
+                .initial_value(0);
+            let info = vk::SemaphoreCreateInfo::builder().push_next(&mut semaphore_type);
+            let semaphore = device
+                .create_semaphore(&info, None)
+                .expect("timeline semaphore must be created");
+            Some(semaphore)
+        } else {
+            None
+        };
+        Self {
+            queue,
+            command_pools,
+            command_buffers,
+            semaphore,
+            submitted: vec![0; FRAMES_PROCESSING_CONCURRENCY],
+            value: 0,
+            pending: None,
+        }
+    }
+
+    pub(crate) fn semaphore(&self) -> Option<vk::Semaphore> {
+        self.semaphore
+    }
+
+    pub unsafe fn submit<F>(&mut self, device: &Device, frame: usize, record: F)
+    where
+        F: FnOnce(vk::CommandBuffer),
+    {
+        if let Some(semaphore) = self.semaphore {
+            // command pool of this frame may still be executed by previous submission
+            let semaphores = &[semaphore];
+            let values = &[self.submitted[frame]];
+            let info = vk::SemaphoreWaitInfo::builder()
+                .semaphores(semaphores)
+                .values(values);
+            device
+                .wait_semaphores_khr(&info, u64::MAX)
+                .expect("compute semaphore must be waited");
+        }
+        let pool = self.command_pools[frame];
+        device
+            .reset_command_pool(pool, vk::CommandPoolResetFlags::empty())
+            .expect("compute command pool must be reset");
+        let commands = self.command_buffers[frame];
+        let info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        device
+            .begin_command_buffer(commands, &info)
+            .expect("compute command buffer must begin");
+        record(commands);
+        device
+            .end_command_buffer(commands)
+            .expect("compute command buffer must end");
+        let command_buffers = &[commands];
+        match self.semaphore {
+            Some(semaphore) => {
+                self.value += 1;
+                let signal_semaphores = &[semaphore];
+                let signal_values = &[self.value];
+                let mut timeline = vk::TimelineSemaphoreSubmitInfo::builder()
+                    .signal_semaphore_values(signal_values);
+                let info = vk::SubmitInfo::builder()
+                    .command_buffers(command_buffers)
+                    .signal_semaphores(signal_semaphores)
+                    .push_next(&mut timeline);
+                device
+                    .queue_submit(self.queue, &[info], vk::Fence::null())
+                    .expect("compute queue must be submitted");
+                self.submitted[frame] = self.value;
+                self.pending = Some(self.value);
+            }
+            None => {
+                let info = vk::SubmitInfo::builder().command_buffers(command_buffers);
+                device
+                    .queue_submit(self.queue, &[info], vk::Fence::null())
+                    .expect("compute queue must be submitted");
+                device
+                    .queue_wait_idle(self.queue)
+                    .expect("compute queue must be idle");
+            }
+        }
+    }
+}
+
+/// Represents GLSL storage image written by compute shader and presented on screen
+/// without graphics pipeline.
+///
+/// ```glsl
+/// layout (set = 0, binding = 0, rgba8) uniform writeonly image2D surface;
+/// ```
+pub struct ComputeSurface {
+    pub extent: vk::Extent2D,
+    images: Vec<vk::Image>,
+    memory: Vec<vk::DeviceMemory>,
+    views: Vec<vk::ImageView>,
+    layout: vk::DescriptorSetLayout,
+    pool: vk::DescriptorPool,
+    sets: Vec<vk::DescriptorSet>,
+    device: Device,
+}
+
+impl ComputeSurface {
+    pub const FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+
+    pub unsafe fn create(vulkan: &Vulkan, width: u32, height: u32) -> Self {
+        let device = &vulkan.device;
+        let extent = vk::Extent2D { width, height };
+        let physical_device_memory = vulkan
+            .instance
+            .get_physical_device_memory_properties(vulkan.physical_device);
+        let mut images = vec![];
+        let mut memory = vec![];
+        let mut views = vec![];
+        for _ in 0..FRAMES_PROCESSING_CONCURRENCY {
+            let info = vk::ImageCreateInfo::builder()
+                .image_type(vk::ImageType::_2D)
+                .extent(vk::Extent3D {
+                    width,
+                    height,
+                    depth: 1,
+                })
+                .mip_levels(1)
+                .array_layers(1)
+                .format(Self::FORMAT)
+                .tiling(vk::ImageTiling::OPTIMAL)
+                .initial_layout(vk::ImageLayout::UNDEFINED)
+                .usage(vk::ImageUsageFlags::STORAGE | vk::ImageUsageFlags::TRANSFER_SRC)
+                .sharing_mode(vk::SharingMode::EXCLUSIVE)
+                .samples(vk::SampleCountFlags::_1);
+            let image = device
+                .create_image(&info, None)
+                .expect("surface image must be created");
+            let requirements = device.get_image_memory_requirements(image);
+            let memory_type_index = get_memory_type_index(
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                requirements,
+                physical_device_memory,
+            );
+            let info = vk::MemoryAllocateInfo::builder()
+                .allocation_size(requirements.size)
+                .memory_type_index(memory_type_index);
+            let image_memory = allocate_memory(device, &info, MemoryKind::Image)
+                .expect("surface image memory must be allocated");
+            device
+                .bind_image_memory(image, image_memory, 0)
+                .expect("surface image memory must bound");
+            images.push(image);
+            memory.push(image_memory);
+            views.push(create_image_view(device, image, Self::FORMAT));
+        }
+        let bindings = [vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::COMPUTE)
+            .build()];
+        let info = vk::DescriptorSetLayoutCreateInfo::builder().bindings(&bindings);
+        let layout = device
+            .create_descriptor_set_layout(&info, None)
+            .expect("surface descriptor set layout must be created");
+        let pool_sizes = [vk::DescriptorPoolSize::builder()
+            .type_(vk::DescriptorType::STORAGE_IMAGE)
+            .descriptor_count(FRAMES_PROCESSING_CONCURRENCY as u32)
+            .build()];
+        let info = vk::DescriptorPoolCreateInfo::builder()
+            .pool_sizes(&pool_sizes)
+            .max_sets(FRAMES_PROCESSING_CONCURRENCY as u32);
+        let pool = device
+            .create_descriptor_pool(&info, None)
+            .expect("surface descriptor pool must be created");
+        let layouts = vec![layout; FRAMES_PROCESSING_CONCURRENCY];
+        let info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(pool)
+            .set_layouts(&layouts);
+        let sets = device
+            .allocate_descriptor_sets(&info)
+            .expect("surface descriptor sets must be allocated");
+        record_descriptor_sets(sets.len());
+        for (set, view) in sets.iter().zip(views.iter()) {
+            let image = [vk::DescriptorImageInfo::builder()
+                .image_layout(vk::ImageLayout::GENERAL)
+                .image_view(*view)
+                .build()];
+            let write = vk::WriteDescriptorSet::builder()
+                .dst_set(*set)
+                .dst_binding(0)
+                .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
+                .image_info(&image);
+            device.update_descriptor_sets(&[write], &[] as &[vk::CopyDescriptorSet]);
+        }
+        info!("Creates compute surface {extent:?}");
+        Self {
+            extent,
+            images,
+            memory,
+            views,
+            layout,
+            pool,
+            sets,
+            device: device.clone(),
+        }
+    }
+
+    pub fn layout(&self) -> vk::DescriptorSetLayout {
+        self.layout
+    }
+
+    pub fn descriptor(&self, frame: usize) -> vk::DescriptorSet {
+        self.sets[frame]
+    }
+
+    /// Makes surface image of frame available for shader writes.
+    pub(crate) unsafe fn begin(&self, commands: vk::CommandBuffer, frame: usize) {
+        let barrier = image_barrier(
+            self.images[frame],
+            vk::ImageLayout::UNDEFINED,
+            vk::ImageLayout::GENERAL,
+            vk::AccessFlags::empty(),
+            vk::AccessFlags::SHADER_WRITE,
+        );
+        self.device.cmd_pipeline_barrier(
+            commands,
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &[] as &[vk::BufferMemoryBarrier],
+            &[barrier],
+        );
+    }
+
+    /// Copies surface image of frame to swapchain image, scaling it to swapchain extent.
+    pub(crate) unsafe fn blit(
+        &self,
+        commands: vk::CommandBuffer,
+        frame: usize,
+        target: vk::Image,
+        extent: vk::Extent2D,
+    ) {
+        let barriers = [
+            image_barrier(
+                self.images[frame],
+                vk::ImageLayout::GENERAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::AccessFlags::SHADER_WRITE,
+                vk::AccessFlags::TRANSFER_READ,
+            ),
+            image_barrier(
+                target,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::AccessFlags::empty(),
+                vk::AccessFlags::TRANSFER_WRITE,
+            ),
+        ];
+        self.device.cmd_pipeline_barrier(
+            commands,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &[] as &[vk::BufferMemoryBarrier],
+            &barriers,
+        );
+        let subresource = vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(0)
+            .base_array_layer(0)
+            .layer_count(1)
+            .build();
+        let corner = |extent: vk::Extent2D| vk::Offset3D {
+            x: extent.width as i32,
+            y: extent.height as i32,
+            z: 1,
+        };
+        let region = vk::ImageBlit::builder()
+            .src_subresource(subresource)
+            .src_offsets([vk::Offset3D::default(), corner(self.extent)])
+            .dst_subresource(subresource)
+            .dst_offsets([vk::Offset3D::default(), corner(extent)]);
+        self.device.cmd_blit_image(
+            commands,
+            self.images[frame],
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            target,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[region],
+            vk::Filter::LINEAR,
+        );
+        let barrier = image_barrier(
+            target,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::PRESENT_SRC_KHR,
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::AccessFlags::empty(),
+        );
+        self.device.cmd_pipeline_barrier(
+            commands,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &[] as &[vk::BufferMemoryBarrier],
+            &[barrier],
+        );
+    }
+
+    pub unsafe fn destroy(&self) {
+        self.device.destroy_descriptor_pool(self.pool, None);
+        self.device.destroy_descriptor_set_layout(self.layout, None);
+        for index in 0..self.images.len() {
+            self.device.destroy_image_view(self.views[index], None);
+            self.device.destroy_image(self.images[index], None);
+            free_memory(&self.device, self.memory[index]);
+        }
+    }
+}
+
+fn image_barrier(
+    image: vk::Image,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+    src_access_mask: vk::AccessFlags,
+    dst_access_mask: vk::AccessFlags,
+) -> vk::ImageMemoryBarrier {
+    let subresource = vk::ImageSubresourceRange::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_mip_level(0)
+        .level_count(1)
+        .base_array_layer(0)
+        .layer_count(1);
+    vk::ImageMemoryBarrier::builder()
+        .old_layout(old_layout)
+        .new_layout(new_layout)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(subresource)
+        .src_access_mask(src_access_mask)
+        .dst_access_mask(dst_access_mask)
+        .build()
+}
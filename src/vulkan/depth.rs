@@ -0,0 +1,108 @@
+use crate::vulkan::get_memory_type_index;
+use crate::vulkan::memory::{allocate_memory, free_memory, MemoryKind};
+use log::info;
+use vulkanalia::vk::{DeviceV1_0, HasBuilder, InstanceV1_0};
+use vulkanalia::{vk, Device, Instance};
+
+/// Represents depth attachment shared by frame buffers of render pass.
+pub struct DepthBuffer {
+    pub format: vk::Format,
+    pub image: vk::Image,
+    pub memory: vk::DeviceMemory,
+    pub view: vk::ImageView,
+}
+
+const DEPTH_FORMATS: &[vk::Format] = &[
+    vk::Format::D32_SFLOAT,
+    vk::Format::D32_SFLOAT_S8_UINT,
+    vk::Format::D24_UNORM_S8_UINT,
+];
+
+pub unsafe fn get_depth_format(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+) -> vk::Format {
+    DEPTH_FORMATS
+        .iter()
+        .cloned()
+        .find(|format| {
+            let properties =
+                instance.get_physical_device_format_properties(physical_device, *format);
+            properties
+                .optimal_tiling_features
+                .contains(vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)
+        })
+        .expect("supported depth format must be found")
+}
+
+impl DepthBuffer {
+    pub unsafe fn create(
+        instance: &Instance,
+        device: &Device,
+        physical_device: vk::PhysicalDevice,
+        extent: vk::Extent2D,
+        samples: vk::SampleCountFlags,
+    ) -> Self {
+        let format = get_depth_format(instance, physical_device);
+        let info = vk::ImageCreateInfo::builder()
+            .image_type(vk::ImageType::_2D)
+            .extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+            .mip_levels(1)
+            .array_layers(1)
+            .format(format)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .usage(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .samples(samples);
+        let image = device
+            .create_image(&info, None)
+            .expect("depth image must be created");
+        let requirements = device.get_image_memory_requirements(image);
+        let physical_device_memory = instance.get_physical_device_memory_properties(physical_device);
+        let memory_type_index = get_memory_type_index(
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            requirements,
+            physical_device_memory,
+        );
+        let info = vk::MemoryAllocateInfo::builder()
+            .allocation_size(requirements.size)
+            .memory_type_index(memory_type_index);
+        let memory = allocate_memory(device, &info, MemoryKind::Image)
+            .expect("depth image memory must be allocated");
+        device
+            .bind_image_memory(image, memory, 0)
+            .expect("depth image memory must bound");
+        let subresource_range = vk::ImageSubresourceRange::builder()
+            .aspect_mask(vk::ImageAspectFlags::DEPTH)
+            .base_mip_level(0)
+            .level_count(1)
+            .base_array_layer(0)
+            .layer_count(1);
+        let info = vk::ImageViewCreateInfo::builder()
+            .image(image)
+            .view_type(vk::ImageViewType::_2D)
+            .format(format)
+            .subresource_range(subresource_range);
+        let view = device
+            .create_image_view(&info, None)
+            .expect("depth image view must be created");
+        info!("Creates depth buffer format={format:?} extent={extent:?}");
+        Self {
+            format,
+            image,
+            memory,
+            view,
+        }
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        device.destroy_image_view(self.view, None);
+        device.destroy_image(self.image, None);
+        free_memory(device, self.memory);
+    }
+}
@@ -1,21 +1,105 @@
 use std::collections::HashMap;
 use std::env;
 
-use log::info;
-use vulkanalia::vk::{HasBuilder, InstanceV1_0, InstanceV1_1};
+use log::{info, warn};
+use vulkanalia::vk::{
+    HasBuilder, InstanceV1_0, InstanceV1_1, KhrGetPhysicalDeviceProperties2Extension,
+};
 use vulkanalia::{vk, Device, Instance};
 
 use crate::vulkan::{QueueFamilyIndex, DEVICE_EXTENSIONS, VALIDATION_LAYER};
 
+pub unsafe fn supports_device_extension(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+    extension: vk::ExtensionName,
+) -> bool {
+    instance
+        .enumerate_device_extension_properties(physical_device, None)
+        .expect("device extensions must be got")
+        .iter()
+        .any(|properties| properties.extension_name == extension)
+}
+
+/// Returns true if device supports timeline semaphores of `VK_KHR_timeline_semaphore`,
+/// instance is created with Vulkan 1.0 so core 1.2 semaphores are not used.
+pub unsafe fn supports_timeline_semaphore(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+) -> bool {
+    let extension = vk::KHR_TIMELINE_SEMAPHORE_EXTENSION.name;
+    if !supports_device_extension(instance, physical_device, extension) {
+        return false;
+    }
+    let mut timeline = vk::PhysicalDeviceTimelineSemaphoreFeatures::default();
+    let mut features2 = vk::PhysicalDeviceFeatures2::builder().push_next(&mut timeline);
+    instance.get_physical_device_features2_khr(physical_device, &mut features2);
+    timeline.timeline_semaphore == vk::TRUE
+}
+
+/// Returns features of portability subset implementation, e.g. MoltenVK on macOS,
+/// None if device implements Vulkan fully.
+///
+/// Instance is created with Vulkan 1.0, so features are queried by
+/// `VK_KHR_get_physical_device_properties2` which portability subset requires.
+pub unsafe fn get_portability_subset(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+) -> Option<vk::PhysicalDevicePortabilitySubsetFeaturesKHR> {
+    let extension = vk::KHR_PORTABILITY_SUBSET_EXTENSION.name;
+    if !supports_device_extension(instance, physical_device, extension) {
+        return None;
+    }
+    let mut portability = vk::PhysicalDevicePortabilitySubsetFeaturesKHR::default();
+    let mut features2 = vk::PhysicalDeviceFeatures2::builder().push_next(&mut portability);
+    instance.get_physical_device_features2_khr(physical_device, &mut features2);
+    let unsupported = get_unsupported_portability_features(&portability);
+    warn!("Device is portability subset implementation, unsupported features: {unsupported:?}");
+    Some(portability)
+}
+
+pub fn get_unsupported_portability_features(
+    features: &vk::PhysicalDevicePortabilitySubsetFeaturesKHR,
+) -> Vec<&'static str> {
+    let features = [
+        (
+            "constantAlphaColorBlendFactors",
+            features.constant_alpha_color_blend_factors,
+        ),
+        ("events", features.events),
+        ("imageViewFormatSwizzle", features.image_view_format_swizzle),
+        (
+            "mutableComparisonSamplers",
+            features.mutable_comparison_samplers,
+        ),
+        ("pointPolygons", features.point_polygons),
+        ("samplerMipLodBias", features.sampler_mip_lod_bias),
+        ("separateStencilMaskRef", features.separate_stencil_mask_ref),
+        ("triangleFans", features.triangle_fans),
+    ];
+    features
+        .into_iter()
+        .filter(|(_, supported)| *supported == vk::FALSE)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Creates logical device, portability subset features must be given if device
+/// is portability subset implementation, supported features are enabled.
 pub unsafe fn create_logical_device(
     instance: &Instance,
     physical_device: vk::PhysicalDevice,
     queues: QueueFamilyIndex,
-) -> Device {
-    let mut priority = HashMap::new();
+    timeline: bool,
+    portability: Option<&mut vk::PhysicalDevicePortabilitySubsetFeaturesKHR>,
+) -> Result<Device, vk::ErrorCode> {
+    let mut priority: HashMap<u32, Vec<f32>> = HashMap::new();
     for index in queues.indices() {
-        let queue_priorities = vec![1.0; (index.queue + 1) as usize];
-        priority.insert(index.family, queue_priorities);
+        let queue_priorities = priority.entry(index.family).or_default();
+        let count = (index.queue + 1) as usize;
+        if queue_priorities.len() < count {
+            queue_priorities.resize(count, 1.0);
+        }
     }
     let queue_infos: Vec<_> = priority
         .iter()
@@ -112,19 +196,54 @@ pub unsafe fn create_logical_device(
         .sampler_anisotropy(true)
         .fill_mode_non_solid(true);
 
-    let extensions = DEVICE_EXTENSIONS
+    let mut extensions = DEVICE_EXTENSIONS
         .iter()
         .map(|e| e.as_ptr())
         .collect::<Vec<_>>();
-    let info = vk::DeviceCreateInfo::builder()
+    let mut timeline_features =
+        vk::PhysicalDeviceTimelineSemaphoreFeatures::builder().timeline_semaphore(true);
+    if timeline {
+        info!("Enables timeline semaphores for async compute");
+        extensions.push(vk::KHR_TIMELINE_SEMAPHORE_EXTENSION.name.as_ptr());
+    }
+    if portability.is_some() {
+        info!("Enables portability subset");
+        extensions.push(vk::KHR_PORTABILITY_SUBSET_EXTENSION.name.as_ptr());
+    }
+    let mut info = vk::DeviceCreateInfo::builder()
         .queue_create_infos(&queue_infos)
         .enabled_layer_names(&layers)
         .enabled_extension_names(&extensions)
         .enabled_features(&features)
         .push_next(&mut indexing);
+    if timeline {
+        info = info.push_next(&mut timeline_features);
+    }
+    if let Some(portability) = portability {
+        info = info.push_next(portability);
+    }
     // .push_next(&mut features12);
     info!("Creates Vulkan logical device");
-    instance
-        .create_device(physical_device, &info, None)
-        .expect("Vulkan device must be created")
+    instance.create_device(physical_device, &info, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::device::get_unsupported_portability_features;
+    use vulkanalia::vk;
+
+    #[test]
+    pub fn test_moltenvk_unsupported_features() {
+        let features = vk::PhysicalDevicePortabilitySubsetFeaturesKHR {
+            constant_alpha_color_blend_factors: vk::TRUE,
+            events: vk::TRUE,
+            image_view_format_swizzle: vk::TRUE,
+            mutable_comparison_samplers: vk::TRUE,
+            sampler_mip_lod_bias: vk::TRUE,
+            separate_stencil_mask_ref: vk::TRUE,
+            ..Default::default()
+        };
+        let unsupported = get_unsupported_portability_features(&features);
+        assert_eq!(unsupported, vec!["pointPolygons", "triangleFans"]);
+    }
 }
@@ -0,0 +1,117 @@
+use mesura::{Gauge, GaugeValue};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use vulkanalia::vk::{DeviceV1_0, Handle};
+use vulkanalia::{vk, Device};
+
+/// Resource device memory is allocated for, every allocation backs single resource.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum MemoryKind {
+    Buffer,
+    Image,
+}
+
+/// Device memory and resources allocated by graphics, see [`crate::Graphics::memory_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemoryStats {
+    /// Bytes of device memory allocated for buffers and images.
+    pub allocated: u64,
+    pub buffers: usize,
+    pub images: usize,
+    /// Descriptor sets allocated since start.
+    pub descriptor_sets: usize,
+    pub swapchain_recreations: usize,
+}
+
+static ALLOCATIONS: Mutex<BTreeMap<u64, (MemoryKind, u64)>> = Mutex::new(BTreeMap::new());
+static DESCRIPTOR_SETS: AtomicUsize = AtomicUsize::new(0);
+static SWAPCHAIN_RECREATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Allocates device memory and records allocation, must be freed by [`free_memory`].
+pub(crate) unsafe fn allocate_memory(
+    device: &Device,
+    info: &vk::MemoryAllocateInfo,
+    kind: MemoryKind,
+) -> Result<vk::DeviceMemory, vk::ErrorCode> {
+    let memory = device.allocate_memory(info, None)?;
+    ALLOCATIONS
+        .lock()
+        .expect("allocations must be lockable")
+        .insert(memory.as_raw(), (kind, info.allocation_size));
+    Ok(memory)
+}
+
+pub(crate) unsafe fn free_memory(device: &Device, memory: vk::DeviceMemory) {
+    ALLOCATIONS
+        .lock()
+        .expect("allocations must be lockable")
+        .remove(&memory.as_raw());
+    device.free_memory(memory, None);
+}
+
+pub(crate) fn record_descriptor_sets(count: usize) {
+    DESCRIPTOR_SETS.fetch_add(count, Ordering::Relaxed);
+}
+
+pub(crate) fn record_swapchain_recreation() {
+    SWAPCHAIN_RECREATIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn memory_stats() -> MemoryStats {
+    let mut stats = MemoryStats {
+        descriptor_sets: DESCRIPTOR_SETS.load(Ordering::Relaxed),
+        swapchain_recreations: SWAPCHAIN_RECREATIONS.load(Ordering::Relaxed),
+        ..MemoryStats::default()
+    };
+    let allocations = ALLOCATIONS.lock().expect("allocations must be lockable");
+    for (kind, size) in allocations.values() {
+        stats.allocated += size;
+        match kind {
+            MemoryKind::Buffer => stats.buffers += 1,
+            MemoryKind::Image => stats.images += 1,
+        }
+    }
+    stats
+}
+
+/// Reports memory stats as gauges, gauges are changed by difference to previous record.
+pub(crate) struct MemoryMetrics {
+    allocated: Gauge,
+    buffers: Gauge,
+    images: Gauge,
+    descriptor_sets: Gauge,
+    swapchain_recreations: Gauge,
+    previous: MemoryStats,
+}
+
+impl MemoryMetrics {
+    pub fn new() -> Self {
+        let gauge = |kind| Gauge::with_labels("device_resources", ["kind"], [kind]);
+        Self {
+            allocated: Gauge::with_labels("device_memory", ["kind"], ["allocated"]),
+            buffers: gauge("buffers"),
+            images: gauge("images"),
+            descriptor_sets: gauge("descriptor_sets"),
+            swapchain_recreations: gauge("swapchain_recreations"),
+            previous: MemoryStats::default(),
+        }
+    }
+
+    pub fn record(&mut self, stats: MemoryStats) {
+        let delta = |current: u64, previous: u64| (current as i64 - previous as i64) as f32;
+        let previous = self.previous;
+        let count = |current: usize, previous: usize| delta(current as u64, previous as u64);
+        self.allocated
+            .add(delta(stats.allocated, previous.allocated));
+        self.buffers.add(count(stats.buffers, previous.buffers));
+        self.images.add(count(stats.images, previous.images));
+        self.descriptor_sets
+            .add(count(stats.descriptor_sets, previous.descriptor_sets));
+        self.swapchain_recreations.add(count(
+            stats.swapchain_recreations,
+            previous.swapchain_recreations,
+        ));
+        self.previous = stats;
+    }
+}
@@ -1,10 +1,12 @@
 use log::{debug, error, info, trace, warn};
-use sdl2::video::Window;
 use std::collections::HashSet;
 
 use std::ffi::{c_void, CStr};
 
-use std::sync::atomic::{AtomicPtr, Ordering};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock, Weak};
 
 use std::{env, fmt};
 use vulkanalia::bytecode::Bytecode;
@@ -18,39 +20,84 @@ use vulkanalia::vk::{ExtDebugUtilsExtension, Handle, HasBuilder};
 use vulkanalia::vk::{KhrSurfaceExtension, PhysicalDevice};
 use vulkanalia::{vk, Device, Entry, Instance, Version};
 
-use crate::camera::Camera;
+use crate::camera::{Camera, CameraHandle};
 
-use crate::vulkan::device::create_logical_device;
+use crate::system::set_crash_context;
+use crate::vulkan::compute::{AsyncCompute, ComputeProgram, ComputeProgramShared};
+use crate::vulkan::depth::DepthBuffer;
+use crate::vulkan::device::{
+    create_logical_device, get_portability_subset, supports_timeline_semaphore,
+};
+use crate::vulkan::memory::{
+    allocate_memory, free_memory, record_descriptor_sets, record_swapchain_recreation, MemoryKind,
+};
+use crate::vulkan::multisample::{create_color_attachments, get_sample_count, MultisampleBuffer};
+use crate::vulkan::program::ProgramShared;
+use crate::vulkan::retire::{RetireQueue, Retired};
+use crate::vulkan::surface::VulkanWindow;
+use crate::vulkan::target::{MultiTarget, RenderTarget};
 use crate::vulkan::textures::VulkanTextureLoaderDevice;
-use crate::Program;
+use crate::vulkan::validation::record_message;
+use crate::{ColorConversions, GraphicsError, Program, ProgramError, ProgramOptions};
 
+pub mod compute;
+mod depth;
 mod device;
+pub(crate) mod memory;
+mod multisample;
 pub mod program;
+pub(crate) mod retire;
+pub mod sampler;
 pub mod shaders;
+pub mod stats;
+pub mod surface;
+pub mod target;
 pub mod textures;
+pub mod validation;
 pub mod variables;
 
 pub struct Vulkan {
     _entry: Entry,
     _messenger: vk::DebugUtilsMessengerEXT,
+    /// Errors reported by validation layer to messenger of this instance.
+    validation_errors: &'static AtomicUsize,
     pub(crate) instance: Instance,
     pub(crate) physical_device: vk::PhysicalDevice,
     pub(crate) device: Device,
     queues: QueueFamilyIndex,
     pub(crate) queue: vk::Queue,
     present_queue: vk::Queue,
+    pub(crate) compute: AsyncCompute,
     surface: vk::SurfaceKHR,
     pub(crate) swapchain: Swapchain,
     pub(crate) render_pass: vk::RenderPass,
+    /// Sample count of color and depth attachments, render targets and pipelines.
+    pub(crate) samples: vk::SampleCountFlags,
+    depth: DepthBuffer,
+    multisample: Option<MultisampleBuffer>,
     framebuffers: Vec<vk::Framebuffer>,
     sync: Sync,
+    /// Shared with meshes, so buffers outgrown by vertices are retired too.
+    retired: Rc<RefCell<RetireQueue>>,
     pub(crate) chain: usize,
     need_resize: bool,
-    programs: Vec<AtomicPtr<Program>>,
-    cameras: Vec<AtomicPtr<Camera>>,
+    /// Programs are referenced weakly, dropped ones are removed on next access.
+    programs: Vec<Weak<ProgramShared>>,
+    compute_programs: Vec<Weak<ComputeProgramShared>>,
+    /// Command buffer programs currently record to, set between prepare and present.
+    recording: Option<vk::CommandBuffer>,
+    cameras: Vec<Weak<RwLock<Camera>>>,
     pub(crate) command_buffers: Vec<vk::CommandBuffer>,
     pub(crate) command_pools: Vec<vk::CommandPool>,
     present_mode: vk::PresentModeKHR,
+    /// Recreates registered programs when shader files change, checked every frame.
+    pub(crate) hot_reload_shaders: bool,
+    /// False on portability subset implementations without sampler LOD bias, e.g. MoltenVK.
+    pub(crate) lod_bias_supported: bool,
+    /// Maximum textures of bindless array supported by device descriptor indexing.
+    pub(crate) max_bindless_textures: u32,
+    /// Swapchain and textures use SRGB formats, so blending happens in linear space.
+    pub(crate) srgb: bool,
 }
 
 #[derive(Debug)]
@@ -65,18 +112,41 @@ impl From<vk::ErrorCode> for FrameError {
 }
 
 impl Vulkan {
-    pub unsafe fn create(window: &Window, present_mode: vk::PresentModeKHR) -> Self {
+    /// Creates Vulkan rendering to window, frames are prepared and presented with the same
+    /// window, e.g. winit window if `winit` feature is enabled.
+    ///
+    /// Validation layer is enabled if requested or by `VULKAN_DEBUG` environment variable.
+    ///
+    /// # Safety
+    ///
+    /// Window must outlive Vulkan.
+    pub unsafe fn create(
+        window: &dyn VulkanWindow,
+        present_mode: vk::PresentModeKHR,
+        samples: u32,
+        srgb: bool,
+        validation: bool,
+    ) -> Result<Self, GraphicsError> {
         info!("Loads Vulkan library");
-        let loader = LibloadingLoader::new(LIBRARY).expect("Vulkan loader must be created");
-        let entry = Entry::new(loader).expect("Vulkan entry point must be loaded");
-        let version = entry.version().expect("entry version must be got");
+        let loader = LibloadingLoader::new(LIBRARY)
+            .map_err(|error| GraphicsError::Vulkan(format!("unable to load {LIBRARY}, {error}")))?;
+        let entry = Entry::new(loader).map_err(|error| {
+            GraphicsError::Vulkan(format!("unable to load entry point, {error}"))
+        })?;
+        let version = entry.version().map_err(vulkan_error)?;
         info!("Uses Vulkan {version}");
         let available_layers = entry
             .enumerate_instance_layer_properties()
-            .expect("entry layers must be got")
+            .map_err(vulkan_error)?
             .iter()
             .map(|layer| layer.layer_name)
             .collect::<HashSet<_>>();
+        let available_extensions = entry
+            .enumerate_instance_extension_properties(None)
+            .map_err(vulkan_error)?
+            .iter()
+            .map(|extension| extension.extension_name)
+            .collect::<HashSet<_>>();
         for layer in available_layers {
             debug!("Vulkan layer {layer}")
         }
@@ -86,25 +156,20 @@ impl Vulkan {
             .engine_name(b"No Engine\0")
             .engine_version(vk::make_version(1, 0, 0))
             .api_version(vk::make_version(1, 0, 0));
-        let mut extensions: Vec<_> = window
-            .vulkan_instance_extensions()
-            .expect("SDL2 vulkan extensions must be got")
-            .iter()
-            .map(|name| name.as_ptr() as *const _)
-            .collect();
+        let mut extensions = window.instance_extensions()?;
         let mut flags = vk::InstanceCreateFlags::empty();
+        let properties2 = vk::KHR_GET_PHYSICAL_DEVICE_PROPERTIES2_EXTENSION.name;
+        if available_extensions.contains(&properties2) {
+            // required to query portability subset and timeline semaphore features on Vulkan 1.0
+            extensions.push(properties2.as_ptr());
+        }
         if version >= Version::new(1, 3, 216) {
             info!("Enables extensions for macOS portability");
-            extensions.push(
-                vk::KHR_GET_PHYSICAL_DEVICE_PROPERTIES2_EXTENSION
-                    .name
-                    .as_ptr(),
-            );
             extensions.push(vk::KHR_PORTABILITY_ENUMERATION_EXTENSION.name.as_ptr());
             flags = vk::InstanceCreateFlags::ENUMERATE_PORTABILITY_KHR;
         }
         let mut layers = Vec::new();
-        let is_vulkan_debug = env::var("VULKAN_DEBUG").is_ok();
+        let is_vulkan_debug = validation || env::var("VULKAN_DEBUG").is_ok();
         if is_vulkan_debug {
             info!("Enables validation layer");
             layers.push(VALIDATION_LAYER.as_ptr());
@@ -115,32 +180,44 @@ impl Vulkan {
             .application_info(&application_info)
             .enabled_layer_names(&layers)
             .enabled_extension_names(&extensions);
+        // leaked as debug messenger is never destroyed and may report until process exit
+        let validation_errors: &'static AtomicUsize = Box::leak(Box::new(AtomicUsize::new(0)));
         let mut debug_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
             .message_severity(vk::DebugUtilsMessageSeverityFlagsEXT::all())
             .message_type(vk::DebugUtilsMessageTypeFlagsEXT::all())
-            .user_callback(Some(debug_callback));
+            .user_callback(Some(debug_callback))
+            .user_data(validation_errors as *const AtomicUsize as *mut c_void);
         if is_vulkan_debug {
             info = info.push_next(&mut debug_info);
         }
         info!("Creates Vulkan instance");
-        let instance = entry
-            .create_instance(&info, None)
-            .expect("Vulkan instance must be created");
+        let instance = entry.create_instance(&info, None).map_err(vulkan_error)?;
         let mut messenger = Default::default();
         if is_vulkan_debug {
             messenger = instance
                 .create_debug_utils_messenger_ext(&debug_info, None)
-                .expect("Vulkan debug messenger must be created");
+                .map_err(vulkan_error)?;
         }
         debug!("Creates Vulkan surface");
-        let surface_handle = window
-            .vulkan_create_surface(instance.handle().as_raw())
-            .expect("SDL2 Vulkan surface must be created");
-        let surface = vk::SurfaceKHR::from_raw(surface_handle);
-        let (queues, physical_device) = find_physical_device(&instance, surface);
-        let device = create_logical_device(&instance, physical_device, queues);
+        let surface = window.create_surface(&instance)?;
+        let (queues, physical_device) = find_physical_device(&instance, surface)?;
+        let max_bindless_textures = log_indexing(&instance, physical_device);
+        let timeline = supports_timeline_semaphore(&instance, physical_device);
+        let mut portability = get_portability_subset(&instance, physical_device);
+        let device = create_logical_device(
+            &instance,
+            physical_device,
+            queues,
+            timeline,
+            portability.as_mut(),
+        )
+        .map_err(vulkan_error)?;
+        let lod_bias_supported = portability
+            .map(|features| features.sampler_mip_lod_bias == vk::TRUE)
+            .unwrap_or(true);
         let queue = device.get_device_queue(queues.graphics.family, queues.graphics.queue);
         let present_queue = device.get_device_queue(queues.present.family, queues.present.queue);
+        let compute = AsyncCompute::create(&device, queues.compute, timeline);
         //
         let swapchain = Swapchain::create(
             window,
@@ -150,34 +227,94 @@ impl Vulkan {
             queues,
             surface,
             present_mode,
+            srgb,
         );
-        let render_pass = create_render_pass(&device, &swapchain);
-        let framebuffers = create_framebuffers(&device, render_pass, &swapchain);
-        let command_pools = create_command_pools(&device, queues.graphics, &swapchain);
+        let samples = get_sample_count(&instance, physical_device, samples);
+        let depth = DepthBuffer::create(
+            &instance,
+            &device,
+            physical_device,
+            swapchain.extent,
+            samples,
+        );
+        let multisample = MultisampleBuffer::create(
+            &instance,
+            &device,
+            physical_device,
+            swapchain.format,
+            swapchain.extent,
+            samples,
+        );
+        let render_pass = create_render_pass(&device, &swapchain, &depth, samples);
+        let framebuffers = create_framebuffers(
+            &device,
+            render_pass,
+            &swapchain,
+            &depth,
+            multisample.as_ref(),
+        );
+        let command_pools = create_command_pools(&device, queues.graphics);
         let command_buffers = create_command_buffers(&device, &command_pools);
         let sync = Sync::create(&device, &swapchain);
-        Vulkan {
+        Ok(Vulkan {
             _entry: entry,
             instance,
             _messenger: messenger,
+            validation_errors,
             physical_device,
             device,
             queues,
             queue,
             present_queue,
+            compute,
             surface,
             swapchain,
             render_pass,
+            samples,
+            depth,
+            multisample,
             framebuffers,
             sync,
+            retired: Rc::new(RefCell::new(RetireQueue::new())),
             need_resize: false,
             programs: vec![],
+            compute_programs: vec![],
+            recording: None,
             cameras: vec![],
             command_buffers,
             command_pools,
             chain: 0,
             present_mode,
-        }
+            hot_reload_shaders: cfg!(debug_assertions),
+            lod_bias_supported,
+            max_bindless_textures,
+            srgb,
+        })
+    }
+
+    /// Creates Vulkan for window owned by external framework, e.g. editor shell,
+    /// instead of SDL window. Size is drawable size in pixels.
+    ///
+    /// # Safety
+    ///
+    /// Handles must stay valid while Vulkan exists. Frames must be prepared with
+    /// [`surface::RawWindow`] of the same handles and actual size.
+    #[cfg(feature = "raw-window-handle")]
+    pub unsafe fn create_from_raw_handle(
+        display: raw_window_handle::RawDisplayHandle,
+        window: raw_window_handle::RawWindowHandle,
+        size: [u32; 2],
+        present_mode: vk::PresentModeKHR,
+        samples: u32,
+        srgb: bool,
+        validation: bool,
+    ) -> Result<Self, GraphicsError> {
+        let window = surface::RawWindow {
+            display,
+            window,
+            size,
+        };
+        Self::create(&window, present_mode, samples, srgb, validation)
     }
 
     pub fn create_texture_loader_device(&self) -> VulkanTextureLoaderDevice {
@@ -193,68 +330,174 @@ impl Vulkan {
                 physical_device: self.physical_device.clone(),
                 command_pool,
                 queue,
+                format: match self.is_srgb() {
+                    true => vk::Format::R8G8B8A8_SRGB,
+                    false => vk::Format::R8G8B8A8_UNORM,
+                },
             }
         }
     }
 
-    pub fn register(&mut self, program: &mut Box<Program>) {
-        let ptr = AtomicPtr::new(program.as_mut());
-        self.programs.push(ptr);
+    /// Registers program for hot reload and frame updates, program registered
+    /// mid-frame records to current command buffer immediately.
+    pub fn register(&mut self, program: &mut Program) {
+        if let Some(commands) = self.recording {
+            program.set_command_buffer(commands);
+            program.set_frame(self.sync.frame);
+        }
+        self.programs.push(program.shared());
+    }
+
+    /// Registers compute program for hot reload.
+    pub fn register_compute(&mut self, program: &ComputeProgram) {
+        self.compute_programs.push(program.shared());
     }
 
-    pub fn register_camera(&mut self, camera: &mut Box<Camera>) {
-        let ptr = AtomicPtr::new(camera.as_mut());
-        self.cameras.push(ptr);
+    /// Registers camera to update its screen once swapchain is recreated.
+    pub fn register_camera(&mut self, camera: &CameraHandle) {
+        self.cameras.push(Arc::downgrade(camera));
     }
 
     pub fn update(&mut self) {
-        #[cfg(debug_assertions)]
-        {
-            for (_index, program) in self.programs().into_iter().enumerate() {
-                if program.frag.changed() || program.vert.changed() {
-                    unsafe {
-                        self.device.device_wait_idle().expect("device must be idle");
-                        program.recreate(&self.swapchain, self.render_pass);
-                        info!("Recreate done");
+        if !self.hot_reload_shaders {
+            return;
+        }
+        for program in self.programs() {
+            if program.changed() {
+                unsafe {
+                    self.device.device_wait_idle().expect("device must be idle");
+                    match program.recreate(&self.swapchain, self.render_pass, self.samples) {
+                        Ok(()) => info!("Recreate done"),
+                        Err(error) => error!("unable to recreate program, {error:?}"),
+                    }
+                }
+            }
+        }
+        for program in self.compute_programs() {
+            if program.changed() {
+                unsafe {
+                    self.device.device_wait_idle().expect("device must be idle");
+                    match program.recreate() {
+                        Ok(()) => info!("Recreate done"),
+                        Err(error) => error!("unable to recreate compute program, {error:?}"),
                     }
                 }
             }
         }
     }
 
-    pub fn programs(&self) -> Vec<&mut Program> {
+    pub fn submit_async_compute<F>(&mut self, record: F)
+    where
+        F: FnOnce(vk::CommandBuffer),
+    {
         unsafe {
-            let mut values = vec![];
-            for ptr in &self.programs {
-                let ptr = ptr.load(Ordering::Relaxed);
-                let value = &mut *ptr;
-                values.push(value);
-            }
-            values
+            self.compute.submit(&self.device, self.sync.frame, record);
         }
     }
 
-    pub fn cameras(&self) -> Vec<&mut Camera> {
-        unsafe {
-            let mut values = vec![];
-            for ptr in &self.cameras {
-                let ptr = ptr.load(Ordering::Relaxed);
-                let value = &mut *ptr;
-                values.push(value);
-            }
-            values
+    /// Redirects commands of all registered programs to offscreen target until
+    /// [`Vulkan::end_target`] is called.
+    pub fn begin_target(&mut self, target: &mut RenderTarget, clear_color: [f32; 4]) {
+        let clear_color = self.get_clear_color(clear_color);
+        let commands = unsafe { target.begin(self.sync.frame, clear_color) };
+        self.recording = Some(commands);
+        for program in self.programs() {
+            program.set_command_buffer(commands);
+        }
+    }
+
+    pub fn end_target(&mut self, target: &mut RenderTarget) {
+        unsafe { target.end(self.queue) };
+        self.recording = Some(self.command_buffers[self.sync.frame]);
+        for program in self.programs() {
+            program.set_command_buffer(self.command_buffers[self.sync.frame]);
         }
     }
 
-    pub fn prepare(&mut self, window: &Window, clear_color: [f32; 4]) {
+    /// Redirects commands of all registered programs to multi target until
+    /// [`Vulkan::end_multi_target`] is called.
+    pub fn begin_multi_target(&mut self, target: &mut MultiTarget, clear_color: [f32; 4]) {
+        let clear_color = self.get_clear_color(clear_color);
+        let commands = unsafe { target.begin(self.sync.frame, clear_color) };
+        self.recording = Some(commands);
+        for program in self.programs() {
+            program.set_command_buffer(commands);
+        }
+    }
+
+    pub fn end_multi_target(&mut self, target: &mut MultiTarget) {
+        unsafe { target.end(self.queue) };
+        self.recording = Some(self.command_buffers[self.sync.frame]);
+        for program in self.programs() {
+            program.set_command_buffer(self.command_buffers[self.sync.frame]);
+        }
+    }
+
+    /// Schedules resource destruction after all frames in flight complete.
+    pub fn retire(&mut self, resource: Retired) {
+        self.retired.borrow_mut().retire(resource);
+    }
+
+    pub(crate) fn retire_queue(&self) -> Rc<RefCell<RetireQueue>> {
+        self.retired.clone()
+    }
+
+    /// Returns registered programs which are still alive.
+    pub(crate) fn programs(&mut self) -> Vec<Arc<ProgramShared>> {
+        self.programs.retain(|program| program.strong_count() > 0);
+        self.programs.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    /// Returns queue families accessing buffers of variables, graphics and async compute
+    /// ones if compute runs on dedicated family.
+    pub(crate) fn buffer_families(&self) -> Vec<u32> {
+        let mut families = vec![self.queues.graphics.family, self.queues.compute.family];
+        families.dedup();
+        families
+    }
+
+    pub(crate) fn compute_programs(&mut self) -> Vec<Arc<ComputeProgramShared>> {
+        self.compute_programs
+            .retain(|program| program.strong_count() > 0);
+        self.compute_programs
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    /// Returns registered cameras which are still alive.
+    pub fn cameras(&mut self) -> Vec<CameraHandle> {
+        self.cameras.retain(|camera| camera.strong_count() > 0);
+        self.cameras.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    /// Returns true if swapchain has SRGB format, requested format may be not supported.
+    pub(crate) fn is_srgb(&self) -> bool {
+        matches!(
+            self.swapchain.format,
+            vk::Format::R8G8B8A8_SRGB | vk::Format::B8G8R8A8_SRGB
+        )
+    }
+
+    /// Returns clear color in space of color attachments, colors are given in sRGB.
+    fn get_clear_color(&self, color: [f32; 4]) -> [f32; 4] {
+        match self.is_srgb() {
+            true => color.to_linear(),
+            false => color,
+        }
+    }
+
+    pub fn prepare(&mut self, window: &dyn VulkanWindow, clear_color: [f32; 4]) {
+        let clear_color = self.get_clear_color(clear_color);
         loop {
             unsafe {
                 if let Some(chain) = self.acquire_next_image(window) {
                     self.chain = chain;
                     self.begin_render_pass(clear_color);
+                    self.recording = Some(self.command_buffers[self.sync.frame]);
                     for program in self.programs() {
-                        program.set_command_buffer(self.command_buffers[self.chain]);
-                        program.set_chain(self.chain);
+                        program.set_command_buffer(self.command_buffers[self.sync.frame]);
+                        program.set_frame(self.sync.frame);
                     }
                     break;
                 }
@@ -262,13 +505,32 @@ impl Vulkan {
         }
     }
 
-    unsafe fn acquire_next_image(&mut self, window: &Window) -> Option<usize> {
+    /// Acquires next swapchain image and begins command buffer without render pass,
+    /// frame must be finished by [`Vulkan::present_compute`].
+    pub fn prepare_compute(&mut self, window: &dyn VulkanWindow) -> vk::CommandBuffer {
+        loop {
+            unsafe {
+                if let Some(chain) = self.acquire_next_image(window) {
+                    self.chain = chain;
+                    return self.begin_commands();
+                }
+            }
+        }
+    }
+
+    unsafe fn acquire_next_image(&mut self, window: &dyn VulkanWindow) -> Option<usize> {
         let fence = self.sync.fences[self.sync.frame];
         self.device
             .wait_for_fences(&[fence], true, u64::MAX)
             .expect("fence must be acquired");
+        self.retired.borrow_mut().collect(&self.device);
 
         if self.need_resize {
+            let (width, height) = window.drawable_size();
+            if width == 0 || height == 0 {
+                // minimized window, resize is kept until window is restored
+                return None;
+            }
             self.resize(window);
             self.need_resize = false;
             return None;
@@ -284,7 +546,7 @@ impl Vulkan {
         let chain = match result {
             Ok((next_image, _)) => next_image as usize,
             Err(vk::ErrorCode::OUT_OF_DATE_KHR) => {
-                self.resize(window);
+                self.need_resize = true;
                 return None;
             }
             Err(error) => panic!("unable to acquire next image {error}"),
@@ -304,17 +566,55 @@ impl Vulkan {
         unsafe {
             self.end_render_pass();
         }
+        self.recording = None;
+        self.submit_and_present(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT);
+    }
 
+    /// Presents frame recorded without render pass, swapchain image
+    /// must be transitioned to present layout by recorded commands.
+    pub fn present_compute(&mut self) {
+        let buf = self.command_buffers[self.sync.frame];
+        unsafe {
+            self.device
+                .end_command_buffer(buf)
+                .expect("command buffer must end");
+        }
+        self.submit_and_present(vk::PipelineStageFlags::TRANSFER);
+    }
+
+    fn submit_and_present(&mut self, wait_stage: vk::PipelineStageFlags) {
         let fence = self.sync.images[self.chain];
-        let wait_semaphores = &[self.sync.image_available[self.sync.frame]];
-        let wait_stages = &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
-        let command_buffers = &[self.command_buffers[self.chain]];
+        let mut wait_semaphores = vec![self.sync.image_available[self.sync.frame]];
+        let mut wait_stages = vec![wait_stage];
+        let mut wait_values = vec![0];
+        let command_buffers = &[self.command_buffers[self.sync.frame]];
         let signal_semaphores = &[self.sync.render_finished[self.sync.frame]];
-        let info = vk::SubmitInfo::builder()
-            .wait_semaphores(wait_semaphores)
-            .wait_dst_stage_mask(wait_stages)
+        let signal_values = &[0];
+        let compute = match (self.compute.pending.take(), self.compute.semaphore()) {
+            (Some(value), Some(semaphore)) => {
+                wait_semaphores.push(semaphore);
+                wait_stages.push(
+                    vk::PipelineStageFlags::DRAW_INDIRECT
+                        | vk::PipelineStageFlags::VERTEX_INPUT
+                        | vk::PipelineStageFlags::VERTEX_SHADER
+                        | vk::PipelineStageFlags::FRAGMENT_SHADER,
+                );
+                wait_values.push(value);
+                true
+            }
+            _ => false,
+        };
+        let mut timeline = vk::TimelineSemaphoreSubmitInfo::builder()
+            .wait_semaphore_values(&wait_values)
+            .signal_semaphore_values(signal_values);
+        let mut info = vk::SubmitInfo::builder()
+            .wait_semaphores(&wait_semaphores)
+            .wait_dst_stage_mask(&wait_stages)
             .command_buffers(command_buffers)
             .signal_semaphores(signal_semaphores);
+        if compute {
+            info = info.push_next(&mut timeline);
+        }
         unsafe {
             self.device
                 .reset_fences(&[fence])
@@ -339,19 +639,25 @@ impl Vulkan {
             panic!("unable to present {}", error);
         }
         self.sync.frame = (self.sync.frame + 1) % FRAMES_PROCESSING_CONCURRENCY;
+        self.retired.borrow_mut().advance();
     }
 
-    unsafe fn begin_render_pass(&self, clear_color: [f32; 4]) {
-        let command_pool = self.command_pools[self.chain];
+    unsafe fn begin_commands(&self) -> vk::CommandBuffer {
+        let command_pool = self.command_pools[self.sync.frame];
         self.device
             .reset_command_pool(command_pool, vk::CommandPoolResetFlags::empty())
             .expect("command pool must be reset");
-        let buf = self.command_buffers[self.chain];
+        let buf = self.command_buffers[self.sync.frame];
         let info = vk::CommandBufferBeginInfo::builder()
             .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
         self.device
             .begin_command_buffer(buf, &info)
             .expect("command buffer must begin");
+        buf
+    }
+
+    unsafe fn begin_render_pass(&self, clear_color: [f32; 4]) {
+        let buf = self.begin_commands();
         let render_area = vk::Rect2D::builder()
             .offset(vk::Offset2D::default())
             .extent(self.swapchain.extent);
@@ -360,7 +666,13 @@ impl Vulkan {
                 float32: clear_color,
             },
         };
-        let clear_values = &[color_clear_value];
+        let depth_clear_value = vk::ClearValue {
+            depth_stencil: vk::ClearDepthStencilValue {
+                depth: 1.0,
+                stencil: 0,
+            },
+        };
+        let clear_values = &[color_clear_value, depth_clear_value];
         let info = vk::RenderPassBeginInfo::builder()
             .render_pass(self.render_pass)
             .framebuffer(self.framebuffers[self.chain])
@@ -368,16 +680,53 @@ impl Vulkan {
             .clear_values(clear_values);
         self.device
             .cmd_begin_render_pass(buf, &info, vk::SubpassContents::INLINE);
+        set_viewport(&self.device, buf, self.swapchain.extent);
     }
 
     unsafe fn end_render_pass(&self) {
-        let buf = self.command_buffers[self.chain];
+        let buf = self.command_buffers[self.sync.frame];
         self.device.cmd_end_render_pass(buf);
         self.device
             .end_command_buffer(buf)
             .expect("command buffer must end");
     }
 
+    /// Returns index of frame in flight, resources duplicated per frame in flight
+    /// can be safely updated by this index after frame is prepared.
+    pub fn frame_in_flight(&self) -> usize {
+        self.sync.frame
+    }
+
+    /// Schedules swapchain recreation on next frame, e.g. after window mode change,
+    /// because not every platform reports outdated swapchain on resize.
+    pub fn request_resize(&mut self) {
+        self.need_resize = true;
+    }
+
+    /// Returns true if swapchain recreation is scheduled on next frame.
+    pub fn is_resize_requested(&self) -> bool {
+        self.need_resize
+    }
+
+    /// Schedules swapchain recreation with new present mode on next frame.
+    pub fn set_present_mode(&mut self, present_mode: vk::PresentModeKHR) {
+        self.present_mode = present_mode;
+        self.need_resize = true;
+    }
+
+    /// Returns number of errors reported by validation layer for this instance,
+    /// unlike [`validation::validation_errors`] not affected by other instances.
+    pub fn validation_errors(&self) -> usize {
+        self.validation_errors.load(Ordering::Relaxed)
+    }
+
+    /// Blocks until all submitted frames complete.
+    pub fn wait_idle(&self) {
+        unsafe {
+            self.device.device_wait_idle().expect("device must be idle");
+        }
+    }
+
     pub fn swapchain_image_size(&self) -> [f32; 2] {
         [
             self.swapchain.extent.width as f32,
@@ -385,17 +734,22 @@ impl Vulkan {
         ]
     }
 
-    pub unsafe fn resize(&mut self, window: &Window) {
+    pub unsafe fn resize(&mut self, window: &dyn VulkanWindow) {
         info!(
             "Handles window resize from {:?} to {:?}",
             self.swapchain.extent,
-            window.size()
+            window.drawable_size()
         );
         self.device.device_wait_idle().expect("device must be idle");
+        record_swapchain_recreation();
         self.framebuffers
             .iter()
             .for_each(|f| self.device.destroy_framebuffer(*f, None));
         self.device.destroy_render_pass(self.render_pass, None);
+        self.depth.destroy(&self.device);
+        if let Some(multisample) = self.multisample.take() {
+            multisample.destroy(&self.device);
+        }
         self.swapchain
             .views
             .iter()
@@ -410,15 +764,42 @@ impl Vulkan {
             self.queues,
             self.surface,
             self.present_mode,
+            self.srgb,
+        );
+        self.depth = DepthBuffer::create(
+            &self.instance,
+            &self.device,
+            self.physical_device,
+            self.swapchain.extent,
+            self.samples,
+        );
+        self.multisample = MultisampleBuffer::create(
+            &self.instance,
+            &self.device,
+            self.physical_device,
+            self.swapchain.format,
+            self.swapchain.extent,
+            self.samples,
+        );
+        self.render_pass =
+            create_render_pass(&self.device, &self.swapchain, &self.depth, self.samples);
+        self.framebuffers = create_framebuffers(
+            &self.device,
+            self.render_pass,
+            &self.swapchain,
+            &self.depth,
+            self.multisample.as_ref(),
         );
-        self.render_pass = create_render_pass(&self.device, &self.swapchain);
-        self.framebuffers = create_framebuffers(&self.device, self.render_pass, &self.swapchain);
         // recreate programs
         self.device.device_wait_idle().expect("device must be idle");
+        self.retired.borrow_mut().destroy_all(&self.device);
         for program in self.programs() {
-            program.recreate(&self.swapchain, self.render_pass);
+            if let Err(error) = program.recreate(&self.swapchain, self.render_pass, self.samples) {
+                error!("unable to recreate program, {error:?}");
+            }
         }
         for camera in self.cameras() {
+            let mut camera = camera.write().expect("camera must be writable");
             camera.update(self);
         }
         self.sync
@@ -447,17 +828,25 @@ impl Vulkan {
     // }
 }
 
+/// Creates buffer owned by single queue family, or shared concurrently if
+/// several families are given, e.g. by graphics and async compute.
 unsafe fn create_buffer(
     device: &Device,
     size: vk::DeviceSize,
     usage: vk::BufferUsageFlags,
     properties: vk::MemoryPropertyFlags,
     physical_device_memory: vk::PhysicalDeviceMemoryProperties,
+    families: &[u32],
 ) -> MemoryBuffer {
-    let buffer_info = vk::BufferCreateInfo::builder()
+    let mut buffer_info = vk::BufferCreateInfo::builder()
         .size(size)
         .usage(usage)
         .sharing_mode(vk::SharingMode::EXCLUSIVE);
+    if families.len() > 1 {
+        buffer_info = buffer_info
+            .sharing_mode(vk::SharingMode::CONCURRENT)
+            .queue_family_indices(families);
+    }
     let handle = device
         .create_buffer(&buffer_info, None)
         .expect("buffer must be created");
@@ -466,8 +855,7 @@ unsafe fn create_buffer(
     let memory_info = vk::MemoryAllocateInfo::builder()
         .allocation_size(requirements.size)
         .memory_type_index(memory_type_index);
-    let memory = device
-        .allocate_memory(&memory_info, None)
+    let memory = allocate_memory(device, &memory_info, MemoryKind::Buffer)
         .expect("buffer memory must be allocated");
     device
         .bind_buffer_memory(handle, memory, 0)
@@ -482,10 +870,21 @@ extern "system" fn debug_callback(
     severity: vk::DebugUtilsMessageSeverityFlagsEXT,
     type_: vk::DebugUtilsMessageTypeFlagsEXT,
     data: *const vk::DebugUtilsMessengerCallbackDataEXT,
-    _: *mut c_void,
+    user_data: *mut c_void,
 ) -> vk::Bool32 {
     let data = unsafe { *data };
+    if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::ERROR && !user_data.is_null() {
+        let errors = unsafe { &*(user_data as *const AtomicUsize) };
+        errors.fetch_add(1, Ordering::Relaxed);
+    }
     let message = unsafe { CStr::from_ptr(data.message) }.to_string_lossy();
+    if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::WARNING {
+        let id = match data.message_id_name.is_null() {
+            true => Default::default(),
+            false => unsafe { CStr::from_ptr(data.message_id_name) }.to_string_lossy(),
+        };
+        record_message(severity, &id, &message);
+    }
     if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::ERROR {
         error!("({:?}) {}", type_, message);
     } else if severity >= vk::DebugUtilsMessageSeverityFlagsEXT::WARNING {
@@ -498,7 +897,8 @@ extern "system" fn debug_callback(
     vk::FALSE
 }
 
-fn log_indexing(instance: &Instance, physical_device: PhysicalDevice) {
+/// Logs descriptor indexing limits, returns maximum textures of bindless array.
+fn log_indexing(instance: &Instance, physical_device: PhysicalDevice) -> u32 {
     let mut indexing = PhysicalDeviceDescriptorIndexingProperties::default();
     let mut props = PhysicalDeviceProperties2::builder().push_next(&mut indexing);
     unsafe {
@@ -512,15 +912,25 @@ fn log_indexing(instance: &Instance, physical_device: PhysicalDevice) {
     info!("Max indexing textures {}", indexing.max_descriptor_set_update_after_bind_sampled_images);
     #[rustfmt::skip]
     info!("Max indexing samplers {}", indexing.max_descriptor_set_update_after_bind_samplers);
+    // bindless arrays are visible to all stages, so per stage limits apply too
+    [
+        indexing.max_descriptor_set_update_after_bind_sampled_images,
+        indexing.max_descriptor_set_update_after_bind_samplers,
+        indexing.max_per_stage_descriptor_update_after_bind_sampled_images,
+        indexing.max_per_stage_descriptor_update_after_bind_samplers,
+    ]
+    .into_iter()
+    .min()
+    .unwrap_or(0)
 }
 
 unsafe fn find_physical_device(
     instance: &Instance,
     surface: vk::SurfaceKHR,
-) -> (QueueFamilyIndex, vk::PhysicalDevice) {
+) -> Result<(QueueFamilyIndex, vk::PhysicalDevice), GraphicsError> {
     let physical_devices = instance
         .enumerate_physical_devices()
-        .expect("physical devices must be got");
+        .map_err(vulkan_error)?;
     for physical_device in physical_devices {
         let properties = instance.get_physical_device_properties(physical_device);
         if let Some(queues) = QueueFamilyIndex::find(instance, physical_device, surface) {
@@ -533,17 +943,35 @@ unsafe fn find_physical_device(
                 continue;
             }
             info!("Uses physical device {}", properties.device_name);
+            let api = properties.api_version;
+            set_crash_context(
+                "gpu",
+                format!(
+                    "{} vendor={:#x} driver={:#x} api={}.{}.{}",
+                    properties.device_name,
+                    properties.vendor_id,
+                    properties.driver_version,
+                    vk::version_major(api),
+                    vk::version_minor(api),
+                    vk::version_patch(api)
+                ),
+            );
             info!("Uses queues {queues:?}");
-            log_indexing(instance, physical_device);
-            return (queues, physical_device);
+            return Ok((queues, physical_device));
         } else {
             info!("Skips physical device {}", properties.device_name);
         }
     }
-    panic!("unable to find physical device");
+    Err(GraphicsError::Vulkan(
+        "unable to find suitable physical device".to_string(),
+    ))
 }
 
-#[derive(Copy, Clone, Default)]
+fn vulkan_error(error: vk::ErrorCode) -> GraphicsError {
+    GraphicsError::Vulkan(error.to_string())
+}
+
+#[derive(Copy, Clone, Default, PartialEq)]
 struct QueueIndex {
     family: u32,
     queue: u32,
@@ -566,11 +994,12 @@ pub struct QueueFamilyIndex {
     graphics: QueueIndex,
     present: QueueIndex,
     loading: QueueIndex,
+    compute: QueueIndex,
 }
 
 impl QueueFamilyIndex {
     fn indices(&self) -> Vec<QueueIndex> {
-        vec![self.graphics, self.present, self.loading]
+        vec![self.graphics, self.present, self.loading, self.compute]
     }
 
     unsafe fn find(
@@ -587,16 +1016,32 @@ impl QueueFamilyIndex {
                 family.queue_flags, family.queue_count
             );
         }
+        // dedicated compute family runs dispatches in parallel with graphics on most devices,
+        // buffers accessed by both families are shared concurrently, see `buffer_families`
+        let dedicated_compute = families
+            .iter()
+            .position(|family| {
+                family.queue_flags.contains(vk::QueueFlags::COMPUTE)
+                    && !family.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+            })
+            .map(|family| QueueIndex::new(family as u32, 0));
         let mut families = families.into_iter();
         let family = families.next().expect("first queue family must exist");
         if family.queue_flags.contains(vk::QueueFlags::GRAPHICS)
             && instance.get_physical_device_surface_support_khr(device, 0, surface) == Ok(true)
         {
+            // otherwise async compute uses separate queue of graphics family if available
+            let compute = match dedicated_compute {
+                Some(compute) => compute,
+                None if family.queue_count > 2 => QueueIndex::new(0, 2),
+                None => QueueIndex::new(0, 0),
+            };
             if family.queue_count > 1 {
                 return Some(QueueFamilyIndex {
                     graphics: QueueIndex::new(0, 0),
                     present: QueueIndex::new(0, 0),
                     loading: QueueIndex::new(0, 1),
+                    compute,
                 });
             } else {
                 let family = families.next().expect("second queue family must exist");
@@ -605,6 +1050,7 @@ impl QueueFamilyIndex {
                         graphics: QueueIndex::new(0, 0),
                         present: QueueIndex::new(0, 0),
                         loading: QueueIndex::new(1, 0),
+                        compute,
                     });
                 }
             }
@@ -613,6 +1059,10 @@ impl QueueFamilyIndex {
     }
 }
 
+/// Number of frames recorded by CPU while previous ones are processed by GPU.
+///
+/// Command buffers, storages, uniforms and meshes are duplicated per frame in flight
+/// regardless of swapchain images count, frame resources are reused once its fence is waited.
 const FRAMES_PROCESSING_CONCURRENCY: usize = 2;
 
 struct Sync {
@@ -668,10 +1118,6 @@ impl Sync {
     // }
 }
 
-// if cfg!(target_os = "macos") {
-// Required by Vulkan SDK on macOS since 1.3.216.
-// NOTE: deprecated provisional feature
-// extensions.push(vk::KHR_PORTABILITY_SUBSET_EXTENSION.name.as_ptr());
 const DEVICE_EXTENSIONS: &[vk::ExtensionName] = &[
     vk::KHR_SWAPCHAIN_EXTENSION.name,
     vk::KHR_MAINTENANCE3_EXTENSION.name,
@@ -688,16 +1134,17 @@ pub struct Swapchain {
 
 impl Swapchain {
     unsafe fn create(
-        window: &Window,
+        window: &dyn VulkanWindow,
         instance: &Instance,
         device: &Device,
         physical_device: vk::PhysicalDevice,
         index: QueueFamilyIndex,
         surface: vk::SurfaceKHR,
         present_mode: vk::PresentModeKHR,
+        srgb: bool,
     ) -> Self {
         let support = SwapchainSupport::get(instance, surface, physical_device);
-        let surface_format = support.get_swapchain_surface_format();
+        let surface_format = support.get_swapchain_surface_format(srgb);
         let present_mode = support.get_swapchain_present_mode(present_mode);
         let extent = support.get_swapchain_extent(window);
         let format = surface_format.format;
@@ -707,6 +1154,9 @@ impl Swapchain {
         {
             image_count = support.capabilities.max_image_count;
         }
+        // transfer usage allows to present images produced without graphics pipeline
+        let usage = vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | (support.capabilities.supported_usage_flags & vk::ImageUsageFlags::TRANSFER_DST);
         let mut queue_family_indices = vec![];
         let image_sharing_mode = if index.graphics.family != index.present.family {
             queue_family_indices.push(index.graphics.family);
@@ -722,7 +1172,7 @@ impl Swapchain {
             .image_color_space(surface_format.color_space)
             .image_extent(extent)
             .image_array_layers(1)
-            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+            .image_usage(usage)
             .image_sharing_mode(image_sharing_mode)
             .queue_family_indices(&queue_family_indices)
             .pre_transform(support.capabilities.current_transform)
@@ -741,6 +1191,10 @@ impl Swapchain {
             .map(|image| create_image_view(device, *image, format))
             .collect();
         info!("Creates swap chain mode={present_mode:?} format={format:?} extent={extent:?} images={} handle={handle:?}", images.len());
+        set_crash_context(
+            "swapchain",
+            format!("mode={present_mode:?} format={format:?} extent={extent:?}"),
+        );
         Swapchain {
             format,
             extent,
@@ -784,78 +1238,131 @@ impl SwapchainSupport {
         }
     }
 
-    fn get_swapchain_surface_format(&self) -> vk::SurfaceFormatKHR {
-        self.formats
-            .iter()
-            .cloned()
-            .find(|surface| {
-                surface.format == vk::Format::R8G8B8A8_UNORM
+    /// Returns SRGB format if requested, so hardware encodes linear colors on write.
+    fn get_swapchain_surface_format(&self, srgb: bool) -> vk::SurfaceFormatKHR {
+        let preferred: &[vk::Format] = match srgb {
+            true => &[vk::Format::R8G8B8A8_SRGB, vk::Format::B8G8R8A8_SRGB],
+            false => &[vk::Format::R8G8B8A8_UNORM],
+        };
+        let format = preferred.iter().find_map(|format| {
+            self.formats.iter().cloned().find(|surface| {
+                surface.format == *format
                     && surface.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
             })
-            .unwrap_or_else(|| self.formats[0])
+        });
+        if srgb && format.is_none() {
+            warn!("SRGB swapchain format is not supported, blending happens in gamma space");
+        }
+        format.unwrap_or_else(|| self.formats[0])
     }
 
+    /// Returns preferred present mode or fallback without tearing, FIFO is always supported.
     fn get_swapchain_present_mode(&self, preferred: vk::PresentModeKHR) -> vk::PresentModeKHR {
-        self.present_modes
+        let fallbacks: &[vk::PresentModeKHR] = match preferred {
+            vk::PresentModeKHR::IMMEDIATE => &[vk::PresentModeKHR::MAILBOX],
+            _ => &[],
+        };
+        let mode = [preferred]
             .iter()
+            .chain(fallbacks)
             .cloned()
-            .find(|mode| *mode == preferred)
-            .unwrap_or(vk::PresentModeKHR::IMMEDIATE)
+            .find(|mode| self.present_modes.contains(mode))
+            .unwrap_or(vk::PresentModeKHR::FIFO);
+        if mode != preferred {
+            warn!("Present mode {preferred:?} is not supported, falls back to {mode:?}");
+        }
+        mode
     }
 
-    fn get_swapchain_extent(&self, window: &Window) -> vk::Extent2D {
-        if self.capabilities.current_extent.width != u32::MAX {
-            self.capabilities.current_extent
-        } else {
-            let (width, height) = window.vulkan_drawable_size();
-            let clamp = |min: u32, max: u32, v: u32| min.max(max.min(v));
-            let width = clamp(
-                self.capabilities.min_image_extent.width,
-                self.capabilities.max_image_extent.width,
-                width,
-            );
-            let height = clamp(
-                self.capabilities.min_image_extent.height,
-                self.capabilities.max_image_extent.height,
-                height,
-            );
-            vk::Extent2D::builder().width(width).height(height).build()
-        }
+    fn get_swapchain_extent(&self, window: &dyn VulkanWindow) -> vk::Extent2D {
+        choose_swapchain_extent(&self.capabilities, window.drawable_size())
     }
 }
 
-unsafe fn create_shader_module(device: &Device, bytecode: &[u8]) -> vk::ShaderModule {
-    let bytecode = Bytecode::new(bytecode).unwrap();
+/// Returns surface extent, or drawable size in pixels clamped by surface limits if surface
+/// size is determined by swapchain, so high DPI displays get swapchain in pixels not points.
+fn choose_swapchain_extent(
+    capabilities: &vk::SurfaceCapabilitiesKHR,
+    drawable: (u32, u32),
+) -> vk::Extent2D {
+    if capabilities.current_extent.width != u32::MAX {
+        return capabilities.current_extent;
+    }
+    let (width, height) = drawable;
+    let (min, max) = (capabilities.min_image_extent, capabilities.max_image_extent);
+    vk::Extent2D::builder()
+        .width(width.clamp(min.width, max.width))
+        .height(height.clamp(min.height, max.height))
+        .build()
+}
+
+unsafe fn create_shader_module(
+    device: &Device,
+    bytecode: &[u8],
+) -> Result<vk::ShaderModule, ProgramError> {
+    let bytecode = Bytecode::new(bytecode)
+        .map_err(|error| ProgramError::Shader(format!("invalid SPIR-V bytecode, {error:?}")))?;
     let info = vk::ShaderModuleCreateInfo::builder()
         .code_size(bytecode.code_size())
         .code(bytecode.code());
-    device
-        .create_shader_module(&info, None)
-        .expect("shader module must be created")
+    let module = device.create_shader_module(&info, None)?;
+    Ok(module)
 }
 
-unsafe fn create_render_pass(device: &Device, swapchain: &Swapchain) -> vk::RenderPass {
-    let color_attachment = vk::AttachmentDescription::builder()
-        .format(swapchain.format)
-        .samples(vk::SampleCountFlags::_1)
-        .load_op(vk::AttachmentLoadOp::CLEAR)
-        .store_op(vk::AttachmentStoreOp::STORE)
-        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
-        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
-        .initial_layout(vk::ImageLayout::UNDEFINED)
-        .final_layout(vk::ImageLayout::PRESENT_SRC_KHR);
+unsafe fn create_render_pass(
+    device: &Device,
+    swapchain: &Swapchain,
+    depth: &DepthBuffer,
+    samples: vk::SampleCountFlags,
+) -> vk::RenderPass {
+    let attachments = create_color_attachments(
+        swapchain.format,
+        depth.format,
+        samples,
+        vk::ImageLayout::PRESENT_SRC_KHR,
+    );
     let color_attachment_ref = vk::AttachmentReference::builder()
         .attachment(0)
         .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+    let depth_attachment_ref = vk::AttachmentReference::builder()
+        .attachment(1)
+        .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+    let resolve_attachment_ref = vk::AttachmentReference::builder()
+        .attachment(2)
+        .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
     let color_attachments = &[color_attachment_ref];
-    let subpass = vk::SubpassDescription::builder()
+    let resolve_attachments = &[resolve_attachment_ref];
+    let mut subpass = vk::SubpassDescription::builder()
         .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
-        .color_attachments(color_attachments);
-    let attachments = &[color_attachment];
+        .color_attachments(color_attachments)
+        .depth_stencil_attachment(&depth_attachment_ref);
+    if samples != vk::SampleCountFlags::_1 {
+        subpass = subpass.resolve_attachments(resolve_attachments);
+    }
+    // depth buffer is shared between frames in flight,
+    // so next frame must wait previous depth writes
+    let dependency = vk::SubpassDependency::builder()
+        .src_subpass(vk::SUBPASS_EXTERNAL)
+        .dst_subpass(0)
+        .src_stage_mask(
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+        )
+        .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+        .dst_stage_mask(
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+        )
+        .dst_access_mask(
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+        );
     let subpasses = &[subpass];
+    let dependencies = &[dependency];
     let info = vk::RenderPassCreateInfo::builder()
-        .attachments(attachments)
-        .subpasses(subpasses);
+        .attachments(&attachments)
+        .subpasses(subpasses)
+        .dependencies(dependencies);
     info!("Creates render pass");
     device
         .create_render_pass(&info, None)
@@ -866,16 +1373,24 @@ unsafe fn create_pipeline(
     device: &Device,
     swapchain: &Swapchain,
     render_pass: vk::RenderPass,
+    samples: vk::SampleCountFlags,
     descriptor_layouts: Vec<vk::DescriptorSetLayout>,
     vert: &[u8],
     frag: &[u8],
     push_constants: Vec<vk::PushConstantRange>,
     vertex_input: PipelineVertexInputStateCreateInfo,
-) -> (vk::PipelineLayout, vk::Pipeline) {
+    options: ProgramOptions,
+) -> Result<(vk::PipelineLayout, vk::Pipeline), ProgramError> {
     debug!("Compiles vert shader");
-    let vert_shader_module = create_shader_module(device, vert);
+    let vert_shader_module = create_shader_module(device, vert)?;
     debug!("Compiles frag shader");
-    let frag_shader_module = create_shader_module(device, frag);
+    let frag_shader_module = match create_shader_module(device, frag) {
+        Ok(module) => module,
+        Err(error) => {
+            device.destroy_shader_module(vert_shader_module, None);
+            return Err(error);
+        }
+    };
     let vert_stage = vk::PipelineShaderStageCreateInfo::builder()
         .stage(vk::ShaderStageFlags::VERTEX)
         .module(vert_shader_module)
@@ -902,6 +1417,10 @@ unsafe fn create_pipeline(
     let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
         .viewports(viewports)
         .scissors(scissors);
+    // viewport is set on render pass begin, so same pipeline can render to swapchain
+    // and to offscreen targets of different size
+    let dynamic_states = &[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+    let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder().dynamic_states(dynamic_states);
     let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
         .depth_clamp_enable(false)
         .rasterizer_discard_enable(false)
@@ -912,9 +1431,15 @@ unsafe fn create_pipeline(
         .depth_bias_enable(false);
     let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
         .sample_shading_enable(false)
-        .rasterization_samples(vk::SampleCountFlags::_1);
+        .rasterization_samples(samples);
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(options.depth_test)
+        .depth_write_enable(options.depth_write)
+        .depth_compare_op(vk::CompareOp::LESS_OR_EQUAL)
+        .depth_bounds_test_enable(false)
+        .stencil_test_enable(false);
     let attachment = vk::PipelineColorBlendAttachmentState::builder()
-        .color_write_mask(vk::ColorComponentFlags::all())
+        .color_write_mask(options.color_write_mask)
         .blend_enable(true)
         .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
         .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
@@ -922,7 +1447,14 @@ unsafe fn create_pipeline(
         .src_alpha_blend_factor(vk::BlendFactor::ONE)
         .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
         .alpha_blend_op(vk::BlendOp::ADD);
-    let attachments = &[attachment];
+    // extra outputs are overwritten, so values like object IDs are never blended
+    let overwrite = vk::PipelineColorBlendAttachmentState::builder()
+        .color_write_mask(options.color_write_mask)
+        .blend_enable(false)
+        .build();
+    let mut attachments = vec![attachment.build()];
+    attachments.resize(options.color_attachments.max(1), overwrite);
+    let attachments = attachments.as_slice();
     let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
         .logic_op_enable(false)
         .logic_op(vk::LogicOp::COPY)
@@ -934,9 +1466,14 @@ unsafe fn create_pipeline(
         layout_info = layout_info.push_constant_ranges(push_constant_ranges);
     }
     debug!("Creates pipeline layout");
-    let pipeline_layout = device
-        .create_pipeline_layout(&layout_info, None)
-        .expect("pipeline layout must be created");
+    let pipeline_layout = match device.create_pipeline_layout(&layout_info, None) {
+        Ok(layout) => layout,
+        Err(error) => {
+            device.destroy_shader_module(vert_shader_module, None);
+            device.destroy_shader_module(frag_shader_module, None);
+            return Err(error.into());
+        }
+    };
     let stages = &[vert_stage, frag_stage];
     let info = vk::GraphicsPipelineCreateInfo::builder()
         .stages(stages)
@@ -945,34 +1482,60 @@ unsafe fn create_pipeline(
         .viewport_state(&viewport_state)
         .rasterization_state(&rasterization_state)
         .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
         .color_blend_state(&color_blend_state)
+        .dynamic_state(&dynamic_state)
         .layout(pipeline_layout)
         .render_pass(render_pass)
         .subpass(0);
     debug!("Creates graphics pipeline");
-    let pipeline = device
-        .create_graphics_pipelines(vk::PipelineCache::null(), &[info], None)
-        .expect("graphics pipeline must be created")
-        .0[0];
+    let pipelines = device.create_graphics_pipelines(vk::PipelineCache::null(), &[info], None);
     device.destroy_shader_module(vert_shader_module, None);
     device.destroy_shader_module(frag_shader_module, None);
-    (pipeline_layout, pipeline)
+    match pipelines {
+        Ok((pipelines, _)) => Ok((pipeline_layout, pipelines[0])),
+        Err(error) => {
+            device.destroy_pipeline_layout(pipeline_layout, None);
+            Err(error.into())
+        }
+    }
+}
+
+unsafe fn set_viewport(device: &Device, commands: vk::CommandBuffer, extent: vk::Extent2D) {
+    let viewport = vk::Viewport::builder()
+        .x(0.0)
+        .y(0.0)
+        .width(extent.width as f32)
+        .height(extent.height as f32)
+        .min_depth(0.0)
+        .max_depth(1.0);
+    let scissor = vk::Rect2D::builder()
+        .offset(vk::Offset2D { x: 0, y: 0 })
+        .extent(extent);
+    device.cmd_set_viewport(commands, 0, &[viewport]);
+    device.cmd_set_scissor(commands, 0, &[scissor]);
 }
 
 unsafe fn create_framebuffers(
     device: &Device,
     render_pass: vk::RenderPass,
     swapchain: &Swapchain,
+    depth: &DepthBuffer,
+    multisample: Option<&MultisampleBuffer>,
 ) -> Vec<vk::Framebuffer> {
     info!("Creates {} frame buffers", swapchain.views.len());
     swapchain
         .views
         .iter()
         .map(|image| {
-            let attachments = &[*image];
+            // multisampled image is rendered to and resolved to swapchain image
+            let attachments = match multisample {
+                Some(multisample) => vec![multisample.view, depth.view, *image],
+                None => vec![*image, depth.view],
+            };
             let create_info = vk::FramebufferCreateInfo::builder()
                 .render_pass(render_pass)
-                .attachments(attachments)
+                .attachments(&attachments)
                 .width(swapchain.extent.width)
                 .height(swapchain.extent.height)
                 .layers(1);
@@ -1023,7 +1586,7 @@ impl MemoryBuffer {
     pub fn destroy(&self, device: &Device) {
         unsafe {
             device.destroy_buffer(self.handle, None);
-            device.free_memory(self.memory, None);
+            free_memory(device, self.memory);
         }
     }
 }
@@ -1031,18 +1594,20 @@ impl MemoryBuffer {
 pub unsafe fn create_buffers(
     usage: vk::BufferUsageFlags,
     device: &Device,
-    swapchain: usize,
+    frames: usize,
     physical_device_memory: vk::PhysicalDeviceMemoryProperties,
     size: usize,
+    families: &[u32],
 ) -> Vec<MemoryBuffer> {
     let mut buffers = vec![];
-    for _ in 0..swapchain {
+    for _ in 0..frames {
         let buffer = create_buffer(
             device,
             size as u64,
             usage,
             vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
             physical_device_memory,
+            families,
         );
         buffers.push(buffer);
     }
@@ -1109,6 +1674,7 @@ unsafe fn create_descriptors(
     let descriptor_sets = device
         .allocate_descriptor_sets(&info)
         .expect("descriptor sets must be created");
+    record_descriptor_sets(descriptor_sets.len());
     debug!("Creates variables {:?}", descriptor_sets);
     descriptor_sets
 }
@@ -1137,13 +1703,9 @@ unsafe fn get_memory_type_index(
         .expect("suitable memory type must be found")
 }
 
-unsafe fn create_command_pools(
-    device: &Device,
-    queue: QueueIndex,
-    swapchain: &Swapchain,
-) -> Vec<vk::CommandPool> {
+unsafe fn create_command_pools(device: &Device, queue: QueueIndex) -> Vec<vk::CommandPool> {
     let mut command_pools = vec![];
-    for _ in 0..swapchain.images.len() {
+    for _ in 0..FRAMES_PROCESSING_CONCURRENCY {
         let command_pool = create_command_pool(device, queue);
         command_pools.push(command_pool);
     }
@@ -1193,69 +1755,6 @@ unsafe fn submit_commands(
     device.free_command_buffers(pool, &[buffer]);
 }
 
-// unsafe fn create_pixel_perfect_sampler(device: &Device) -> vk::Sampler {
-//     let info = vk::SamplerCreateInfo::builder()
-//         .mag_filter(vk::Filter::NEAREST)
-//         .min_filter(vk::Filter::NEAREST)
-//         .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-//         .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-//         .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-//         .anisotropy_enable(false)
-//         .max_anisotropy(16.0)
-//         .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
-//         .unnormalized_coordinates(false)
-//         .compare_enable(false)
-//         .compare_op(vk::CompareOp::ALWAYS)
-//         .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
-//         .min_lod(0.0)
-//         .max_lod(0.0)
-//         .mip_lod_bias(0.0);
-//     device
-//         .create_sampler(&info, None)
-//         .expect("sampler must be created")
-// }
-
-// unsafe fn create_smooth_sampler(device: &Device) -> vk::Sampler {
-//     let info = vk::SamplerCreateInfo::builder()
-//         .mag_filter(vk::Filter::LINEAR)
-//         .min_filter(vk::Filter::LINEAR)
-//         .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-//         .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-//         .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
-//         .anisotropy_enable(true)
-//         .max_anisotropy(16.0)
-//         .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
-//         .unnormalized_coordinates(false)
-//         .compare_enable(false)
-//         .compare_op(vk::CompareOp::ALWAYS)
-//         .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
-//         .min_lod(0.0)
-//         .max_lod(2.0)
-//         .mip_lod_bias(0.0);
-//     device
-//         .create_sampler(&info, None)
-//         .expect("sampler must be created")
-// }
-
-// unsafe fn create_sampler(device: &Device) -> vk::Sampler {
-//     let info = vk::SamplerCreateInfo::builder()
-//         .mag_filter(vk::Filter::LINEAR)
-//         .min_filter(vk::Filter::LINEAR)
-//         .address_mode_u(vk::SamplerAddressMode::REPEAT)
-//         .address_mode_v(vk::SamplerAddressMode::REPEAT)
-//         .address_mode_w(vk::SamplerAddressMode::REPEAT)
-//         .anisotropy_enable(true)
-//         .max_anisotropy(16.0)
-//         .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
-//         .unnormalized_coordinates(false)
-//         .compare_enable(false)
-//         .compare_op(vk::CompareOp::ALWAYS)
-//         .mipmap_mode(vk::SamplerMipmapMode::LINEAR);
-//     device
-//         .create_sampler(&info, None)
-//         .expect("sampler must be created")
-// }
-
 unsafe fn create_image_view(
     device: &Device,
     image: vk::Image,
@@ -1276,3 +1775,34 @@ unsafe fn create_image_view(
         .create_image_view(&info, None)
         .expect("image view must be created")
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::choose_swapchain_extent;
+    use vulkanalia::vk;
+
+    #[test]
+    pub fn test_high_dpi_swapchain_extent() {
+        let extent = |width, height| vk::Extent2D { width, height };
+        let mut capabilities = vk::SurfaceCapabilitiesKHR {
+            current_extent: extent(u32::MAX, u32::MAX),
+            min_image_extent: extent(1, 1),
+            max_image_extent: extent(4096, 4096),
+            ..Default::default()
+        };
+        // Retina window of 1440x900 points
+        assert_eq!(
+            choose_swapchain_extent(&capabilities, (2880, 1800)),
+            extent(2880, 1800)
+        );
+        assert_eq!(
+            choose_swapchain_extent(&capabilities, (5120, 2880)),
+            extent(4096, 2880)
+        );
+        capabilities.current_extent = extent(2880, 1800);
+        assert_eq!(
+            choose_swapchain_extent(&capabilities, (1440, 900)),
+            extent(2880, 1800)
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use crate::vulkan::memory::{allocate_memory, free_memory, MemoryKind};
+use crate::vulkan::{create_image_view, get_memory_type_index};
+use log::info;
+use vulkanalia::vk::{DeviceV1_0, HasBuilder, InstanceV1_0};
+use vulkanalia::{vk, Device, Instance};
+
+/// Represents multisampled color attachment, resolved to single sampled image
+/// (swapchain image or target texture) at the end of render pass.
+pub struct MultisampleBuffer {
+    pub image: vk::Image,
+    pub memory: vk::DeviceMemory,
+    pub view: vk::ImageView,
+}
+
+/// Returns highest sample count supported by device for both color and depth attachments,
+/// but not greater than requested one.
+pub unsafe fn get_sample_count(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+    requested: u32,
+) -> vk::SampleCountFlags {
+    let limits = instance
+        .get_physical_device_properties(physical_device)
+        .limits;
+    let supported = limits.framebuffer_color_sample_counts & limits.framebuffer_depth_sample_counts;
+    let samples = [
+        vk::SampleCountFlags::_64,
+        vk::SampleCountFlags::_32,
+        vk::SampleCountFlags::_16,
+        vk::SampleCountFlags::_8,
+        vk::SampleCountFlags::_4,
+        vk::SampleCountFlags::_2,
+    ]
+    .into_iter()
+    .find(|samples| samples.bits() <= requested && supported.contains(*samples))
+    .unwrap_or(vk::SampleCountFlags::_1);
+    if samples.bits() != requested.max(1) {
+        info!("Uses {samples:?} samples instead of requested {requested}, supported {supported:?}");
+    }
+    samples
+}
+
+impl MultisampleBuffer {
+    /// Returns multisampled buffer or `None` if multisampling is disabled (single sample).
+    pub unsafe fn create(
+        instance: &Instance,
+        device: &Device,
+        physical_device: vk::PhysicalDevice,
+        format: vk::Format,
+        extent: vk::Extent2D,
+        samples: vk::SampleCountFlags,
+    ) -> Option<Self> {
+        if samples == vk::SampleCountFlags::_1 {
+            return None;
+        }
+        let info = vk::ImageCreateInfo::builder()
+            .image_type(vk::ImageType::_2D)
+            .extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+            .mip_levels(1)
+            .array_layers(1)
+            .format(format)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .usage(
+                vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+            )
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .samples(samples);
+        let image = device
+            .create_image(&info, None)
+            .expect("multisample image must be created");
+        let requirements = device.get_image_memory_requirements(image);
+        let physical_device_memory =
+            instance.get_physical_device_memory_properties(physical_device);
+        let memory_type_index = get_memory_type_index(
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            requirements,
+            physical_device_memory,
+        );
+        let info = vk::MemoryAllocateInfo::builder()
+            .allocation_size(requirements.size)
+            .memory_type_index(memory_type_index);
+        let memory = allocate_memory(device, &info, MemoryKind::Image)
+            .expect("multisample image memory must be allocated");
+        device
+            .bind_image_memory(image, memory, 0)
+            .expect("multisample image memory must bound");
+        let view = create_image_view(device, image, format);
+        info!("Creates multisample buffer samples={samples:?} extent={extent:?}");
+        Some(Self {
+            image,
+            memory,
+            view,
+        })
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        device.destroy_image_view(self.view, None);
+        device.destroy_image(self.image, None);
+        free_memory(device, self.memory);
+    }
+}
+
+/// Returns attachments of color pass: color and depth attachments with given sample count
+/// and, if multisampled, single sampled resolve attachment at index 2 ending in final layout.
+pub fn create_color_attachments(
+    format: vk::Format,
+    depth_format: vk::Format,
+    samples: vk::SampleCountFlags,
+    final_layout: vk::ImageLayout,
+) -> Vec<vk::AttachmentDescription> {
+    let multisampled = samples != vk::SampleCountFlags::_1;
+    let color_attachment = vk::AttachmentDescription::builder()
+        .format(format)
+        .samples(samples)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(if multisampled {
+            vk::AttachmentStoreOp::DONT_CARE
+        } else {
+            vk::AttachmentStoreOp::STORE
+        })
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(if multisampled {
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+        } else {
+            final_layout
+        })
+        .build();
+    let depth_attachment = vk::AttachmentDescription::builder()
+        .format(depth_format)
+        .samples(samples)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+        .build();
+    let mut attachments = vec![color_attachment, depth_attachment];
+    if multisampled {
+        let resolve_attachment = vk::AttachmentDescription::builder()
+            .format(format)
+            .samples(vk::SampleCountFlags::_1)
+            .load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .store_op(vk::AttachmentStoreOp::STORE)
+            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .final_layout(final_layout)
+            .build();
+        attachments.push(resolve_attachment);
+    }
+    attachments
+}
@@ -1,22 +1,101 @@
+use crate::vulkan::stats::{record_draw, record_textures};
 use crate::vulkan::{create_pipeline, Swapchain};
-use crate::{Mesh, Shader, Storage, Textures, Uniform, Variable, Vertices};
+use crate::{Indices, Mesh, Shader, Storage, Textures, Uniform, Variable, Vertices};
 use log::info;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 use vulkanalia::vk::{DeviceV1_0, Handle, HasBuilder, PipelineVertexInputStateCreateInfo};
 use vulkanalia::{vk, Device};
 
+#[derive(Debug)]
+pub enum ProgramError {
+    Shader(String),
+    Vulkan(vk::ErrorCode),
+}
+
+impl From<vk::ErrorCode> for ProgramError {
+    fn from(value: vk::ErrorCode) -> Self {
+        ProgramError::Vulkan(value)
+    }
+}
+
 pub struct Program {
-    name: String,
     pub device: Device,
-    pipeline_layout: vk::PipelineLayout,
-    pipeline: vk::Pipeline,
-    pub(crate) vert: Shader,
-    pub(crate) frag: Shader,
     pub sampler: vk::Sampler,
+    shared: Arc<ProgramShared>,
+}
+
+/// Part of program updated by Vulkan registry, e.g. command buffer of render target
+/// or pipeline recreated after swapchain change.
+///
+/// Registry holds weak reference, so dropped program is unregistered instead of
+/// being referenced by dangling pointer, and program can be moved between threads.
+pub(crate) struct ProgramShared {
+    name: String,
+    device: Device,
+    commands: AtomicUsize,
+    frame: AtomicUsize,
+    pipeline: AtomicU64,
+    pipeline_layout: AtomicU64,
+    source: Mutex<ProgramSource>,
+}
+
+/// Describes how to recreate program pipeline.
+struct ProgramSource {
+    vert: Shader,
+    frag: Shader,
     push_constants: Vec<vk::PushConstantRange>,
     layouts: Vec<vk::DescriptorSetLayout>,
-    current_commands: vk::CommandBuffer,
-    current_frame: usize,
     vertex_input_state: PipelineVertexInputStateCreateInfo,
+    options: ProgramOptions,
+    /// Render pass of multi target program is created for, used instead of swapchain one.
+    target_render_pass: Option<vk::RenderPass>,
+}
+
+// vertex input state refers to static descriptions only, see `VertexLayout::input_state`
+unsafe impl Send for ProgramSource {}
+
+/// Describes fixed function state of program pipeline.
+#[derive(Clone, Copy, Debug)]
+pub struct ProgramOptions {
+    pub depth_test: bool,
+    pub depth_write: bool,
+    pub color_write_mask: vk::ColorComponentFlags,
+    pub color_attachments: usize,
+}
+
+impl Default for ProgramOptions {
+    fn default() -> Self {
+        Self {
+            depth_test: false,
+            depth_write: false,
+            color_write_mask: vk::ColorComponentFlags::all(),
+            color_attachments: 1,
+        }
+    }
+}
+
+impl ProgramOptions {
+    /// Enables depth test and write, elements with lower depth value overlap others.
+    /// Transparent elements should be sorted back-to-front and drawn without depth write.
+    pub fn depth(mut self, test: bool, write: bool) -> Self {
+        self.depth_test = test;
+        self.depth_write = write;
+        self
+    }
+
+    /// Limits color channels written by program, e.g. `ColorComponentFlags::A` to write
+    /// mask alpha only or single color channel to isolate it in debug view.
+    pub fn color_write_mask(mut self, mask: vk::ColorComponentFlags) -> Self {
+        self.color_write_mask = mask;
+        self
+    }
+
+    /// Sets number of color outputs of fragment shader, see [`crate::MultiTarget`].
+    pub fn color_attachments(mut self, count: usize) -> Self {
+        self.color_attachments = count.max(1);
+        self
+    }
 }
 
 pub fn range<T>() -> vk::PushConstantRange {
@@ -27,9 +106,82 @@ pub fn range<T>() -> vk::PushConstantRange {
         .build()
 }
 
+impl ProgramShared {
+    pub fn frame(&self) -> usize {
+        self.frame.load(Ordering::Relaxed)
+    }
+
+    pub fn set_frame(&self, frame: usize) {
+        self.frame.store(frame, Ordering::Relaxed);
+    }
+
+    pub fn commands(&self) -> vk::CommandBuffer {
+        vk::CommandBuffer::from_raw(self.commands.load(Ordering::Relaxed))
+    }
+
+    pub fn set_command_buffer(&self, commands: vk::CommandBuffer) {
+        self.commands.store(commands.as_raw(), Ordering::Relaxed);
+    }
+
+    pub fn pipeline(&self) -> vk::Pipeline {
+        vk::Pipeline::from_raw(self.pipeline.load(Ordering::Relaxed))
+    }
+
+    pub fn pipeline_layout(&self) -> vk::PipelineLayout {
+        vk::PipelineLayout::from_raw(self.pipeline_layout.load(Ordering::Relaxed))
+    }
+
+    /// Returns true if shader files changed since pipeline creation.
+    pub fn changed(&self) -> bool {
+        let source = self.source.lock().expect("program source must be lockable");
+        source.frag.changed() || source.vert.changed()
+    }
+
+    pub unsafe fn destroy(&self) {
+        info!("Destroy program: {} {:?}", self.name, self.pipeline());
+        let device = &self.device;
+        device.destroy_pipeline(self.pipeline(), None);
+        device.destroy_pipeline_layout(self.pipeline_layout(), None);
+    }
+
+    /// Recreates pipeline from renewed shaders, keeps current pipeline if new one fails.
+    pub unsafe fn recreate(
+        &self,
+        swapchain: &Swapchain,
+        render_pass: vk::RenderPass,
+        samples: vk::SampleCountFlags,
+    ) -> Result<(), ProgramError> {
+        info!("Renew program: {} {:?}", self.name, self.pipeline());
+        let mut source = self.source.lock().expect("program source must be lockable");
+        let (render_pass, samples) = match source.target_render_pass {
+            Some(render_pass) => (render_pass, vk::SampleCountFlags::_1),
+            None => (render_pass, samples),
+        };
+        source.vert = source.vert.renew();
+        source.frag = source.frag.renew();
+        let (pipeline_layout, pipeline) = create_pipeline(
+            &self.device,
+            &swapchain,
+            render_pass,
+            samples,
+            source.layouts.clone(),
+            &source.vert.read()?,
+            &source.frag.read()?,
+            source.push_constants.clone(),
+            source.vertex_input_state.clone(),
+            source.options,
+        )?;
+        self.destroy();
+        self.pipeline.store(pipeline.as_raw(), Ordering::Relaxed);
+        self.pipeline_layout
+            .store(pipeline_layout.as_raw(), Ordering::Relaxed);
+        Ok(())
+    }
+}
+
 impl Program {
     pub fn frame(&self) -> usize {
-        self.current_frame
+        self.shared.frame()
     }
 
     pub unsafe fn create(
@@ -39,55 +191,88 @@ impl Program {
         // physical_device: vk::PhysicalDevice,
         swapchain: &Swapchain,
         render_pass: vk::RenderPass,
+        samples: vk::SampleCountFlags,
         mut vert: Shader,
         mut frag: Shader,
         push_constants: Vec<vk::PushConstantRange>,
         sampler: vk::Sampler,
         layouts: Vec<vk::DescriptorSetLayout>,
         vertex_input: Option<PipelineVertexInputStateCreateInfo>,
-    ) -> Self {
+        options: ProgramOptions,
+    ) -> Result<Self, ProgramError> {
         let vertex_input =
             vertex_input.unwrap_or(PipelineVertexInputStateCreateInfo::builder().build());
         let (pipeline_layout, pipeline) = create_pipeline(
             &device,
             &swapchain,
             render_pass,
+            samples,
             layouts.clone(),
-            &vert.read(),
-            &frag.read(),
+            &vert.read()?,
+            &frag.read()?,
             push_constants.clone(),
             vertex_input,
-        );
+            options,
+        )?;
         info!("Creates {name} {:?}", pipeline);
-        Self {
-            name: name.to_string(),
-            device: device.clone(),
-            pipeline_layout,
-            pipeline,
+        let source = ProgramSource {
             vert,
             frag,
-            sampler,
             push_constants,
-            current_commands: vk::CommandBuffer::null(),
-            current_frame: 0,
             layouts,
             vertex_input_state: vertex_input,
-        }
+            options,
+            target_render_pass: None,
+        };
+        let shared = ProgramShared {
+            name: name.to_string(),
+            device: device.clone(),
+            commands: AtomicUsize::new(vk::CommandBuffer::null().as_raw()),
+            frame: AtomicUsize::new(0),
+            pipeline: AtomicU64::new(pipeline.as_raw()),
+            pipeline_layout: AtomicU64::new(pipeline_layout.as_raw()),
+            source: Mutex::new(source),
+        };
+        Ok(Self {
+            device: device.clone(),
+            sampler,
+            shared: Arc::new(shared),
+        })
+    }
+
+    /// Returns weak reference to state updated by Vulkan registry.
+    pub(crate) fn shared(&self) -> Weak<ProgramShared> {
+        Arc::downgrade(&self.shared)
+    }
+
+    /// Makes program render to multi target, pipeline is recreated with target render pass.
+    pub(crate) fn set_target_render_pass(&self, render_pass: vk::RenderPass) {
+        let mut source = self
+            .shared
+            .source
+            .lock()
+            .expect("program source must be lockable");
+        source.target_render_pass = Some(render_pass);
     }
 
     pub fn commands(&self) -> vk::CommandBuffer {
-        if self.current_commands == vk::CommandBuffer::null() {
+        let commands = self.shared.commands();
+        if commands == vk::CommandBuffer::null() {
             panic!("program command buffer must be configured")
         }
-        self.current_commands
+        commands
+    }
+
+    fn current_commands(&self) -> vk::CommandBuffer {
+        self.shared.commands()
     }
 
     pub fn set_command_buffer(&mut self, commands: vk::CommandBuffer) {
-        self.current_commands = commands
+        self.shared.set_command_buffer(commands);
     }
 
-    pub fn set_chain(&mut self, chain: usize) {
-        self.current_frame = chain;
+    pub fn set_frame(&mut self, frame: usize) {
+        self.shared.set_frame(frame);
     }
 
     pub fn bind_pipeline(&mut self) {
@@ -95,20 +280,21 @@ impl Program {
             self.device.cmd_bind_pipeline(
                 self.commands(),
                 vk::PipelineBindPoint::GRAPHICS,
-                self.pipeline,
+                self.shared.pipeline(),
             );
         }
     }
 
     pub fn bind_variable(&self, variable: &Variable) {
-        self.bind_descriptor(variable.set, variable.descriptor(self.current_frame));
+        self.bind_descriptor(variable.set, variable.descriptor(self.frame()));
     }
 
     pub fn bind_uniform<T>(&self, variable: &Uniform<T>) {
-        self.bind_descriptor(variable.slot, variable.descriptor(self.current_frame));
+        self.bind_descriptor(variable.slot, variable.descriptor(self.frame()));
     }
 
     pub fn bind_textures(&self, variable: &Textures) {
+        record_textures(variable.len());
         self.bind_descriptor(variable.slot, variable.descriptor())
     }
 
@@ -117,7 +303,7 @@ impl Program {
             self.device.cmd_bind_descriptor_sets(
                 self.commands(),
                 vk::PipelineBindPoint::GRAPHICS,
-                self.pipeline_layout,
+                self.shared.pipeline_layout(),
                 index,
                 &[set],
                 &[],
@@ -126,13 +312,13 @@ impl Program {
     }
 
     pub fn push_constants<T>(&self, value: &T) {
-        let buf = self.current_commands;
+        let buf = self.current_commands();
         unsafe {
             let size = std::mem::size_of::<T>();
             let constants = std::slice::from_raw_parts(value as *const T as *const u8, size);
             self.device.cmd_push_constants(
                 buf,
-                self.pipeline_layout,
+                self.shared.pipeline_layout(),
                 vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
                 0,
                 constants,
@@ -141,54 +327,101 @@ impl Program {
     }
 
     pub unsafe fn destroy(&mut self) {
-        info!("Destroy program: {} {:?}", self.name, self.pipeline);
-        let device = &self.device;
-        device.destroy_pipeline(self.pipeline, None);
-        device.destroy_pipeline_layout(self.pipeline_layout, None);
-    }
-
-    pub unsafe fn recreate(&mut self, swapchain: &Swapchain, render_pass: vk::RenderPass) {
-        self.destroy();
-        info!("Renew program: {} {:?}", self.name, self.pipeline);
-        self.vert = self.vert.renew();
-        self.frag = self.frag.renew();
-        let (pipeline_layout, pipeline) = create_pipeline(
-            &self.device,
-            &swapchain,
-            render_pass,
-            self.layouts.clone(),
-            &self.vert.read(),
-            &self.frag.read(),
-            self.push_constants.clone(),
-            self.vertex_input_state.clone(),
-        );
-        self.pipeline = pipeline;
-        self.pipeline_layout = pipeline_layout;
+        self.shared.destroy();
     }
 
     pub fn draw(&self, vertex_count: usize, elements: usize) {
+        record_draw(vertex_count, elements);
         unsafe {
-            let buf = self.current_commands;
+            let buf = self.current_commands();
             self.device
                 .cmd_draw(buf, vertex_count as u32, elements as u32, 0, 0);
         }
     }
 
+    /// Binds storage of current frame as 32-bit index buffer for indexed draws.
+    pub fn bind_index_buffer(&self, indices: &Storage<u32>) {
+        unsafe {
+            self.device.cmd_bind_index_buffer(
+                self.current_commands(),
+                indices.buffers[self.frame()].handle,
+                0,
+                vk::IndexType::UINT32,
+            );
+        }
+    }
+
+    pub fn draw_indexed(&self, index_count: usize) {
+        self.draw_indexed_instanced(index_count, 1);
+    }
+
+    pub fn draw_indexed_instanced(&self, index_count: usize, instances: usize) {
+        record_draw(index_count, instances);
+        unsafe {
+            self.device.cmd_draw_indexed(
+                self.current_commands(),
+                index_count as u32,
+                instances as u32,
+                0,
+                0,
+                0,
+            );
+        }
+    }
+
+    /// Draws range of bound index buffer, e.g. returned by [`Mesh::add_polygon_indexed`].
+    pub fn draw_sub_indexed(&self, indices: Indices) {
+        record_draw(indices.len, 1);
+        unsafe {
+            self.device.cmd_draw_indexed(
+                self.current_commands(),
+                indices.len as u32,
+                1,
+                indices.ptr as u32,
+                0,
+                0,
+            );
+        }
+    }
+
+    /// Draws commands built in storage of current frame, e.g. by compute culling pass.
+    pub fn draw_indirect(&self, commands: &Storage<vk::DrawIndirectCommand>, draw_count: usize) {
+        self.draw_indirect_buffer(commands.buffers[self.frame()].handle, draw_count);
+    }
+
+    /// Draws commands built in buffer, e.g. of [`crate::DeviceStorage`] written by compute shader.
+    ///
+    /// Vertices and instances are not known on CPU, so only draw calls are recorded to stats.
+    pub fn draw_indirect_buffer(&self, buffer: vk::Buffer, draw_count: usize) {
+        record_draw(0, 0);
+        unsafe {
+            self.device.cmd_draw_indirect(
+                self.current_commands(),
+                buffer,
+                0,
+                draw_count as u32,
+                std::mem::size_of::<vk::DrawIndirectCommand>() as u32,
+            );
+        }
+    }
+
     pub fn bind_mesh(&self, mesh: &Mesh) {
         unsafe {
             self.device.cmd_bind_vertex_buffers(
-                self.current_commands,
+                self.current_commands(),
                 0,
-                &[mesh.buffers[self.current_frame].handle],
+                &[mesh.buffers[self.frame()].handle],
                 &[0],
             );
         }
     }
 
+    /// Draws range of bound mesh vertices, e.g. returned by [`Mesh::append`].
     pub fn draw_sub_mesh(&self, vertices: Vertices) {
+        record_draw(vertices.len, 1);
         unsafe {
             self.device.cmd_draw(
-                self.current_commands,
+                self.current_commands(),
                 vertices.len as u32,
                 1,
                 vertices.ptr as u32,
@@ -0,0 +1,78 @@
+use crate::vulkan::compute::ComputeSurface;
+use crate::vulkan::target::{MultiTarget, RenderTarget};
+use crate::vulkan::{MemoryBuffer, FRAMES_PROCESSING_CONCURRENCY};
+use crate::Texture;
+use log::debug;
+use std::collections::VecDeque;
+use vulkanalia::Device;
+
+/// Represents GPU resource which is no longer used by application,
+/// but may still be referenced by frames in flight.
+pub enum Retired {
+    Texture(Texture),
+    Buffer(MemoryBuffer),
+    RenderTarget(RenderTarget),
+    MultiTarget(MultiTarget),
+    ComputeSurface(ComputeSurface),
+}
+
+impl Retired {
+    fn destroy(&self, device: &Device) {
+        match self {
+            Retired::Texture(texture) => texture.destroy(device),
+            Retired::Buffer(buffer) => buffer.destroy(device),
+            Retired::RenderTarget(target) => unsafe { target.destroy() },
+            Retired::MultiTarget(target) => unsafe { target.destroy() },
+            Retired::ComputeSurface(surface) => unsafe { surface.destroy() },
+        }
+    }
+}
+
+/// Defers destruction of resources until all frames submitted before retirement complete.
+///
+/// Resource retired during frame N is destroyed when frame N + [`FRAMES_PROCESSING_CONCURRENCY`]
+/// begins, at this point fence of frame N is already waited.
+pub struct RetireQueue {
+    frame: u64,
+    pending: VecDeque<(u64, Retired)>,
+}
+
+impl RetireQueue {
+    pub fn new() -> Self {
+        Self {
+            frame: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn retire(&mut self, resource: Retired) {
+        self.pending.push_back((self.frame, resource));
+    }
+
+    /// Must be called once frame is submitted.
+    pub fn advance(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Destroys resources retired at least [`FRAMES_PROCESSING_CONCURRENCY`] frames ago,
+    /// must be called after fence of current frame is waited.
+    pub fn collect(&mut self, device: &Device) {
+        let frames = FRAMES_PROCESSING_CONCURRENCY as u64;
+        while let Some((retired, _)) = self.pending.front() {
+            if retired + frames > self.frame {
+                break;
+            }
+            if let Some((retired, resource)) = self.pending.pop_front() {
+                debug!("Destroys resource retired at frame {retired}");
+                resource.destroy(device);
+            }
+        }
+    }
+
+    /// Destroys all pending resources immediately, device must be idle.
+    pub fn destroy_all(&mut self, device: &Device) {
+        for (_, resource) in self.pending.drain(..) {
+            resource.destroy(device);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::TextureFiltering;
+use vulkanalia::vk::{DeviceV1_0, HasBuilder};
+use vulkanalia::{vk, Device};
+
+/// Describes sampler variant, e.g. to force crunchier or smoother sampling per layer.
+///
+/// LOD bias and clamping take effect on textures with mip levels only.
+///
+/// ```ignore
+/// let tiles = graphics.create_sampler(SamplerOptions::default().linear().repeat());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerOptions {
+    pub filter: vk::Filter,
+    pub mipmap: vk::SamplerMipmapMode,
+    pub lod_bias: f32,
+    pub min_lod: f32,
+    pub max_lod: f32,
+    /// Anisotropic filtering level, disabled if not set.
+    pub anisotropy: Option<f32>,
+    pub address_u: vk::SamplerAddressMode,
+    pub address_v: vk::SamplerAddressMode,
+    /// Color outside texture if address mode is clamp to border.
+    pub border: vk::BorderColor,
+}
+
+impl Default for SamplerOptions {
+    fn default() -> Self {
+        Self {
+            filter: vk::Filter::NEAREST,
+            mipmap: vk::SamplerMipmapMode::NEAREST,
+            lod_bias: 0.0,
+            min_lod: 0.0,
+            max_lod: vk::LOD_CLAMP_NONE,
+            anisotropy: None,
+            address_u: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            address_v: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            border: vk::BorderColor::INT_OPAQUE_BLACK,
+        }
+    }
+}
+
+impl SamplerOptions {
+    pub fn linear(mut self) -> Self {
+        self.filter = vk::Filter::LINEAR;
+        self.mipmap = vk::SamplerMipmapMode::LINEAR;
+        self
+    }
+
+    pub fn filter(mut self, filter: vk::Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Sets filtering between mip levels.
+    pub fn mipmap(mut self, mipmap: vk::SamplerMipmapMode) -> Self {
+        self.mipmap = mipmap;
+        self
+    }
+
+    /// Enables anisotropic filtering, level is clamped to 1..16.
+    pub fn anisotropy(mut self, level: f32) -> Self {
+        self.anisotropy = Some(level);
+        self
+    }
+
+    pub fn address(mut self, mode: vk::SamplerAddressMode) -> Self {
+        self.address_u = mode;
+        self.address_v = mode;
+        self
+    }
+
+    pub fn address_uv(mut self, u: vk::SamplerAddressMode, v: vk::SamplerAddressMode) -> Self {
+        self.address_u = u;
+        self.address_v = v;
+        self
+    }
+
+    /// Tiles texture, e.g. for scrolling backgrounds.
+    pub fn repeat(self) -> Self {
+        self.address(vk::SamplerAddressMode::REPEAT)
+    }
+
+    pub fn mirrored_repeat(self) -> Self {
+        self.address(vk::SamplerAddressMode::MIRRORED_REPEAT)
+    }
+
+    /// Samples border color outside texture, e.g. transparent for shadow maps or masks.
+    pub fn clamp_to_border(mut self, border: vk::BorderColor) -> Self {
+        self.border = border;
+        self.address(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+    }
+
+    /// Sets filter and anisotropy by texture filtering mode.
+    pub fn filtering(mut self, filtering: TextureFiltering) -> Self {
+        match filtering {
+            TextureFiltering::Nearest => {
+                self.filter = vk::Filter::NEAREST;
+                self.mipmap = vk::SamplerMipmapMode::NEAREST;
+                self.anisotropy = None;
+            }
+            TextureFiltering::Linear => {
+                self = self.linear();
+                self.anisotropy = None;
+            }
+            TextureFiltering::Anisotropic(level) => {
+                self = self.linear();
+                self.anisotropy = Some(level);
+            }
+        }
+        self
+    }
+
+    /// Positive bias selects smaller mip levels (smoother), negative selects larger (crunchier).
+    pub fn lod_bias(mut self, bias: f32) -> Self {
+        self.lod_bias = bias;
+        self
+    }
+
+    pub fn lod(mut self, min: f32, max: f32) -> Self {
+        self.min_lod = min;
+        self.max_lod = max;
+        self
+    }
+}
+
+pub unsafe fn create_sampler(device: &Device, options: SamplerOptions) -> vk::Sampler {
+    let info = vk::SamplerCreateInfo::builder()
+        .mag_filter(options.filter)
+        .min_filter(options.filter)
+        .address_mode_u(options.address_u)
+        .address_mode_v(options.address_v)
+        .address_mode_w(options.address_u)
+        .anisotropy_enable(options.anisotropy.is_some())
+        .max_anisotropy(options.anisotropy.unwrap_or(1.0).clamp(1.0, 16.0))
+        .border_color(options.border)
+        .unnormalized_coordinates(false)
+        .compare_enable(false)
+        .compare_op(vk::CompareOp::ALWAYS)
+        .mipmap_mode(options.mipmap)
+        .min_lod(options.min_lod)
+        .max_lod(options.max_lod)
+        .mip_lod_bias(options.lod_bias);
+    device
+        .create_sampler(&info, None)
+        .expect("sampler must be created")
+}
@@ -1,9 +1,17 @@
+use crate::ProgramError;
+use std::collections::BTreeMap;
 use std::fs;
+use std::sync::Mutex;
 use std::time::SystemTime;
 
+/// Bytecode of shader files read ahead by [`Shader::preload`], keyed by path
+/// with modification time, so changed files are read again.
+static CACHE: Mutex<BTreeMap<String, (SystemTime, Vec<u8>)>> = Mutex::new(BTreeMap::new());
+
 pub struct Shader {
     path: String,
     version: SystemTime,
+    bytecode: Option<&'static [u8]>,
 }
 
 impl Shader {
@@ -11,25 +19,63 @@ impl Shader {
         Self {
             version: Self::modified(path),
             path: path.to_string(),
+            bytecode: None,
+        }
+    }
+
+    /// Creates shader from SPIR-V bytecode embedded in binary, e.g. by `include_bytes!`,
+    /// name is used in errors only. Embedded shader is never changed.
+    pub fn embedded(name: &str, bytecode: &'static [u8]) -> Shader {
+        Self {
+            version: SystemTime::UNIX_EPOCH,
+            path: name.to_string(),
+            bytecode: Some(bytecode),
         }
     }
 
     pub fn renew(&self) -> Shader {
-        Self::new(&self.path)
+        match self.bytecode {
+            Some(bytecode) => Self::embedded(&self.path, bytecode),
+            None => Self::new(&self.path),
+        }
     }
 
+    /// Returns modification time of shader file, or UNIX epoch if file is not available,
+    /// e.g. while it is being rewritten by shader compiler.
     pub fn modified(path: &str) -> SystemTime {
-        let metadata = fs::metadata(path).expect("metadata must be available");
-        metadata
-            .modified()
-            .expect("modified time must be available")
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH)
     }
 
     pub fn changed(&self) -> bool {
-        self.version != Self::modified(&self.path)
+        self.bytecode.is_none() && self.version != Self::modified(&self.path)
     }
 
-    pub fn read(&mut self) -> Vec<u8> {
-        fs::read(&self.path).expect("file must be read")
+    /// Reads shader file into cache, so programs created later don't wait for file system,
+    /// see [`crate::AssetRef::Shader`].
+    pub fn preload(path: &str) -> Result<(), ProgramError> {
+        let version = Self::modified(path);
+        let bytecode = read_file(path)?;
+        let mut cache = CACHE.lock().expect("shader cache must be lockable");
+        cache.insert(path.to_string(), (version, bytecode));
+        Ok(())
     }
+
+    pub fn read(&mut self) -> Result<Vec<u8>, ProgramError> {
+        if let Some(bytecode) = self.bytecode {
+            return Ok(bytecode.to_vec());
+        }
+        let cache = CACHE.lock().expect("shader cache must be lockable");
+        if let Some((version, bytecode)) = cache.get(&self.path) {
+            if *version == self.version {
+                return Ok(bytecode.clone());
+            }
+        }
+        read_file(&self.path)
+    }
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, ProgramError> {
+    fs::read(path).map_err(|error| ProgramError::Shader(format!("unable to read {path}, {error}")))
 }
@@ -0,0 +1,84 @@
+use mesura::{Gauge, GaugeValue};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counters of work submitted by renderers in frame, see [`crate::Graphics::frame_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    pub draw_calls: usize,
+    pub instances: usize,
+    pub vertices: usize,
+    /// Bytes written to storage, uniform and mesh buffers.
+    pub bytes_uploaded: usize,
+    /// Textures in texture arrays bound by programs, counted on every bind.
+    pub textures_bound: usize,
+}
+
+static DRAW_CALLS: AtomicUsize = AtomicUsize::new(0);
+static INSTANCES: AtomicUsize = AtomicUsize::new(0);
+static VERTICES: AtomicUsize = AtomicUsize::new(0);
+static BYTES_UPLOADED: AtomicUsize = AtomicUsize::new(0);
+static TEXTURES_BOUND: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn record_draw(vertices: usize, instances: usize) {
+    DRAW_CALLS.fetch_add(1, Ordering::Relaxed);
+    INSTANCES.fetch_add(instances, Ordering::Relaxed);
+    VERTICES.fetch_add(vertices * instances, Ordering::Relaxed);
+}
+
+pub(crate) fn record_upload(bytes: usize) {
+    BYTES_UPLOADED.fetch_add(bytes, Ordering::Relaxed);
+}
+
+pub(crate) fn record_textures(count: usize) {
+    TEXTURES_BOUND.fetch_add(count, Ordering::Relaxed);
+}
+
+/// Returns counters recorded since previous call and resets them.
+pub(crate) fn take_frame_stats() -> FrameStats {
+    FrameStats {
+        draw_calls: DRAW_CALLS.swap(0, Ordering::Relaxed),
+        instances: INSTANCES.swap(0, Ordering::Relaxed),
+        vertices: VERTICES.swap(0, Ordering::Relaxed),
+        bytes_uploaded: BYTES_UPLOADED.swap(0, Ordering::Relaxed),
+        textures_bound: TEXTURES_BOUND.swap(0, Ordering::Relaxed),
+    }
+}
+
+pub(crate) struct FrameMetrics {
+    draw_calls: Gauge,
+    instances: Gauge,
+    vertices: Gauge,
+    bytes_uploaded: Gauge,
+    textures_bound: Gauge,
+    previous: FrameStats,
+}
+
+impl FrameMetrics {
+    pub fn new() -> Self {
+        let gauge = |counter| Gauge::with_labels("frame_stats", ["counter"], [counter]);
+        Self {
+            draw_calls: gauge("draw_calls"),
+            instances: gauge("instances"),
+            vertices: gauge("vertices"),
+            bytes_uploaded: gauge("bytes_uploaded"),
+            textures_bound: gauge("textures_bound"),
+            previous: FrameStats::default(),
+        }
+    }
+
+    /// Gauges hold counters of last frame, so only change since previous frame is added.
+    pub fn record(&mut self, stats: &FrameStats) {
+        let delta = |current: usize, previous: usize| (current as i64 - previous as i64) as f32;
+        let previous = self.previous;
+        self.draw_calls
+            .add(delta(stats.draw_calls, previous.draw_calls));
+        self.instances
+            .add(delta(stats.instances, previous.instances));
+        self.vertices.add(delta(stats.vertices, previous.vertices));
+        self.bytes_uploaded
+            .add(delta(stats.bytes_uploaded, previous.bytes_uploaded));
+        self.textures_bound
+            .add(delta(stats.textures_bound, previous.textures_bound));
+        self.previous = *stats;
+    }
+}
@@ -0,0 +1,104 @@
+use crate::GraphicsError;
+use sdl2::video::Window;
+use std::ffi::c_char;
+use vulkanalia::vk::{self, Handle};
+use vulkanalia::Instance;
+
+/// Window Vulkan renders to, decouples surface creation and swapchain extent
+/// from windowing library.
+///
+/// Implemented for SDL window, for winit window if `winit` feature is enabled
+/// and for [`RawWindow`] if `raw-window-handle` feature is enabled.
+pub trait VulkanWindow {
+    /// Returns names of instance extensions required to create surface for this window.
+    fn instance_extensions(&self) -> Result<Vec<*const c_char>, GraphicsError>;
+
+    /// # Safety
+    ///
+    /// Instance must be created with [`VulkanWindow::instance_extensions`] enabled,
+    /// and window must outlive surface.
+    unsafe fn create_surface(&self, instance: &Instance) -> Result<vk::SurfaceKHR, GraphicsError>;
+
+    /// Returns size in pixels, may differ from window size on high DPI displays.
+    fn drawable_size(&self) -> (u32, u32);
+}
+
+impl VulkanWindow for Window {
+    fn instance_extensions(&self) -> Result<Vec<*const c_char>, GraphicsError> {
+        // SDL returns static names with null terminator
+        let extensions = self
+            .vulkan_instance_extensions()
+            .map_err(GraphicsError::Sdl)?
+            .iter()
+            .map(|name| name.as_ptr() as *const c_char)
+            .collect();
+        Ok(extensions)
+    }
+
+    unsafe fn create_surface(&self, instance: &Instance) -> Result<vk::SurfaceKHR, GraphicsError> {
+        let surface = self
+            .vulkan_create_surface(instance.handle().as_raw())
+            .map_err(GraphicsError::Sdl)?;
+        Ok(vk::SurfaceKHR::from_raw(surface))
+    }
+
+    fn drawable_size(&self) -> (u32, u32) {
+        self.vulkan_drawable_size()
+    }
+}
+
+#[cfg(feature = "winit")]
+impl VulkanWindow for winit::window::Window {
+    fn instance_extensions(&self) -> Result<Vec<*const c_char>, GraphicsError> {
+        let extensions = vulkanalia::window::get_required_instance_extensions(self)
+            .iter()
+            .map(|name| name.as_ptr())
+            .collect();
+        Ok(extensions)
+    }
+
+    unsafe fn create_surface(&self, instance: &Instance) -> Result<vk::SurfaceKHR, GraphicsError> {
+        vulkanalia::window::create_surface(instance, self, self)
+            .map_err(|error| GraphicsError::Vulkan(error.to_string()))
+    }
+
+    fn drawable_size(&self) -> (u32, u32) {
+        let size = self.inner_size();
+        (size.width, size.height)
+    }
+}
+
+/// Window owned by external framework, e.g. Qt widget, identified by raw handles.
+#[cfg(feature = "raw-window-handle")]
+#[derive(Clone, Copy, Debug)]
+pub struct RawWindow {
+    pub display: raw_window_handle::RawDisplayHandle,
+    pub window: raw_window_handle::RawWindowHandle,
+    /// Drawable size in pixels, must be updated by window owner on resize.
+    pub size: [u32; 2],
+}
+
+#[cfg(feature = "raw-window-handle")]
+impl VulkanWindow for RawWindow {
+    fn instance_extensions(&self) -> Result<Vec<*const c_char>, GraphicsError> {
+        // handle is only matched to select platform extensions, never dereferenced
+        let window = unsafe { raw_window_handle::WindowHandle::borrow_raw(self.window) };
+        let extensions = vulkanalia::window::get_required_instance_extensions(&window)
+            .iter()
+            .map(|name| name.as_ptr())
+            .collect();
+        Ok(extensions)
+    }
+
+    unsafe fn create_surface(&self, instance: &Instance) -> Result<vk::SurfaceKHR, GraphicsError> {
+        let display = raw_window_handle::DisplayHandle::borrow_raw(self.display);
+        let window = raw_window_handle::WindowHandle::borrow_raw(self.window);
+        vulkanalia::window::create_surface(instance, &display, &window)
+            .map_err(|error| GraphicsError::Vulkan(error.to_string()))
+    }
+
+    fn drawable_size(&self) -> (u32, u32) {
+        let [width, height] = self.size;
+        (width, height)
+    }
+}
@@ -0,0 +1,525 @@
+use crate::vulkan::depth::DepthBuffer;
+use crate::vulkan::memory::{allocate_memory, MemoryKind};
+use crate::vulkan::multisample::{create_color_attachments, MultisampleBuffer};
+use crate::vulkan::{
+    create_command_buffers, create_command_pool, create_image_view, get_memory_type_index,
+    set_viewport, Vulkan, FRAMES_PROCESSING_CONCURRENCY,
+};
+use crate::Texture;
+use log::info;
+use vulkanalia::vk::{DeviceV1_0, HasBuilder, InstanceV1_0};
+use vulkanalia::{vk, Device};
+
+/// Represents offscreen color image which programs can render to and sample from.
+///
+/// Target render pass is compatible with swapchain render pass (same color and depth formats
+/// and sample count), so any program can draw into target without pipeline recreation.
+pub struct RenderTarget {
+    pub texture: Texture,
+    pub extent: vk::Extent2D,
+    viewport: vk::Extent2D,
+    depth: DepthBuffer,
+    multisample: Option<MultisampleBuffer>,
+    render_pass: vk::RenderPass,
+    framebuffer: vk::Framebuffer,
+    commands: TargetCommands,
+    device: Device,
+}
+
+impl RenderTarget {
+    pub unsafe fn create(vulkan: &Vulkan, width: u32, height: u32) -> Self {
+        let device = &vulkan.device;
+        let format = vulkan.swapchain.format;
+        let extent = vk::Extent2D { width, height };
+        let texture = create_target_texture(vulkan, extent, format);
+        let view = texture.view;
+        let samples = vulkan.samples;
+        let depth = DepthBuffer::create(
+            &vulkan.instance,
+            device,
+            vulkan.physical_device,
+            extent,
+            samples,
+        );
+        let multisample = MultisampleBuffer::create(
+            &vulkan.instance,
+            device,
+            vulkan.physical_device,
+            format,
+            extent,
+            samples,
+        );
+        let render_pass = create_target_render_pass(device, format, depth.format, samples);
+        // multisampled image is rendered to and resolved to target texture
+        let attachments = match multisample.as_ref() {
+            Some(multisample) => vec![multisample.view, depth.view, view],
+            None => vec![view, depth.view],
+        };
+        let info = vk::FramebufferCreateInfo::builder()
+            .render_pass(render_pass)
+            .attachments(&attachments)
+            .width(width)
+            .height(height)
+            .layers(1);
+        let framebuffer = device
+            .create_framebuffer(&info, None)
+            .expect("target frame buffer must be created");
+        let commands = TargetCommands::create(vulkan);
+        info!("Creates render target {extent:?} format={format:?}");
+        Self {
+            texture,
+            extent,
+            viewport: extent,
+            depth,
+            multisample,
+            render_pass,
+            framebuffer,
+            commands,
+            device: device.clone(),
+        }
+    }
+
+    /// Limits rendering to top left region of target, e.g. to render at reduced
+    /// resolution without target recreation. Region is clamped to target extent.
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        self.viewport = vk::Extent2D {
+            width: width.clamp(1, self.extent.width),
+            height: height.clamp(1, self.extent.height),
+        };
+    }
+
+    pub fn viewport(&self) -> vk::Extent2D {
+        self.viewport
+    }
+
+    pub(crate) unsafe fn begin(&mut self, frame: usize, clear_color: [f32; 4]) -> vk::CommandBuffer {
+        let commands = self.commands.begin(frame);
+        let render_area = vk::Rect2D::builder()
+            .offset(vk::Offset2D::default())
+            .extent(self.viewport);
+        let clear_values = &[
+            vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: clear_color,
+                },
+            },
+            vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            },
+        ];
+        let info = vk::RenderPassBeginInfo::builder()
+            .render_pass(self.render_pass)
+            .framebuffer(self.framebuffer)
+            .render_area(render_area)
+            .clear_values(clear_values);
+        self.device
+            .cmd_begin_render_pass(commands, &info, vk::SubpassContents::INLINE);
+        set_viewport(&self.device, commands, self.viewport);
+        commands
+    }
+
+    pub(crate) unsafe fn end(&mut self, queue: vk::Queue) {
+        self.device.cmd_end_render_pass(self.commands.current());
+        self.commands.submit(queue);
+    }
+
+    pub unsafe fn destroy(&self) {
+        let device = &self.device;
+        self.commands.destroy();
+        device.destroy_framebuffer(self.framebuffer, None);
+        device.destroy_render_pass(self.render_pass, None);
+        self.depth.destroy(device);
+        if let Some(multisample) = self.multisample.as_ref() {
+            multisample.destroy(device);
+        }
+        self.texture.destroy(device);
+    }
+}
+
+/// Represents offscreen target with several color images written in one pass,
+/// e.g. color, emissive and object ID for deferred 2D lighting and picking buffers.
+///
+/// Programs drawing into target are created by [`crate::Graphics::create_multi_target_program`],
+/// fragment shader writes attachments by location:
+///
+/// ```glsl
+/// layout (location = 0) out vec4 color;
+/// layout (location = 1) out vec4 emissive;
+/// layout (location = 2) out vec4 id;
+/// ```
+///
+/// Only first attachment is alpha blended, others are overwritten, so IDs are never mixed.
+/// Target is not multisampled.
+pub struct MultiTarget {
+    pub textures: Vec<Texture>,
+    pub extent: vk::Extent2D,
+    depth: DepthBuffer,
+    pub(crate) render_pass: vk::RenderPass,
+    framebuffer: vk::Framebuffer,
+    commands: TargetCommands,
+    device: Device,
+}
+
+impl MultiTarget {
+    pub unsafe fn create(vulkan: &Vulkan, width: u32, height: u32, formats: &[vk::Format]) -> Self {
+        let device = &vulkan.device;
+        let extent = vk::Extent2D { width, height };
+        let textures: Vec<Texture> = formats
+            .iter()
+            .map(|format| create_target_texture(vulkan, extent, *format))
+            .collect();
+        let depth = DepthBuffer::create(
+            &vulkan.instance,
+            device,
+            vulkan.physical_device,
+            extent,
+            vk::SampleCountFlags::_1,
+        );
+        let render_pass = create_multi_target_render_pass(device, formats, depth.format);
+        let mut attachments: Vec<vk::ImageView> =
+            textures.iter().map(|texture| texture.view).collect();
+        attachments.push(depth.view);
+        let info = vk::FramebufferCreateInfo::builder()
+            .render_pass(render_pass)
+            .attachments(&attachments)
+            .width(width)
+            .height(height)
+            .layers(1);
+        let framebuffer = device
+            .create_framebuffer(&info, None)
+            .expect("multi target frame buffer must be created");
+        let commands = TargetCommands::create(vulkan);
+        info!("Creates multi target {extent:?} formats={formats:?}");
+        Self {
+            textures,
+            extent,
+            depth,
+            render_pass,
+            framebuffer,
+            commands,
+            device: device.clone(),
+        }
+    }
+
+    /// Clears first attachment with clear color, others with transparent black.
+    pub(crate) unsafe fn begin(
+        &mut self,
+        frame: usize,
+        clear_color: [f32; 4],
+    ) -> vk::CommandBuffer {
+        let commands = self.commands.begin(frame);
+        let render_area = vk::Rect2D::builder()
+            .offset(vk::Offset2D::default())
+            .extent(self.extent);
+        let mut clear_values: Vec<vk::ClearValue> = (0..self.textures.len())
+            .map(|index| vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: if index == 0 { clear_color } else { [0.0; 4] },
+                },
+            })
+            .collect();
+        clear_values.push(vk::ClearValue {
+            depth_stencil: vk::ClearDepthStencilValue {
+                depth: 1.0,
+                stencil: 0,
+            },
+        });
+        let info = vk::RenderPassBeginInfo::builder()
+            .render_pass(self.render_pass)
+            .framebuffer(self.framebuffer)
+            .render_area(render_area)
+            .clear_values(&clear_values);
+        self.device
+            .cmd_begin_render_pass(commands, &info, vk::SubpassContents::INLINE);
+        set_viewport(&self.device, commands, self.extent);
+        commands
+    }
+
+    pub(crate) unsafe fn end(&mut self, queue: vk::Queue) {
+        self.device.cmd_end_render_pass(self.commands.current());
+        self.commands.submit(queue);
+    }
+
+    /// Destroys target, programs created for it must be destroyed before.
+    pub unsafe fn destroy(&self) {
+        let device = &self.device;
+        self.commands.destroy();
+        device.destroy_framebuffer(self.framebuffer, None);
+        device.destroy_render_pass(self.render_pass, None);
+        self.depth.destroy(device);
+        for texture in &self.textures {
+            texture.destroy(device);
+        }
+    }
+}
+
+/// Command buffers of offscreen target, submitted separately from frame commands
+/// and fenced per frame in flight.
+struct TargetCommands {
+    command_pools: Vec<vk::CommandPool>,
+    command_buffers: Vec<vk::CommandBuffer>,
+    fences: Vec<vk::Fence>,
+    frame: usize,
+    device: Device,
+}
+
+impl TargetCommands {
+    unsafe fn create(vulkan: &Vulkan) -> Self {
+        let device = &vulkan.device;
+        let command_pools: Vec<_> = (0..FRAMES_PROCESSING_CONCURRENCY)
+            .map(|_| create_command_pool(device, vulkan.queues.graphics))
+            .collect();
+        let command_buffers = create_command_buffers(device, &command_pools);
+        let fence_info = vk::FenceCreateInfo::builder().flags(vk::FenceCreateFlags::SIGNALED);
+        let fences = (0..FRAMES_PROCESSING_CONCURRENCY)
+            .map(|_| {
+                device
+                    .create_fence(&fence_info, None)
+                    .expect("target fence must be created")
+            })
+            .collect();
+        Self {
+            command_pools,
+            command_buffers,
+            fences,
+            frame: 0,
+            device: device.clone(),
+        }
+    }
+
+    fn current(&self) -> vk::CommandBuffer {
+        self.command_buffers[self.frame]
+    }
+
+    /// Begins command buffer of frame once its previous submission completes.
+    unsafe fn begin(&mut self, frame: usize) -> vk::CommandBuffer {
+        self.frame = frame;
+        let fence = self.fences[frame];
+        self.device
+            .wait_for_fences(&[fence], true, u64::MAX)
+            .expect("target fence must be acquired");
+        self.device
+            .reset_command_pool(self.command_pools[frame], vk::CommandPoolResetFlags::empty())
+            .expect("target command pool must be reset");
+        let commands = self.command_buffers[frame];
+        let info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        self.device
+            .begin_command_buffer(commands, &info)
+            .expect("target command buffer must begin");
+        commands
+    }
+
+    unsafe fn submit(&mut self, queue: vk::Queue) {
+        let commands = self.current();
+        let fence = self.fences[self.frame];
+        self.device
+            .end_command_buffer(commands)
+            .expect("target command buffer must end");
+        let command_buffers = &[commands];
+        let info = vk::SubmitInfo::builder().command_buffers(command_buffers);
+        self.device
+            .reset_fences(&[fence])
+            .expect("target fence must be reset");
+        self.device
+            .queue_submit(queue, &[info], fence)
+            .expect("target commands must be submitted");
+    }
+
+    unsafe fn destroy(&self) {
+        let device = &self.device;
+        device
+            .wait_for_fences(&self.fences, true, u64::MAX)
+            .expect("target fences must be acquired");
+        for fence in &self.fences {
+            device.destroy_fence(*fence, None);
+        }
+        for pool in &self.command_pools {
+            device.destroy_command_pool(*pool, None);
+        }
+    }
+}
+
+/// Creates color image target pass renders to and next passes sample.
+unsafe fn create_target_texture(
+    vulkan: &Vulkan,
+    extent: vk::Extent2D,
+    format: vk::Format,
+) -> Texture {
+    let device = &vulkan.device;
+    let info = vk::ImageCreateInfo::builder()
+        .image_type(vk::ImageType::_2D)
+        .extent(vk::Extent3D {
+            width: extent.width,
+            height: extent.height,
+            depth: 1,
+        })
+        .mip_levels(1)
+        .array_layers(1)
+        .format(format)
+        .tiling(vk::ImageTiling::OPTIMAL)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .usage(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED)
+        .sharing_mode(vk::SharingMode::EXCLUSIVE)
+        .samples(vk::SampleCountFlags::_1);
+    let image = device
+        .create_image(&info, None)
+        .expect("target image must be created");
+    let requirements = device.get_image_memory_requirements(image);
+    let physical_device_memory = vulkan
+        .instance
+        .get_physical_device_memory_properties(vulkan.physical_device);
+    let memory_type_index = get_memory_type_index(
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        requirements,
+        physical_device_memory,
+    );
+    let info = vk::MemoryAllocateInfo::builder()
+        .allocation_size(requirements.size)
+        .memory_type_index(memory_type_index);
+    let memory = allocate_memory(device, &info, MemoryKind::Image)
+        .expect("target image memory must be allocated");
+    device
+        .bind_image_memory(image, memory, 0)
+        .expect("target image memory must bound");
+    let view = create_image_view(device, image, format);
+    Texture {
+        image,
+        memory,
+        view,
+        size: [extent.width, extent.height],
+        format,
+    }
+}
+
+unsafe fn create_target_render_pass(
+    device: &Device,
+    format: vk::Format,
+    depth_format: vk::Format,
+    samples: vk::SampleCountFlags,
+) -> vk::RenderPass {
+    let attachments = create_color_attachments(
+        format,
+        depth_format,
+        samples,
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+    );
+    let color_attachment_ref = vk::AttachmentReference::builder()
+        .attachment(0)
+        .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+    let depth_attachment_ref = vk::AttachmentReference::builder()
+        .attachment(1)
+        .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+    let resolve_attachment_ref = vk::AttachmentReference::builder()
+        .attachment(2)
+        .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+    let color_attachments = &[color_attachment_ref];
+    let resolve_attachments = &[resolve_attachment_ref];
+    let mut subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .color_attachments(color_attachments)
+        .depth_stencil_attachment(&depth_attachment_ref);
+    if samples != vk::SampleCountFlags::_1 {
+        subpass = subpass.resolve_attachments(resolve_attachments);
+    }
+    let subpasses = &[subpass];
+    let dependencies = &create_target_dependencies();
+    let info = vk::RenderPassCreateInfo::builder()
+        .attachments(&attachments)
+        .subpasses(subpasses)
+        .dependencies(dependencies);
+    device
+        .create_render_pass(&info, None)
+        .expect("target render pass must be created")
+}
+
+fn create_target_dependencies() -> [vk::SubpassDependency; 2] {
+    // previous frame may still sample target image in fragment shader
+    let dependency = vk::SubpassDependency::builder()
+        .src_subpass(vk::SUBPASS_EXTERNAL)
+        .dst_subpass(0)
+        .src_stage_mask(
+            vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+        )
+        .src_access_mask(
+            vk::AccessFlags::SHADER_READ | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+        )
+        .dst_stage_mask(
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+        )
+        .dst_access_mask(
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+        );
+    // next passes sample target image in fragment shader
+    let sampling = vk::SubpassDependency::builder()
+        .src_subpass(0)
+        .dst_subpass(vk::SUBPASS_EXTERNAL)
+        .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+        .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+        .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+        .dst_access_mask(vk::AccessFlags::SHADER_READ);
+    [dependency.build(), sampling.build()]
+}
+
+unsafe fn create_multi_target_render_pass(
+    device: &Device,
+    formats: &[vk::Format],
+    depth_format: vk::Format,
+) -> vk::RenderPass {
+    let mut attachments: Vec<vk::AttachmentDescription> = formats
+        .iter()
+        .map(|format| {
+            vk::AttachmentDescription::builder()
+                .format(*format)
+                .samples(vk::SampleCountFlags::_1)
+                .load_op(vk::AttachmentLoadOp::CLEAR)
+                .store_op(vk::AttachmentStoreOp::STORE)
+                .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+                .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+                .initial_layout(vk::ImageLayout::UNDEFINED)
+                .final_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .build()
+        })
+        .collect();
+    attachments.push(
+        vk::AttachmentDescription::builder()
+            .format(depth_format)
+            .samples(vk::SampleCountFlags::_1)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+            .build(),
+    );
+    let color_attachments: Vec<vk::AttachmentReference> = (0..formats.len())
+        .map(|index| {
+            vk::AttachmentReference::builder()
+                .attachment(index as u32)
+                .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+                .build()
+        })
+        .collect();
+    let depth_attachment_ref = vk::AttachmentReference::builder()
+        .attachment(formats.len() as u32)
+        .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+    let subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .color_attachments(&color_attachments)
+        .depth_stencil_attachment(&depth_attachment_ref);
+    let subpasses = &[subpass];
+    let dependencies = &create_target_dependencies();
+    let info = vk::RenderPassCreateInfo::builder()
+        .attachments(&attachments)
+        .subpasses(subpasses)
+        .dependencies(dependencies);
+    device
+        .create_render_pass(&info, None)
+        .expect("multi target render pass must be created")
+}
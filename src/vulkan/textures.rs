@@ -1,4 +1,7 @@
-use crate::textures::{read_texture_from_data, Texture, TextureError, TextureLoaderDevice};
+use crate::textures::{
+    read_texture_from_data, Texture, TextureError, TextureFormat, TextureLoaderDevice,
+};
+use crate::vulkan::memory::{allocate_memory, free_memory, MemoryKind};
 use crate::vulkan::{
     command_once, create_buffer, create_image_view, get_memory_type_index, submit_commands,
     MemoryBuffer,
@@ -16,12 +19,13 @@ pub struct VulkanTextureLoaderDevice {
     pub(crate) physical_device: PhysicalDevice,
     pub(crate) command_pool: CommandPool,
     pub(crate) queue: Queue,
+    /// Format of created textures, SRGB if swapchain is SRGB.
+    pub(crate) format: vk::Format,
 }
 
 impl VulkanTextureLoaderDevice {
     pub fn update_texture_data(&self, texture: Texture, data: &[u8]) {
         unsafe {
-            let format = vk::Format::R8G8B8A8_UNORM;
             update_image(
                 &self.instance,
                 &self.device,
@@ -29,7 +33,6 @@ impl VulkanTextureLoaderDevice {
                 self.queue,
                 self.command_pool,
                 texture,
-                format,
                 data,
             )
         }
@@ -37,14 +40,13 @@ impl VulkanTextureLoaderDevice {
 
     pub fn create_texture_handle(&self, width: usize, height: usize) -> Texture {
         unsafe {
-            let format = vk::Format::R8G8B8A8_UNORM;
             create_image(
                 &self.instance,
                 &self.device,
                 self.physical_device,
                 width as u32,
                 height as u32,
-                format,
+                self.format,
                 vk::ImageTiling::LINEAR,
                 vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
                 vk::MemoryPropertyFlags::DEVICE_LOCAL,
@@ -53,7 +55,23 @@ impl VulkanTextureLoaderDevice {
     }
 
     pub fn create_texture(&self, width: u32, height: u32, data: &[u8]) -> Texture {
-        let texture = unsafe {
+        self.create_texture_with_format(width, height, TextureFormat::Rgba8, data)
+    }
+
+    /// Creates texture of data in given format, data must be tightly packed rows.
+    pub fn create_texture_with_format(
+        &self,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        data: &[u8],
+    ) -> Texture {
+        assert_eq!(
+            data.len(),
+            format.data_size(width, height),
+            "texture data must match size and format"
+        );
+        unsafe {
             create_texture(
                 &self.instance,
                 &self.device,
@@ -62,10 +80,18 @@ impl VulkanTextureLoaderDevice {
                 self.command_pool,
                 width,
                 height,
+                self.vulkan_format(format),
                 data,
             )
-        };
-        texture
+        }
+    }
+
+    /// Returns Vulkan format of texture format, color textures follow swapchain SRGB.
+    pub fn vulkan_format(&self, format: TextureFormat) -> vk::Format {
+        match format {
+            TextureFormat::Rgba8 => self.format,
+            _ => format.to_vulkan(false),
+        }
     }
 }
 
@@ -81,6 +107,7 @@ impl TextureLoaderDevice for VulkanTextureLoaderDevice {
                     self.command_pool,
                     image.width as u32,
                     image.height as u32,
+                    self.format,
                     &data,
                 )
             };
@@ -96,7 +123,6 @@ unsafe fn update_image(
     queue: vk::Queue,
     command_pool: vk::CommandPool,
     texture: Texture,
-    format: vk::Format,
     data: &[u8],
 ) {
     let t = Instant::now();
@@ -109,6 +135,7 @@ unsafe fn update_image(
         vk::BufferUsageFlags::TRANSFER_SRC,
         vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
         physical_device_memory,
+        &[],
     );
     let t0 = t.elapsed();
     let t = Instant::now();
@@ -146,7 +173,7 @@ unsafe fn update_image(
     );
     let t4 = t.elapsed();
     device.destroy_buffer(staging.handle, None);
-    device.free_memory(staging.memory, None);
+    free_memory(device, staging.memory);
     // println!(
     //     "create_buffer {t0:?}, update {t1:?}, trans1 {t2:?}, copy_buffer {t3:?}, trans2 {t4:?} {texture:?}"
     // );
@@ -160,6 +187,7 @@ unsafe fn create_texture(
     command_pool: vk::CommandPool,
     width: u32,
     height: u32,
+    format: vk::Format,
     data: &[u8],
 ) -> Texture {
     let size = data.len() as u64;
@@ -170,13 +198,21 @@ unsafe fn create_texture(
         vk::BufferUsageFlags::TRANSFER_SRC,
         vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
         physical_device_memory,
+        &[],
     );
     let memory = device
         .map_memory(staging.memory, 0, size, vk::MemoryMapFlags::empty())
         .expect("memory must be mapped");
     std::ptr::copy_nonoverlapping(data.as_ptr(), memory.cast(), data.len());
     device.unmap_memory(staging.memory);
-    let format = vk::Format::R8G8B8A8_UNORM;
+    // data is copied from staging buffer, so tiling matters only for sampling support
+    let linear = instance
+        .get_physical_device_format_properties(physical_device, format)
+        .linear_tiling_features;
+    let tiling = match linear.contains(vk::FormatFeatureFlags::SAMPLED_IMAGE) {
+        true => vk::ImageTiling::LINEAR,
+        false => vk::ImageTiling::OPTIMAL,
+    };
     let texture = create_image(
         instance,
         device,
@@ -184,7 +220,7 @@ unsafe fn create_texture(
         width,
         height,
         format,
-        vk::ImageTiling::LINEAR,
+        tiling,
         vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
         vk::MemoryPropertyFlags::DEVICE_LOCAL,
     );
@@ -215,7 +251,7 @@ unsafe fn create_texture(
         vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
     );
     device.destroy_buffer(staging.handle, None);
-    device.free_memory(staging.memory, None);
+    free_memory(device, staging.memory);
     texture
 }
 
@@ -254,18 +290,18 @@ unsafe fn create_image(
     let info = vk::MemoryAllocateInfo::builder()
         .allocation_size(requirements.size)
         .memory_type_index(memory_type_index);
-    let memory = device
-        .allocate_memory(&info, None)
-        .expect("image memory must be allocated");
+    let memory =
+        allocate_memory(device, &info, MemoryKind::Image).expect("image memory must be allocated");
     device
         .bind_image_memory(image, memory, 0)
         .expect("image memory must bound");
-    let view = create_image_view(device, image, vk::Format::R8G8B8A8_UNORM);
+    let view = create_image_view(device, image, format);
     Texture {
         image,
         memory,
         view,
         size: [width, height],
+        format,
     }
 }
 
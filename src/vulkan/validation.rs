@@ -0,0 +1,95 @@
+use log::info;
+use std::backtrace::Backtrace;
+use std::sync::Mutex;
+use vulkanalia::vk;
+
+/// Represents deduplicated message of validation layer.
+#[derive(Clone, Debug)]
+pub struct ValidationMessage {
+    pub severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    /// Validation rule identifier, e.g. `VUID-vkCmdDraw-None-02699`.
+    pub id: String,
+    /// Text of first occurrence, repeated messages may differ by object handles only.
+    pub message: String,
+    pub count: usize,
+    /// Application backtrace of first occurrence.
+    pub backtrace: String,
+}
+
+static MESSAGES: Mutex<Vec<ValidationMessage>> = Mutex::new(Vec::new());
+
+pub(crate) fn record_message(
+    severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    id: &str,
+    message: &str,
+) {
+    let mut messages = MESSAGES
+        .lock()
+        .expect("validation messages must be lockable");
+    let id = if id.is_empty() { message } else { id };
+    match messages
+        .iter_mut()
+        .find(|record| record.severity == severity && record.id == id)
+    {
+        Some(record) => record.count += 1,
+        None => messages.push(ValidationMessage {
+            severity,
+            id: id.to_string(),
+            message: message.to_string(),
+            count: 1,
+            backtrace: Backtrace::force_capture().to_string(),
+        }),
+    }
+}
+
+/// Returns warnings and errors reported by validation layer since process start,
+/// most severe and frequent first.
+pub fn validation_summary() -> Vec<ValidationMessage> {
+    let mut messages = MESSAGES
+        .lock()
+        .expect("validation messages must be lockable")
+        .clone();
+    messages.sort_by(|a, b| {
+        b.severity
+            .bits()
+            .cmp(&a.severity.bits())
+            .then(b.count.cmp(&a.count))
+    });
+    messages
+}
+
+/// Returns number of errors reported by validation layer since process start,
+/// validation layer is enabled by `VULKAN_DEBUG` environment variable.
+pub fn validation_errors() -> usize {
+    MESSAGES
+        .lock()
+        .expect("validation messages must be lockable")
+        .iter()
+        .filter(|record| record.severity >= vk::DebugUtilsMessageSeverityFlagsEXT::ERROR)
+        .map(|record| record.count)
+        .sum()
+}
+
+/// Logs validation summary, backtraces are logged for errors only.
+pub fn log_validation_summary() {
+    let messages = validation_summary();
+    if messages.is_empty() {
+        return;
+    }
+    let errors = validation_errors();
+    let total: usize = messages.iter().map(|record| record.count).sum();
+    info!(
+        "Validation summary: {errors} errors, {} warnings, {} unique messages",
+        total - errors,
+        messages.len()
+    );
+    for record in messages {
+        info!(
+            "{}x ({:?}) {}",
+            record.count, record.severity, record.message
+        );
+        if record.severity >= vk::DebugUtilsMessageSeverityFlagsEXT::ERROR {
+            info!("First occurrence at\n{}", record.backtrace);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::vulkan::{
+    create_buffer, create_descriptor_pool, create_descriptor_set_layout, create_descriptors,
+    MemoryBuffer, Vulkan, FRAMES_PROCESSING_CONCURRENCY,
+};
+use crate::Variable;
+use log::info;
+use std::marker::PhantomData;
+use vulkanalia::vk::{DeviceV1_0, HasBuilder, InstanceV1_0};
+use vulkanalia::{vk, Device};
+
+/// Represents GLSL storage buffer in device local memory, written and read by GPU only,
+/// e.g. simulation state updated by compute shader and drawn without CPU round trip.
+///
+/// Storage has single buffer shared by frames in flight, or buffer per frame in flight
+/// if GPU writes it every frame while previous frame may still read it.
+pub struct DeviceStorage<T> {
+    pub(crate) buffers: Vec<MemoryBuffer>,
+    range: u64,
+    device: Device,
+    _element: PhantomData<T>,
+}
+
+impl<T> DeviceStorage<T> {
+    pub unsafe fn create(vulkan: &Vulkan, n: usize, per_frame: bool) -> Self {
+        let device = &vulkan.device;
+        let physical_device_memory = vulkan
+            .instance
+            .get_physical_device_memory_properties(vulkan.physical_device);
+        let size = size_of::<T>();
+        let range = size * n;
+        let frames = if per_frame {
+            FRAMES_PROCESSING_CONCURRENCY
+        } else {
+            1
+        };
+        info!("Creates device storage buffers n={n} size={size} range={range} frames={frames}");
+        let usage = vk::BufferUsageFlags::STORAGE_BUFFER
+            | vk::BufferUsageFlags::INDIRECT_BUFFER
+            | vk::BufferUsageFlags::TRANSFER_DST;
+        let families = vulkan.buffer_families();
+        let buffers = (0..frames)
+            .map(|_| {
+                create_buffer(
+                    device,
+                    range as u64,
+                    usage,
+                    vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                    physical_device_memory,
+                    &families,
+                )
+            })
+            .collect();
+        Self {
+            buffers,
+            range: range as u64,
+            device: device.clone(),
+            _element: PhantomData,
+        }
+    }
+
+    /// Returns buffer used in frame in flight.
+    pub fn buffer(&self, frame: usize) -> vk::Buffer {
+        self.buffers[frame % self.buffers.len()].handle
+    }
+
+    /// Records fill of buffer used in frame with repeated 32-bit value.
+    pub fn fill(&self, commands: vk::CommandBuffer, frame: usize, value: u32) {
+        unsafe {
+            self.device
+                .cmd_fill_buffer(commands, self.buffer(frame), 0, vk::WHOLE_SIZE, value);
+        }
+    }
+
+    /// Records update of buffer used in frame from start, data must not exceed 65536 bytes.
+    pub fn update(&self, commands: vk::CommandBuffer, frame: usize, data: &[T]) {
+        let size = std::mem::size_of_val(data);
+        let bytes = unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, size) };
+        unsafe {
+            self.device
+                .cmd_update_buffer(commands, self.buffer(frame), 0, bytes);
+        }
+    }
+
+    pub fn layout(&self, set: u32, binding: u32) -> Variable {
+        let device = &self.device;
+        let frames = FRAMES_PROCESSING_CONCURRENCY;
+        unsafe {
+            let bindings = vec![(
+                binding,
+                vk::DescriptorType::STORAGE_BUFFER,
+                vk::ShaderStageFlags::FRAGMENT
+                    | vk::ShaderStageFlags::VERTEX
+                    | vk::ShaderStageFlags::COMPUTE,
+                1,
+            )];
+            let pool = create_descriptor_pool(device, &bindings, frames);
+            let layout = create_descriptor_set_layout(device, bindings);
+            let descriptors = create_descriptors(device, pool, layout, frames);
+            for (frame, set) in descriptors.iter().enumerate() {
+                let info = vk::DescriptorBufferInfo::builder()
+                    .buffer(self.buffer(frame))
+                    .offset(0)
+                    .range(self.range);
+                let buffer_info = &[info];
+                let write = vk::WriteDescriptorSet::builder()
+                    .dst_set(*set)
+                    .dst_binding(binding)
+                    .dst_array_element(0)
+                    .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                    .buffer_info(buffer_info);
+                device.update_descriptor_sets(&[write], &[] as &[vk::CopyDescriptorSet]);
+            }
+            Variable {
+                set,
+                binding,
+                layout,
+                descriptors,
+            }
+        }
+    }
+
+    pub fn destroy(&self) {
+        for buffer in &self.buffers {
+            buffer.destroy(&self.device);
+        }
+    }
+}
@@ -1,32 +1,86 @@
 use crate::math::{Vec2, Vec4, VecArith, VecComponents};
+use crate::vulkan::memory::{allocate_memory, MemoryKind};
+use crate::vulkan::retire::{RetireQueue, Retired};
+use crate::vulkan::stats::record_upload;
 use crate::vulkan::{
-    create_buffers, get_memory_type_index, MemoryBuffer, Vulkan,
+    create_buffers, get_memory_type_index, MemoryBuffer, Vulkan, FRAMES_PROCESSING_CONCURRENCY,
 };
-use crate::Colors;
-
+use crate::{Colors, Gradient, Storage};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+use std::ops::Range;
+use std::rc::Rc;
 
 use vulkanalia::vk::{
     BufferCreateInfo, BufferUsageFlags, DeviceV1_0, Format, HasBuilder,
     InstanceV1_0, MemoryAllocateInfo, MemoryMapFlags, MemoryPropertyFlags, PhysicalDevice,
-    PipelineVertexInputStateCreateInfo, SharingMode,
+    PhysicalDeviceMemoryProperties, PipelineVertexInputStateCreateInfo, SharingMode,
     VertexInputAttributeDescription, VertexInputBindingDescription, VertexInputRate,
 };
 use vulkanalia::{Device, Instance};
 
-/// Represents GLSL vertices static buffer.
+pub use motoro_derive::VertexLayout;
+
+/// Represents GLSL vertices buffer, grows on demand up to optional limit.
+///
+/// Dynamic geometry is appended every frame and uploaded by [`Mesh::update`],
+/// static geometry is appended once, uploaded by [`Mesh::upload`] and drawn selectively
+/// by returned [`Vertices`].
 pub struct Mesh {
     pub buffers: Vec<MemoryBuffer>,
     device: Device,
+    physical_device_memory: PhysicalDeviceMemoryProperties,
     pub vertices: Vec<Vertex>,
     pub cursor: usize,
+    /// Vertices allocated in buffer, per frame in flight.
+    capacities: Vec<usize>,
+    /// Vertices already uploaded to buffer, per frame in flight.
+    uploaded: Vec<usize>,
+    limit: Option<usize>,
+    /// Indices of vertices appended by indexed builders, shared by next indexed shapes.
+    shared: HashMap<VertexKey, u32>,
+    retired: Rc<RefCell<RetireQueue>>,
 }
 
+/// Range of mesh vertices, e.g. shape appended to mesh.
 #[derive(Debug, Clone, Copy)]
 pub struct Vertices {
     pub ptr: usize,
     pub len: usize,
 }
 
+/// Range of index storage, e.g. indexed shape appended to mesh.
+#[derive(Debug, Clone, Copy)]
+pub struct Indices {
+    pub ptr: usize,
+    pub len: usize,
+}
+
+/// Bit pattern of vertex, equal vertices are shared by indexed shapes.
+type VertexKey = [u32; 8];
+
+impl Vertices {
+    /// Returns sub-range of len vertices starting at offset within this range.
+    pub fn slice(&self, offset: usize, len: usize) -> Vertices {
+        let offset = offset.min(self.len);
+        Vertices {
+            ptr: self.ptr + offset,
+            len: len.min(self.len - offset),
+        }
+    }
+
+    /// Returns range covering both ranges, e.g. consecutive shapes drawn by single call.
+    pub fn join(&self, other: Vertices) -> Vertices {
+        let ptr = self.ptr.min(other.ptr);
+        let end = (self.ptr + self.len).max(other.ptr + other.len);
+        Vertices {
+            ptr,
+            len: end - ptr,
+        }
+    }
+}
+
 impl Mesh {
     pub const RECT_VERTICES_N: usize = 6;
 
@@ -40,7 +94,140 @@ impl Mesh {
 
     pub fn add_polygon(&mut self, vertices: &[Vec2], color: impl Colors) -> Option<Vertices> {
         let color = color.to_vec4();
-        let mut vertices: Vec<Vertex> = vertices
+        self.add_polygon_colored(vertices, |_| color)
+    }
+
+    /// Adds polygon with vertex colors evaluated by gradient at vertex positions.
+    pub fn add_polygon_with_gradient(
+        &mut self,
+        vertices: &[Vec2],
+        gradient: &Gradient,
+    ) -> Option<Vertices> {
+        self.add_polygon_colored(vertices, |position| gradient.color_at(position))
+    }
+
+    /// Adds circle approximated by segments, UV spans bounding square.
+    pub fn add_circle(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        segments: usize,
+        color: impl Colors,
+    ) -> Option<Vertices> {
+        self.add_ellipse(center, [radius, radius], segments, color)
+    }
+
+    /// Adds ellipse approximated by segments, UV spans bounding rect.
+    pub fn add_ellipse(
+        &mut self,
+        center: Vec2,
+        radius: Vec2,
+        segments: usize,
+        color: impl Colors,
+    ) -> Option<Vertices> {
+        let points = arc_points(center, radius, 0.0..TAU, segments.max(3));
+        let triangles = fan_triangles(&points[..points.len() - 1]);
+        self.add_triangles_with_uv(&triangles, center.sub(radius), radius.mul(2.0), color)
+    }
+
+    /// Adds rect with corners rounded by radius, each corner approximated by segments.
+    pub fn add_rounded_rect(
+        &mut self,
+        start: Vec2,
+        size: Vec2,
+        radius: f32,
+        segments: usize,
+        color: impl Colors,
+    ) -> Option<Vertices> {
+        let points = rounded_rect_points(start, size, radius, segments);
+        let triangles = fan_triangles(&points);
+        self.add_triangles_with_uv(&triangles, start, size, color)
+    }
+
+    /// Adds ring between radius and radius minus thickness.
+    pub fn add_ring(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        thickness: f32,
+        segments: usize,
+        color: impl Colors,
+    ) -> Option<Vertices> {
+        self.add_arc(center, radius, thickness, 0.0..TAU, segments, color)
+    }
+
+    /// Adds arc between radius and radius minus thickness from start to end angle in radians,
+    /// UV spans bounding square of full circle.
+    pub fn add_arc(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        thickness: f32,
+        angles: Range<f32>,
+        segments: usize,
+        color: impl Colors,
+    ) -> Option<Vertices> {
+        let inner = (radius - thickness).max(0.0);
+        let segments = segments.max(1);
+        let outer = arc_points(center, [radius, radius], angles.clone(), segments);
+        let inner = arc_points(center, [inner, inner], angles, segments);
+        let mut triangles = Vec::with_capacity(segments * 6);
+        for n in 1..outer.len() {
+            let quad = [outer[n - 1], outer[n], inner[n], inner[n - 1]];
+            triangles.extend(fan_triangles(&quad));
+        }
+        let start = center.sub(radius);
+        self.add_triangles_with_uv(&triangles, start, [radius * 2.0; 2], color)
+    }
+
+    /// Adds triangle list, UV of vertices maps bounds of shape to unit square.
+    fn add_triangles_with_uv(
+        &mut self,
+        triangles: &[Vec2],
+        start: Vec2,
+        size: Vec2,
+        color: impl Colors,
+    ) -> Option<Vertices> {
+        let color = color.to_vec4();
+        let size = size.map(|value| if value == 0.0 { 1.0 } else { value });
+        let vertices: Vec<Vertex> = triangles
+            .iter()
+            .map(|position| Vertex {
+                position: *position,
+                color,
+                uv: position.sub(start).div(size),
+            })
+            .collect();
+        self.append(&vertices)
+    }
+
+    /// Adds rect as 4 vertices shared with previous indexed shapes and 6 indices.
+    pub fn add_rect_indexed(
+        &mut self,
+        indices: &mut Storage<u32>,
+        start: Vec2,
+        size: Vec2,
+        color: impl Colors,
+    ) -> Option<Indices> {
+        let a = start;
+        let b = start.add([size.x(), 0.0]);
+        let c = start.add(size);
+        let d = start.add([0.0, size.y()]);
+        self.add_polygon_indexed(indices, &[a, b, c, d], color)
+    }
+
+    /// Adds convex polygon as triangle fan of indices, vertices equal to vertices
+    /// of previous indexed shapes are not duplicated, e.g. corners of terrain cells.
+    ///
+    /// Indices are absolute, so mesh and index storage must be drawn from start.
+    pub fn add_polygon_indexed(
+        &mut self,
+        indices: &mut Storage<u32>,
+        vertices: &[Vec2],
+        color: impl Colors,
+    ) -> Option<Indices> {
+        let color = color.to_vec4();
+        let vertices: Vec<Vertex> = vertices
             .iter()
             .map(|position| Vertex {
                 position: *position,
@@ -48,6 +235,32 @@ impl Mesh {
                 uv: [0.0, 0.0],
             })
             .collect();
+        let (unique, corners, keys) = share_vertices(&self.shared, self.cursor, &vertices);
+        let triangles = triangle_fan(&corners);
+        if indices.len() + triangles.len() > indices.capacity() {
+            return None;
+        }
+        self.append(&unique)?;
+        self.shared.extend(keys);
+        let ptr = indices.extend(&triangles).ok()? as usize;
+        Some(Indices {
+            ptr,
+            len: triangles.len(),
+        })
+    }
+
+    fn add_polygon_colored<F>(&mut self, vertices: &[Vec2], color: F) -> Option<Vertices>
+    where
+        F: Fn(Vec2) -> Vec4,
+    {
+        let mut vertices: Vec<Vertex> = vertices
+            .iter()
+            .map(|position| Vertex {
+                position: *position,
+                color: color(*position),
+                uv: [0.0, 0.0],
+            })
+            .collect();
         if vertices.len() > 3 {
             // default renderer uses TRIANGLES_LIST mode
             let mut triangles = vec![];
@@ -63,7 +276,7 @@ impl Mesh {
 
     pub unsafe fn create(vulkan: &Vulkan, n: usize) -> Self {
         let device = vulkan.device.clone();
-        let frames = vulkan.swapchain.images.len();
+        let frames = FRAMES_PROCESSING_CONCURRENCY;
         let physical_device_memory = vulkan
             .instance
             .get_physical_device_memory_properties(vulkan.physical_device);
@@ -73,53 +286,141 @@ impl Mesh {
             frames,
             physical_device_memory,
             n * std::mem::size_of::<Vertex>(),
+            &[],
         );
         let vertices = vec![Vertex::default(); n];
         Self {
             buffers,
             device,
+            physical_device_memory,
             vertices,
             cursor: 0,
+            capacities: vec![n; frames],
+            uploaded: vec![0; frames],
+            limit: None,
+            shared: HashMap::new(),
+            retired: vulkan.retire_queue(),
         }
     }
 
+    /// Limits number of vertices mesh may grow to, appends beyond limit fail.
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    pub fn len(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cursor == 0
+    }
+
     pub fn input_state(&self) -> Option<PipelineVertexInputStateCreateInfo> {
         Some(Vertex::input_state())
     }
 
+    /// Appends vertices, returns None if mesh limit is reached.
+    ///
+    /// GPU buffers are reallocated to fit vertices on next upload.
     pub fn append(&mut self, vertices: &[Vertex]) -> Option<Vertices> {
         let ptr = self.cursor;
         let len = vertices.len();
+        if let Some(limit) = self.limit {
+            if ptr + len > limit {
+                return None;
+            }
+        }
         if ptr + len > self.vertices.len() {
-            return None;
+            let mut capacity = self.vertices.len().max(Self::RECT_VERTICES_N);
+            while capacity < ptr + len {
+                capacity *= 2;
+            }
+            if let Some(limit) = self.limit {
+                capacity = capacity.min(limit);
+            }
+            self.vertices.resize(capacity, Vertex::default());
         }
         self.vertices[ptr..ptr + len].copy_from_slice(vertices);
         self.cursor = ptr + len;
         Some(Vertices { ptr, len })
     }
 
+    /// Uploads and keeps static geometry in buffers of all frames.
+    ///
+    /// Must not be called while frames in flight may read mesh, e.g. on loading.
     pub fn update_all(&mut self) {
-        // TODO: single buffer, no swapchain
-        for chain in 0..self.buffers.len() {
-            self.update(chain);
+        for frame in 0..self.buffers.len() {
+            self.upload(frame);
         }
     }
 
+    /// Uploads vertices appended this frame and starts next frame geometry.
     pub fn update(&mut self, frame: usize) -> usize {
-        let value = self.vertices.as_slice();
+        let count = self.upload(frame);
+        self.clear();
+        count
+    }
+
+    /// Uploads vertices appended since last upload to buffer of frame, keeps geometry.
+    ///
+    /// Buffer is reallocated if vertices outgrow it, outgrown buffer is destroyed
+    /// once frames in flight complete.
+    pub fn upload(&mut self, frame: usize) -> usize {
         let count = self.cursor;
-        self.cursor = 0;
-        self.update_from(frame, value);
+        if count > self.capacities[frame] {
+            let capacity = self.vertices.len();
+            let buffer = unsafe {
+                create_buffers(
+                    BufferUsageFlags::VERTEX_BUFFER,
+                    &self.device,
+                    1,
+                    self.physical_device_memory,
+                    capacity * std::mem::size_of::<Vertex>(),
+                    &[],
+                )
+            }
+            .pop()
+            .expect("vertex buffer must be created");
+            let previous = std::mem::replace(&mut self.buffers[frame], buffer);
+            self.retired.borrow_mut().retire(Retired::Buffer(previous));
+            self.capacities[frame] = capacity;
+            self.uploaded[frame] = 0;
+        }
+        let uploaded = self.uploaded[frame].min(count);
+        if uploaded < count {
+            self.upload_range(frame, uploaded, &self.vertices[uploaded..count]);
+        }
+        self.uploaded[frame] = count;
         count
     }
 
+    /// Discards appended vertices, e.g. to rebuild static geometry.
+    pub fn clear(&mut self) {
+        self.cursor = 0;
+        self.uploaded.fill(0);
+        self.shared.clear();
+    }
+
     pub fn update_from(&self, frame: usize, value: &[Vertex]) {
+        self.upload_range(frame, 0, value);
+    }
+
+    fn upload_range(&self, frame: usize, offset: usize, value: &[Vertex]) {
+        if value.is_empty() {
+            return;
+        }
+        record_upload(value.len() * std::mem::size_of::<Vertex>());
         unsafe {
             let memory = self
                 .device
                 .map_memory(
                     self.buffers[frame].memory,
-                    0,
+                    (offset * std::mem::size_of::<Vertex>()) as u64,
                     (value.len() * std::mem::size_of::<Vertex>()) as u64,
                     MemoryMapFlags::empty(),
                 )
@@ -134,6 +435,99 @@ impl Mesh {
             buffer.destroy(&self.device);
         }
     }
+
+    /// Takes vertex buffers out of mesh, so they can be destroyed later.
+    pub(crate) fn take_buffers(self) -> Vec<MemoryBuffer> {
+        self.buffers
+    }
+}
+
+fn vertex_key(vertex: &Vertex) -> VertexKey {
+    let [x, y] = vertex.position;
+    let [r, g, b, a] = vertex.color;
+    let [u, v] = vertex.uv;
+    [x, y, r, g, b, a, u, v].map(f32::to_bits)
+}
+
+/// Returns vertices not shared yet, indices of polygon corners and keys of new vertices
+/// once they are appended to mesh at first index.
+fn share_vertices(
+    shared: &HashMap<VertexKey, u32>,
+    first: usize,
+    vertices: &[Vertex],
+) -> (Vec<Vertex>, Vec<u32>, Vec<(VertexKey, u32)>) {
+    let mut unique = vec![];
+    let mut corners = Vec::with_capacity(vertices.len());
+    let mut keys: Vec<(VertexKey, u32)> = vec![];
+    for vertex in vertices {
+        let key = vertex_key(vertex);
+        let index = match shared.get(&key) {
+            Some(index) => *index,
+            None => match keys.iter().find(|(other, _)| *other == key) {
+                Some((_, index)) => *index,
+                None => {
+                    let index = (first + unique.len()) as u32;
+                    unique.push(*vertex);
+                    keys.push((key, index));
+                    index
+                }
+            },
+        };
+        corners.push(index);
+    }
+    (unique, corners, keys)
+}
+
+/// Returns segments + 1 points of elliptic arc from start to end angle.
+fn arc_points(center: Vec2, radius: Vec2, angles: Range<f32>, segments: usize) -> Vec<Vec2> {
+    let step = (angles.end - angles.start) / segments as f32;
+    (0..=segments)
+        .map(|n| {
+            let angle = angles.start + step * n as f32;
+            center.add([angle.cos(), angle.sin()].mul(radius))
+        })
+        .collect()
+}
+
+/// Returns outline of rounded rect clockwise in screen space starting from top left corner.
+fn rounded_rect_points(start: Vec2, size: Vec2, radius: f32, segments: usize) -> Vec<Vec2> {
+    let radius = radius.min(size.x() / 2.0).min(size.y() / 2.0).max(0.0);
+    let [x, y] = start;
+    let [w, h] = size;
+    let corners = [
+        ([x + radius, y + radius], PI),
+        ([x + w - radius, y + radius], PI + FRAC_PI_2),
+        ([x + w - radius, y + h - radius], 0.0),
+        ([x + radius, y + h - radius], FRAC_PI_2),
+    ];
+    let mut points = vec![];
+    for (center, angle) in corners {
+        let arc = angle..angle + FRAC_PI_2;
+        points.extend(arc_points(center, [radius, radius], arc, segments.max(1)));
+    }
+    points
+}
+
+/// Triangulates convex polygon to triangle list.
+fn fan_triangles(points: &[Vec2]) -> Vec<Vec2> {
+    let mut triangles = vec![];
+    for n in 2..points.len() {
+        triangles.push(points[0]);
+        triangles.push(points[n - 1]);
+        triangles.push(points[n]);
+    }
+    triangles
+}
+
+/// Triangulates convex polygon corners, triangle list mode is used by default renderer.
+fn triangle_fan(corners: &[u32]) -> Vec<u32> {
+    let mut triangles = vec![];
+    for n in 2..corners.len() {
+        triangles.push(corners[0]);
+        triangles.push(corners[n - 1]);
+        triangles.push(corners[n]);
+    }
+    triangles
 }
 
 pub unsafe fn create_vertex_buffer(
@@ -160,8 +554,7 @@ pub unsafe fn create_vertex_buffer(
     let memory_info = MemoryAllocateInfo::builder()
         .allocation_size(requirements.size)
         .memory_type_index(memory_type_index);
-    let memory = device
-        .allocate_memory(&memory_info, None)
+    let memory = allocate_memory(device, &memory_info, MemoryKind::Buffer)
         .expect("vertex buffer memory must be allocated");
 
     device
@@ -176,8 +569,73 @@ pub unsafe fn create_vertex_buffer(
     MemoryBuffer { handle, memory }
 }
 
+pub type VertexAttribute = VertexInputAttributeDescription;
+
+pub type VertexBinding = VertexInputBindingDescription;
+
+/// Maps Rust field type to Vulkan vertex attribute format.
+pub trait VertexFormat {
+    const FORMAT: Format;
+}
+
+macro_rules! vertex_format {
+    ($($value:ty => $format:ident),* $(,)?) => {
+        $(
+            impl VertexFormat for $value {
+                const FORMAT: Format = Format::$format;
+            }
+        )*
+    };
+}
+
+vertex_format! {
+    f32 => R32_SFLOAT,
+    [f32; 2] => R32G32_SFLOAT,
+    [f32; 3] => R32G32B32_SFLOAT,
+    [f32; 4] => R32G32B32A32_SFLOAT,
+    u32 => R32_UINT,
+    [u32; 2] => R32G32_UINT,
+    [u32; 3] => R32G32B32_UINT,
+    [u32; 4] => R32G32B32A32_UINT,
+    i32 => R32_SINT,
+    [i32; 2] => R32G32_SINT,
+    [i32; 3] => R32G32B32_SINT,
+    [i32; 4] => R32G32B32A32_SINT,
+}
+
+/// Describes vertex input of `#[repr(C)]` struct, use `#[derive(VertexLayout)]`
+/// to generate it instead of maintaining offsets and stride by hand.
+pub trait VertexLayout {
+    const ATTRIBUTES: &'static [VertexAttribute];
+    const BINDINGS: &'static [VertexBinding];
+
+    fn input_state() -> PipelineVertexInputStateCreateInfo {
+        PipelineVertexInputStateCreateInfo::builder()
+            .vertex_binding_descriptions(Self::BINDINGS)
+            .vertex_attribute_descriptions(Self::ATTRIBUTES)
+            .build()
+    }
+}
+
+pub const fn vertex_attribute(location: u32, format: Format, offset: u32) -> VertexAttribute {
+    VertexInputAttributeDescription {
+        location,
+        binding: 0,
+        format,
+        offset,
+    }
+}
+
+pub const fn vertex_binding(stride: u32) -> VertexBinding {
+    VertexInputBindingDescription {
+        binding: 0,
+        stride,
+        input_rate: VertexInputRate::VERTEX,
+    }
+}
+
 #[repr(C)]
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, VertexLayout)]
 pub struct Vertex {
     pub position: Vec2,
     pub color: Vec4,
@@ -217,38 +675,71 @@ impl Vertex {
             uv: [0.0, 0.0],
         },
     ];
+}
 
-    const ATTRIBUTES: [VertexInputAttributeDescription; 3] = [
-        VertexInputAttributeDescription {
-            location: 0,
-            binding: 0,
-            format: Format::R32G32_SFLOAT,
-            offset: 0,
-        },
-        VertexInputAttributeDescription {
-            location: 1,
-            binding: 0,
-            format: Format::R32G32B32A32_SFLOAT,
-            offset: 8,
-        },
-        VertexInputAttributeDescription {
-            location: 2,
-            binding: 0,
-            format: Format::R32G32_SFLOAT,
-            offset: 24,
-        },
-    ];
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::variables::mesh::{rounded_rect_points, share_vertices, triangle_fan};
+    use crate::{Vertex, VertexLayout, Vertices};
+    use std::collections::HashMap;
+    use vulkanalia::vk::Format;
 
-    const BINDING: [VertexInputBindingDescription; 1] = [VertexInputBindingDescription {
-        binding: 0,
-        stride: 32,
-        input_rate: VertexInputRate::VERTEX,
-    }];
+    #[test]
+    pub fn test_vertex_layout_derive() {
+        let offsets: Vec<u32> = Vertex::ATTRIBUTES.iter().map(|attr| attr.offset).collect();
+        let formats: Vec<Format> = Vertex::ATTRIBUTES.iter().map(|attr| attr.format).collect();
+        assert_eq!(offsets, vec![0, 8, 24]);
+        assert_eq!(
+            formats,
+            vec![
+                Format::R32G32_SFLOAT,
+                Format::R32G32B32A32_SFLOAT,
+                Format::R32G32_SFLOAT
+            ]
+        );
+        assert_eq!(Vertex::BINDINGS[0].stride, 32);
+    }
 
-    pub fn input_state() -> PipelineVertexInputStateCreateInfo {
-        PipelineVertexInputStateCreateInfo::builder()
-            .vertex_binding_descriptions(&Self::BINDING)
-            .vertex_attribute_descriptions(&Self::ATTRIBUTES)
-            .build()
+    #[test]
+    pub fn test_vertices_slice_and_join() {
+        let rect = Vertices { ptr: 12, len: 6 };
+        let triangle = rect.slice(3, 3);
+        assert_eq!((triangle.ptr, triangle.len), (15, 3));
+        let clamped = rect.slice(4, 10);
+        assert_eq!((clamped.ptr, clamped.len), (16, 2));
+        let joined = Vertices { ptr: 0, len: 6 }.join(rect);
+        assert_eq!((joined.ptr, joined.len), (0, 18));
+    }
+
+    #[test]
+    pub fn test_shared_vertices_of_adjacent_cells() {
+        let vertex = |x, y| Vertex {
+            position: [x, y],
+            ..Vertex::default()
+        };
+        let left = [vertex(0.0, 0.0), vertex(1.0, 0.0), vertex(1.0, 1.0), vertex(0.0, 1.0)];
+        let (unique, corners, keys) = share_vertices(&HashMap::new(), 0, &left);
+        assert_eq!(unique.len(), 4);
+        assert_eq!(triangle_fan(&corners), vec![0, 1, 2, 0, 2, 3]);
+        let shared = HashMap::from_iter(keys);
+        let right = [vertex(1.0, 0.0), vertex(2.0, 0.0), vertex(2.0, 1.0), vertex(1.0, 1.0)];
+        let (unique, corners, _) = share_vertices(&shared, 4, &right);
+        assert_eq!(unique.len(), 2);
+        assert_eq!(triangle_fan(&corners), vec![1, 4, 5, 1, 5, 2]);
+    }
+
+    #[test]
+    pub fn test_rounded_rect_outline() {
+        let points = rounded_rect_points([10.0, 20.0], [100.0, 40.0], 50.0, 4);
+        assert_eq!(points.len(), 20);
+        let near = |a: [f32; 2], b: [f32; 2]| (a[0] - b[0]).abs() + (a[1] - b[1]).abs() < 1e-3;
+        assert!(near(points[0], [10.0, 40.0]));
+        assert!(near(points[4], [30.0, 20.0]));
+        assert!(near(points[5], [90.0, 20.0]));
+        assert!(near(points[15], [30.0, 60.0]));
+        for [x, y] in points {
+            assert!((10.0 - 1e-3..=110.0 + 1e-3).contains(&x));
+            assert!((20.0 - 1e-3..=60.0 + 1e-3).contains(&y));
+        }
     }
 }
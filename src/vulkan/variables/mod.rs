@@ -1,11 +1,15 @@
+pub use device_storage::*;
 pub use mesh::*;
 pub use storage::*;
+pub use texel_buffer::*;
 pub use textures::*;
 pub use uniform::*;
 pub use variable::*;
 
+mod device_storage;
 mod mesh;
 mod storage;
+mod texel_buffer;
 mod textures;
 mod uniform;
 mod variable;
@@ -1,11 +1,13 @@
+use crate::vulkan::stats::record_upload;
 use crate::vulkan::{
     create_buffers, create_descriptor_pool, create_descriptor_set_layout, create_descriptors,
-    MemoryBuffer, Vulkan,
+    MemoryBuffer, Vulkan, FRAMES_PROCESSING_CONCURRENCY,
 };
-use crate::Variable;
-use log::{error, info};
+use crate::{Variable, VariableError};
+use log::info;
 use std::any::type_name;
 use std::marker::PhantomData;
+use std::ops::Range;
 use vulkanalia::vk::{
     Buffer, BufferUsageFlags, CopyDescriptorSet, DescriptorBufferInfo, DescriptorSet,
     DescriptorSetLayout, DescriptorType, DeviceV1_0, HasBuilder, InstanceV1_0, MemoryMapFlags,
@@ -20,30 +22,43 @@ use vulkanalia::{vk, Device};
 ///     Element elements[];
 /// } canvas;
 /// ```
+///
+/// Only elements written since buffer of frame in flight was last updated are uploaded,
+/// so mostly static collections kept by [`Storage::update`] cost nothing to refresh.
 pub struct Storage<T> {
     pub(crate) buffers: Vec<MemoryBuffer>,
     pub(crate) range: u64,
     device: Device,
     collection: Vec<T>,
     cursor: usize,
+    /// Span of elements written since last update of buffer, per frame in flight.
+    dirty: Vec<Option<Range<usize>>>,
 }
 
 impl<T: Default + Clone + Copy> Storage<T> {
     pub unsafe fn create(vulkan: &Vulkan, n: usize) -> Self {
         let device = &vulkan.device;
-        let frames = vulkan.swapchain.images.len();
+        let frames = FRAMES_PROCESSING_CONCURRENCY;
         let physical_device_memory = vulkan
             .instance
             .get_physical_device_memory_properties(vulkan.physical_device);
         let size = size_of::<T>();
         let range = size * n;
         info!("Creates storage buffers n={n} size={size} range={range}");
+        // storage may also be bound as index buffer, GPU-built draw list or texel buffer
+        let usage = BufferUsageFlags::STORAGE_BUFFER
+            | BufferUsageFlags::INDEX_BUFFER
+            | BufferUsageFlags::INDIRECT_BUFFER
+            | BufferUsageFlags::UNIFORM_TEXEL_BUFFER
+            | BufferUsageFlags::STORAGE_TEXEL_BUFFER;
+        let families = vulkan.buffer_families();
         let buffers = create_buffers(
-            BufferUsageFlags::STORAGE_BUFFER,
+            usage,
             device,
             frames,
             physical_device_memory,
             range,
+            &families,
         );
         Self {
             buffers,
@@ -51,56 +66,114 @@ impl<T: Default + Clone + Copy> Storage<T> {
             collection: vec![T::default(); n],
             cursor: 0,
             range: range as u64,
+            dirty: vec![None; frames],
         }
     }
 
-    pub fn push(&mut self, value: T) -> u32 {
+    pub fn push(&mut self, value: T) -> Result<u32, VariableError> {
         if self.cursor >= self.collection.len() {
-            error!(
-                "enable to push, storage limit {} exceeded",
-                self.collection.len()
-            );
-            return 0;
+            return Err(VariableError::StorageOverflow {
+                limit: self.collection.len(),
+            });
         }
         self.collection[self.cursor] = value;
+        self.mark_dirty(self.cursor..self.cursor + 1);
         self.cursor += 1;
-        (self.cursor - 1) as u32
+        Ok((self.cursor - 1) as u32)
     }
 
-    pub fn extend(&mut self, values: &[T]) -> u32 {
+    pub fn extend(&mut self, values: &[T]) -> Result<u32, VariableError> {
         let count = values.len();
-        if self.cursor + count >= self.collection.len() {
-            error!(
-                "unable to extend, storage limit {} exceeded",
-                self.collection.len()
-            );
-            return 0;
+        if self.cursor + count > self.collection.len() {
+            return Err(VariableError::StorageOverflow {
+                limit: self.collection.len(),
+            });
         }
         self.collection[self.cursor..self.cursor + count].copy_from_slice(values);
+        self.mark_dirty(self.cursor..self.cursor + count);
         self.cursor += count;
-        (self.cursor - count) as u32
+        Ok((self.cursor - count) as u32)
+    }
+
+    /// Replaces written element, keeps other elements untouched.
+    pub fn set(&mut self, index: usize, value: T) -> Result<(), VariableError> {
+        if index >= self.cursor {
+            return Err(VariableError::StorageOverflow { limit: self.cursor });
+        }
+        self.collection[index] = value;
+        self.mark_dirty(index..index + 1);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.collection.len()
     }
 
     pub fn is_empty(&self) -> bool {
         self.cursor == 0
     }
 
+    /// Returns written elements to change in place, e.g. to sort them before upload.
+    pub fn written_mut(&mut self) -> &mut [T] {
+        self.mark_dirty(0..self.cursor);
+        &mut self.collection[..self.cursor]
+    }
+
+    /// Discards written elements, e.g. to rebuild static collection.
+    pub fn clear(&mut self) {
+        self.cursor = 0;
+    }
+
+    /// Uploads elements written this frame and starts next collection.
     pub fn take_and_update(&mut self, frame: usize) -> usize {
-        let value = self.collection.as_slice();
-        let count = self.cursor;
+        let count = self.update(frame);
         self.cursor = 0;
-        self.update_from(frame, value);
+        count
+    }
+
+    /// Uploads written elements changed since last update of frame buffer, keeps collection.
+    pub fn update(&mut self, frame: usize) -> usize {
+        let count = self.cursor;
+        if let Some(dirty) = self.dirty[frame].take() {
+            // elements beyond count are not drawn and marked dirty once written again
+            let end = dirty.end.min(count);
+            if dirty.start < end {
+                self.upload(frame, dirty.start, &self.collection[dirty.start..end]);
+            }
+        }
         count
     }
 
     pub fn update_from(&self, frame: usize, value: &[T]) {
+        self.upload(frame, 0, value);
+    }
+
+    fn mark_dirty(&mut self, span: Range<usize>) {
+        for dirty in self.dirty.iter_mut() {
+            *dirty = match dirty.take() {
+                Some(dirty) => Some(dirty.start.min(span.start)..dirty.end.max(span.end)),
+                None => Some(span.clone()),
+            };
+        }
+    }
+
+    fn upload(&self, frame: usize, offset: usize, value: &[T]) {
+        if value.is_empty() {
+            return;
+        }
+        let size = size_of_val(value);
+        record_upload(size);
         unsafe {
             let memory = self
                 .device
                 .map_memory(
                     self.buffers[frame].memory,
-                    0,
-                    (value.len() * size_of::<T>()) as u64,
+                    (offset * size_of::<T>()) as u64,
+                    size as u64,
                     MemoryMapFlags::empty(),
                 )
                 .expect("memory must be mapped");
@@ -116,7 +189,7 @@ impl<T: Default + Clone + Copy> Storage<T> {
             let bindings = vec![(
                 binding,
                 DescriptorType::STORAGE_BUFFER,
-                ShaderStageFlags::FRAGMENT | ShaderStageFlags::VERTEX,
+                ShaderStageFlags::FRAGMENT | ShaderStageFlags::VERTEX | ShaderStageFlags::COMPUTE,
                 1,
             )];
             let pool = create_descriptor_pool(device, &bindings, frames);
@@ -0,0 +1,217 @@
+use crate::vulkan::{
+    create_descriptor_pool, create_descriptor_set_layout, create_descriptors, Vulkan,
+};
+use crate::{Storage, Variable, VariableError};
+use log::info;
+use std::mem::size_of;
+use vulkanalia::vk::{DeviceV1_0, HasBuilder, InstanceV1_0};
+use vulkanalia::{vk, Device};
+
+/// Represents GLSL texel buffer, table of formatted elements fetched by index.
+///
+/// Unlike storage buffer, elements are converted by format on fetch, so very large
+/// per-instance tables bind as single view on drivers with small storage ranges.
+/// Element must be single texel of format, e.g. `[f32; 4]` for `R32G32B32A32_SFLOAT`.
+///
+/// ```glsl
+/// layout (set = 2, binding = 0) uniform samplerBuffer instances;
+/// // vec4 instance = texelFetch(instances, gl_InstanceIndex);
+/// ```
+pub struct TexelBuffer<T> {
+    storage: Storage<T>,
+    views: Vec<vk::BufferView>,
+    kind: vk::DescriptorType,
+    device: Device,
+}
+
+impl<T: Default + Clone + Copy> TexelBuffer<T> {
+    /// Creates buffer of n elements in format, kind is uniform or storage texel buffer.
+    ///
+    /// Fails if format is not supported by device, element is not single texel of format
+    /// or n exceeds texel buffer limit of device.
+    pub unsafe fn create(
+        vulkan: &Vulkan,
+        n: usize,
+        format: vk::Format,
+        kind: vk::DescriptorType,
+    ) -> Result<Self, VariableError> {
+        let device = &vulkan.device;
+        let instance = &vulkan.instance;
+        let limits = instance
+            .get_physical_device_properties(vulkan.physical_device)
+            .limits;
+        info!("Creates texel buffer {kind:?} format={format:?} n={n}");
+        let limit = limits.max_texel_buffer_elements as usize;
+        if n > limit {
+            return Err(VariableError::StorageOverflow { limit });
+        }
+        let texel = texel_size(format).ok_or(VariableError::UnsupportedFormat { format })?;
+        if size_of::<T>() != texel {
+            return Err(VariableError::ElementSize { texel });
+        }
+        let features = instance
+            .get_physical_device_format_properties(vulkan.physical_device, format)
+            .buffer_features;
+        let feature = match kind {
+            vk::DescriptorType::STORAGE_TEXEL_BUFFER => {
+                vk::FormatFeatureFlags::STORAGE_TEXEL_BUFFER
+            }
+            _ => vk::FormatFeatureFlags::UNIFORM_TEXEL_BUFFER,
+        };
+        if !features.contains(feature) {
+            return Err(VariableError::UnsupportedFormat { format });
+        }
+        let storage = Storage::create(vulkan, n);
+        let views = storage
+            .buffers
+            .iter()
+            .map(|buffer| {
+                let info = vk::BufferViewCreateInfo::builder()
+                    .buffer(buffer.handle)
+                    .format(format)
+                    .offset(0)
+                    .range(storage.range);
+                device
+                    .create_buffer_view(&info, None)
+                    .expect("buffer view must be created")
+            })
+            .collect();
+        Ok(Self {
+            storage,
+            views,
+            kind,
+            device: device.clone(),
+        })
+    }
+
+    pub fn push(&mut self, value: T) -> Result<u32, VariableError> {
+        self.storage.push(value)
+    }
+
+    pub fn extend(&mut self, values: &[T]) -> Result<u32, VariableError> {
+        self.storage.extend(values)
+    }
+
+    pub fn set(&mut self, index: usize, value: T) -> Result<(), VariableError> {
+        self.storage.set(index, value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.storage.clear();
+    }
+
+    /// Uploads elements written this frame and starts next table, see [`Storage`].
+    pub fn take_and_update(&mut self, frame: usize) -> usize {
+        self.storage.take_and_update(frame)
+    }
+
+    /// Uploads changed elements and keeps table.
+    pub fn update(&mut self, frame: usize) -> usize {
+        self.storage.update(frame)
+    }
+
+    pub fn layout(&self, set: u32, binding: u32) -> Variable {
+        let device = &self.device;
+        let frames = self.views.len();
+        unsafe {
+            let bindings = vec![(
+                binding,
+                self.kind,
+                vk::ShaderStageFlags::FRAGMENT
+                    | vk::ShaderStageFlags::VERTEX
+                    | vk::ShaderStageFlags::COMPUTE,
+                1,
+            )];
+            let pool = create_descriptor_pool(device, &bindings, frames);
+            let layout = create_descriptor_set_layout(device, bindings);
+            let descriptors = create_descriptors(device, pool, layout, frames);
+            for (set, view) in descriptors.iter().zip(&self.views) {
+                let views = [*view];
+                let write = vk::WriteDescriptorSet::builder()
+                    .dst_set(*set)
+                    .dst_binding(binding)
+                    .dst_array_element(0)
+                    .descriptor_type(self.kind)
+                    .texel_buffer_view(&views);
+                device.update_descriptor_sets(&[write], &[] as &[vk::CopyDescriptorSet]);
+            }
+            Variable {
+                set,
+                binding,
+                layout,
+                descriptors,
+            }
+        }
+    }
+
+    /// Destroys views and buffers, must be called once frames in flight complete.
+    pub fn destroy(&self) {
+        unsafe {
+            for view in &self.views {
+                self.device.destroy_buffer_view(*view, None);
+            }
+        }
+        for buffer in &self.storage.buffers {
+            buffer.destroy(&self.device);
+        }
+    }
+}
+
+/// Returns bytes of single texel of format, None for formats not used by texel buffers,
+/// e.g. compressed or depth formats.
+fn texel_size(format: vk::Format) -> Option<usize> {
+    let size = match format {
+        vk::Format::R8_UNORM | vk::Format::R8_SNORM | vk::Format::R8_UINT | vk::Format::R8_SINT => {
+            1
+        }
+        vk::Format::R8G8_UNORM
+        | vk::Format::R8G8_SNORM
+        | vk::Format::R8G8_UINT
+        | vk::Format::R8G8_SINT
+        | vk::Format::R16_UNORM
+        | vk::Format::R16_SNORM
+        | vk::Format::R16_UINT
+        | vk::Format::R16_SINT
+        | vk::Format::R16_SFLOAT => 2,
+        vk::Format::R8G8B8A8_UNORM
+        | vk::Format::R8G8B8A8_SNORM
+        | vk::Format::R8G8B8A8_UINT
+        | vk::Format::R8G8B8A8_SINT
+        | vk::Format::B8G8R8A8_UNORM
+        | vk::Format::A2B10G10R10_UNORM_PACK32
+        | vk::Format::A2B10G10R10_UINT_PACK32
+        | vk::Format::B10G11R11_UFLOAT_PACK32
+        | vk::Format::R16G16_UNORM
+        | vk::Format::R16G16_SNORM
+        | vk::Format::R16G16_UINT
+        | vk::Format::R16G16_SINT
+        | vk::Format::R16G16_SFLOAT
+        | vk::Format::R32_UINT
+        | vk::Format::R32_SINT
+        | vk::Format::R32_SFLOAT => 4,
+        vk::Format::R16G16B16A16_UNORM
+        | vk::Format::R16G16B16A16_SNORM
+        | vk::Format::R16G16B16A16_UINT
+        | vk::Format::R16G16B16A16_SINT
+        | vk::Format::R16G16B16A16_SFLOAT
+        | vk::Format::R32G32_UINT
+        | vk::Format::R32G32_SINT
+        | vk::Format::R32G32_SFLOAT => 8,
+        vk::Format::R32G32B32_UINT | vk::Format::R32G32B32_SINT | vk::Format::R32G32B32_SFLOAT => {
+            12
+        }
+        vk::Format::R32G32B32A32_UINT
+        | vk::Format::R32G32B32A32_SINT
+        | vk::Format::R32G32B32A32_SFLOAT => 16,
+        _ => return None,
+    };
+    Some(size)
+}
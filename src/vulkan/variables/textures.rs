@@ -1,5 +1,10 @@
-use crate::{Texture, Variable};
+use crate::vulkan::memory::record_descriptor_sets;
+use crate::vulkan::FRAMES_PROCESSING_CONCURRENCY;
+use crate::{Texture, Variable, VariableError};
 use log::info;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::{Rc, Weak};
 use vulkanalia::vk::{
     DescriptorPoolCreateFlags, DescriptorSet, DescriptorSetLayout, DescriptorSetLayoutCreateFlags,
     DescriptorType, DeviceV1_0, HasBuilder, Sampler, ShaderStageFlags,
@@ -11,13 +16,14 @@ use vulkanalia::{vk, Device};
 /// #extension GL_EXT_nonuniform_qualifier: require
 /// layout (set = 1, binding = 0) uniform sampler2D textures[];
 /// ```
+///
+/// Slots of destroyed textures are released and reused once frames in flight complete.
 pub struct Textures {
     pub(crate) slot: u32,
     pub(crate) binding: u32,
-    max_descriptors: u32,
     layout: DescriptorSetLayout,
     set: DescriptorSet,
-    textures: Vec<Texture>,
+    slots: Rc<RefCell<TextureSlots>>,
     device: Device,
 }
 
@@ -30,9 +36,30 @@ impl Textures {
         self.set
     }
 
-    pub fn create(slot: u32, binding: u32, device: &Device) -> Self {
+    /// Returns number of textures stored in array.
+    pub fn len(&self) -> usize {
+        self.slots.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Releases slot of texture no longer drawn, e.g. evicted from cache.
+    ///
+    /// Slots of textures destroyed by graphics are released automatically.
+    pub fn release(&mut self, texture: Texture) {
+        self.slots.borrow_mut().release(texture);
+    }
+
+    pub(crate) fn slots(&self) -> Weak<RefCell<TextureSlots>> {
+        Rc::downgrade(&self.slots)
+    }
+
+    /// Creates array of up to max descriptors textures, see [`crate::GraphicsConfig`].
+    pub fn create(slot: u32, binding: u32, device: &Device, max_descriptors: u32) -> Self {
         info!("Creates bindless texture, layout(set = {slot}, binding = {binding})");
-        let max_descriptors = 256;
+        info!("Bindless texture capacity is {max_descriptors}");
         // layout
         let bindings = [vk::DescriptorSetLayoutBinding::builder()
             .binding(binding)
@@ -82,49 +109,167 @@ impl Textures {
                 .allocate_descriptor_sets(&descriptors)
                 .expect("descriptor sets must be created")
         };
+        record_descriptor_sets(descriptors.len());
         Self {
             slot,
             binding,
-            max_descriptors,
             layout,
             set: descriptors[0],
-            textures: vec![],
+            slots: Rc::new(RefCell::new(TextureSlots::new(max_descriptors))),
             device: device.clone(),
         }
     }
 
-    pub fn store(&mut self, texture: Texture, sampler: Sampler) -> u32 {
-        match self
-            .textures
+    pub fn store(&mut self, texture: Texture, sampler: Sampler) -> Result<u32, VariableError> {
+        let mut slots = self.slots.borrow_mut();
+        if let Some(index) = slots.find(texture) {
+            return Ok(index);
+        }
+        let index = slots
+            .acquire(texture)
+            .ok_or(VariableError::TexturesExhausted { limit: slots.limit })?;
+        let image = [vk::DescriptorImageInfo::builder()
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(texture.view)
+            .sampler(sampler)
+            .build()];
+        let write = vk::WriteDescriptorSet::builder()
+            .dst_set(self.set)
+            .dst_binding(self.binding)
+            .dst_array_element(index)
+            .descriptor_type(DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(&image)
+            .build();
+        let writes = [write];
+        unsafe {
+            self.device
+                .update_descriptor_sets(&writes, &[] as &[vk::CopyDescriptorSet]);
+        }
+        Ok(index)
+    }
+}
+
+/// Assigns array indices to textures, released index is reused once frames in flight
+/// which may still sample previous texture complete.
+pub(crate) struct TextureSlots {
+    limit: u32,
+    textures: Vec<Option<Texture>>,
+    released: VecDeque<(u64, u32)>,
+    frame: u64,
+}
+
+impl TextureSlots {
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            textures: vec![],
+            released: VecDeque::new(),
+            frame: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.textures.iter().flatten().count()
+    }
+
+    pub fn find(&self, texture: Texture) -> Option<u32> {
+        self.textures
             .iter()
-            .position(|record| record.image == texture.image)
-        {
-            None => {
-                let index = self.textures.len() as u32;
-                if index == self.max_descriptors {
-                    panic!("unable to store texture, all variables are used up")
-                }
-                let image = [vk::DescriptorImageInfo::builder()
-                    .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-                    .image_view(texture.view)
-                    .sampler(sampler)
-                    .build()];
-                let write = vk::WriteDescriptorSet::builder()
-                    .dst_set(self.set)
-                    .dst_binding(self.binding)
-                    .dst_array_element(index)
-                    .descriptor_type(DescriptorType::COMBINED_IMAGE_SAMPLER)
-                    .image_info(&image)
-                    .build();
-                let writes = [write];
-                unsafe {
-                    self.device
-                        .update_descriptor_sets(&writes, &[] as &[vk::CopyDescriptorSet]);
-                }
-                self.textures.push(texture);
+            .position(|record| *record == Some(texture))
+            .map(|index| index as u32)
+    }
+
+    /// Returns free index for texture, None if all indices are taken.
+    pub fn acquire(&mut self, texture: Texture) -> Option<u32> {
+        let frames = FRAMES_PROCESSING_CONCURRENCY as u64;
+        let index = match self.released.front() {
+            Some((released, index)) if released + frames <= self.frame => {
+                let index = *index;
+                self.released.pop_front();
                 index
             }
-            Some(index) => index as u32,
+            _ if self.textures.len() < self.limit as usize => {
+                self.textures.push(None);
+                (self.textures.len() - 1) as u32
+            }
+            _ => return None,
+        };
+        self.textures[index as usize] = Some(texture);
+        Some(index)
+    }
+
+    pub fn release(&mut self, texture: Texture) {
+        if let Some(index) = self.find(texture) {
+            self.textures[index as usize] = None;
+            self.released.push_back((self.frame, index));
+        }
+    }
+
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+}
+
+/// Releases slots of destroyed textures in all bindless arrays created by graphics.
+#[derive(Default)]
+pub(crate) struct TextureSlotsRegistry {
+    slots: RefCell<Vec<Weak<RefCell<TextureSlots>>>>,
+}
+
+impl TextureSlotsRegistry {
+    pub fn register(&self, slots: Weak<RefCell<TextureSlots>>) {
+        self.slots.borrow_mut().push(slots);
+    }
+
+    pub fn release(&self, texture: Texture) {
+        for slots in self.slots.borrow().iter() {
+            if let Some(slots) = slots.upgrade() {
+                slots.borrow_mut().release(texture);
+            }
+        }
+    }
+
+    pub fn next_frame(&self) {
+        self.slots
+            .borrow_mut()
+            .retain(|slots| match slots.upgrade() {
+                Some(slots) => {
+                    slots.borrow_mut().next_frame();
+                    true
+                }
+                None => false,
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::variables::textures::TextureSlots;
+    use crate::vulkan::FRAMES_PROCESSING_CONCURRENCY;
+    use crate::Texture;
+    use vulkanalia::vk;
+    use vulkanalia::vk::Handle;
+
+    #[test]
+    pub fn test_released_slot_reused_after_frames_in_flight() {
+        let texture = |handle| Texture {
+            image: vk::Image::from_raw(handle),
+            memory: vk::DeviceMemory::null(),
+            view: vk::ImageView::from_raw(handle),
+            size: [1, 1],
+            format: vk::Format::R8G8B8A8_UNORM,
+        };
+        let mut slots = TextureSlots::new(2);
+        assert_eq!(slots.acquire(texture(1)), Some(0));
+        assert_eq!(slots.acquire(texture(2)), Some(1));
+        assert_eq!(slots.acquire(texture(3)), None);
+        slots.release(texture(1));
+        assert_eq!(slots.find(texture(1)), None);
+        assert_eq!(slots.acquire(texture(3)), None);
+        for _ in 0..FRAMES_PROCESSING_CONCURRENCY {
+            slots.next_frame();
         }
+        assert_eq!(slots.acquire(texture(3)), Some(0));
+        assert_eq!(slots.len(), 2);
     }
 }
@@ -1,6 +1,7 @@
+use crate::vulkan::stats::record_upload;
 use crate::vulkan::{
     create_buffers, create_descriptor_pool, create_descriptor_set_layout, create_descriptors,
-    MemoryBuffer, Vulkan,
+    MemoryBuffer, Vulkan, FRAMES_PROCESSING_CONCURRENCY,
 };
 use log::info;
 use std::any::type_name;
@@ -46,7 +47,7 @@ impl<T> Uniform<T> {
             type_name::<T>()
         );
         let device = &vulkan.device;
-        let frames = vulkan.swapchain.images.len();
+        let frames = FRAMES_PROCESSING_CONCURRENCY;
         let bindings = vec![(
             binding,
             DescriptorType::UNIFORM_BUFFER,
@@ -65,6 +66,7 @@ impl<T> Uniform<T> {
             frames,
             physical_device_memory,
             size_of::<T>(),
+            &vulkan.buffer_families(),
         );
         let uniform = Uniform {
             slot,
@@ -82,6 +84,7 @@ impl<T> Uniform<T> {
     }
 
     pub fn update(&self, frame: usize, value: &T) {
+        record_upload(size_of::<T>());
         unsafe {
             let memory = self
                 .device
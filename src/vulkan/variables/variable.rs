@@ -1,4 +1,4 @@
-use vulkanalia::vk::{DescriptorSet, DescriptorSetLayout};
+use vulkanalia::vk::{DescriptorSet, DescriptorSetLayout, Format};
 
 pub struct Variable {
     pub set: u32,
@@ -12,3 +12,12 @@ impl Variable {
         self.descriptors[frame]
     }
 }
+
+#[derive(Debug)]
+pub enum VariableError {
+    StorageOverflow { limit: usize },
+    TexturesExhausted { limit: u32 },
+    UnsupportedFormat { format: Format },
+    ElementSize { texel: usize },
+    LayerNotCreated { layer: usize },
+}
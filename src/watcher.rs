@@ -0,0 +1,98 @@
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Detects changed textures and fonts in watched directories by polling file modification
+/// time in background thread, so artists see changes without application restart.
+pub(crate) struct AssetWatcher {
+    changes: Receiver<PathBuf>,
+}
+
+impl AssetWatcher {
+    const INTERVAL: Duration = Duration::from_millis(500);
+    const EXTENSIONS: [&'static str; 3] = ["png", "ttf", "otf"];
+
+    pub fn new(directories: Vec<String>) -> Self {
+        info!("Starts asset watcher of {directories:?}");
+        for directory in &directories {
+            if !Path::new(directory).is_dir() {
+                warn!("Unable to watch assets directory {directory}, directory not found");
+            }
+        }
+        let (sender, changes) = channel();
+        thread::Builder::new()
+            .name("asset-watcher".to_string())
+            .spawn(move || {
+                let mut versions = scan(&directories);
+                loop {
+                    thread::sleep(Self::INTERVAL);
+                    let current = scan(&directories);
+                    for (path, version) in &current {
+                        let changed = versions
+                            .get(path)
+                            .is_some_and(|previous| previous != version);
+                        if changed && sender.send(path.clone()).is_err() {
+                            // graphics dropped
+                            return;
+                        }
+                    }
+                    versions = current;
+                }
+            })
+            .expect("asset watcher thread must be spawned");
+        Self { changes }
+    }
+
+    /// Returns files changed since previous call.
+    pub fn changes(&self) -> Vec<PathBuf> {
+        let mut changes: Vec<PathBuf> = self.changes.try_iter().collect();
+        changes.dedup();
+        changes
+    }
+}
+
+fn scan(directories: &[String]) -> HashMap<PathBuf, SystemTime> {
+    let mut versions = HashMap::new();
+    for directory in directories {
+        scan_directory(Path::new(directory), &mut versions);
+    }
+    versions
+}
+
+fn scan_directory(directory: &Path, versions: &mut HashMap<PathBuf, SystemTime>) {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        // directory may be temporarily unavailable, e.g. while assets are synchronized
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            scan_directory(&path, versions);
+            continue;
+        }
+        let watched = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| AssetWatcher::EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+            .unwrap_or(false);
+        if watched {
+            if let Ok(modified) = entry.metadata().and_then(|metadata| metadata.modified()) {
+                versions.insert(path, modified);
+            }
+        }
+    }
+}
+
+/// Returns true if both paths point to the same existing file, e.g. `./assets/a.png`
+/// and `assets/a.png`.
+pub(crate) fn same_file(a: impl AsRef<Path>, b: impl AsRef<Path>) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
@@ -0,0 +1,249 @@
+use crate::math::VecArith;
+use crate::UserInput;
+use sdl2::keyboard::{Keycode, Scancode};
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{Key, KeyCode, KeyLocation, NamedKey, PhysicalKey};
+
+/// Height of wheel line in pixels, touchpads report scroll in pixels instead of lines.
+const PIXELS_PER_LINE: f64 = 20.0;
+
+impl UserInput {
+    /// Translates winit window event to input state, so game code reads the same
+    /// input regardless of windowing library.
+    ///
+    /// Keys are translated to SDL key codes and scancodes. Unlike SDL events,
+    /// winit events are not collected to `events`.
+    pub fn handle_winit_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                let keycode = translate_key(&event.logical_key, event.location);
+                let scancode = match event.physical_key {
+                    PhysicalKey::Code(code) => translate_scancode(code),
+                    PhysicalKey::Unidentified(_) => None,
+                };
+                match event.state {
+                    ElementState::Pressed => {
+                        if let Some(keycode) = keycode {
+                            if !self.keys.down.contains(&keycode) {
+                                self.keys.down.push(keycode);
+                            }
+                        }
+                        if let Some(scancode) = scancode {
+                            if !self.keys.scancodes.contains(&scancode) {
+                                self.keys.scancodes.push(scancode);
+                            }
+                        }
+                    }
+                    ElementState::Released => {
+                        if let Some(keycode) = keycode {
+                            self.keys.down.retain(|down| *down != keycode);
+                            self.keys.pressed.insert(keycode);
+                        }
+                        if let Some(scancode) = scancode {
+                            self.keys.scancodes.retain(|down| *down != scancode);
+                            self.keys.scancodes_pressed.insert(scancode);
+                        }
+                    }
+                }
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let raw = [position.x as i32, position.y as i32];
+                let motion = [
+                    (raw[0] - self.mouse.raw[0]) as f32,
+                    (raw[1] - self.mouse.raw[1]) as f32,
+                ];
+                self.mouse.raw = raw;
+                self.mouse.motion = self.mouse.motion.add(motion);
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    MouseButton::Left => &mut self.mouse.left,
+                    MouseButton::Right => &mut self.mouse.right,
+                    _ => return,
+                };
+                match state {
+                    ElementState::Pressed => button.down = true,
+                    ElementState::Released => {
+                        button.down = false;
+                        button.click = true;
+                        // winit doesn't count consecutive clicks
+                        button.clicks = 1;
+                    }
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.mouse.wheel = match *delta {
+                    MouseScrollDelta::LineDelta(x, y) => [x, y],
+                    MouseScrollDelta::PixelDelta(position) => [
+                        (position.x / PIXELS_PER_LINE) as f32,
+                        (position.y / PIXELS_PER_LINE) as f32,
+                    ],
+                };
+            }
+            WindowEvent::Focused(focused) => {
+                self.window.focused = *focused;
+                self.window.focus_changed = true;
+            }
+            WindowEvent::Moved(position) => {
+                self.window.position = [position.x, position.y];
+                self.window.moved = true;
+            }
+            WindowEvent::Resized(size) => {
+                // winit has no minimization event, minimized window is resized to zero
+                let minimized = size.width == 0 || size.height == 0;
+                if minimized != self.window.minimized {
+                    self.window.minimized = minimized;
+                    self.window.minimized_changed = true;
+                }
+                self.window.size = [size.width, size.height];
+                self.window.resized = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn translate_key(key: &Key, location: KeyLocation) -> Option<Keycode> {
+    let right = location == KeyLocation::Right;
+    let keycode = match key {
+        Key::Character(text) => {
+            let mut chars = text.chars().flat_map(char::to_lowercase);
+            match (chars.next(), chars.next()) {
+                // SDL key codes of printable keys are unshifted characters
+                (Some(character), None) => return Keycode::from_i32(character as i32),
+                _ => return None,
+            }
+        }
+        Key::Named(named) => match named {
+            NamedKey::Enter => Keycode::Return,
+            NamedKey::Escape => Keycode::Escape,
+            NamedKey::Backspace => Keycode::Backspace,
+            NamedKey::Tab => Keycode::Tab,
+            NamedKey::Space => Keycode::Space,
+            NamedKey::Delete => Keycode::Delete,
+            NamedKey::Insert => Keycode::Insert,
+            NamedKey::Home => Keycode::Home,
+            NamedKey::End => Keycode::End,
+            NamedKey::PageUp => Keycode::PageUp,
+            NamedKey::PageDown => Keycode::PageDown,
+            NamedKey::ArrowUp => Keycode::Up,
+            NamedKey::ArrowDown => Keycode::Down,
+            NamedKey::ArrowLeft => Keycode::Left,
+            NamedKey::ArrowRight => Keycode::Right,
+            NamedKey::CapsLock => Keycode::CapsLock,
+            NamedKey::Shift if right => Keycode::RShift,
+            NamedKey::Shift => Keycode::LShift,
+            NamedKey::Control if right => Keycode::RCtrl,
+            NamedKey::Control => Keycode::LCtrl,
+            NamedKey::Alt if right => Keycode::RAlt,
+            NamedKey::Alt => Keycode::LAlt,
+            NamedKey::Super if right => Keycode::RGui,
+            NamedKey::Super => Keycode::LGui,
+            NamedKey::F1 => Keycode::F1,
+            NamedKey::F2 => Keycode::F2,
+            NamedKey::F3 => Keycode::F3,
+            NamedKey::F4 => Keycode::F4,
+            NamedKey::F5 => Keycode::F5,
+            NamedKey::F6 => Keycode::F6,
+            NamedKey::F7 => Keycode::F7,
+            NamedKey::F8 => Keycode::F8,
+            NamedKey::F9 => Keycode::F9,
+            NamedKey::F10 => Keycode::F10,
+            NamedKey::F11 => Keycode::F11,
+            NamedKey::F12 => Keycode::F12,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(keycode)
+}
+
+fn translate_scancode(code: KeyCode) -> Option<Scancode> {
+    let scancode = match code {
+        KeyCode::KeyA => Scancode::A,
+        KeyCode::KeyB => Scancode::B,
+        KeyCode::KeyC => Scancode::C,
+        KeyCode::KeyD => Scancode::D,
+        KeyCode::KeyE => Scancode::E,
+        KeyCode::KeyF => Scancode::F,
+        KeyCode::KeyG => Scancode::G,
+        KeyCode::KeyH => Scancode::H,
+        KeyCode::KeyI => Scancode::I,
+        KeyCode::KeyJ => Scancode::J,
+        KeyCode::KeyK => Scancode::K,
+        KeyCode::KeyL => Scancode::L,
+        KeyCode::KeyM => Scancode::M,
+        KeyCode::KeyN => Scancode::N,
+        KeyCode::KeyO => Scancode::O,
+        KeyCode::KeyP => Scancode::P,
+        KeyCode::KeyQ => Scancode::Q,
+        KeyCode::KeyR => Scancode::R,
+        KeyCode::KeyS => Scancode::S,
+        KeyCode::KeyT => Scancode::T,
+        KeyCode::KeyU => Scancode::U,
+        KeyCode::KeyV => Scancode::V,
+        KeyCode::KeyW => Scancode::W,
+        KeyCode::KeyX => Scancode::X,
+        KeyCode::KeyY => Scancode::Y,
+        KeyCode::KeyZ => Scancode::Z,
+        KeyCode::Digit0 => Scancode::Num0,
+        KeyCode::Digit1 => Scancode::Num1,
+        KeyCode::Digit2 => Scancode::Num2,
+        KeyCode::Digit3 => Scancode::Num3,
+        KeyCode::Digit4 => Scancode::Num4,
+        KeyCode::Digit5 => Scancode::Num5,
+        KeyCode::Digit6 => Scancode::Num6,
+        KeyCode::Digit7 => Scancode::Num7,
+        KeyCode::Digit8 => Scancode::Num8,
+        KeyCode::Digit9 => Scancode::Num9,
+        KeyCode::Enter => Scancode::Return,
+        KeyCode::Escape => Scancode::Escape,
+        KeyCode::Backspace => Scancode::Backspace,
+        KeyCode::Tab => Scancode::Tab,
+        KeyCode::Space => Scancode::Space,
+        KeyCode::Minus => Scancode::Minus,
+        KeyCode::Equal => Scancode::Equals,
+        KeyCode::BracketLeft => Scancode::LeftBracket,
+        KeyCode::BracketRight => Scancode::RightBracket,
+        KeyCode::Backslash => Scancode::Backslash,
+        KeyCode::Semicolon => Scancode::Semicolon,
+        KeyCode::Quote => Scancode::Apostrophe,
+        KeyCode::Backquote => Scancode::Grave,
+        KeyCode::Comma => Scancode::Comma,
+        KeyCode::Period => Scancode::Period,
+        KeyCode::Slash => Scancode::Slash,
+        KeyCode::CapsLock => Scancode::CapsLock,
+        KeyCode::F1 => Scancode::F1,
+        KeyCode::F2 => Scancode::F2,
+        KeyCode::F3 => Scancode::F3,
+        KeyCode::F4 => Scancode::F4,
+        KeyCode::F5 => Scancode::F5,
+        KeyCode::F6 => Scancode::F6,
+        KeyCode::F7 => Scancode::F7,
+        KeyCode::F8 => Scancode::F8,
+        KeyCode::F9 => Scancode::F9,
+        KeyCode::F10 => Scancode::F10,
+        KeyCode::F11 => Scancode::F11,
+        KeyCode::F12 => Scancode::F12,
+        KeyCode::Insert => Scancode::Insert,
+        KeyCode::Home => Scancode::Home,
+        KeyCode::PageUp => Scancode::PageUp,
+        KeyCode::Delete => Scancode::Delete,
+        KeyCode::End => Scancode::End,
+        KeyCode::PageDown => Scancode::PageDown,
+        KeyCode::ArrowRight => Scancode::Right,
+        KeyCode::ArrowLeft => Scancode::Left,
+        KeyCode::ArrowDown => Scancode::Down,
+        KeyCode::ArrowUp => Scancode::Up,
+        KeyCode::ShiftLeft => Scancode::LShift,
+        KeyCode::ShiftRight => Scancode::RShift,
+        KeyCode::ControlLeft => Scancode::LCtrl,
+        KeyCode::ControlRight => Scancode::RCtrl,
+        KeyCode::AltLeft => Scancode::LAlt,
+        KeyCode::AltRight => Scancode::RAlt,
+        KeyCode::SuperLeft => Scancode::LGui,
+        KeyCode::SuperRight => Scancode::RGui,
+        _ => return None,
+    };
+    Some(scancode)
+}